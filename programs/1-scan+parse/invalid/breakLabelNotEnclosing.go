@@ -0,0 +1,11 @@
+// A labeled break has to be inside the loop or switch with the label
+package main
+func main() {
+first:
+  for i := 0; i < 3; i++ {
+    continue first
+  }
+  for {
+    break first
+  }
+}
@@ -0,0 +1,11 @@
+// A labeled continue has to name a loop, not a switch
+package main
+func main() {
+  for {
+  sw:
+    switch {
+    default:
+      continue sw
+    }
+  }
+}
@@ -0,0 +1,10 @@
+// The final clause of a switch has no clause to fall through to
+package main
+func main() {
+  switch 1 {
+    case 1: println("one")
+    default:
+      println("other")
+      fallthrough
+  }
+}
@@ -0,0 +1,12 @@
+// fallthrough cannot be nested inside another statement of the clause
+package main
+func main() {
+  x := 1
+  switch x {
+    case 1:
+      if x > 0 {
+        fallthrough
+      }
+    case 2: println("two")
+  }
+}
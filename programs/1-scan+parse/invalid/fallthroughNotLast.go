@@ -0,0 +1,10 @@
+// fallthrough must be the last statement of its case clause
+package main
+func main() {
+  switch 1 {
+    case 1:
+      fallthrough
+      println("one")
+    case 2: println("two")
+  }
+}
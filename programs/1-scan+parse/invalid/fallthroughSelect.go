@@ -0,0 +1,10 @@
+// fallthrough is not allowed in a select
+package main
+func main() {
+  ch := make(chan int, 1)
+  select {
+    case ch <- 1:
+      fallthrough
+    default: println("full")
+  }
+}
@@ -0,0 +1,10 @@
+// fallthrough is not allowed in a type switch
+package main
+func main() {
+  var x interface{} = 1
+  switch x.(type) {
+    case int:
+      fallthrough
+    case string: println("string")
+  }
+}
@@ -0,0 +1,6 @@
+// Either all the results of a function are named or none of them are
+package main
+
+func f() (a int, []int) {
+	return
+}
@@ -0,0 +1,10 @@
+// A goto cannot jump into a block
+package main
+func main() {
+  x := 1
+  goto inside
+  if x > 0 {
+  inside:
+    println(x)
+  }
+}
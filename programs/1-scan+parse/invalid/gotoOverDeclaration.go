@@ -0,0 +1,9 @@
+// A goto cannot jump forward over a variable declaration
+package main
+func main() {
+  goto end
+  x := 1
+  println(x)
+end:
+  println("end")
+}
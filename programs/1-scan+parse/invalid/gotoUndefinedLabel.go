@@ -0,0 +1,5 @@
+// A goto needs a label in its function
+package main
+func main() {
+  goto end
+}
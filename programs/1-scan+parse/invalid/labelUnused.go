@@ -0,0 +1,8 @@
+// Labels have to be used
+package main
+func main() {
+unused:
+  for {
+    break
+  }
+}
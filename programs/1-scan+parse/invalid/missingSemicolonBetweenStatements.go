@@ -0,0 +1,6 @@
+// Only the last statement of a block can go without a semicolon
+package main
+
+func main() {
+	x := 1 println(x)
+}
@@ -0,0 +1,6 @@
+// Only the final parameter can be variadic
+package main
+func f(xs ...int, n int) {
+}
+func main() {
+}
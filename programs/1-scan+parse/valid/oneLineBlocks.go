@@ -0,0 +1,14 @@
+// The semicolon can be left out after the last statement of a block
+package main
+
+func add(a, b int) int { return a + b }
+
+func main() {
+	if add(1, 2) == 3 { println("three") } else { println("not three") }
+	for i := 0; i < 2; i++ { println(i); println(-i) }
+	switch x := add(1, 1); x { case 2: println("two") }
+	f := func() { println("literal") }
+	f()
+	{ }
+	{ ; }
+}
@@ -0,0 +1,11 @@
+// The address of a function call result cannot be taken
+package main
+
+func one() int {
+	return 1
+}
+
+func main() {
+	p := &one()
+	println(*p)
+}
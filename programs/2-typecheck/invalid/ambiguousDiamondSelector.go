@@ -0,0 +1,24 @@
+// x is promoted from c through both a and b at the same depth, so the selector is ambiguous
+package main
+
+type c struct {
+	x int
+}
+
+type a struct {
+	c
+}
+
+type b struct {
+	c
+}
+
+type s struct {
+	a
+	b
+}
+
+func main() {
+	var v s
+	println(v.x)
+}
@@ -0,0 +1,20 @@
+// greet is promoted from both embedded interfaces at the same depth, so the selector is ambiguous
+package main
+
+type greeter interface {
+	greet() string
+}
+
+type welcomer interface {
+	greet() string
+}
+
+type host struct {
+	greeter
+	welcomer
+}
+
+func main() {
+	var h host
+	println(h.greet())
+}
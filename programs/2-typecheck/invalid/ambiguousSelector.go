@@ -0,0 +1,20 @@
+// x is promoted from both embedded fields at the same depth, so the selector is ambiguous
+package main
+
+type a struct {
+	x int
+}
+
+type b struct {
+	x int
+}
+
+type c struct {
+	a
+	b
+}
+
+func main() {
+	var v c
+	println(v.x)
+}
@@ -0,0 +1,6 @@
+// The elements of an array literal must fit in the array
+package main
+
+func main() {
+	a := [2]int{1, 2, 3}
+}
@@ -0,0 +1,10 @@
+// A return statement without values is only allowed when the results are named
+package main
+
+func f() (int, int) {
+	return
+}
+
+func main() {
+	f()
+}
@@ -0,0 +1,7 @@
+// Only functions can be called
+package main
+
+func main() {
+	x := 1
+	x()
+}
@@ -0,0 +1,7 @@
+// Strings have a length but no capacity
+package main
+
+func main() {
+	var s string = "abc"
+	println(cap(s))
+}
@@ -0,0 +1,8 @@
+// Only map lookups can give a second value telling whether the key was found
+package main
+
+func main() {
+	var a [3]int
+	v, ok := a[0]
+	println(v, ok)
+}
@@ -0,0 +1,8 @@
+// Constants must be known at compile time
+package main
+
+func main() {
+	x := 3
+	const y = x + 1
+	println(y)
+}
@@ -0,0 +1,9 @@
+// Untyped constants are exact, but they have to fit in int when they are used as one
+package main
+
+const huge = 1 << 40
+
+func main() {
+	var x int = huge
+	println(x)
+}
@@ -0,0 +1,9 @@
+// An untyped constant must fit in the sized integer type it is given
+package main
+
+const c = 200
+
+func main() {
+	var x int8 = c
+	println(x)
+}
@@ -0,0 +1,9 @@
+// A float constant can only become an int if it has no fractional part
+package main
+
+const half = 0.5
+
+func main() {
+	var x int = half
+	println(x)
+}
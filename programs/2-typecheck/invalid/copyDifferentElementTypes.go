@@ -0,0 +1,8 @@
+// Both arguments of copy must be slices with the same element type
+package main
+
+func main() {
+	a := make([]int, 3)
+	b := make([]float64, 3)
+	copy(a, b)
+}
@@ -0,0 +1,7 @@
+// Only calls can be deferred, not conversions
+package main
+
+func main() {
+	x := 1.5
+	defer int(x)
+}
@@ -0,0 +1,7 @@
+// delete only takes maps
+package main
+
+func main() {
+	var s []int
+	delete(s, 0)
+}
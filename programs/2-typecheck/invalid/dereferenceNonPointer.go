@@ -0,0 +1,7 @@
+// Only pointers can be dereferenced
+package main
+
+func main() {
+	x := 1
+	println(*x)
+}
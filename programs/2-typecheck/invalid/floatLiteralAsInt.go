@@ -0,0 +1,7 @@
+// A float literal with a fraction cannot be an int
+package main
+
+func main() {
+	var x int = 1.5
+	println(x)
+}
@@ -0,0 +1,10 @@
+// A function value must have exactly the parameter and result types that are expected
+package main
+
+func apply(f func(int) int, x int) int {
+	return f(x)
+}
+
+func main() {
+	apply(func(x float64) int { return 1 }, 2)
+}
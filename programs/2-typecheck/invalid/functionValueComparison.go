@@ -0,0 +1,9 @@
+// Function values are not comparable
+package main
+
+func f() {}
+
+func main() {
+	g := f
+	println(g == f)
+}
@@ -0,0 +1,7 @@
+// A go statement must call a function
+package main
+
+func main() {
+	x := 3
+	go x
+}
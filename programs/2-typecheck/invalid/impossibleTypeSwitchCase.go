@@ -0,0 +1,22 @@
+// A type switch case can only be a type that has the methods of the interface
+package main
+
+type stepper interface {
+	next() int
+}
+
+type counter int
+
+func (c counter) next() int {
+	return int(c) + 1
+}
+
+func main() {
+	var s stepper = counter(1)
+	switch s.(type) {
+	case counter:
+		println("counter")
+	case string:
+		println("string")
+	}
+}
@@ -0,0 +1,18 @@
+// A value can only be used as an interface if its type has all the methods of the interface
+package main
+
+type shape interface {
+	area() int
+	perimeter() int
+}
+
+type square int
+
+func (s square) area() int {
+	return int(s * s)
+}
+
+func main() {
+	var s shape = square(2)
+	println(s.area())
+}
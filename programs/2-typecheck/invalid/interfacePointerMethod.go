@@ -0,0 +1,20 @@
+// Methods with a pointer receiver are only in the method set of the pointer type
+package main
+
+type counter struct {
+	n int
+}
+
+func (c *counter) increment() {
+	c.n++
+}
+
+type incrementer interface {
+	increment()
+}
+
+func main() {
+	var c counter
+	var i incrementer = c
+	i.increment()
+}
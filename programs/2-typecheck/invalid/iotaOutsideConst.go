@@ -0,0 +1,7 @@
+// iota only exists inside constant declarations
+package main
+
+func main() {
+	x := iota
+	println(x)
+}
@@ -0,0 +1,7 @@
+// len only takes strings, arrays and slices
+package main
+
+func main() {
+	var x int
+	println(len(x))
+}
@@ -0,0 +1,7 @@
+// A literal must fit in the sized integer type it is given
+package main
+
+func main() {
+	var x int8 = 200
+	println(x)
+}
@@ -0,0 +1,7 @@
+// make needs the length of the slice it makes
+package main
+
+func main() {
+	s := make([]int)
+	println(len(s))
+}
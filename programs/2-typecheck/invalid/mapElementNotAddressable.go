@@ -0,0 +1,11 @@
+// Map elements are not addressable, so their fields cannot be assigned to
+package main
+
+type point struct {
+	x, y int
+}
+
+func main() {
+	m := make(map[int]point)
+	m[0].x = 1
+}
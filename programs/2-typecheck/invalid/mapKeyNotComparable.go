@@ -0,0 +1,7 @@
+// Map keys must be comparable, which slices are not
+package main
+
+func main() {
+	var m map[[]int]string
+	println(len(m))
+}
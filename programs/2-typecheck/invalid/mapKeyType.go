@@ -0,0 +1,7 @@
+// The index of a map must have the type of its keys
+package main
+
+func main() {
+	m := make(map[string]int)
+	println(m[1])
+}
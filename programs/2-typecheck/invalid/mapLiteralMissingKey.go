@@ -0,0 +1,7 @@
+// Every element of a map literal needs a key
+package main
+
+func main() {
+	m := map[string]int{"a": 1, 2}
+	println(len(m))
+}
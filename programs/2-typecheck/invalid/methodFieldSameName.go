@@ -0,0 +1,13 @@
+// A struct type cannot have a field and a method with the same name
+package main
+
+type point struct {
+	x, y int
+}
+
+func (p point) x() int {
+	return 0
+}
+
+func main() {
+}
@@ -0,0 +1,9 @@
+// Methods can only be declared on defined types
+package main
+
+func (x int) double() int {
+	return x * 2
+}
+
+func main() {
+}
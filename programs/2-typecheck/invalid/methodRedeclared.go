@@ -0,0 +1,15 @@
+// A type cannot have two methods with the same name
+package main
+
+type counter int
+
+func (c counter) get() int {
+	return 0
+}
+
+func (c *counter) get() int {
+	return 1
+}
+
+func main() {
+}
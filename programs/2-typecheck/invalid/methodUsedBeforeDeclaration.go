@@ -0,0 +1,19 @@
+// Like functions, methods can only be used once they are declared
+package main
+
+type stack struct {
+	size int
+}
+
+func (s stack) empty() bool {
+	return s.count() == 0
+}
+
+func (s stack) count() int {
+	return s.size
+}
+
+func main() {
+	var s stack
+	println(s.empty())
+}
@@ -0,0 +1,10 @@
+// A call giving several values cannot be used as a single value
+package main
+
+func f() (int, int) {
+	return 1, 2
+}
+
+func main() {
+	println(f() + 1)
+}
@@ -0,0 +1,7 @@
+// A constant converted to byte, which is uint8, cannot be negative
+package main
+
+func main() {
+	var b = byte(-1)
+	println(b)
+}
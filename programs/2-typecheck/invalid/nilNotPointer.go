@@ -0,0 +1,7 @@
+// nil is only a value of pointer and function types
+package main
+
+func main() {
+	var x int = nil
+	println(x)
+}
@@ -0,0 +1,9 @@
+// A function literal with a result must end in a terminating statement
+package main
+
+func main() {
+	f := func() int {
+		print("hi")
+	}
+	f()
+}
@@ -0,0 +1,11 @@
+// Like print, panic only takes values of basic types
+package main
+
+type point struct {
+	x, y int
+}
+
+func main() {
+	var p point
+	panic(p)
+}
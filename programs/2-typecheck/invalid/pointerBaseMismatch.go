@@ -0,0 +1,8 @@
+// Pointers to different types cannot be assigned to each other
+package main
+
+func main() {
+	var p *int
+	var f float64
+	p = &f
+}
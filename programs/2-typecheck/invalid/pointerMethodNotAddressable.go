@@ -0,0 +1,19 @@
+// Pointer methods need an addressable receiver
+package main
+
+type counter struct {
+	n int
+}
+
+func (c *counter) increment() {
+	c.n++
+}
+
+func newCounter() counter {
+	var c counter
+	return c
+}
+
+func main() {
+	newCounter().increment()
+}
@@ -0,0 +1,9 @@
+// Only strings, arrays and slices can be ranged over
+package main
+
+func main() {
+	x := 3
+	for i := range x {
+		println(i)
+	}
+}
@@ -0,0 +1,10 @@
+// The value of a range over a string is a rune
+package main
+
+func main() {
+	var s string
+	for _, c := range "abc" {
+		s = c
+	}
+	println(s)
+}
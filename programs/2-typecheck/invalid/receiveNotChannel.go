@@ -0,0 +1,7 @@
+// Only channels can be received from
+package main
+
+func main() {
+	x := 3
+	println(<-x)
+}
@@ -0,0 +1,10 @@
+// A return statement must give as many values as the function has results
+package main
+
+func f() (int, bool) {
+	return 1
+}
+
+func main() {
+	f()
+}
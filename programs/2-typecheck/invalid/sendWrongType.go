@@ -0,0 +1,7 @@
+// The value sent on a channel must have the type of its elements
+package main
+
+func main() {
+	ch := make(chan int, 1)
+	ch <- "one"
+}
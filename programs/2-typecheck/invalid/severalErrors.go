@@ -0,0 +1,19 @@
+// Independent errors are all reported, in the order they appear in
+package main
+
+func first() int {
+	return "one"
+}
+
+var second bool = 2
+
+func third() {
+	var x int
+	x = true
+	y := undefined + 1
+}
+
+func main() {
+	first(1)
+	println(second && 1)
+}
@@ -0,0 +1,8 @@
+// Constant slice indices must not decrease
+package main
+
+func main() {
+	var a [5]int
+	s := a[3:1]
+	println(len(s))
+}
@@ -0,0 +1,6 @@
+// Each index of an array or slice literal can only be given once
+package main
+
+func main() {
+	s := []int{1, 0: 2}
+}
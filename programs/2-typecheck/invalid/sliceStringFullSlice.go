@@ -0,0 +1,7 @@
+// Strings have no capacity, so they cannot be given a full slice expression
+package main
+
+func main() {
+	s := "hello"
+	println(s[1:2:3])
+}
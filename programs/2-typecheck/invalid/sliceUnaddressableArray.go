@@ -0,0 +1,12 @@
+// The slice of an array shares its elements, so only an array variable can be sliced
+package main
+
+func numbers() [3]int {
+	var a [3]int
+	return a
+}
+
+func main() {
+	s := numbers()[1:]
+	println(len(s))
+}
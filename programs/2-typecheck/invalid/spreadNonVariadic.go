@@ -0,0 +1,12 @@
+// Only the last parameter of a variadic function can be given a slice with ...
+package main
+
+func add(a, b int) int {
+	return a + b
+}
+
+func main() {
+	var xs []int
+	xs = append(xs, 1)
+	println(add(xs...))
+}
@@ -0,0 +1,11 @@
+// A spread slice must have the kind of the variadic parameter
+package main
+
+func sum(xs ...int) int {
+	return len(xs)
+}
+
+func main() {
+	var words []string
+	println(sum(words...))
+}
@@ -0,0 +1,10 @@
+// A struct literal must either name all the fields it sets or give every field in order
+package main
+
+type point struct {
+	x, y int
+}
+
+func main() {
+	p := point{x: 1, 2}
+}
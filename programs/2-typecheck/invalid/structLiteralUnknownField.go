@@ -0,0 +1,10 @@
+// The fields named in a struct literal must be fields of its type
+package main
+
+type point struct {
+	x, y int
+}
+
+func main() {
+	p := point{x: 1, z: 2}
+}
@@ -0,0 +1,11 @@
+// The number of variables must match the number of results of the call
+package main
+
+func f() (int, int, int) {
+	return 1, 2, 3
+}
+
+func main() {
+	a, b := f()
+	println(a, b)
+}
@@ -0,0 +1,12 @@
+// The results of a call keep their types when they are assigned
+package main
+
+func f() (int, string) {
+	return 1, "a"
+}
+
+func main() {
+	var a, b int
+	a, b = f()
+	println(a, b)
+}
@@ -0,0 +1,8 @@
+// Only values of an interface type can be asserted to have another type
+package main
+
+func main() {
+	x := 3
+	y := x.(int)
+	println(y)
+}
@@ -0,0 +1,9 @@
+// Methods must be declared before they are selected
+package main
+
+type counter int
+
+func main() {
+	var c counter
+	c.increment()
+}
@@ -0,0 +1,6 @@
+// nil has no default type, so it cannot initialize a variable without one
+package main
+
+func main() {
+	x := nil
+}
@@ -0,0 +1,14 @@
+// Each trailing argument must have the kind of the elements of the variadic parameter
+package main
+
+func sum(xs ...int) int {
+	total := 0
+	for _, x := range xs {
+		total += x
+	}
+	return total
+}
+
+func main() {
+	println(sum(1, 2, "3"))
+}
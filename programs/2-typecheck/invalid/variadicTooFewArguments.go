@@ -0,0 +1,17 @@
+// The parameters before the variadic one must all be given arguments
+package main
+
+func join(separator string, words ...string) string {
+	result := ""
+	for i, word := range words {
+		if i > 0 {
+			result += separator
+		}
+		result += word
+	}
+	return result
+}
+
+func main() {
+	println(join())
+}
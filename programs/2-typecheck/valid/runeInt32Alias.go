@@ -0,0 +1,25 @@
+package main
+
+// rune is another name for int32, so their values mix freely
+func next(i int32) rune {
+	return i + 1
+}
+
+func main() {
+	var r rune = 'a'
+	var i int32 = r
+	r = i
+	i = next(r)
+	r = next(i) + 'b'
+
+	var runes []rune
+	var ints []int32 = runes
+	runes = append(ints, r)
+
+	for _, c := range "go" {
+		i = c
+	}
+
+	var p *int32 = &r
+	*p = i
+}
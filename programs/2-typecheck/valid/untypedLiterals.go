@@ -0,0 +1,15 @@
+// Literals are untyped constants, which take the type they are used as
+package main
+
+type celsius float64
+type counter int
+
+func main() {
+	var n int64 = 3
+	var b byte = 'a'
+	var f float32 = 1.5
+	var t celsius = -273.15
+	var c counter = 1
+	c = c * 2
+	println(n+1, b, f/2, t < 0, c == 2)
+}
@@ -0,0 +1,28 @@
+//~4950000
+//~100000
+package main
+
+// Each worker blocks on the receive until main sends it a job, then on the send until main takes
+// the result, so all of them are waiting at once
+func worker(jobs chan int, results chan int) {
+	job := <-jobs
+	results <- job
+}
+
+func main() {
+	jobs := make(chan int)
+	results := make(chan int)
+	for i := 0; i < 100000; i++ {
+		go worker(jobs, results)
+	}
+	for i := 0; i < 100000; i++ {
+		jobs <- i % 100
+	}
+	sum, count := 0, 0
+	for i := 0; i < 100000; i++ {
+		sum += <-results
+		count++
+	}
+	println(sum)
+	println(count)
+}
@@ -0,0 +1,148 @@
+//~0
+//~1
+//~4
+//~9
+//~16
+//~2 3
+//~a b
+//~0
+//~true false
+//~true 0 0
+//~nothing ready
+//~sent
+//~full
+//~7 true
+//~2
+//~4
+//~6
+//~30
+//~data 0
+//~data 1
+//~data 2
+//~done
+//~1 2
+//~true false
+package main
+
+func produce(ch chan int, n int) {
+	for i := 0; i < n; i++ {
+		ch <- i * i
+	}
+	close(ch)
+}
+
+type request struct {
+	n     int
+	reply chan int
+}
+
+func server(requests chan request) {
+	for r := range requests {
+		r.reply <- r.n * 2
+	}
+}
+
+func main() {
+	// An unbuffered channel, ranged over until it is closed
+	ch := make(chan int)
+	go produce(ch, 5)
+	for v := range ch {
+		println(v)
+	}
+
+	// A buffered channel
+	buf := make(chan string, 3)
+	buf <- "a"
+	buf <- "b"
+	println(len(buf), cap(buf))
+	println(<-buf, <-buf)
+	println(len(buf))
+
+	// Receiving from a closed channel gives the zero value
+	close(buf)
+	s, ok := <-buf
+	println(s == "", ok)
+
+	var nilch chan int
+	println(nilch == nil, len(nilch), cap(nilch))
+
+	// A select with a default case doesn't block
+	empty := make(chan int)
+	select {
+	case x := <-empty:
+		println("received", x)
+	default:
+		println("nothing ready")
+	}
+	full := make(chan int, 1)
+	for i := 7; i < 9; i++ {
+		select {
+		case full <- i:
+			println("sent")
+		default:
+			println("full")
+		}
+	}
+	select {
+	case x, ok := <-full:
+		println(x, ok)
+	}
+
+	// Each request carries the channel to reply on
+	requests := make(chan request)
+	go server(requests)
+	for i := 1; i <= 3; i++ {
+		var r request
+		r.n = i
+		r.reply = make(chan int)
+		requests <- r
+		println(<-r.reply)
+	}
+	close(requests)
+
+	// Goroutines started by function literals
+	results := make(chan int)
+	for w := 0; w < 3; w++ {
+		go func(w int) {
+			results <- w * 10
+		}(w)
+	}
+	total := 0
+	for i := 0; i < 3; i++ {
+		total += <-results
+	}
+	println(total)
+
+	// Breaking out of a loop from a select
+	quit := make(chan bool)
+	data := make(chan int)
+	go func() {
+		for i := 0; i < 3; i++ {
+			data <- i
+		}
+		quit <- true
+	}()
+loop:
+	for {
+		select {
+		case v := <-data:
+			println("data", v)
+		case <-quit:
+			break loop
+		}
+	}
+	println("done")
+
+	// Values are copied when they are sent
+	arrays := make(chan [2]int, 1)
+	var arr [2]int
+	arr[0] = 1
+	arrays <- arr
+	arr[0] = 2
+	got := <-arrays
+	println(got[0], arr[0])
+
+	// Copies of a channel are the same channel
+	other := ch
+	println(other == ch, other == empty)
+}
@@ -0,0 +1,59 @@
+//~1 2 3
+//~10 20
+//~3
+//~9
+//~4
+//~0 1 2
+package main
+
+func counter() func() int {
+	n := 0
+	return func() int {
+		n++
+		return n
+	}
+}
+
+func apply(f func(int, int) int, a, b int) int {
+	return f(a, b)
+}
+
+func adder(a int) func(int) int {
+	return func(b int) int {
+		return a + b
+	}
+}
+
+func square(x int) int {
+	return x * x
+}
+
+func main() {
+	next := counter()
+	a := next()
+	b := next()
+	println(a, b, next())
+
+	x := 10
+	get := func() int {
+		return x
+	}
+	print(get(), " ")
+	x = 20
+	println(get())
+
+	println(apply(func(a, b int) int { return a + b }, 1, 2))
+
+	var f func(int) int
+	f = square
+	println(f(3))
+
+	println(adder(1)(3))
+
+	var saved []func() int
+	for i := 0; i < 3; i++ {
+		j := i
+		saved = append(saved, func() int { return j })
+	}
+	println(saved[0](), saved[1](), saved[2]())
+}
@@ -0,0 +1,62 @@
+//~1 2
+//~3 0
+//~4 5
+//~1 2 3
+//~3: 10 20 30
+//~5: 0 0 7 8 0
+//~2: 1 2 | 3 4
+//~false 0
+//~1 2 3 4
+//~9 9
+//~1 2
+//~5 6
+package main
+
+type point struct {
+	x, y int
+}
+
+type line struct {
+	from, to point
+}
+
+func show(s []int) {
+	print(len(s), ":")
+	for _, v := range s {
+		print(" ", v)
+	}
+	println()
+}
+
+func main() {
+	p := point{x: 1, y: 2}
+	println(p.x, p.y)
+	q := point{x: 3}
+	println(q.x, q.y)
+	r := point{4, 5}
+	println(r.x, r.y)
+
+	a := [3]int{1, 2, 3}
+	println(a[0], a[1], a[2])
+	show([]int{10, 20, 30})
+	b := [...]int{2: 7, 8, 4: 0}
+	show(b[:])
+
+	// The kinds of the inner literals can be left out
+	points := []point{{1, 2}, {x: 3, y: 4}}
+	print(len(points), ": ")
+	println(points[0].x, points[0].y, "|", points[1].x, points[1].y)
+	flags := map[string][]bool{"a": {false}}
+	println(flags["a"][0], len(flags["b"]))
+	l := line{point{1, 2}, point{3, 4}}
+	println(l.from.x, l.from.y, l.to.x, l.to.y)
+
+	// Arrays in a literal are copied, and `&T{}` points to a new variable
+	c := [1]int{9}
+	d := [2][1]int{c, c}
+	c[0] = 0
+	println(d[0][0], d[1][0])
+	ptrs := []*point{{1, 2}, &point{5, 6}}
+	println(ptrs[0].x, ptrs[0].y)
+	println(ptrs[1].x, ptrs[1].y)
+}
@@ -0,0 +1,103 @@
+//~0 1 2
+//~1 2 4 8
+//~3 4
+//~4
+//~1
+//~+5.000000e-001
+//~+3.000000e+000
+//~-2147483648
+//~2147483647
+//~7
+//~hello, world
+//~12
+//~97
+//~a
+//~true
+//~12
+//~-3 -1
+//~2 3 5
+//~+1.000000e+000
+//~big
+package main
+
+const (
+	a = iota
+	b
+	c
+)
+
+const (
+	kb = 1 << iota
+	mb
+	gb
+	tb
+)
+
+type celsius float64
+
+const boiling celsius = 100
+
+// Untyped constants are exact, so intermediate values can be larger than any type
+const huge = 1 << 100
+const small = huge >> 98
+
+const greeting = "hello" + ", " + "world"
+
+func half(f float64) float64 {
+	return f
+}
+
+func main() {
+	println(a, b, c)
+	println(kb, mb, gb, tb)
+
+	const (
+		x, y = iota + 3, iota + 4
+	)
+	println(x, y)
+	println(small)
+
+	// Integer division, since both constants are integers
+	const three, two = 3, 2
+	println(three / two)
+
+	// The same constant used as a float64
+	println(half(three / 6.0))
+	var f float64 = three
+	println(f)
+
+	const minInt = -1 << 31
+	println(minInt)
+	const maxInt = 1<<31 - 1
+	println(maxInt)
+
+	var i int = 4
+	println(i + three)
+
+	println(greeting)
+	println(len(greeting))
+
+	const r = 'a'
+	println(r)
+	println(string(r))
+
+	var temperature celsius = boiling
+	println(temperature == boiling)
+
+	var arr [12]int
+	const n = len(arr)
+	println(n)
+
+	const quotient, complement = -7 / 2, ^0
+	println(quotient, complement)
+	const and, or, xor = 0x2 & 7, 1 | 2, 1 ^ 4
+	println(and, or, xor)
+
+	var g float64 = huge / huge
+	println(g)
+
+	const threshold = 50
+	if boiling > threshold {
+		println("big")
+	}
+}
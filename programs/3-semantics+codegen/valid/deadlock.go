@@ -0,0 +1,13 @@
+//!
+package main
+
+// Main waits for a value that no goroutine will send
+func main() {
+	ch := make(chan int)
+	done := make(chan bool)
+	go func() {
+		<-done
+		ch <- 1
+	}()
+	println(<-ch)
+}
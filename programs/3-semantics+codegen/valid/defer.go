@@ -0,0 +1,94 @@
+//~body 2
+//~x 1
+//~deferred 2
+//~deferred 1
+//~deferred 0
+//~42 1
+//~count 3
+//~first
+//~count 1
+//~1
+//~0
+//~inner
+//~outer
+package main
+
+type counter struct {
+	n int
+}
+
+func (c *counter) add(k int) {
+	c.n += k
+}
+
+func (c counter) show() {
+	println("count", c.n)
+}
+
+// Deferred calls run last first, with the arguments they were given
+func order() {
+	for i := 0; i < 3; i++ {
+		defer println("deferred", i)
+	}
+	x := 1
+	defer println("x", x)
+	x = 2
+	println("body", x)
+}
+
+// Deferred calls can change named results after the return
+func double() (r int) {
+	defer func() {
+		r *= 2
+	}()
+	return 21
+}
+
+func unchanged() int {
+	x := 1
+	defer func() {
+		x = 5
+	}()
+	return x
+}
+
+// The function value and the receiver are evaluated by the defer statement
+func values() {
+	var c counter
+	c.add(1)
+	defer c.show()
+
+	f := func() {
+		println("first")
+	}
+	defer f()
+	f = func() {
+		println("second")
+	}
+
+	c.add(2)
+	println("count", c.n)
+}
+
+func nested() {
+	defer println("outer")
+	func() {
+		defer println("inner")
+	}()
+}
+
+func main() {
+	order()
+	println(double(), unchanged())
+	values()
+
+	m := make(map[string]int)
+	m["a"] = 1
+	func() {
+		defer delete(m, "a")
+		println(len(m))
+	}()
+	println(len(m))
+
+	nested()
+}
@@ -0,0 +1,13 @@
+//!
+package main
+
+// The deferred call still runs before the program stops
+func get(a []int, i int) int {
+	defer println("leaving get")
+	return a[i]
+}
+
+func main() {
+	var a []int
+	println(get(a, 3))
+}
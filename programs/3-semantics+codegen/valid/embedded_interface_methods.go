@@ -0,0 +1,91 @@
+//~hello
+//~hello
+//~hello, world
+//~1 2
+//~3
+//~4 10
+//~bye
+package main
+
+type greeter interface {
+	greet() string
+}
+
+type english struct {
+	name string
+}
+
+func (e english) greet() string {
+	return "hello"
+}
+
+// The methods of an embedded interface are promoted to the struct, so it is a greeter too
+type polite struct {
+	greeter
+}
+
+// They are promoted from deeper embedded fields like the others
+type formal struct {
+	polite
+	title string
+}
+
+type counter interface {
+	next() int
+}
+
+type count struct {
+	n int
+}
+
+func (c *count) next() int {
+	c.n++
+	return c.n
+}
+
+// Through an embedded pointer, the methods of the interface it embeds are promoted too
+type wrapped struct {
+	counter
+}
+
+type tally struct {
+	*wrapped
+	total int
+}
+
+// An outer method hides the one of the embedded interface
+type rude struct {
+	greeter
+}
+
+func (r rude) greet() string {
+	return "bye"
+}
+
+func main() {
+	var p = polite{english{"world"}}
+	println(p.greet())
+
+	// The struct is assigned to an interface through its embedded field
+	var g greeter = p
+	println(g.greet())
+
+	var f = formal{p, "sir"}
+	g = f
+	println(g.greet() + ", " + f.polite.greeter.(english).name)
+
+	// The interface holds a pointer, so every call counts on the same value
+	var c = &count{}
+	var w = wrapped{c}
+	var i counter = w
+	println(i.next(), w.next())
+	i.next()
+	println(c.n)
+
+	var t = tally{&wrapped{&count{3}}, 10}
+	i = t
+	println(i.next(), t.total)
+
+	g = rude{english{"world"}}
+	println(g.greet())
+}
@@ -0,0 +1,74 @@
+//~one
+//~two
+//~three
+//~default
+//~four
+//~5
+//~negative other
+//~3
+package main
+
+func classify(x int) string {
+	switch {
+	case x < 0:
+		return "negative"
+	case x == 0:
+		fallthrough
+	default:
+		return "other"
+	}
+}
+
+func main() {
+	switch 1 {
+	case 1:
+		println("one")
+		fallthrough
+	case 2:
+		println("two")
+		fallthrough
+	case 3:
+		println("three")
+	case 4:
+		println("not reached")
+	}
+
+	// The default clause can be fallen into, and can fall into the next clause
+	switch x := 7; x {
+	case 1:
+		println("one")
+	default:
+		println("default")
+		fallthrough
+	case 4:
+		println("four")
+	}
+
+	// The cases of a clause that is fallen into are not evaluated
+	n := 0
+	switch n {
+	case 0:
+		n += 2
+		fallthrough
+	case n + 100:
+		n += 3
+	}
+	println(n)
+
+	println(classify(-1), classify(0))
+
+	// break still leaves the switch from a clause that was fallen into
+	count := 0
+	for i := 0; i < 3; i++ {
+		switch i {
+		case 0, 1:
+			fallthrough
+		case 2:
+			count++
+			if count > 5 {
+				break
+			}
+		}
+	}
+	println(count)
+}
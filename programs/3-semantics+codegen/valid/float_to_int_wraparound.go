@@ -0,0 +1,23 @@
+//~3 -3
+//~-1294967296 1294967296
+//~-1981284352 1981284352
+//~1661992960 -2147483648
+//~-2147483648 0 -1
+package main
+
+// Conversions truncate towards zero, and the values that do not fit an int are wrapped around
+func main() {
+	var f float64 = 3.9
+	println(int(f), int(-f))
+	f = 3000000000.0
+	println(int(f), int(-f))
+	f = 10000000000000000000.0
+	println(int(f), int(-f))
+	f = 100000000000000000000.0
+	var g float64 = 2147483648.5
+	println(int(f), int(g))
+
+	// Shifting by the width of an int or more
+	a, b, n := 1, -8, 31
+	println(a<<n, a<<(n+1), b>>(n+9))
+}
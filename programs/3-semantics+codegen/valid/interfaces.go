@@ -0,0 +1,120 @@
+//~12
+//~48
+//~2
+//~3
+//~4
+//~int 5
+//~string five
+//~shape 12
+//~int or float
+//~nil
+//~other
+//~3 true
+//~0 false
+//~true
+//~false
+//~true
+//~48 48
+//~4 true
+//~false
+package main
+
+type shape interface {
+	area() int
+	scale(k int)
+}
+
+type rect struct {
+	w, h int
+}
+
+func (r rect) area() int {
+	return r.w * r.h
+}
+
+// Only pointers to rectangles can be scaled, so only they are shapes
+func (r *rect) scale(k int) {
+	r.w *= k
+	r.h *= k
+}
+
+type counter int
+
+func (c counter) next() int {
+	return int(c) + 1
+}
+
+type value interface{}
+
+type stepper interface {
+	next() int
+}
+
+func describe(x interface{}) {
+	switch v := x.(type) {
+	case int:
+		println("int", v)
+	case string:
+		println("string", v)
+	case shape:
+		println("shape", v.area())
+	case float64, rune:
+		println("int or float")
+	case nil:
+		println("nil")
+	default:
+		println("other")
+	}
+}
+
+func main() {
+	var r rect
+	r.w, r.h = 3, 4
+	var s shape = &r
+	println(s.area())
+
+	// The interface holds the pointer, so scaling changes r
+	s.scale(2)
+	println(r.w * r.h)
+
+	// Each counter in the interface is its own value
+	var c counter = 1
+	var st stepper = c
+	c = 5
+	println(st.next())
+	st = counter(2)
+	f := st.next
+	println(f())
+	st = counter(st.next())
+	println(st.next())
+
+	describe(5)
+	describe("five")
+	q := new(rect)
+	q.w, q.h = 2, 6
+	describe(q)
+	describe(1.5)
+	describe(nil)
+	describe(true)
+
+	var e interface{} = 3
+	n, ok := e.(int)
+	println(n, ok)
+	m, ok := e.(counter)
+	println(m, ok)
+
+	// Interfaces are equal when they hold equal values of the same type
+	var a, b interface{} = 3, 3
+	println(a == b)
+	b = counter(3)
+	println(a == b)
+	b = nil
+	println(b == nil)
+
+	var t shape = &r
+	u := t.(interface{ area() int })
+	println(t.area(), u.area())
+	_, ok = e.(stepper)
+	println(e.(int) + 1, !ok)
+	println(e == value(4))
+}
@@ -0,0 +1,119 @@
+//~0 0
+//~1 0
+//~1 1
+//~3
+//~i 0
+//~i 1
+//~5
+//~after skip
+//~found 2 3
+//~20
+//~8
+//~4
+package main
+
+// A function can end in a goto
+func collatz(n int) int {
+	steps := 0
+loop:
+	if n == 1 {
+		return steps
+	}
+	steps++
+	if n%2 == 0 {
+		n /= 2
+	} else {
+		n = 3*n + 1
+	}
+	goto loop
+}
+
+func main() {
+	// A labeled continue goes on with the outer loop
+outer:
+	for i := 0; i < 3; i++ {
+		for j := 0; j < 3; j++ {
+			if j > i {
+				continue outer
+			}
+			if i == 2 {
+				break outer
+			}
+			println(i, j)
+		}
+	}
+
+	// A labeled break can leave a loop from a switch inside it
+	count := 0
+loop:
+	for {
+		switch count {
+		case 3:
+			break loop
+		default:
+			count++
+		}
+	}
+	println(count)
+
+	x := 5
+sw:
+	switch {
+	case x > 0:
+		for i := 0; ; i++ {
+			if i == 2 {
+				break sw
+			}
+			println("i", i)
+		}
+		println("not reached")
+	}
+
+	n := 0
+again:
+	n++
+	if n < 5 {
+		goto again
+	}
+	println(n)
+
+	goto skip
+	println("skipped")
+skip:
+	println("after skip")
+
+	for i := 1; i < 10; i++ {
+		for j := 1; j < 10; j++ {
+			if i*j == 6 && i > 1 {
+				println("found", i, j)
+				goto found
+			}
+		}
+	}
+found:
+
+	// Jumping back declares the variables after the label again
+	total := 0
+	k := 0
+add:
+	doubled := k * 2
+	total += doubled
+	k++
+	if k <= 4 {
+		goto add
+	}
+	println(total)
+
+	println(collatz(6))
+
+	f := func() int {
+	top:
+		for i := 0; ; i++ {
+			if i == 4 {
+				break top
+			}
+		}
+		return 4
+	}
+	println(f())
+}
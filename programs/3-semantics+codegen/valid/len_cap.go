@@ -0,0 +1,40 @@
+//~0 0
+//~1 1
+//~2 2
+//~3 4
+//~4 4
+//~5 8
+//~5 5
+//~3
+//~6
+//~0
+//~4
+//~calls: 1
+package main
+
+var calls int
+
+func makeArray() [4]int {
+	calls++
+	var a [4]int
+	return a
+}
+
+func main() {
+	var s []int
+	println(len(s), cap(s))
+	for i := 0; i < 5; i++ {
+		s = append(s, i)
+		println(len(s), cap(s))
+	}
+
+	var a [5]string
+	println(len(a), cap(a))
+	println(len("abc"))
+	println(len("héllo"))
+	println(len(""))
+
+	// Not a constant since it calls a function
+	println(len(makeArray()))
+	println("calls:", calls)
+}
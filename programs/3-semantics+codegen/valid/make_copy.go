@@ -0,0 +1,67 @@
+//~3 3: 0 0 0
+//~1 4 true true
+//~z
+//~3
+//~5 5: 1 2 3 0 0
+//~2
+//~3 3: 0 0 3
+//~6 6: 0 0 1 2 3 4
+//~6 6: 1 2 3 4 3 4
+//~0 0
+//~0 4
+package main
+
+type point struct {
+	x int
+	y int
+}
+
+func show(s []int) {
+	print(len(s), " ", cap(s), ":")
+	for _, v := range s {
+		print(" ", v)
+	}
+	println()
+}
+
+func main() {
+	a := make([]int, 3)
+	show(a)
+
+	// The elements past the length are zero values as well
+	b := make([]string, 1, 4)
+	b[0] = "x"
+	println(len(b), cap(b), b[0] == "x", b[:4][3] == "")
+
+	// Appending within the capacity shares the elements
+	c := append(b, "y")
+	c[0] = "z"
+	println(b[0])
+
+	for i := range a {
+		a[i] = i + 1
+	}
+	d := make([]int, 5)
+	n := copy(d, a)
+	println(n)
+	show(d)
+	println(copy(a, d[3:]))
+	show(a)
+
+	// Overlapping slices are copied as if through a temporary
+	e := make([]int, 6)
+	for i := range e {
+		e[i] = i
+	}
+	copy(e[1:], e)
+	show(e)
+	copy(e, e[2:])
+	show(e)
+
+	var none []int
+	println(copy(none, a), len(make([]bool, 0)))
+
+	ps := make([]point, 2)
+	ps[1].y = 4
+	println(ps[0].x, ps[1].y)
+}
@@ -0,0 +1,26 @@
+//~3 5
+//~0 0 0
+//~2 2
+//~4
+//~1
+package main
+
+func main() {
+	var n int64 = 3
+	var c uint64 = 5
+	s := make([]int, n, c)
+	println(len(s), cap(s))
+	println(s[0], s[1], s[2])
+
+	var m uint8 = 2
+	t := make([]string, m)
+	println(len(t), cap(t))
+
+	var size int64 = 4
+	ch := make(chan int, size)
+	println(cap(ch))
+
+	table := make(map[string]int, size)
+	table["a"] = 1
+	println(len(table))
+}
@@ -0,0 +1,8 @@
+//!
+package main
+
+func main() {
+	var n int64 = 1 << 40
+	s := make([]int, n)
+	println(len(s))
+}
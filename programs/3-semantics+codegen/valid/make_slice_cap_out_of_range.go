@@ -0,0 +1,8 @@
+//!
+package main
+
+func main() {
+	n := 5
+	s := make([]int, n, n-1)
+	println(len(s))
+}
@@ -0,0 +1,31 @@
+//~49995000
+//~1999000
+package main
+
+func worker(i int, results chan int) {
+	results <- i
+}
+
+func main() {
+	// Each goroutine finishes as soon as it sent, so only a few are running at once
+	buffered := make(chan int, 10000)
+	for i := 0; i < 10000; i++ {
+		go worker(i, buffered)
+	}
+	sum := 0
+	for i := 0; i < 10000; i++ {
+		sum += <-buffered
+	}
+	println(sum)
+
+	// These stay blocked on the send until main receives from them
+	unbuffered := make(chan int)
+	for i := 0; i < 2000; i++ {
+		go worker(i, unbuffered)
+	}
+	sum = 0
+	for i := 0; i < 2000; i++ {
+		sum += <-unbuffered
+	}
+	println(sum)
+}
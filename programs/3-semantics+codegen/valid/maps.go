@@ -0,0 +1,86 @@
+//~3
+//~1 0
+//~10 true
+//~0 false
+//~2
+//~6 60
+//~5
+//~0 0
+//~1 1
+//~x
+//~4
+//~1 2
+package main
+
+type point struct {
+	x, y int
+}
+
+func main() {
+	ages := map[string]int{
+		"alice": 1,
+		"bob":   10,
+		"carol": 100,
+	}
+	println(len(ages))
+
+	// Missing keys give the zero value
+	counts := make(map[int]int)
+	counts[3]++
+	println(counts[3], counts[4])
+
+	age, ok := ages["bob"]
+	println(age, ok)
+	age, ok = ages["dave"]
+	println(age, ok)
+
+	delete(ages, "carol")
+	delete(ages, "dave")
+	println(len(ages))
+
+	// The order of iteration is not specified, so only the totals are printed
+	squares := map[int]int{1: 10, 2: 20, 3: 30}
+	keys, values := 0, 0
+	for k, v := range squares {
+		keys += k
+		values += v
+	}
+	println(keys, values)
+
+	// Maps are shared by their copies
+	alias := squares
+	alias[4] = 40
+	squares[5] += 1
+	println(len(squares))
+
+	var empty map[string]int
+	println(len(empty), empty["x"])
+
+	// Deleting during a range loop skips the deleted entries
+	visited := 0
+	for k := range squares {
+		visited++
+		for other := range squares {
+			if other != k {
+				delete(squares, other)
+			}
+		}
+	}
+	println(len(squares), visited)
+
+	var p point
+	p.x, p.y = 1, 2
+	names := map[point]string{p: "x"}
+	var q point
+	q.x, q.y = 1, 2
+	println(names[q])
+
+	nested := map[string]map[string]int{"a": make(map[string]int)}
+	nested["a"]["b"] = 4
+	println(nested["a"]["b"])
+
+	lists := map[int][]int{}
+	lists[0] = append(lists[0], 1)
+	lists[0] = append(lists[0], 2)
+	println(lists[0][0], lists[0][1])
+}
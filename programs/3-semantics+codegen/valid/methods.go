@@ -0,0 +1,99 @@
+//~3
+//~2 4
+//~2 7
+//~0 3
+//~20
+//~6
+//~200 400
+//~42
+//~3 6
+//~6
+package main
+
+type point struct {
+	x, y int
+}
+
+func (p point) sum() int {
+	return p.x + p.y
+}
+
+// Pointer receivers modify the original value
+func (p *point) scale(k int) {
+	p.x *= k
+	p.y *= k
+}
+
+// Value receivers work on a copy
+func (p point) moved(dx int) point {
+	p.x += dx
+	return p
+}
+
+type counter int
+
+func (c counter) double() counter {
+	return c * 2
+}
+
+type stack struct {
+	items []int
+	size  int
+}
+
+func (s *stack) push(x int) {
+	s.items = append(s.items, x)
+	s.size++
+}
+
+func (s *stack) sumFrom(i int) int {
+	if i == s.size {
+		return 0
+	}
+	return s.items[i] + s.sumFrom(i+1)
+}
+
+func (s *stack) total() int {
+	return s.sumFrom(0)
+}
+
+func main() {
+	var p point
+	p.x = 1
+	p.y = 2
+	println(p.sum())
+
+	p.scale(2)
+	println(p.x, p.y)
+
+	q := p.moved(5)
+	println(p.x, q.x)
+
+	// Pointer methods on addressable array and slice elements
+	var points [2]point
+	points[1].x = 1
+	points[1].scale(3)
+	println(points[0].x, points[1].x)
+
+	var ps []point
+	ps = append(ps, p)
+	ps[0].scale(10)
+	println(ps[0].x)
+
+	// A value method value copies its receiver, a pointer one does not
+	f := p.sum
+	g := p.scale
+	g(100)
+	println(f())
+	println(p.x, p.y)
+
+	var c counter = 21
+	println(c.double())
+
+	var s stack
+	s.push(1)
+	s.push(2)
+	s.push(3)
+	println(s.size, s.total())
+	println(s.items[0] + s.items[1] + s.items[2])
+}
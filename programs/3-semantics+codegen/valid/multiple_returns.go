@@ -0,0 +1,74 @@
+//~2 1
+//~3 1
+//~7 false
+//~20 true
+//~0 0
+//~5 6
+//~called
+//~5 4
+package main
+
+func swap(a, b int) (int, int) {
+	return b, a
+}
+
+func divmod(a, b int) (q, r int) {
+	q = a / b
+	r = a % b
+	return
+}
+
+func find(values []int, x int) (index int, found bool) {
+	for i, v := range values {
+		if v == x {
+			return i, true
+		}
+	}
+	return len(values) + 5, false
+}
+
+func zeros() (a, _ int) {
+	return
+}
+
+func forward(a, b int) (int, int) {
+	return swap(b, a)
+}
+
+func called() (int, string) {
+	println("called")
+	return 0, ""
+}
+
+func main() {
+	x, y := swap(1, 2)
+	println(x, y)
+
+	q, r := divmod(10, 3)
+	println(q, r)
+
+	var s []int
+	s = append(s, 10)
+	s = append(s, 20)
+	i, ok := find(s, 30)
+	println(i, ok)
+
+	var v int
+	var found bool
+	i, found = find(s, 20)
+	v = s[i]
+	println(v, found)
+
+	z1, z2 := zeros()
+	println(z1, z2)
+
+	var a, b = forward(5, 6)
+	println(a, b)
+
+	called()
+
+	a, b = 4, 5
+	a, b = swap(b, a)
+	a, b = swap(a, b)
+	println(a, b)
+}
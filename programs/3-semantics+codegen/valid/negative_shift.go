@@ -0,0 +1,9 @@
+//!
+package main
+
+func main() {
+	a, n := 1, 3
+	println(a << n)
+	n = -1
+	println(a << n)
+}
@@ -0,0 +1,12 @@
+//!
+package main
+
+type node struct {
+	value int
+	next  *node
+}
+
+func main() {
+	n := new(node)
+	println(n.next.value)
+}
@@ -0,0 +1,17 @@
+//!
+package main
+
+type celsius float64
+
+func check(t celsius) {
+	defer println("checked")
+	if t < -273.15 {
+		panic(t)
+	}
+}
+
+func main() {
+	check(20)
+	check(-300)
+	println("unreachable")
+}
@@ -0,0 +1,16 @@
+//~1 -1
+package main
+
+// A call to panic ends a function like a return statement
+func sign(x int) int {
+	if x > 0 {
+		return 1
+	} else if x < 0 {
+		return -1
+	}
+	panic("zero has no sign")
+}
+
+func main() {
+	println(sign(5), sign(-3))
+}
@@ -0,0 +1,85 @@
+//~2 2
+//~5
+//~3 4
+//~0 true
+//~123
+//~6
+//~10 20
+//~false true
+//~true
+//~7
+//~true false
+package main
+
+type point struct {
+	x, y int
+}
+
+type node struct {
+	value int
+	next  *node
+}
+
+// Struct arguments can be changed in place through a pointer
+func move(p *point, dx, dy int) {
+	p.x += dx
+	p.y += dy
+}
+
+func push(head *node, value int) *node {
+	n := new(node)
+	n.value = value
+	n.next = head
+	return n
+}
+
+func main() {
+	x := 1
+	p := &x
+	*p = 2
+	println(x, *p)
+
+	q := p
+	*q += 3
+	println(x)
+
+	var pt point
+	move(&pt, 3, 4)
+	println(pt.x, pt.y)
+
+	// new gives a pointer to a zero value
+	n := new(int)
+	println(*n, n != nil)
+
+	var list *node
+	for i := 3; i > 0; i-- {
+		list = push(list, i)
+	}
+	digits := 0
+	for it := list; it != nil; it = it.next {
+		digits = digits*10 + it.value
+	}
+	println(digits)
+	println(list.next.next.value + (*list).value + list.next.value)
+
+	// Pointers to array and slice elements
+	var a [2]int
+	var s []int
+	s = append(s, 0)
+	pa := &a[1]
+	ps := &s[0]
+	*pa = 10
+	*ps = 20
+	println(a[1], s[0])
+
+	// Pointers are equal when they point to the same variable
+	y := 7
+	println(&x == &y, p == q)
+	pp := &p
+	println(*pp == p)
+	**pp = 7
+	println(x)
+
+	var f func()
+	println(f == nil, pt.x == 0)
+}
@@ -0,0 +1,82 @@
+//~0 10
+//~1 20
+//~2 30
+//~sum: 60
+//~0 104
+//~1 233
+//~3 108
+//~3
+//~a
+//~b
+//~0
+//~1
+//~2
+//~2 0
+//~1
+//~2
+//~3
+//~10
+//~30
+package main
+
+func main() {
+	var s []int
+	s = append(s, 10)
+	s = append(s, 20)
+	s = append(s, 30)
+
+	sum := 0
+	for i, v := range s {
+		println(i, v)
+		sum += v
+	}
+	println("sum:", sum)
+
+	// Strings are iterated over by rune, with the byte offset of each
+	for i, r := range "hél" {
+		println(i, r)
+	}
+
+	count := 0
+	for range s {
+		count++
+	}
+	println(count)
+
+	var words [2]string
+	words[0] = "a"
+	words[1] = "b"
+	for _, w := range words {
+		println(w)
+	}
+
+	for i := range s {
+		println(i)
+	}
+
+	// Arrays are copied before the loop starts, but slices are not
+	var a [3]int
+	for i, v := range a {
+		a[2] = 5
+		if i == 2 {
+			println(i, v)
+		}
+	}
+	var t []int
+	t = append(t, 1)
+	t = append(t, 2)
+	t = append(t, 0)
+	for i, v := range t {
+		if i == 1 {
+			t[2] = 3
+		}
+		println(v)
+	}
+
+	for i, v := range s {
+		if i == 1 {
+			continue
+		}
+		println(v)
+	}
+}
@@ -0,0 +1,11 @@
+//!
+package main
+
+// Sending on a closed channel panics, even from a buffered channel with room
+func main() {
+	ch := make(chan int, 2)
+	ch <- 1
+	close(ch)
+	println(<-ch)
+	ch <- 2
+}
@@ -0,0 +1,63 @@
+//~44 44 211
+//~-128 -128
+//~4294967295 1 3410065408
+//~-9223372036854775808 0
+//~18446744073709551615 6148914691236517205 true
+//~32767 -32768
+//~-1 -3
+//~200 -200
+//~66 B
+//~+1.000000e-001 false true
+//~4
+package main
+
+const shift = 40
+
+func main() {
+	// byte is uint8, and arithmetic wraps around
+	var b byte = byte(200)
+	b += byte(100)
+	var u uint8 = b
+	println(b, u, ^u)
+
+	var i int8 = int8(127)
+	i++
+	println(i, -i)
+
+	var x uint32
+	x--
+	var y uint32 = uint32(4000000000)
+	println(x, x>>uint32(31), y*uint32(3))
+
+	var big int64 = int64(1) << int64(62)
+	println(big*int64(2), big*int64(4))
+
+	var ubig uint64
+	ubig--
+	println(ubig, ubig/uint64(3), ubig > uint64(1))
+
+	var s int16 = int16(-32768)
+	println(s-int16(1), s/int16(-1))
+
+	var m int8 = int8(-7)
+	println(m%int8(3), m/int8(2))
+
+	// Conversions truncate floats
+	var f = 200.7
+	println(uint8(f), int(-f))
+
+	var r rune = 'A'
+	var w int64 = int64(r) + int64(1)
+	println(w, string(rune(w)))
+
+	// float32 values are rounded to 24 bits of mantissa
+	var g float32 = float32(0.1)
+	var h float32 = float32(16777216.0)
+	h++
+	println(g, float64(g) == 0.1, h == float32(16777216.0))
+
+	// Untyped constants take the kind they are used as
+	const k = 1 << shift
+	var q int64 = k
+	println(q >> int64(38))
+}
@@ -0,0 +1,84 @@
+//~3 4: 10 20 30
+//~11
+//~21 21
+//~2 3: 21 30
+//~3 3: 21 30 40
+//~99 99
+//~2 2: 0 11
+//~0 21 5 7
+//~3 4: 5 11 7
+//~0 0 8
+//~3
+//~world hello l
+//~0
+//~0 0:
+//~0 0:
+package main
+
+type pair struct {
+	values [3]int
+}
+
+func show(s []int) {
+	print(len(s), " ", cap(s), ":")
+	for _, v := range s {
+		print(" ", v)
+	}
+	println()
+}
+
+func main() {
+	var a [5]int
+	for i := range a {
+		a[i] = i * 10
+	}
+	s := a[1:4]
+	show(s)
+	s[0] = 11
+	println(a[1])
+
+	// A slice of a slice shares its elements, from its own offset
+	t := s[1:]
+	t[0] = 21
+	println(s[1], a[2])
+	show(t)
+
+	// Slicing up to the capacity reaches past the length
+	u := t[:3]
+	show(u)
+
+	// Appending within the capacity writes to the array
+	v := s[:2]
+	v = append(v, 99)
+	println(a[3], s[2])
+
+	// A full slice expression limits the capacity, so appending copies
+	w := a[0:2:2]
+	show(w)
+	w = append(w, 7)
+	w[0] = 5
+	println(a[0], a[2], w[0], w[2])
+	show(w)
+
+	// Assigning to the array changes what its slices see
+	var b [5]int
+	b[4] = 8
+	a = b
+	println(s[0], s[2], u[2])
+
+	// The array of a struct, through a pointer
+	var p pair
+	q := &p
+	r := q.values[:]
+	r[2] = 3
+	println(p.values[2])
+
+	// Strings are sliced by bytes
+	str := "hello, world"
+	println(str[7:], str[:5], str[3:4])
+	println(len(str[:0]))
+
+	var n []int
+	show(n[:0])
+	show(n[0:0:0])
+}
@@ -0,0 +1,10 @@
+//!
+package main
+
+func main() {
+	var a [4]int
+	s := a[1:3]
+	i := 4
+	println(len(s[:i]))
+	println(len(s[:i+1]))
+}
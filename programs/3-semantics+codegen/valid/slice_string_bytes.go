@@ -0,0 +1,36 @@
+//~6
+//~2 4
+//~true
+//~1 true
+//~0 104
+//~1 65533
+//~0 65533
+//~1 108
+//~2 108
+//~3 111
+//~true true
+package main
+
+func main() {
+	// Strings are sliced by bytes, even through a character that takes several of them
+	s := "héllo"
+	println(len(s))
+	a := s[:2]
+	b := s[2:]
+	println(len(a), len(b))
+	println(a+b == s)
+
+	e := "é"
+	println(len(e[:1]), e[:1] == s[1:2])
+
+	// Each byte of a partial character is a rune of its own
+	for i, r := range a {
+		println(i, r)
+	}
+	for i, r := range b {
+		println(i, r)
+	}
+
+	// The bytes are compared as they are
+	println(s[1:3] == e, s[1:2] > s[2:3])
+}
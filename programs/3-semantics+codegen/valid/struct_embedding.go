@@ -0,0 +1,94 @@
+//~1 2 3
+//~10 2 30
+//~0 0 0
+//~12
+//~20 4
+//~inner outer
+//~5 20
+//~10
+//~24
+//~10 40
+package main
+
+type point struct {
+	x, y int
+}
+
+func (p point) sum() int {
+	return p.x + p.y
+}
+
+func (p *point) scale(k int) {
+	p.x *= k
+	p.y *= k
+}
+
+type named struct {
+	name string
+}
+
+func (n named) describe() string {
+	return n.name
+}
+
+// The fields and methods of point are promoted to circle
+type circle struct {
+	point
+	r int
+}
+
+// An outer field shadows the promoted one
+type labelled struct {
+	named
+	name string
+}
+
+// Through an embedded pointer, every copy shares the same point
+type shared struct {
+	*point
+}
+
+type summer interface {
+	sum() int
+}
+
+type scaler interface {
+	scale(k int)
+}
+
+func main() {
+	var c = circle{point{1, 2}, 3}
+	println(c.x, c.y, c.r)
+	c.x = 10
+	c.r *= 10
+	println(c.point.x, c.y, c.r)
+
+	// Embedded fields get the zero value of their type
+	var z circle
+	println(z.x, z.y, z.r)
+
+	// Promoted value and pointer methods
+	c.y = 2
+	println(c.sum())
+	c.scale(2)
+	println(c.x, c.y)
+
+	var l = labelled{named{"inner"}, "outer"}
+	println(l.describe(), l.name)
+
+	var s = shared{&point{1, 4}}
+	var t = s
+	t.scale(5)
+	t.x = 5
+	println(s.x, s.y)
+	println(s.point.x+s.sum()-s.y)
+
+	// A promoted method satisfies an interface
+	var i summer = c
+	println(i.sum())
+
+	// The pointer method of an embedded pointer is in the method set of the value
+	var j scaler = s
+	j.scale(2)
+	println(s.x, t.y)
+}
@@ -0,0 +1,11 @@
+//!
+package main
+
+type celsius int
+
+// A type assertion to the wrong type panics
+func main() {
+	var x interface{} = celsius(20)
+	n := x.(int)
+	println(n)
+}
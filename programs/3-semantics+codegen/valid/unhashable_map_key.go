@@ -0,0 +1,12 @@
+//!
+package main
+
+// An interface used as a map key can't hold a slice, which has no hash
+func main() {
+	m := make(map[interface{}]int)
+	m[1] = 1
+	m["a"] = 2
+	println(len(m))
+	var k interface{} = []int{1, 2}
+	m[k] = 3
+}
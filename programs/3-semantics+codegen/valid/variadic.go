@@ -0,0 +1,91 @@
+//~0 0
+//~3 6
+//~5 15
+//~sum: 10
+//~[a b c]
+//~2 true
+//~99
+//~go 2
+//~7 8 9
+//~deferred 3
+package main
+
+func sum(xs ...int) (int, int) {
+	total := 0
+	for _, x := range xs {
+		total += x
+	}
+	return len(xs), total
+}
+
+func labelled(label string, xs ...int) {
+	_, total := sum(xs...)
+	println(label, total)
+}
+
+type list struct {
+	items []string
+}
+
+func (l *list) add(items ...string) {
+	for _, item := range items {
+		l.items = append(l.items, item)
+	}
+}
+
+func count(xs ...interface{}) int {
+	return len(xs)
+}
+
+func deferred(xs ...int) {
+	println("deferred", len(xs))
+}
+
+func main() {
+	n, total := sum()
+	println(n, total)
+	n, total = sum(1, 2, 3)
+	println(n, total)
+	n, total = sum(1, 2, 3, 4, 5)
+	println(n, total)
+
+	labelled("sum:", 1, 2, 3, 4)
+
+	var l list
+	l.add("a")
+	l.add("b", "c")
+	print("[")
+	for i, item := range l.items {
+		if i > 0 {
+			print(" ")
+		}
+		print(item)
+	}
+	println("]")
+
+	// Each trailing argument is converted to the kind of the elements
+	println(count(1, "two"), count() == 0)
+
+	// A spread slice is passed itself, so the function sees the same elements
+	var s []int
+	s = append(s, 1)
+	set := func(xs ...int) {
+		xs[0] = 99
+	}
+	set(s...)
+	println(s[0])
+
+	// The arguments of deferred calls and goroutines are packed when the statement runs
+	defer deferred(1, 2, 3)
+	done := make(chan bool)
+	go func(xs ...int) {
+		println("go", len(xs))
+		done <- true
+	}(4, 5)
+	<-done
+
+	var f func(...int) = func(xs ...int) {
+		println(xs[0], xs[1], xs[2])
+	}
+	f(7, 8, 9)
+}
@@ -0,0 +1,8 @@
+//! goroutines and channels are not supported by the C backend.
+package main
+
+func main() {
+	c := make(chan int, 1)
+	c <- 1
+	println(<-c)
+}
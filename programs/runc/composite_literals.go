@@ -0,0 +1,7 @@
+//! composite literals are not supported by the C backend.
+package main
+
+func main() {
+	s := []int{1, 2, 3}
+	println(len(s))
+}
@@ -0,0 +1,7 @@
+//! copy is not supported by the C backend.
+package main
+
+func main() {
+	var a, b []int
+	println(copy(a, b))
+}
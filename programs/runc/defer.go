@@ -0,0 +1,10 @@
+//! defer is not supported by the C backend.
+package main
+
+func f() {
+	println("f")
+}
+
+func main() {
+	defer f()
+}
@@ -0,0 +1,11 @@
+//! function values are not supported by the C backend.
+package main
+
+func f() int {
+	return 1
+}
+
+func main() {
+	g := f
+	println(g())
+}
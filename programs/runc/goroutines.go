@@ -0,0 +1,10 @@
+//! goroutines and channels are not supported by the C backend.
+package main
+
+func f() {
+	println("f")
+}
+
+func main() {
+	go f()
+}
@@ -0,0 +1,8 @@
+//! interfaces are not supported by the C backend.
+package main
+
+func main() {
+	var x interface{}
+	x = 1
+	println(x == 1)
+}
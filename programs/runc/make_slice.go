@@ -0,0 +1,7 @@
+//! make of slices is not supported by the C backend.
+package main
+
+func main() {
+	s := make([]int, 3)
+	println(len(s))
+}
@@ -0,0 +1,7 @@
+//! maps are not supported by the C backend.
+package main
+
+func main() {
+	m := make(map[string]int)
+	m["a"] = 1
+}
@@ -0,0 +1,15 @@
+//! methods are not supported by the C backend.
+package main
+
+type counter struct {
+	n int
+}
+
+func (c counter) get() int {
+	return c.n
+}
+
+func main() {
+	var c counter
+	println(c.get())
+}
@@ -0,0 +1,6 @@
+//! panic is not supported by the C backend.
+package main
+
+func main() {
+	panic("stop")
+}
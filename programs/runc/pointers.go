@@ -0,0 +1,8 @@
+//! pointers are not supported by the C backend.
+package main
+
+func main() {
+	x := 1
+	p := &x
+	println(*p)
+}
@@ -0,0 +1,106 @@
+RESULTS=()
+
+# The C files and binaries are built out of the tree
+TMP=$(mktemp -d)
+trap 'rm -rf "$TMP"' EXIT
+
+
+
+echo -e "\033[93m"
+echo "    runc semantics valid"
+echo "    ======================"
+echo -e -n "\033[0m"
+
+# Programs using a feature that the C backend doesn't support are skipped, the others are built
+# with gcc and checked the same way as verify.sh checks the interpreter
+COUNT=0
+COUNT_PASSED=0
+COUNT_UNSUPPORTED=0
+for PROGRAM in `find programs/3-semantics+codegen/valid -name "*.go" | sort`
+do
+  if ! ./target/debug/golite codegen_c < $PROGRAM > $TMP/prog.c 2> $TMP/errors
+  then
+    if grep -q "not supported by the C backend" $TMP/errors
+    then
+      ((COUNT_UNSUPPORTED++))
+      continue
+    fi
+  fi
+  ((COUNT++))
+
+  USER_OUTPUT=
+  EXIT_CODE=
+  if [[ -s $TMP/prog.c ]] && gcc -std=c99 -o $TMP/prog $TMP/prog.c -lm 2>/dev/null
+  then
+    USER_OUTPUT="$($TMP/prog 2>&1)"
+    EXIT_CODE=$?
+  fi
+  EXPECTED_OUTPUT="$(grep "//~" $PROGRAM | sed 's/\/\/~//')"
+  SHOULD_ERROR=$(grep "//!" $PROGRAM | wc -l)
+
+  if [[ -n $EXIT_CODE && ( "$USER_OUTPUT" == "$EXPECTED_OUTPUT" ||
+        ( $SHOULD_ERROR == "1" && $EXIT_CODE != 0 && $EXIT_CODE -lt 128 ) ) ]]
+  then
+    ((COUNT_PASSED++))
+  else
+    STATUS_COLOUR="31"
+    echo "$PROGRAM " | tr -d '\n'
+    echo -e -n " \033[0;${STATUS_COLOUR}m[fail]\033[0m"
+    echo
+  fi
+
+done
+if [ $COUNT -eq $COUNT_PASSED ]
+then
+  STATUS_COLOUR="42"
+else
+  STATUS_COLOUR="41"
+fi
+echo -e "\e[${STATUS_COLOUR}m# runc semantics valid: ${COUNT_PASSED}/${COUNT} (${COUNT_UNSUPPORTED} unsupported)\e[49m"
+RESULTS+=("\e[${STATUS_COLOUR}m# runc semantics valid: ${COUNT_PASSED}/${COUNT} (${COUNT_UNSUPPORTED} unsupported)\e[49m")
+
+
+
+
+echo -e "\033[93m"
+echo "    runc unsupported"
+echo "    ======================"
+echo -e -n "\033[0m"
+
+# Each program uses a feature that the C backend doesn't support, and must get the error on its
+# `//!` line instead of any C
+COUNT=0
+COUNT_PASSED=0
+for PROGRAM in `find programs/runc -name "*.go" | sort`
+do
+  ((COUNT++))
+
+  EXPECTED_ERROR="$(grep "//!" $PROGRAM | sed 's/\/\/! //')"
+  if ( ! ./target/debug/golite codegen_c < $PROGRAM > $TMP/prog.c 2> $TMP/errors &&
+    [[ ! -s $TMP/prog.c ]] &&
+    grep -q -F "$EXPECTED_ERROR" $TMP/errors ) then
+    ((COUNT_PASSED++))
+  else
+    STATUS_COLOUR="31"
+    echo "$PROGRAM " | tr -d '\n'
+    echo -e -n " \033[0;${STATUS_COLOUR}m[fail]\033[0m"
+    echo
+  fi
+
+done
+if [ $COUNT -eq $COUNT_PASSED ]
+then
+  STATUS_COLOUR="42"
+else
+  STATUS_COLOUR="41"
+fi
+echo -e "\e[${STATUS_COLOUR}m# runc unsupported: ${COUNT_PASSED}/${COUNT}\e[49m"
+RESULTS+=("\e[${STATUS_COLOUR}m# runc unsupported: ${COUNT_PASSED}/${COUNT}\e[49m")
+
+
+
+
+echo RESULTS:
+for i in ${!RESULTS[*]}; do
+	echo -e ${RESULTS[$i]}
+done
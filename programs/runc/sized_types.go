@@ -0,0 +1,7 @@
+//! sized numeric types are not supported by the C backend.
+package main
+
+func main() {
+	var x int64
+	println(x)
+}
@@ -0,0 +1,8 @@
+//! slice expressions are not supported by the C backend.
+package main
+
+func main() {
+	var a [4]int
+	s := a[1:3]
+	println(len(s))
+}
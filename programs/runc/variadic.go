@@ -0,0 +1,10 @@
+//! variadic functions are not supported by the C backend.
+package main
+
+func sum(xs ...int) int {
+	return len(xs)
+}
+
+func main() {
+	println(sum(1, 2))
+}
@@ -9,16 +9,16 @@ if [ $# -lt 2 ]
 then
 	echo "Missing arguments"
 	echo "Usage: $0 <mode> <file>"
-	echo " + mode: scan|tokens|parse|pretty|rename|obfuscate|symbol|typecheck|codegen"
+	echo " + mode: scan|tokens|parse|pretty|rename|obfuscate|symbol|typecheck|codegen|codegen_c|runc"
 	echo " + file: path to file (absolute or relative)"
 	exit 1
 fi
 
-if [[ "|scan|tokens|parse|pretty|rename|obfuscate|symbol|typecheck|codegen|runjs|interpret|" != *"|$1|"* ]]
+if [[ "|scan|tokens|parse|pretty|rename|obfuscate|symbol|typecheck|codegen|runjs|codegen_c|runc|interpret|" != *"|$1|"* ]]
 then
 	echo "Unknown mode \"$1\""
 	echo "Usage: $0 <mode> <file>"
-	echo " + mode: scan|tokens|parse|pretty|symbol|typecheck|codegen"
+	echo " + mode: scan|tokens|parse|pretty|rename|obfuscate|symbol|typecheck|codegen|codegen_c|runc"
 	echo " + file: path to file (absolute or relative)"
 	exit 1
 
@@ -34,6 +34,15 @@ then
   elif [[ "$1" == runjs ]]
   then
     ./target/debug/golite codegen < "$2" > "${2%.*}.js" && node "${2%.*}.js"
+  elif [[ "$1" == codegen_c ]]
+  then
+    ./target/debug/golite "$1" < "$2" > "${2%.*}.c" && echo OK
+  elif [[ "$1" == runc ]]
+  then
+    # A bare file name would be looked up in PATH, so the binary is run from the current directory
+    BINARY="${2%.*}"
+    [[ "$BINARY" == */* ]] || BINARY="./$BINARY"
+    ./target/debug/golite codegen_c < "$2" > "${2%.*}.c" && gcc -std=c99 -o "$BINARY" "${2%.*}.c" -lm && "$BINARY"
   else
     ./target/debug/golite "$1" < "$2"
 fi
@@ -13,6 +13,26 @@
 
 */
 
+#include <stdint.h>
+
+/* Must have the same layout as span::Position and span::Span. Span is also the type of bison's
+ * locations (YYLTYPE).
+ */
+typedef struct Position {
+  uint32_t line;
+  uint32_t column;
+  uint32_t offset;
+} Position;
+
+typedef struct Span {
+  uint32_t file;
+  Position start;
+  Position end;
+} Span;
+
+void report_syntax_error(Span, const char*);
+void add_token(const char *name, const char *text);
+
 #define DECLARE_VEC(NAME, TYPE) \
   typedef struct TYPE##Vec TYPE##Vec;\
   TYPE##Vec * make_##NAME##_vec (void);\
@@ -40,6 +60,7 @@ typedef struct TopLevelDeclarationNode TopLevelDeclarationNode;
 DECLARE_VEC(top_level_declaration, TopLevelDeclarationNode);
 
 typedef struct AstKindNode AstKindNode;
+DECLARE_VEC(kind, AstKindNode);
 
 typedef struct Field Field;
 DECLARE_VEC(field, Field);
@@ -47,8 +68,13 @@ DECLARE_VEC(field, Field);
 typedef struct CaseClause CaseClause;
 DECLARE_VEC(case_clause, CaseClause);
 
+typedef struct KeyedElement KeyedElement;
+DECLARE_VEC(keyed_element, KeyedElement);
+
 typedef struct Program Program;
 
+typedef struct Receiver Receiver;
+
 enum BasicKind {
   kInt = 0,
   kFloat = 1,
@@ -90,48 +116,89 @@ enum UnaryOperator {
     opNot,
 };
 
-Program *make_program(char*, TopLevelDeclarationNodeVec*);
+Program *make_program(Span, char*, TopLevelDeclarationNodeVec*);
 
-TopLevelDeclarationNode *make_var_top_level_declaration(uint32_t, VarSpecVec*);
-TopLevelDeclarationNode *make_type_top_level_declaration(uint32_t, TypeSpecVec*);
+TopLevelDeclarationNode *make_var_top_level_declaration(Span, VarSpecVec*);
+TopLevelDeclarationNode *make_type_top_level_declaration(Span, TypeSpecVec*);
+TopLevelDeclarationNode *make_const_top_level_declaration(Span, VarSpecVec*);
 TopLevelDeclarationNode *make_function_top_level_declaration(
-    uint32_t, char*, FieldVec*, AstKindNode*, StatementNodeVec*);
-
-ExpressionNode *make_identifier_expression(uint32_t, char*);
-ExpressionNode *make_literal_expression(uint32_t, char*,enum BasicKind);
-ExpressionNode *make_append_expression(uint32_t, ExpressionNode*, ExpressionNode*);
-ExpressionNode *make_binary_operation_expression(uint32_t, enum BinaryOperator, ExpressionNode*, ExpressionNode*);
-ExpressionNode *make_unary_operation_expression(uint32_t, enum UnaryOperator, ExpressionNode*);
-ExpressionNode *make_selector_expression(uint32_t, ExpressionNode*, char *);
-ExpressionNode *make_index_expression(uint32_t, ExpressionNode*, ExpressionNode*);
-ExpressionNode *make_function_call_expression(uint32_t, ExpressionNode*, ExpressionNodeVec*);
-
-StatementNode *make_empty_statement(uint32_t);
-StatementNode *make_block_statement(uint32_t, StatementNodeVec*);
-StatementNode *make_expression_statement(uint32_t, ExpressionNode*);
-StatementNode *make_assignment_statement(uint32_t, ExpressionNodeVec*, ExpressionNodeVec*);
-StatementNode *make_op_assignment_statement(uint32_t, ExpressionNode*, ExpressionNode*, enum BinaryOperator);
-StatementNode *make_var_declaration_statement(uint32_t, VarSpecVec*);
-StatementNode *make_type_declaration_statement(uint32_t, TypeSpecVec*);
-StatementNode *make_short_var_declaration_statement(uint32_t, StringVec*, ExpressionNodeVec*);
-StatementNode *make_inc_dec_statement(uint32_t, int, ExpressionNode*);
-StatementNode *make_print_statement(uint32_t, ExpressionNodeVec*);
-StatementNode *make_println_statement(uint32_t, ExpressionNodeVec*);
-StatementNode *make_if_statement(uint32_t, StatementNode*, ExpressionNode*, StatementNodeVec*, StatementNode*);
-StatementNode *make_for_statement(uint32_t, StatementNode*, ExpressionNode*, StatementNode*, StatementNodeVec*);
-StatementNode *make_switch_statement(uint32_t, StatementNode*, ExpressionNode*, CaseClauseVec*);
-StatementNode *make_break_statement(uint32_t);
-StatementNode *make_continue_statement(uint32_t);
-StatementNode *make_return_statement(uint32_t, ExpressionNode*);
-
-AstKindNode *make_identifier_kind(uint32_t, char*);
-AstKindNode *make_slice_kind(uint32_t, AstKindNode*);
-AstKindNode *make_array_kind(uint32_t, AstKindNode*, char*);
-AstKindNode *make_struct_kind(uint32_t, FieldVec*);
-
-Field *make_field(uint32_t, StringVec*, AstKindNode*);
-
-CaseClause *make_case_clause(uint32_t, ExpressionNodeVec*, StatementNodeVec*);
-VarSpec *make_var_spec(uint32_t, StringVec*, AstKindNode*, ExpressionNodeVec*);
-TypeSpec *make_type_spec(uint32_t, char*, AstKindNode*);
+    Span, char*, FieldVec*, FieldVec*, StatementNodeVec*);
+TopLevelDeclarationNode *make_method_top_level_declaration(
+    Span, Receiver*, char*, FieldVec*, FieldVec*, StatementNodeVec*);
+
+ExpressionNode *make_identifier_expression(Span, char*);
+ExpressionNode *make_literal_expression(Span, char*,enum BasicKind);
+ExpressionNode *make_append_expression(Span, ExpressionNode*, ExpressionNode*);
+ExpressionNode *make_len_expression(Span, ExpressionNode*);
+ExpressionNode *make_cap_expression(Span, ExpressionNode*);
+ExpressionNode *make_make_expression(Span, AstKindNode*, ExpressionNodeVec*);
+ExpressionNode *make_function_literal_expression(Span, FieldVec*, FieldVec*, StatementNodeVec*);
+ExpressionNode *make_delete_expression(Span, ExpressionNode*, ExpressionNode*);
+ExpressionNode *make_new_expression(Span, AstKindNode*);
+ExpressionNode *make_panic_expression(Span, ExpressionNode*);
+ExpressionNode *make_receive_expression(Span, ExpressionNode*);
+ExpressionNode *make_close_expression(Span, ExpressionNode*);
+ExpressionNode *make_copy_expression(Span, ExpressionNode*, ExpressionNode*);
+ExpressionNode *make_composite_literal_expression(Span, AstKindNode*, KeyedElementVec*);
+ExpressionNode *make_binary_operation_expression(Span, enum BinaryOperator, ExpressionNode*, ExpressionNode*);
+ExpressionNode *make_unary_operation_expression(Span, enum UnaryOperator, ExpressionNode*);
+ExpressionNode *make_dereference_expression(Span, ExpressionNode*);
+ExpressionNode *make_address_of_expression(Span, ExpressionNode*);
+ExpressionNode *make_selector_expression(Span, ExpressionNode*, char *);
+ExpressionNode *make_index_expression(Span, ExpressionNode*, ExpressionNode*);
+ExpressionNode *make_slice_expression(Span, ExpressionNode*, ExpressionNode*, ExpressionNode*, ExpressionNode*);
+ExpressionNode *make_function_call_expression(Span, ExpressionNode*, ExpressionNodeVec*);
+ExpressionNode *make_spread_call_expression(Span, ExpressionNode*, ExpressionNodeVec*);
+ExpressionNode *make_type_assertion_expression(Span, ExpressionNode*, AstKindNode*);
+
+StatementNode *make_empty_statement(Span);
+StatementNode *make_block_statement(Span, StatementNodeVec*);
+StatementNode *make_expression_statement(Span, ExpressionNode*);
+StatementNode *make_assignment_statement(Span, ExpressionNodeVec*, ExpressionNodeVec*);
+StatementNode *make_op_assignment_statement(Span, ExpressionNode*, ExpressionNode*, enum BinaryOperator);
+StatementNode *make_var_declaration_statement(Span, VarSpecVec*);
+StatementNode *make_type_declaration_statement(Span, TypeSpecVec*);
+StatementNode *make_const_declaration_statement(Span, VarSpecVec*);
+StatementNode *make_short_var_declaration_statement(Span, StringVec*, ExpressionNodeVec*);
+StatementNode *make_inc_dec_statement(Span, int, ExpressionNode*);
+StatementNode *make_print_statement(Span, ExpressionNodeVec*);
+StatementNode *make_println_statement(Span, ExpressionNodeVec*);
+StatementNode *make_if_statement(Span, StatementNode*, ExpressionNode*, StatementNodeVec*, StatementNode*);
+StatementNode *make_for_statement(Span, StatementNode*, ExpressionNode*, StatementNode*, StatementNodeVec*);
+StatementNode *make_for_range_statement(Span, StringVec*, ExpressionNode*, StatementNodeVec*);
+StatementNode *make_switch_statement(Span, StatementNode*, ExpressionNode*, CaseClauseVec*);
+StatementNode *make_type_switch_statement(Span, StatementNode*, char*, ExpressionNode*, CaseClauseVec*);
+StatementNode *make_break_statement(Span, char*);
+StatementNode *make_continue_statement(Span, char*);
+StatementNode *make_fallthrough_statement(Span);
+StatementNode *make_goto_statement(Span, char*);
+StatementNode *make_labeled_statement(Span, char*, StatementNode*);
+StatementNode *make_return_statement(Span, ExpressionNodeVec*);
+StatementNode *make_defer_statement(Span, StatementNode*);
+StatementNode *make_go_statement(Span, StatementNode*);
+StatementNode *make_send_statement(Span, ExpressionNode*, ExpressionNode*);
+StatementNode *make_select_statement(Span, CaseClauseVec*);
+
+AstKindNode *make_identifier_kind(Span, char*);
+AstKindNode *make_slice_kind(Span, AstKindNode*);
+AstKindNode *make_array_kind(Span, AstKindNode*, char*);
+AstKindNode *make_struct_kind(Span, FieldVec*);
+AstKindNode *make_map_kind(Span, AstKindNode*, AstKindNode*);
+AstKindNode *make_function_kind(Span, FieldVec*, FieldVec*);
+AstKindNode *make_pointer_kind(Span, AstKindNode*);
+AstKindNode *make_interface_kind(Span, FieldVec*);
+AstKindNode *make_chan_kind(Span, AstKindNode*);
+
+Field *make_field(Span, StringVec*, AstKindNode*);
+Field *make_result_field(Span, char*, AstKindNode*);
+Field *make_variadic_field(Span, StringVec*, AstKindNode*);
+Field *make_variadic_result_field(Span, char*, AstKindNode*);
+Receiver *make_receiver(Span, char*, AstKindNode*, int);
+
+CaseClause *make_case_clause(Span, ExpressionNodeVec*, StatementNodeVec*);
+CaseClause *make_type_case_clause(Span, AstKindNodeVec*, StatementNodeVec*);
+CaseClause *make_comm_clause(Span, StatementNode*, StatementNodeVec*);
+VarSpec *make_var_spec(Span, StringVec*, AstKindNode*, ExpressionNodeVec*);
+TypeSpec *make_type_spec(Span, char*, AstKindNode*);
+KeyedElement *make_keyed_element(Span, ExpressionNode*, ExpressionNode*);
 
@@ -1,4 +1,6 @@
-use kind::*;
+use crate::kind::*;
+use crate::span::{Source, Span};
+use std::rc::Rc;
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -41,23 +43,35 @@ pub enum UnaryOperator {
 pub enum AstKindVariant {
     Identifier { name: String },
     Slice { base: Box<AstKindNode> },
+    // The size is `...` in the kind of an array literal, which is given its number of elements
     Array { base: Box<AstKindNode>, size: String },
     Struct { fields: Vec<Field> },
+    Map { key: Box<AstKindNode>, value: Box<AstKindNode> },
+    // The parameters and results can be unnamed, like the results of a function declaration
+    Function { parameters: Vec<Field>, results: Vec<Field> },
+    Pointer { base: Box<AstKindNode> },
+    // Each method is a field with the name of the method and its function kind
+    Interface { methods: Vec<Field> },
+    Chan { base: Box<AstKindNode> },
 }
 
 #[derive(Debug)]
 pub struct AstKindNode {
-    pub line_number: u32,
-    pub variant: AstKindVariant
+    pub span: Span,
+    pub variant: AstKindVariant,
+    pub evaluated_kind: Kind
 }
 
-// This is either the field of a struct or a list of parameters declared with the same type for a
-// function
+// This is either the field of a struct, a list of parameters declared with the same type for a
+// function or a function result. An embedded struct field has no identifiers and is named after
+// its type
 #[derive(Debug)]
 pub struct Field {
-    pub line_number: u32,
+    pub span: Span,
     pub identifiers: Vec<String>,
     pub kind: Box<AstKindNode>,
+    /// A final parameter written `...T`, which is given the trailing arguments of a call as a []T
+    pub variadic: bool,
 }
 
 #[derive(Debug)]
@@ -81,27 +95,133 @@ pub enum ExpressionVariant {
         primary: Box<Expression>,
         name: String,
     },
+    // `a[low:high]` or `a[low:high:max]`. The indices left out are None, and only the low one
+    // can be left out of a full slice expression.
+    Slice {
+        primary: Box<Expression>,
+        low: Option<Box<Expression>>,
+        high: Option<Box<Expression>>,
+        max: Option<Box<Expression>>,
+    },
     FunctionCall {
         primary: Box<Expression>,
         arguments: Vec<Expression>,
+        // `f(a, s...)`, which passes the slice as the variadic parameter instead of packing the
+        // trailing arguments into one
+        spread: bool,
     },
     Append {
         lhs: Box<Expression>,
         rhs: Box<Expression>,
     },
+    Len { expr: Box<Expression> },
+    Cap { expr: Box<Expression> },
     TypeCast { name:String, expr: Box<Expression> },
+    // The arguments after the type, like the size hint of a map
+    Make {
+        kind: Box<AstKindNode>,
+        arguments: Vec<Expression>,
+    },
+    Delete {
+        map: Box<Expression>,
+        key: Box<Expression>,
+    },
+    New { kind: Box<AstKindNode> },
+    // Stops the function and its callers, running their deferred calls, with a runtime panic
+    Panic { expr: Box<Expression> },
+    // `<-ch`, which blocks the goroutine until a value is sent or the channel is closed
+    Receive { channel: Box<Expression> },
+    Close { channel: Box<Expression> },
+    // Copies the elements of `src` that fit into `dst`, and gives how many there were
+    Copy {
+        dst: Box<Expression>,
+        src: Box<Expression>,
+    },
+    Dereference { expr: Box<Expression> },
+    AddressOf { expr: Box<Expression> },
+    // The kind is left out for literals inside another literal, like `{1, 2}` in
+    // `[]point{{1, 2}}`. The typechecker gives every element of a struct, array or slice literal
+    // its key: the field name, or the index as an int literal.
+    CompositeLiteral {
+        kind: Option<Box<AstKindNode>>,
+        elements: Vec<KeyedElement>,
+    },
+    // `x.(T)`, the value of an interface as the kind T, or as the interface T if it has the
+    // methods of T
+    TypeAssertion {
+        expr: Box<Expression>,
+        kind: Box<AstKindNode>,
+    },
+    // A map index, a type assertion or a receive on the right of an assignment to two values. Put
+    // in place of the index, the assertion or the receive by the typechecker; its value is the
+    // element, the asserted value or the received value, and whether the key was found, the
+    // assertion holds or the value was sent before the channel was closed.
+    CommaOk { expr: Box<Expression> },
+    // The function has no name. It is shared with the closures made from it by the interpreter.
+    FunctionLiteral { function: Rc<Function> },
+    // A selector naming a method of the receiver. Put in place of the selector by the
+    // typechecker; `function` is the function implementing the method, which takes the receiver
+    // as its first argument, or its address for a pointer receiver.
+    Method {
+        receiver: Box<Expression>,
+        name: String,
+        function: String,
+        pointer_receiver: bool,
+    },
+    // A value used as an interface kind, which keeps the kind of the value. Put in place of the
+    // value by the typechecker; an interface used as another interface keeps its own value.
+    ToInterface { expr: Box<Expression> },
+    // A selector naming a method of an interface, found from the kind of the value it holds. Put
+    // in place of the selector by the typechecker.
+    InterfaceMethod {
+        receiver: Box<Expression>,
+        name: String,
+    },
+    // The predeclared `nil`. Put in place of the identifier by the typechecker, which gives it
+    // the pointer, function or interface kind it is used as.
+    Nil,
+}
+
+#[derive(Debug)]
+pub struct KeyedElement {
+    pub span: Span,
+    pub key: Option<Expression>,
+    pub value: Expression,
 }
 
 #[derive(Debug)]
 pub struct Expression {
-    pub line_number: u32,
+    pub span: Span,
     pub kind: Kind,
     pub variant: ExpressionVariant,
 }
 
+impl Expression {
+    /// The channel of the receive expression in the received expression of a select case
+    pub fn received_channel(&self) -> &Expression {
+        match self.variant {
+            ExpressionVariant::Receive { ref channel } => channel,
+            ExpressionVariant::CommaOk { ref expr } | ExpressionVariant::ToInterface { ref expr } => {
+                expr.received_channel()
+            }
+            _ => panic!("A select case that isn't a send must be a receive"),
+        }
+    }
+
+    /// For the typechecked function of a call, the number of arguments given before the ones
+    /// packed into a slice for its variadic parameter. None if the arguments are passed as they
+    /// are, because the function isn't variadic or the call spreads a slice.
+    pub fn packed_arguments(&self, spread: bool) -> Option<usize> {
+        match self.kind.resolve() {
+            Kind::Function(ref params, _, true) if !spread => Some(params.len() - 1),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct VarSpec {
-    pub line_number: u32,
+    pub span: Span,
     pub names: Vec<String>,
     pub kind: Option<Box<AstKindNode>>,
     pub rhs: Option<Vec<Expression>>,
@@ -110,7 +230,7 @@ pub struct VarSpec {
 
 #[derive(Debug)]
 pub struct TypeSpec {
-    pub line_number: u32,
+    pub span: Span,
     pub name: String,
     pub kind: Box<AstKindNode>,
 }
@@ -119,13 +239,28 @@ pub struct TypeSpec {
 pub enum SwitchCase {
     Default,
     Cases(Vec<Expression>),
+    // The kinds of a clause of a type switch, where `nil` is the kind of a nil interface
+    Kinds(Vec<AstKindNode>),
+    // The send or receive of a clause of a select statement. It is a send statement, an
+    // expression statement of a receive, or an assignment or short variable declaration with a
+    // single receive on the right.
+    Comm(Box<StatementNode>),
 }
 
 #[derive(Debug)]
 pub struct CaseClause {
-    pub line_number: u32,
+    pub span: Span,
     pub switch_case: SwitchCase,
     pub statements: Vec<StatementNode>,
+    // The variable that a type switch declares in the clause, renamed by the typechecker
+    pub binding: Option<String>,
+}
+
+impl CaseClause {
+    /// Whether the clause ends in `fallthrough`, continuing with the body of the next clause
+    pub fn falls_through(&self) -> bool {
+        matches!(self.statements.last(), Some(&StatementNode { variant: StatementVariant::Fallthrough, .. }))
+    }
 }
 
 #[derive(Debug)]
@@ -133,6 +268,7 @@ pub enum StatementVariant {
     Empty,
     Block(Vec<StatementNode>),
     Expression(Box<Expression>),
+    // A single expression on the right can give all the values, like a comma-ok lookup
     Assignment {
         lhs: Vec<Expression>,
         rhs: Vec<Expression>,
@@ -143,6 +279,9 @@ pub enum StatementVariant {
         operator: BinaryOperator,
     },
     VarDeclarations { declarations: Vec<VarSpec> },
+    // A spec without expressions repeats the previous ones. The typechecker folds every use of
+    // a constant, so nothing needs to be done for these after typechecking.
+    ConstDeclarations { declarations: Vec<VarSpec> },
     TypeDeclarations { declarations: Vec<TypeSpec> },
     ShortVariableDeclaration {
         identifier_list: Vec<String>,
@@ -167,46 +306,130 @@ pub enum StatementVariant {
         post: Box<StatementNode>,
         body: Vec<StatementNode>,
     },
+    // The iteration variables are declared with `:=` and can be left out or be `_`
+    ForRange {
+        key: Option<String>,
+        value: Option<String>,
+        expr: Box<Expression>,
+        body: Vec<StatementNode>,
+    },
     Switch {
         init: Box<StatementNode>,
         expr: Option<Box<Expression>>,
         body: Vec<CaseClause>,
     },
-    Break,
-    Continue,
-    Return(Option<Box<Expression>>)
+    // `switch v := x.(type)`. Each clause declares the variable of its own, with the kind of its
+    // case if it has a single one and the kind of the interface otherwise.
+    TypeSwitch {
+        init: Box<StatementNode>,
+        binding: Option<String>,
+        expr: Box<Expression>,
+        body: Vec<CaseClause>,
+    },
+    // The label of the loop or switch, if it isn't the innermost one
+    Break(Option<String>),
+    Continue(Option<String>),
+    // Only valid as the last statement of a case clause other than the final one
+    Fallthrough,
+    Goto(String),
+    Labeled {
+        label: String,
+        statement: Box<StatementNode>,
+    },
+    Return(Vec<Expression>),
+    // The deferred call is an expression statement, or a print or println statement
+    Defer(Box<StatementNode>),
+    // Like a deferred call, but run in a new goroutine
+    Go(Box<StatementNode>),
+    Send {
+        channel: Box<Expression>,
+        value: Box<Expression>,
+    },
+    // The clauses are `SwitchCase::Comm`, or the default clause
+    Select { body: Vec<CaseClause> },
 }
 
 #[derive(Debug)]
 pub struct StatementNode {
-    pub line_number: u32,
+    pub span: Span,
     pub variant: StatementVariant,
 }
 
+impl StatementNode {
+    /// Whether the statement has the label, among the ones it can have
+    pub fn has_label(&self, label: &str) -> bool {
+        match self.variant {
+            StatementVariant::Labeled { label: ref l, ref statement } => l == label || statement.has_label(label),
+            _ => false,
+        }
+    }
+
+    /// The receive expression of a select case that doesn't send, which the typechecker may have
+    /// put in a comma-ok expression or converted to an interface
+    pub fn received_expression(&self) -> &Expression {
+        match self.variant {
+            StatementVariant::Expression(ref expr) => expr,
+            StatementVariant::Assignment { ref rhs, .. } => &rhs[0],
+            StatementVariant::ShortVariableDeclaration { ref expression_list, .. } => &expression_list[0],
+            _ => panic!("A select case that isn't a send must be a receive"),
+        }
+    }
+}
+
+// The receiver of a method. Unnamed receivers are called `_`.
+#[derive(Debug)]
+pub struct Receiver {
+    pub span: Span,
+    pub name: String,
+    pub kind: Box<AstKindNode>,
+    pub pointer: bool,
+}
+
 #[derive(Debug)]
 pub struct Function {
     pub name: String,
+    pub receiver: Option<Receiver>,
     pub parameters: Vec<Field>,
-    pub return_kind: Option<Box<AstKindNode>>,
+    /// Unnamed results have no identifiers
+    pub results: Vec<Field>,
     pub body: Vec<StatementNode>,
 }
 
+impl Function {
+    /// The name that stack traces give the (typechecked) function, like `main.f`, `main.(*T).m`,
+    /// or `main.func` for a function literal
+    pub fn trace_name(&self) -> String {
+        // Renamed identifiers look like `name·3`
+        let name = self.name.split('·').next().unwrap();
+        match self.receiver {
+            Some(ref r) if r.pointer => format!("main.(*{}).{}", r.kind.evaluated_kind, name),
+            Some(ref r) => format!("main.{}.{}", r.kind.evaluated_kind, name),
+            None if name.is_empty() => "main.func".to_string(),
+            None => format!("main.{}", name),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum TopLevelDeclarationVariant {
     VarDeclarations { declarations: Vec<VarSpec> },
+    ConstDeclarations { declarations: Vec<VarSpec> },
     TypeDeclarations { declarations: Vec<TypeSpec> },
     FunctionDeclaration (Function),
 }
 
 #[derive(Debug)]
 pub struct TopLevelDeclarationNode {
-    pub line_number: u32,
+    pub span: Span,
     pub variant: TopLevelDeclarationVariant
 }
 
 #[repr(C)]
 #[derive(Debug)]
 pub struct Program {
+    pub package_span: Span,
     pub package_name: String,
     pub declarations: Vec<TopLevelDeclarationNode>,
+    /// The file the program was parsed from, kept for the error messages of the later phases
+    pub source: Option<Source>,
 }
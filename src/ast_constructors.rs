@@ -1,11 +1,15 @@
-use ast::*;
-use kind;
-use kind::Kind;
+use crate::ast::*;
+use crate::kind;
+use crate::kind::Kind;
+use std::cell::RefCell;
 use std::ffi::CStr;
+use std::mem;
 use std::os::raw::c_char;
 use std::os::raw::c_int;
+use std::rc::Rc;
 
-use std::process::exit;
+use crate::span::Span;
+use crate::diagnostics::Diagnostic;
 
 
 /// This function turns a C string into a Rust String
@@ -14,6 +18,15 @@ unsafe fn from_c_string(s: *const c_char) -> String {
     CStr::from_ptr(s).to_str().unwrap().into()
 }
 
+/// Like `from_c_string`, but a null pointer (a missing optional name) gives None
+unsafe fn from_c_string_or_none(s: *const c_char) -> Option<String> {
+    if s.is_null() {
+        None
+    } else {
+        Some(from_c_string(s))
+    }
+}
+
 /// This function will be used in C to turn a C string into a Rust String
 #[no_mangle]
 pub extern "C" fn make_string(string: *const c_char) -> *mut String {
@@ -21,6 +34,48 @@ pub extern "C" fn make_string(string: *const c_char) -> *mut String {
 }
 
 
+thread_local! {
+    // Errors can't be returned through the C parser, so they are kept here until it is done
+    static SYNTAX_ERRORS: RefCell<Vec<Diagnostic>> = const { RefCell::new(Vec::new()) };
+}
+
+fn syntax_error(span: Span, message: &str) {
+    SYNTAX_ERRORS.with(|errors| errors.borrow_mut().push(Diagnostic::new(span, message)));
+}
+
+/// Returns the errors found since the last call
+pub fn take_syntax_errors() -> Vec<Diagnostic> {
+    SYNTAX_ERRORS.with(|errors| mem::take(&mut *errors.borrow_mut()))
+}
+
+thread_local! {
+    // The output of the `tokens` mode, one token per line
+    static TOKENS: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// Returns the tokens listed since the last call
+pub fn take_tokens() -> String {
+    TOKENS.with(|tokens| mem::take(&mut *tokens.borrow_mut()))
+}
+
+/// Called by `list_tokens` for each token, `text` being null for tokens that don't carry any
+#[no_mangle]
+pub extern "C" fn add_token(name: *const c_char, text: *const c_char) {
+    let line = if text.is_null() {
+        format!("{}\n", unsafe { from_c_string(name) })
+    } else {
+        format!("{}( {} )\n", unsafe { from_c_string(name) }, unsafe { from_c_string(text) })
+    };
+    TOKENS.with(|tokens| tokens.borrow_mut().push_str(&line));
+}
+
+/// Called by the scanner and the parser on syntax errors so that they are reported the same way
+/// as the other errors
+#[no_mangle]
+pub extern "C" fn report_syntax_error(span: Span, message: *const c_char) {
+    syntax_error(span, &unsafe { from_c_string(message) });
+}
+
 pub unsafe fn from_raw_or_none<T>(t: *mut T) -> Option<Box<T>> {
     if t.is_null() {
         None
@@ -72,6 +127,8 @@ create_vec_functions!(make_top_level_declaration_vec,
 
 create_vec_functions!(make_var_spec_vec, var_spec_vec_push, VarSpec);
 create_vec_functions!(make_type_spec_vec, type_spec_vec_push, TypeSpec);
+create_vec_functions!(make_keyed_element_vec, keyed_element_vec_push, KeyedElement);
+create_vec_functions!(make_kind_vec, kind_vec_push, AstKindNode);
 
 /*
 PROGRAM CONSTRUCTOR
@@ -79,12 +136,14 @@ PROGRAM CONSTRUCTOR
 */
 
 #[no_mangle]
-pub extern "C" fn make_program(pkg: *const c_char,
+pub extern "C" fn make_program(package_span: Span, pkg: *const c_char,
                                    dcls: *mut Vec<TopLevelDeclarationNode>) -> *mut Program {
 
     Box::into_raw(Box::new(Program {
+        package_span,
         package_name: unsafe { from_c_string(pkg) } ,
-        declarations: *unsafe{Box::from_raw(dcls)}
+        declarations: *unsafe{Box::from_raw(dcls)},
+        source: None,
     }))
 }
 
@@ -96,48 +155,135 @@ TOP DECLARATION NODE CONSTRUCTORS
 
 
 /// This is a function that factors out most of the repetition from creating top level declaration nodes
-fn make_top_level_declaration_ptr(line: u32, dcl: TopLevelDeclarationVariant) -> *mut TopLevelDeclarationNode {
+fn make_top_level_declaration_ptr(span: Span, dcl: TopLevelDeclarationVariant) -> *mut TopLevelDeclarationNode {
     Box::into_raw(Box::new(TopLevelDeclarationNode {
-        line_number: line ,
+        span,
         variant: dcl
     }))
 }
 
 #[no_mangle]
-pub extern "C" fn make_var_top_level_declaration(line: u32,
+pub extern "C" fn make_var_top_level_declaration(span: Span,
                                                  decls:  *mut Vec<VarSpec>) -> *mut TopLevelDeclarationNode {
     make_top_level_declaration_ptr(
-        line,
+        span,
         TopLevelDeclarationVariant::VarDeclarations{ declarations:  *unsafe { Box::from_raw(decls) }}
     )
 }
 
 #[no_mangle]
-pub extern "C" fn make_type_top_level_declaration(line: u32,
+pub extern "C" fn make_const_top_level_declaration(span: Span,
+                                                   decls: *mut Vec<VarSpec>) -> *mut TopLevelDeclarationNode {
+    let declarations = *unsafe { Box::from_raw(decls) };
+    check_const_specs(&declarations);
+    make_top_level_declaration_ptr(
+        span,
+        TopLevelDeclarationVariant::ConstDeclarations{ declarations }
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn make_type_top_level_declaration(span: Span,
                                                  decls:  *mut Vec<TypeSpec>) -> *mut TopLevelDeclarationNode {
     make_top_level_declaration_ptr(
-        line,
+        span,
         TopLevelDeclarationVariant::TypeDeclarations{ declarations:  *unsafe { Box::from_raw(decls) }}
     )
 }
 
 #[no_mangle]
-pub extern "C" fn make_function_top_level_declaration(line: u32,
+pub extern "C" fn make_function_top_level_declaration(span: Span,
                                                       name:  *const c_char,
                                                       params: *mut Vec<Field>,
-                                                      return_kind: *mut AstKindNode,
+                                                      results: *mut Vec<Field>,
                                                       body: *mut Vec<StatementNode>) -> *mut TopLevelDeclarationNode {
     make_top_level_declaration_ptr(
-        line,
+        span,
         TopLevelDeclarationVariant::FunctionDeclaration(Function {
             name: unsafe { from_c_string(name) },
-            parameters: *unsafe { Box::from_raw(params) },
-            return_kind: unsafe { from_raw_or_none(return_kind) },
+            receiver: None,
+            parameters: checked_parameters(*unsafe { Box::from_raw(params) }),
+            results: checked_results(group_results(*unsafe { Box::from_raw(results) })),
             body: *unsafe { Box::from_raw(body) }
         })
     )
 }
 
+#[no_mangle]
+pub extern "C" fn make_method_top_level_declaration(span: Span,
+                                                    receiver: *mut Receiver,
+                                                    name:  *const c_char,
+                                                    params: *mut Vec<Field>,
+                                                    results: *mut Vec<Field>,
+                                                    body: *mut Vec<StatementNode>) -> *mut TopLevelDeclarationNode {
+    make_top_level_declaration_ptr(
+        span,
+        TopLevelDeclarationVariant::FunctionDeclaration(Function {
+            name: unsafe { from_c_string(name) },
+            receiver: Some(*unsafe { Box::from_raw(receiver) }),
+            parameters: checked_parameters(*unsafe { Box::from_raw(params) }),
+            results: checked_results(group_results(*unsafe { Box::from_raw(results) })),
+            body: *unsafe { Box::from_raw(body) }
+        })
+    )
+}
+
+/// The parser produces one field per entry of a result list or of the parameters of a function
+/// type, named or not. As in Go, if any entry is named then the unnamed entries are names sharing
+/// the type of the next named entry.
+fn group_results(results: Vec<Field>) -> Vec<Field> {
+    if results.iter().all(|field| field.identifiers.is_empty()) {
+        return results;
+    }
+
+    let mut grouped = vec![];
+    let mut names = vec![];
+    let mut first_unnamed: Option<Span> = None;
+    for mut field in results {
+        if field.identifiers.is_empty() {
+            match field.kind.variant {
+                AstKindVariant::Identifier{ref name} => names.push(name.clone()),
+                _ => {
+                    syntax_error(field.span, "mixed named and unnamed parameters.");
+                    continue;
+                }
+            }
+            first_unnamed = first_unnamed.or(Some(field.span));
+        } else {
+            names.append(&mut field.identifiers);
+            if let Some(first) = first_unnamed.take() {
+                field.span.start = first.start;
+            }
+            field.identifiers = mem::take(&mut names);
+            grouped.push(field);
+        }
+    }
+
+    if let Some(span) = first_unnamed {
+        syntax_error(span, "mixed named and unnamed parameters.");
+    }
+    grouped
+}
+
+/// Only the final parameter of a function can be variadic, and it has a single name
+fn checked_parameters(parameters: Vec<Field>) -> Vec<Field> {
+    for (i, field) in parameters.iter().enumerate() {
+        if field.variadic && (i + 1 < parameters.len() || field.identifiers.len() > 1) {
+            syntax_error(field.span, "can only use ... with final parameter in list.");
+        }
+    }
+    parameters
+}
+
+fn checked_results(results: Vec<Field>) -> Vec<Field> {
+    for field in &results {
+        if field.variadic {
+            syntax_error(field.span, "cannot use ... in result list.");
+        }
+    }
+    results
+}
+
 
 
 /*
@@ -146,19 +292,19 @@ EXPRESSION NODE CONSTRUCTORS
 */
 
 /// This is a function that factors out most of the repetition from creating expression nodes
-fn make_expr_ptr(line: u32, expr: ExpressionVariant) -> *mut Expression {
+fn make_expr_ptr(span: Span, expr: ExpressionVariant) -> *mut Expression {
     Box::into_raw(Box::new(Expression {
-        line_number: line,
+        span,
         variant: expr,
         kind: kind::Kind::Undefined,
     }))
 }
 
 #[no_mangle]
-pub extern "C" fn make_identifier_expression(line: u32, string: *const c_char) -> *mut Expression {
+pub extern "C" fn make_identifier_expression(span: Span, string: *const c_char) -> *mut Expression {
     let name: String = unsafe { from_c_string(string) };
     make_expr_ptr(
-        line,
+        span,
         ExpressionVariant::Identifier { name: name.clone(), original_name: name }
     )
 }
@@ -166,26 +312,27 @@ pub extern "C" fn make_identifier_expression(line: u32, string: *const c_char) -
 
 #[no_mangle]
 pub extern "C" fn make_literal_expression(
-    line: u32,
+    span: Span,
     string: *const c_char,
     kind: kind::BasicKind,
 ) -> *mut Expression {
     Box::into_raw(Box::new(Expression {
-        line_number: line,
+        span,
         variant: ExpressionVariant::RawLiteral { value: unsafe { from_c_string(string) } },
-        kind: kind::Kind::Basic(kind),
+        // Rune literals keep their quotes, which tells them apart from the other int32 literals
+        kind: kind::Kind::Basic(kind.typed()),
     }))
 }
 
 #[no_mangle]
 pub extern "C" fn make_append_expression(
-    line: u32,
+    span: Span,
     lhs: *mut Expression,
     rhs: *mut Expression,
 ) -> *mut Expression {
 
     make_expr_ptr(
-        line,
+        span,
         ExpressionVariant::Append {
             lhs: unsafe { Box::from_raw(lhs) },
             rhs: unsafe { Box::from_raw(rhs) },
@@ -193,17 +340,122 @@ pub extern "C" fn make_append_expression(
     )
 }
 
+#[no_mangle]
+pub extern "C" fn make_len_expression(span: Span, expr: *mut Expression) -> *mut Expression {
+    make_expr_ptr(span, ExpressionVariant::Len { expr: unsafe { Box::from_raw(expr) } })
+}
+
+#[no_mangle]
+pub extern "C" fn make_cap_expression(span: Span, expr: *mut Expression) -> *mut Expression {
+    make_expr_ptr(span, ExpressionVariant::Cap { expr: unsafe { Box::from_raw(expr) } })
+}
+
+#[no_mangle]
+pub extern "C" fn make_make_expression(span: Span, kind: *mut AstKindNode,
+                                       arguments: *mut Vec<Expression>) -> *mut Expression {
+    make_expr_ptr(
+        span,
+        ExpressionVariant::Make {
+            kind: unsafe { Box::from_raw(kind) },
+            arguments: *unsafe { Box::from_raw(arguments) },
+        },
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn make_delete_expression(span: Span, map: *mut Expression,
+                                         key: *mut Expression) -> *mut Expression {
+    make_expr_ptr(
+        span,
+        ExpressionVariant::Delete {
+            map: unsafe { Box::from_raw(map) },
+            key: unsafe { Box::from_raw(key) },
+        },
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn make_new_expression(span: Span, kind: *mut AstKindNode) -> *mut Expression {
+    make_expr_ptr(span, ExpressionVariant::New { kind: unsafe { Box::from_raw(kind) } })
+}
+
+#[no_mangle]
+pub extern "C" fn make_panic_expression(span: Span, expr: *mut Expression) -> *mut Expression {
+    make_expr_ptr(span, ExpressionVariant::Panic { expr: unsafe { Box::from_raw(expr) } })
+}
+
+#[no_mangle]
+pub extern "C" fn make_receive_expression(span: Span, channel: *mut Expression) -> *mut Expression {
+    make_expr_ptr(span, ExpressionVariant::Receive { channel: unsafe { Box::from_raw(channel) } })
+}
+
+#[no_mangle]
+pub extern "C" fn make_close_expression(span: Span, channel: *mut Expression) -> *mut Expression {
+    make_expr_ptr(span, ExpressionVariant::Close { channel: unsafe { Box::from_raw(channel) } })
+}
+
+#[no_mangle]
+pub extern "C" fn make_copy_expression(span: Span, dst: *mut Expression, src: *mut Expression)
+    -> *mut Expression {
+    make_expr_ptr(span, ExpressionVariant::Copy {
+        dst: unsafe { Box::from_raw(dst) },
+        src: unsafe { Box::from_raw(src) },
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn make_composite_literal_expression(span: Span, kind: *mut AstKindNode,
+                                                    elements: *mut Vec<KeyedElement>) -> *mut Expression {
+    make_expr_ptr(
+        span,
+        ExpressionVariant::CompositeLiteral {
+            kind: unsafe { from_raw_or_none(kind) },
+            elements: *unsafe { Box::from_raw(elements) },
+        },
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn make_function_literal_expression(span: Span, params: *mut Vec<Field>,
+                                                   results: *mut Vec<Field>,
+                                                   body: *mut Vec<StatementNode>) -> *mut Expression {
+    make_expr_ptr(
+        span,
+        ExpressionVariant::FunctionLiteral {
+            function: Rc::new(Function {
+                receiver: None,
+                name: String::new(),
+                parameters: checked_parameters(*unsafe { Box::from_raw(params) }),
+                results: checked_results(group_results(*unsafe { Box::from_raw(results) })),
+                body: *unsafe { Box::from_raw(body) },
+            }),
+        },
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn make_keyed_element(span: Span, key: *mut Expression, value: *mut Expression)
+    -> *mut KeyedElement
+{
+    Box::into_raw( Box::new(
+            KeyedElement {
+                span,
+                key: unsafe{ from_raw_or_none(key) }.map(|key| *key),
+                value: *unsafe{ Box::from_raw(value) },
+            }))
+}
+
 
 #[no_mangle]
 pub extern "C" 
 fn make_binary_operation_expression(
-    line: u32,
+    span: Span,
     operator: BinaryOperator,
     left: *mut Expression,
     right: *mut Expression,
 ) -> *mut Expression {
     make_expr_ptr(
-        line,
+        span,
         ExpressionVariant::BinaryOperation {
             op: operator,
             lhs: unsafe { Box::from_raw(left) },
@@ -214,9 +466,9 @@ fn make_binary_operation_expression(
 
 #[no_mangle]
 pub extern "C" 
-fn make_unary_operation_expression(line: u32, operator: UnaryOperator, right: *mut Expression) -> *mut Expression {
+fn make_unary_operation_expression(span: Span, operator: UnaryOperator, right: *mut Expression) -> *mut Expression {
     make_expr_ptr(
-        line,
+        span,
         ExpressionVariant::UnaryOperation {
             op: operator,
             rhs: unsafe { Box::from_raw(right)},
@@ -224,11 +476,21 @@ fn make_unary_operation_expression(line: u32, operator: UnaryOperator, right: *m
     )
 }
 
+#[no_mangle]
+pub extern "C" fn make_dereference_expression(span: Span, expr: *mut Expression) -> *mut Expression {
+    make_expr_ptr(span, ExpressionVariant::Dereference { expr: unsafe { Box::from_raw(expr) } })
+}
+
+#[no_mangle]
+pub extern "C" fn make_address_of_expression(span: Span, expr: *mut Expression) -> *mut Expression {
+    make_expr_ptr(span, ExpressionVariant::AddressOf { expr: unsafe { Box::from_raw(expr) } })
+}
+
 #[no_mangle]
 pub extern "C" 
-fn make_index_expression(line: u32, p: *mut Expression, i: *mut Expression) -> *mut Expression {
+fn make_index_expression(span: Span, p: *mut Expression, i: *mut Expression) -> *mut Expression {
     make_expr_ptr(
-        line,
+        span,
         ExpressionVariant::Index {
             primary: unsafe{ Box::from_raw(p) },
             index: unsafe{ Box::from_raw(i) },
@@ -236,11 +498,26 @@ fn make_index_expression(line: u32, p: *mut Expression, i: *mut Expression) -> *
     )
 }
 
+#[no_mangle]
+pub extern "C"
+fn make_slice_expression(span: Span, p: *mut Expression, low: *mut Expression,
+                         high: *mut Expression, max: *mut Expression) -> *mut Expression {
+    make_expr_ptr(
+        span,
+        ExpressionVariant::Slice {
+            primary: unsafe { Box::from_raw(p) },
+            low: unsafe { from_raw_or_none(low) },
+            high: unsafe { from_raw_or_none(high) },
+            max: unsafe { from_raw_or_none(max) },
+        },
+    )
+}
+
 #[no_mangle]
 pub extern "C" 
-fn make_selector_expression(line: u32, p: *mut Expression, str: *const c_char) -> *mut Expression {
+fn make_selector_expression(span: Span, p: *mut Expression, str: *const c_char) -> *mut Expression {
     make_expr_ptr(
-        line,
+        span,
         ExpressionVariant::Selector {
             primary: unsafe { Box::from_raw(p) },
             name: unsafe { from_c_string(str) },
@@ -251,15 +528,42 @@ fn make_selector_expression(line: u32, p: *mut Expression, str: *const c_char) -
 #[no_mangle]
 pub extern "C" 
 fn make_function_call_expression(
-    line: u32,
+    span: Span,
     p: *mut Expression,
     args: *mut Vec<Expression>
 ) -> *mut Expression {
     make_expr_ptr(
-        line,
+        span,
         ExpressionVariant::FunctionCall {
             primary: unsafe{ Box::from_raw(p) },
             arguments: *unsafe{ Box::from_raw(args) },
+            spread: false,
+        },
+    )
+}
+
+/// A call whose last argument is a slice spread with `...` as the variadic parameter
+#[no_mangle]
+pub extern "C" fn make_spread_call_expression(span: Span, p: *mut Expression, args: *mut Vec<Expression>)
+    -> *mut Expression {
+    make_expr_ptr(
+        span,
+        ExpressionVariant::FunctionCall {
+            primary: unsafe{ Box::from_raw(p) },
+            arguments: *unsafe{ Box::from_raw(args) },
+            spread: true,
+        },
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn make_type_assertion_expression(span: Span, expr: *mut Expression,
+                                                 kind: *mut AstKindNode) -> *mut Expression {
+    make_expr_ptr(
+        span,
+        ExpressionVariant::TypeAssertion {
+            expr: unsafe{ Box::from_raw(expr) },
+            kind: unsafe{ Box::from_raw(kind) },
         },
     )
 }
@@ -273,60 +577,57 @@ STATEMENT NODE CONSTRUCTORS
 */
 
 /// This is a function that factors out most of the repetition from creating statement nodes
-fn make_statement_ptr(line: u32, stmt: StatementVariant) -> *mut StatementNode {
+fn make_statement_ptr(span: Span, stmt: StatementVariant) -> *mut StatementNode {
     Box::into_raw(Box::new(StatementNode {
-        line_number: line ,
+        span,
         variant: stmt
     }))
 }
 
 
 #[no_mangle]
-pub extern "C" fn make_empty_statement(line: u32) -> *mut StatementNode {
+pub extern "C" fn make_empty_statement(span: Span) -> *mut StatementNode {
     make_statement_ptr(
-        line,
+        span,
         StatementVariant::Empty
     )
 }
 
 #[no_mangle]
-pub extern "C" fn make_block_statement(line: u32, stmts: *mut Vec<StatementNode>) -> *mut StatementNode {
+pub extern "C" fn make_block_statement(span: Span, stmts: *mut Vec<StatementNode>) -> *mut StatementNode {
     make_statement_ptr(
-        line,
+        span,
         StatementVariant::Block(*unsafe {Box::from_raw(stmts)})
     )
 }
 
 #[no_mangle]
-pub extern "C" fn make_expression_statement(line: u32, expr: *mut Expression) -> *mut StatementNode {
+pub extern "C" fn make_expression_statement(span: Span, expr: *mut Expression) -> *mut StatementNode {
     let expr  = unsafe {Box::from_raw(expr)};
     match expr.variant {
-        ExpressionVariant::FunctionCall{..} => {
-            make_statement_ptr(
-                line,
-                StatementVariant::Expression(expr)
-                )
-        }
-        _ => {
-            eprintln!("Error: line {}: expression statements can only be function calls", line);
-            exit(1)
-        }
+        ExpressionVariant::FunctionCall{..} | ExpressionVariant::Delete{..} |
+        ExpressionVariant::Panic{..} | ExpressionVariant::Close{..} |
+        ExpressionVariant::Receive{..} | ExpressionVariant::Copy{..} => {},
+        _ => syntax_error(expr.span, "expression statements can only be function calls"),
     }
+    make_statement_ptr(
+        span,
+        StatementVariant::Expression(expr)
+        )
 }
 
 #[no_mangle]
-pub extern "C" fn make_assignment_statement(line: u32, lhs: *mut Vec<Expression>,
+pub extern "C" fn make_assignment_statement(span: Span, lhs: *mut Vec<Expression>,
 rhs: *mut Vec<Expression>) -> *mut StatementNode {
 
     let lhs = *unsafe{Box::from_raw(lhs)};
     let rhs = *unsafe{Box::from_raw(rhs)};
-    if lhs.len() != rhs.len() {
-        eprintln!("Error: line {}: lhs and rhs of assignment have a different amount of elements.", line);
-        exit(1);
+    if lhs.len() != rhs.len() && rhs.len() != 1 {
+        syntax_error(span, "lhs and rhs of assignment have a different amount of elements.");
     }
 
     make_statement_ptr(
-        line,
+        span,
         StatementVariant::Assignment {
             lhs,
             rhs
@@ -335,11 +636,11 @@ rhs: *mut Vec<Expression>) -> *mut StatementNode {
 }
 
 #[no_mangle]
-pub extern "C" fn make_op_assignment_statement(line: u32, lhs: *mut Expression,
+pub extern "C" fn make_op_assignment_statement(span: Span, lhs: *mut Expression,
                                                rhs: *mut Expression,
                                                op: BinaryOperator) -> *mut StatementNode {
     make_statement_ptr(
-        line,
+        span,
         StatementVariant::OpAssignment {
             lhs: unsafe{Box::from_raw(lhs)},
             rhs: unsafe{Box::from_raw(rhs)},
@@ -349,10 +650,10 @@ pub extern "C" fn make_op_assignment_statement(line: u32, lhs: *mut Expression,
 }
 
 #[no_mangle]
-pub extern "C" fn make_var_declaration_statement(line: u32,
+pub extern "C" fn make_var_declaration_statement(span: Span,
                                                  decls: *mut Vec<VarSpec>) -> *mut StatementNode {
     make_statement_ptr(
-        line,
+        span,
         StatementVariant::VarDeclarations {
             declarations: *unsafe{Box::from_raw(decls)}
         }
@@ -360,9 +661,19 @@ pub extern "C" fn make_var_declaration_statement(line: u32,
 }
 
 #[no_mangle]
-pub extern "C" fn make_type_declaration_statement(line: u32, decls: *mut Vec<TypeSpec>) -> *mut StatementNode {
+pub extern "C" fn make_const_declaration_statement(span: Span, decls: *mut Vec<VarSpec>) -> *mut StatementNode {
+    let declarations = *unsafe{Box::from_raw(decls)};
+    check_const_specs(&declarations);
+    make_statement_ptr(
+        span,
+        StatementVariant::ConstDeclarations { declarations }
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn make_type_declaration_statement(span: Span, decls: *mut Vec<TypeSpec>) -> *mut StatementNode {
     make_statement_ptr(
-        line,
+        span,
         StatementVariant::TypeDeclarations {
             declarations: *unsafe{Box::from_raw(decls)}
         }
@@ -370,18 +681,17 @@ pub extern "C" fn make_type_declaration_statement(line: u32, decls: *mut Vec<Typ
 }
 
 #[no_mangle]
-pub extern "C" fn make_short_var_declaration_statement(line: u32, ids: *mut Vec<String>,
+pub extern "C" fn make_short_var_declaration_statement(span: Span, ids: *mut Vec<String>,
                                                        exprs: *mut Vec<Expression> ) -> *mut StatementNode {
 
     let lhs = *unsafe{Box::from_raw(ids)};
     let rhs = *unsafe{Box::from_raw(exprs)};
-    if lhs.len() != rhs.len() {
-        eprintln!("Error: line {}: lhs and rhs of short declaration have a different number of elements.",line);
-        exit(1);
+    if lhs.len() != rhs.len() && rhs.len() != 1 {
+        syntax_error(span, "lhs and rhs of short declaration have a different number of elements.");
     }
 
     make_statement_ptr(
-        line,
+        span,
         StatementVariant::ShortVariableDeclaration {
             identifier_list: lhs,
             expression_list: rhs,
@@ -391,10 +701,10 @@ pub extern "C" fn make_short_var_declaration_statement(line: u32, ids: *mut Vec<
 }
 
 #[no_mangle]
-pub extern "C" fn make_inc_dec_statement(line: u32, is_dec: c_int, expr: *mut Expression ) -> *mut StatementNode {
+pub extern "C" fn make_inc_dec_statement(span: Span, is_dec: c_int, expr: *mut Expression ) -> *mut StatementNode {
 
     make_statement_ptr(
-        line,
+        span,
         StatementVariant::IncDec {
             is_dec: if is_dec == 0 {false} else {true},
             expr: unsafe{Box::from_raw(expr)}
@@ -403,9 +713,9 @@ pub extern "C" fn make_inc_dec_statement(line: u32, is_dec: c_int, expr: *mut Ex
 }
 
 #[no_mangle]
-pub extern "C" fn make_print_statement(line: u32, exprs: *mut Vec<Expression> ) -> *mut StatementNode {
+pub extern "C" fn make_print_statement(span: Span, exprs: *mut Vec<Expression> ) -> *mut StatementNode {
     make_statement_ptr(
-        line,
+        span,
         StatementVariant::Print {
             exprs: *unsafe{Box::from_raw(exprs)}
         }
@@ -413,9 +723,9 @@ pub extern "C" fn make_print_statement(line: u32, exprs: *mut Vec<Expression> )
 }
 
 #[no_mangle]
-pub extern "C" fn make_println_statement(line: u32, exprs: *mut Vec<Expression> ) -> *mut StatementNode {
+pub extern "C" fn make_println_statement(span: Span, exprs: *mut Vec<Expression> ) -> *mut StatementNode {
     make_statement_ptr(
-        line,
+        span,
         StatementVariant::Println {
             exprs: *unsafe{Box::from_raw(exprs)}
         }
@@ -423,13 +733,13 @@ pub extern "C" fn make_println_statement(line: u32, exprs: *mut Vec<Expression>
 }
 
 #[no_mangle]
-pub extern "C" fn make_if_statement(line: u32,
+pub extern "C" fn make_if_statement(span: Span,
                                     init: *mut StatementNode,
                                     cond: *mut Expression,
                                     if_branch: *mut Vec<StatementNode>,
                                     else_branch: *mut StatementNode ) -> *mut StatementNode {
     make_statement_ptr(
-        line,
+        span,
         StatementVariant::If {
             init: unsafe{Box::from_raw(init)},
             condition: unsafe{Box::from_raw(cond)},
@@ -440,42 +750,65 @@ pub extern "C" fn make_if_statement(line: u32,
 }
 
 #[no_mangle]
-pub extern "C" fn make_for_statement(line: u32,
+pub extern "C" fn make_for_statement(span: Span,
                                      init: *mut StatementNode,
                                      cond: *mut Expression,
                                      post: *mut StatementNode,
                                      body: *mut Vec<StatementNode> ) -> *mut StatementNode {
     let post = unsafe{Box::from_raw(post)};
     if let StatementVariant::ShortVariableDeclaration{..} =  post.variant {
-        eprintln!("Error: line {}: cannot have short variable declaration in the post condition of loop", line);
-        exit(1)
-    }
-    else {
-        make_statement_ptr(
-            line,
-            StatementVariant::For {
-                init: unsafe{Box::from_raw(init)},
-                condition: unsafe{from_raw_or_none(cond)},
-                post: post,
-                body: *unsafe{Box::from_raw(body)}
-            }
-        )
+        syntax_error(post.span, "cannot have short variable declaration in the post condition of loop");
     }
+    make_statement_ptr(
+        span,
+        StatementVariant::For {
+            init: unsafe{Box::from_raw(init)},
+            condition: unsafe{from_raw_or_none(cond)},
+            post,
+            body: *unsafe{Box::from_raw(body)}
+        }
+    )
 }
 
 
 #[no_mangle]
-pub extern "C" fn make_switch_statement(line: u32,
+pub extern "C" fn make_for_range_statement(span: Span,
+                                           ids: *mut Vec<String>,
+                                           expr: *mut Expression,
+                                           body: *mut Vec<StatementNode> ) -> *mut StatementNode {
+    let mut ids = match unsafe{from_raw_or_none(ids)} {
+        Some(ids) => *ids,
+        None => Vec::new(),
+    };
+    if ids.len() > 2 {
+        syntax_error(span, "range clause permits at most two iteration variables.");
+    }
+    let value = if ids.len() >= 2 { Some(ids.remove(1)) } else { None };
+    let key = if !ids.is_empty() { Some(ids.remove(0)) } else { None };
+
+    make_statement_ptr(
+        span,
+        StatementVariant::ForRange {
+            key,
+            value,
+            expr: unsafe{Box::from_raw(expr)},
+            body: *unsafe{Box::from_raw(body)}
+        }
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn make_switch_statement(span: Span,
                                      init: *mut StatementNode,
                                      expr: *mut Expression,
                                      body: *mut Vec<CaseClause> ) -> *mut StatementNode {
 
     let body = *unsafe{Box::from_raw(body)};
 
-    verify_only_one_default(line, &body);
+    verify_only_one_default(&body);
 
     make_statement_ptr(
-        line,
+        span,
         StatementVariant::Switch {
             init: unsafe{Box::from_raw(init)},
             expr: unsafe{from_raw_or_none(expr)},
@@ -485,28 +818,117 @@ pub extern "C" fn make_switch_statement(line: u32,
 }
 
 #[no_mangle]
-pub extern "C" fn make_break_statement(line: u32) -> *mut StatementNode{
+pub extern "C" fn make_type_switch_statement(span: Span,
+                                             init: *mut StatementNode,
+                                             binding: *const c_char,
+                                             expr: *mut Expression,
+                                             body: *mut Vec<CaseClause>) -> *mut StatementNode {
+    let body = *unsafe{Box::from_raw(body)};
+
+    verify_only_one_default(&body);
+
+    make_statement_ptr(
+        span,
+        StatementVariant::TypeSwitch {
+            init: unsafe{Box::from_raw(init)},
+            binding: unsafe { from_c_string_or_none(binding) },
+            expr: unsafe{Box::from_raw(expr)},
+            body
+        }
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn make_break_statement(span: Span, label: *const c_char) -> *mut StatementNode{
     make_statement_ptr(
-        line,
-        StatementVariant::Break
+        span,
+        StatementVariant::Break(unsafe { from_c_string_or_none(label) })
 
     )
 }
 
 #[no_mangle]
-pub extern "C" fn make_continue_statement(line: u32) -> *mut StatementNode{
+pub extern "C" fn make_continue_statement(span: Span, label: *const c_char) -> *mut StatementNode{
     make_statement_ptr(
-        line,
-        StatementVariant::Continue
+        span,
+        StatementVariant::Continue(unsafe { from_c_string_or_none(label) })
+
+    )
+}
 
+#[no_mangle]
+pub extern "C" fn make_fallthrough_statement(span: Span) -> *mut StatementNode{
+    make_statement_ptr(
+        span,
+        StatementVariant::Fallthrough
     )
 }
 
 #[no_mangle]
-pub extern "C" fn make_return_statement(line: u32, value: *mut Expression) -> *mut StatementNode{
+pub extern "C" fn make_goto_statement(span: Span, label: *const c_char) -> *mut StatementNode{
     make_statement_ptr(
-        line,
-        StatementVariant::Return(unsafe{from_raw_or_none(value)})
+        span,
+        StatementVariant::Goto(unsafe { from_c_string(label) })
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn make_labeled_statement(span: Span, label: *const c_char,
+                                         statement: *mut StatementNode) -> *mut StatementNode{
+    make_statement_ptr(
+        span,
+        StatementVariant::Labeled {
+            label: unsafe { from_c_string(label) },
+            statement: unsafe { Box::from_raw(statement) },
+        }
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn make_return_statement(span: Span, values: *mut Vec<Expression>) -> *mut StatementNode{
+    make_statement_ptr(
+        span,
+        StatementVariant::Return(*unsafe{ Box::from_raw(values) })
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn make_defer_statement(span: Span, call: *mut StatementNode) -> *mut StatementNode{
+    make_statement_ptr(
+        span,
+        StatementVariant::Defer(unsafe{ Box::from_raw(call) })
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn make_go_statement(span: Span, call: *mut StatementNode) -> *mut StatementNode{
+    make_statement_ptr(
+        span,
+        StatementVariant::Go(unsafe{ Box::from_raw(call) })
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn make_send_statement(span: Span, channel: *mut Expression,
+                                      value: *mut Expression) -> *mut StatementNode{
+    make_statement_ptr(
+        span,
+        StatementVariant::Send {
+            channel: unsafe { Box::from_raw(channel) },
+            value: unsafe { Box::from_raw(value) },
+        }
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn make_select_statement(span: Span, body: *mut Vec<CaseClause>) -> *mut StatementNode {
+    let body = *unsafe{Box::from_raw(body)};
+
+    verify_only_one_default(&body);
+
+    make_statement_ptr(
+        span,
+        StatementVariant::Select { body }
     )
 }
 
@@ -516,7 +938,7 @@ STATEMENT NODE HELPERS
 =======================================
 */
 #[no_mangle]
-pub extern "C" fn make_case_clause(line: u32,
+pub extern "C" fn make_case_clause(span: Span,
                                    tags: *mut Vec<Expression>,
                                    stmts: *mut Vec<StatementNode>) -> *mut CaseClause {
 
@@ -529,27 +951,97 @@ pub extern "C" fn make_case_clause(line: u32,
     }
 
     Box::into_raw(Box::new(CaseClause {
-        line_number: line,
+        span,
         switch_case: tag,
-        statements: *unsafe{Box::from_raw(stmts)}
+        statements: *unsafe{Box::from_raw(stmts)},
+        binding: None,
     }))
 }
 
 #[no_mangle]
-pub extern "C" fn make_var_spec(line: u32, names: *mut Vec<String>, kind: *mut AstKindNode, rhs: *mut Vec<Expression>) 
+pub extern "C" fn make_type_case_clause(span: Span,
+                                        kinds: *mut Vec<AstKindNode>,
+                                        stmts: *mut Vec<StatementNode>) -> *mut CaseClause {
+    let tag = if kinds.is_null() {
+        SwitchCase::Default
+    } else {
+        SwitchCase::Kinds(*unsafe{Box::from_raw(kinds)})
+    };
+
+    Box::into_raw(Box::new(CaseClause {
+        span,
+        switch_case: tag,
+        statements: *unsafe{Box::from_raw(stmts)},
+        binding: None,
+    }))
+}
+
+#[no_mangle]
+pub extern "C" fn make_comm_clause(span: Span,
+                                   comm: *mut StatementNode,
+                                   stmts: *mut Vec<StatementNode>) -> *mut CaseClause {
+    let tag = if comm.is_null() {
+        SwitchCase::Default
+    } else {
+        let comm = unsafe { Box::from_raw(comm) };
+        check_comm(&comm);
+        SwitchCase::Comm(comm)
+    };
+
+    Box::into_raw(Box::new(CaseClause {
+        span,
+        switch_case: tag,
+        statements: *unsafe{Box::from_raw(stmts)},
+        binding: None,
+    }))
+}
+
+/// The case of a select clause must send to a channel or receive from one, maybe assigning the
+/// received value
+fn check_comm(comm: &StatementNode) {
+    let is_receive = |exprs: &Vec<Expression>| {
+        exprs.len() == 1 && matches!(exprs[0].variant, ExpressionVariant::Receive { .. })
+    };
+
+    let valid = match comm.variant {
+        StatementVariant::Send { .. } => true,
+        StatementVariant::Expression(ref expr) =>
+            matches!(expr.variant, ExpressionVariant::Receive { .. }),
+        StatementVariant::Assignment { ref lhs, ref rhs } => lhs.len() <= 2 && is_receive(rhs),
+        StatementVariant::ShortVariableDeclaration { ref identifier_list, ref expression_list, .. } =>
+            identifier_list.len() <= 2 && is_receive(expression_list),
+        _ => false,
+    };
+
+    if !valid {
+        syntax_error(comm.span, "select case must be receive, send or assign recv.");
+    }
+}
+
+/// Only the first spec of a constant declaration needs to have expressions, the others can repeat
+/// them
+fn check_const_specs(specs: &[VarSpec]) {
+    if let Some(first) = specs.first() {
+        if first.rhs.is_none() {
+            syntax_error(first.span, "missing init expression for constant declaration.");
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn make_var_spec(span: Span, names: *mut Vec<String>, kind: *mut AstKindNode, rhs: *mut Vec<Expression>) 
     -> *mut VarSpec
 {
     let names = *unsafe { Box::from_raw( names ) };
     if !rhs.is_null() {
         let rhs = *unsafe { Box::from_raw( rhs ) };
-        if names.len() != rhs.len() {
-            eprintln!("Error: line {}: different number of elements on the sides or the assignment", line);
-            exit(1);
+        if names.len() != rhs.len() && rhs.len() != 1 {
+            syntax_error(span, "different number of elements on the sides or the assignment");
         }
 
         Box::into_raw( Box::new(
                 VarSpec{
-                    line_number: line,
+                    span,
                     names,
                     kind: unsafe{ from_raw_or_none(kind) },
                     rhs: Some(rhs),
@@ -558,7 +1050,7 @@ pub extern "C" fn make_var_spec(line: u32, names: *mut Vec<String>, kind: *mut A
     } else {
         Box::into_raw( Box::new(
                 VarSpec{
-                    line_number: line,
+                    span,
                     names,
                     kind: unsafe{ from_raw_or_none(kind) },
                     rhs: None,
@@ -569,19 +1061,19 @@ pub extern "C" fn make_var_spec(line: u32, names: *mut Vec<String>, kind: *mut A
 
 #[no_mangle]
 pub extern "C" 
-fn make_type_spec(line: u32, name: *mut c_char, kind: *mut AstKindNode)
+fn make_type_spec(span: Span, name: *mut c_char, kind: *mut AstKindNode)
     -> *mut TypeSpec
 {
         Box::into_raw( Box::new(
                 TypeSpec{
-                    line_number: line,
+                    span,
                     name: unsafe{ from_c_string(name) },
                     kind: unsafe{ Box::from_raw(kind) },
                 }))
 }
 
 /// Verify that only one default exists in any switch clause
-fn verify_only_one_default(line: u32, body: &Vec<CaseClause>) {
+fn verify_only_one_default(body: &[CaseClause]) {
     let mut default_exists: bool = false;
 
     for case in body.iter() {
@@ -589,8 +1081,7 @@ fn verify_only_one_default(line: u32, body: &Vec<CaseClause>) {
             default_exists = true;
             //eprintln!("one exists");
         } else if matches!(case.switch_case, SwitchCase::Default) {
-            eprintln!("Error: line {}: declared more than one default switch case.",line);
-            exit(1);
+            syntax_error(case.span, "declared more than one default switch case.");
         }
     }
 }
@@ -602,34 +1093,35 @@ AST KIND NODE CONSTRUCTORS
 
 
 /// This is a function that factors out most of the repetition
-fn make_ast_kind_ptr(line: u32, expr: AstKindVariant) -> *mut AstKindNode {
+fn make_ast_kind_ptr(span: Span, expr: AstKindVariant) -> *mut AstKindNode {
     Box::into_raw(Box::new( AstKindNode{
-        line_number: line,
+        span,
         variant: expr,
+        evaluated_kind: Kind::Undefined,
     }))
 }
 
 #[no_mangle]
-pub extern "C" fn make_identifier_kind(line: u32, string: *const c_char) -> *mut AstKindNode {
+pub extern "C" fn make_identifier_kind(span: Span, string: *const c_char) -> *mut AstKindNode {
     make_ast_kind_ptr(
-        line,
+        span,
         AstKindVariant::Identifier { name: unsafe { from_c_string(string) } },
     )
 }
 
 
 #[no_mangle]
-pub extern "C" fn make_slice_kind(line: u32, base: *mut AstKindNode) -> *mut AstKindNode {
+pub extern "C" fn make_slice_kind(span: Span, base: *mut AstKindNode) -> *mut AstKindNode {
     make_ast_kind_ptr(
-        line,
+        span,
         AstKindVariant::Slice { base: unsafe { Box::from_raw(base) } },
     )
 }
 
 #[no_mangle]
-pub extern "C" fn make_array_kind(line: u32, base: *mut AstKindNode, size: *const c_char) -> *mut AstKindNode {
+pub extern "C" fn make_array_kind(span: Span, base: *mut AstKindNode, size: *const c_char) -> *mut AstKindNode {
     make_ast_kind_ptr(
-        line,
+        span,
         AstKindVariant::Array {
             base: unsafe { Box::from_raw(base) },
             size: unsafe { from_c_string(size) },
@@ -638,9 +1130,9 @@ pub extern "C" fn make_array_kind(line: u32, base: *mut AstKindNode, size: *cons
 }
 
 #[no_mangle]
-pub extern "C" fn make_struct_kind(line: u32, fields: *mut Vec<Field>) -> *mut AstKindNode {
+pub extern "C" fn make_struct_kind(span: Span, fields: *mut Vec<Field>) -> *mut AstKindNode {
     make_ast_kind_ptr(
-        line,
+        span,
         AstKindVariant::Struct {
             fields: *unsafe{ Box::from_raw(fields) }
         },
@@ -648,17 +1140,124 @@ pub extern "C" fn make_struct_kind(line: u32, fields: *mut Vec<Field>) -> *mut A
 }
 
 #[no_mangle]
-pub extern "C" fn make_field(line: u32, fields: *mut Vec<String>, kind: *mut AstKindNode)
+pub extern "C" fn make_map_kind(span: Span, key: *mut AstKindNode, value: *mut AstKindNode) -> *mut AstKindNode {
+    make_ast_kind_ptr(
+        span,
+        AstKindVariant::Map {
+            key: unsafe { Box::from_raw(key) },
+            value: unsafe { Box::from_raw(value) },
+        },
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn make_pointer_kind(span: Span, base: *mut AstKindNode) -> *mut AstKindNode {
+    make_ast_kind_ptr(
+        span,
+        AstKindVariant::Pointer { base: unsafe { Box::from_raw(base) } },
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn make_interface_kind(span: Span, methods: *mut Vec<Field>) -> *mut AstKindNode {
+    make_ast_kind_ptr(
+        span,
+        AstKindVariant::Interface {
+            methods: *unsafe{ Box::from_raw(methods) }
+        },
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn make_chan_kind(span: Span, base: *mut AstKindNode) -> *mut AstKindNode {
+    make_ast_kind_ptr(
+        span,
+        AstKindVariant::Chan { base: unsafe { Box::from_raw(base) } },
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn make_function_kind(span: Span, params: *mut Vec<Field>, results: *mut Vec<Field>)
+    -> *mut AstKindNode {
+    make_ast_kind_ptr(
+        span,
+        AstKindVariant::Function {
+            parameters: checked_parameters(group_results(*unsafe { Box::from_raw(params) })),
+            results: checked_results(group_results(*unsafe { Box::from_raw(results) })),
+        },
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn make_field(span: Span, fields: *mut Vec<String>, kind: *mut AstKindNode)
 -> *mut Field
 {
     Box::into_raw( Box::new(
             Field {
-                line_number: line,
+                span,
                 identifiers: *unsafe{ Box::from_raw(fields) },
-                kind: unsafe{ Box::from_raw(kind) }
+                kind: unsafe{ Box::from_raw(kind) },
+                variadic: false,
             }))
 }
 
+/// The parameters of a function declared with `...T`
+#[no_mangle]
+pub extern "C" fn make_variadic_field(span: Span, fields: *mut Vec<String>, kind: *mut AstKindNode)
+-> *mut Field
+{
+    let field = make_field(span, fields, kind);
+    unsafe { (*field).variadic = true };
+    field
+}
+
+/// A single entry of a function result list, with or without a name
+#[no_mangle]
+pub extern "C" fn make_receiver(span: Span, name: *const c_char, kind: *mut AstKindNode,
+                                pointer: c_int) -> *mut Receiver {
+    let name = if name.is_null() {
+        "_".to_string()
+    } else {
+        unsafe { from_c_string(name) }
+    };
+    Box::into_raw(Box::new(
+            Receiver {
+                span,
+                name,
+                kind: unsafe { Box::from_raw(kind) },
+                pointer: pointer != 0,
+            }))
+}
+
+#[no_mangle]
+pub extern "C" fn make_result_field(span: Span, name: *const c_char, kind: *mut AstKindNode)
+-> *mut Field
+{
+    let identifiers = if name.is_null() {
+        vec![]
+    } else {
+        vec![unsafe { from_c_string(name) }]
+    };
+    Box::into_raw( Box::new(
+            Field {
+                span,
+                identifiers,
+                kind: unsafe{ Box::from_raw(kind) },
+                variadic: false,
+            }))
+}
+
+/// An entry of the parameters of a function type written `...T`. These are parsed like results,
+/// which can't be variadic.
+#[no_mangle]
+pub extern "C" fn make_variadic_result_field(span: Span, name: *const c_char, kind: *mut AstKindNode)
+-> *mut Field
+{
+    let field = make_result_field(span, name, kind);
+    unsafe { (*field).variadic = true };
+    field
+}
+
 
         
 
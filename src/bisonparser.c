@@ -2,19 +2,39 @@
 #include "stdlib.h"
 #include "lex.yy.c" // eh?
 
-extern Program *root;
-
-void say_hello() {
-	printf("hello\n");
+// Makes a scanner that reads the given source. The id of the file is stored in the spans so that
+// errors can find the text back. The scanner must be freed with yylex_destroy.
+static yyscan_t open_source(ParserState *state, const char *source, uint32_t length, uint32_t file) {
+	yyscan_t scanner;
+	state->file = file;
+	state->position.line = 1;
+	state->position.column = 1;
+	state->position.offset = 0;
+	state->last_token = -1;
+	state->lexical_error = 0;
+	state->root = NULL;
+	yylex_init_extra(state, &scanner);
+	yy_scan_bytes(source, length, scanner);
+	return scanner;
 }
 
-void scan() {
-	while(yylex()) {}
+void scan(const char *source, uint32_t length, uint32_t file) {
+	ParserState state;
+	YYSTYPE value;
+	Span location;
+	yyscan_t scanner = open_source(&state, source, length, file);
+	while(yylex(&value, &location, scanner)) {}
+	yylex_destroy(scanner);
 }
 
-void print_tokens() {
+// Passes every token to add_token, with the text of those that carry one
+void list_tokens(const char *source, uint32_t length, uint32_t file) {
+	ParserState state;
+	YYSTYPE value;
+	Span location;
+	yyscan_t scanner = open_source(&state, source, length, file);
 	while(1) {
-		int token = yylex();
+		int token = yylex(&value, &location, scanner);
 		if (token == 0) break;
 
 		switch(token) {
@@ -23,16 +43,26 @@ void print_tokens() {
 			case tRUNEVAL:
 			case tSTRINGVAL:
 			case tIDENTIFIER:
-				printf("%s( %s )\n", yytname[yytranslate[token]], yylval.text);
+				add_token(yytname[yytranslate[token]], value.text);
 				break;
 			default:
-				printf("%s\n", yytname[yytranslate[token]]);
+				add_token(yytname[yytranslate[token]], NULL);
 				break;
 		}
 	}
+	yylex_destroy(scanner);
 }
 
-Program *parse() {
-	yyparse();
-	return root;
+// Returns NULL if there was a syntax error, in which case it was passed to report_syntax_error.
+// Nothing is shared between calls, so sources can be parsed one after the other or from several
+// threads.
+Program *parse(const char *source, uint32_t length, uint32_t file) {
+	ParserState state;
+	yyscan_t scanner = open_source(&state, source, length, file);
+	int failed = yyparse(scanner, &state) != 0 || state.lexical_error;
+	yylex_destroy(scanner);
+	if (failed) {
+		return NULL;
+	}
+	return state.root;
 }
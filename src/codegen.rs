@@ -1,29 +1,63 @@
+use std::collections::{HashMap, HashSet};
 use std::fmt::Write;
-use util::*;
-use ast::*;
-use kind::*;
-use std::fs::File;
-use std::io::prelude::*;
+use std::mem;
+use std::rc::Rc;
+use crate::util::*;
+use crate::ast::*;
+use crate::kind::*;
 
 struct CodeGenVisitor {
     indent: u32,
     id_counter: u32,
-    init_functions: Vec<String>
+    init_functions: Vec<String>,
+    output: String,
+    // The result variables of the function being written, if it defers calls. Its returns set
+    // them and leave the body, so that the deferred calls run and can still change them.
+    deferred_results: Option<Vec<String>>,
+
+    // The labels that the gotos of the function being written jump to. The statements from one
+    // of them are a case of a switch in a loop; a goto sets the variable the switch is on, given
+    // here with the label of the loop and the case, and continues the loop.
+    goto_labels: HashSet<String>,
+    goto_cases: HashMap<String, (String, String, usize)>,
+    // The Go label of each loop and switch around the statement, its label in the generated code
+    // if it needs one, and whether it is a loop
+    jump_targets: Vec<(Option<String>, Option<String>, bool)>,
+    // The Go label of the loop or switch about to be written
+    statement_label: Option<String>,
+    // Variables are declared with `var` in the cases of a goto switch, so that they are still
+    // there when the switch is entered again
+    declaration_keyword: &'static str,
+    // The constant holding the descriptor of each type put in an interface, by type key, and
+    // their declarations, which go before the program
+    type_descriptors: HashMap<String, String>,
+    type_declarations: String,
 }
 
 impl CodeGenVisitor{
     fn visit_program(&mut self, root: &Program) {
-        print_header();
+        self.output.push_str(HEADER);
+        self.output.push('\n');
+
+        // Panics are reported when they get out of the program. The whole program is the main
+        // goroutine, so that the initializers of global variables can block on channels too.
+        let file_name = crate::span::file_name(root.package_span.file).unwrap_or_default();
+        writeln!(self.output, "const source_file = {};", quote_string_literal(&file_name)).unwrap();
+        writeln!(self.output, "run_program(function* () {{").unwrap();
+        let start = self.output.len();
 
         for decl in &root.declarations {
             self.visit_top_level_declaration(&decl);
         }
 
         for init_func_name in &self.init_functions {
-            println!("{}();",init_func_name);
+            writeln!(self.output, "{}yield* {}();", indent(1), init_func_name).unwrap();
         }
+        writeln!(self.output, "{}yield* main();", indent(1)).unwrap();
+        writeln!(self.output, "}});").unwrap();
 
-        println!("main();");
+        // Methods are function declarations, so the descriptors can refer to them
+        self.output.insert_str(start, &self.type_declarations);
     }
 
 
@@ -35,40 +69,123 @@ impl CodeGenVisitor{
                 }
             }
 
-            TopLevelDeclarationVariant::FunctionDeclaration 
-                (Function { ref name, ref parameters, ref body, .. }) => {
-                if name == "_" {
+            // Every use of a constant has been replaced by its value
+            TopLevelDeclarationVariant::ConstDeclarations { .. } => {}
+
+            TopLevelDeclarationVariant::FunctionDeclaration(ref function) => {
+                if function.name == "_" {
                     return;
                 }
 
-                let mut func_name = name.clone();
+                let mut func_name = function.name.clone();
 
                 if func_name == "init" {
                     func_name = format!("init_{}", self.create_id());
                     self.init_functions.push(func_name.clone());
                 }
 
-                let mut params_string = "".to_string();
-                for (i,field) in parameters.iter().enumerate() {
-                    for (j,id) in field.identifiers.iter().enumerate() {
-                        write!(params_string, "{}", id).unwrap();
-                        if i < parameters.len() - 1 || j < field.identifiers.len() - 1 {
-                            write!(params_string, ", ").unwrap();
-                        }
-                    }
-                }
+                self.visit_function(&func_name, function);
+                writeln!(self.output).unwrap();
+            },
 
-                println!("function {} ( {} ) {{", func_name, params_string);
+            _ => {}
+        }
+    }
 
-                self.indent += 1;
-                self.visit_statements(&body);
-                self.indent -= 1;
+    // Writes a function declaration, or a function literal if the name is empty. The receiver of
+    // a method is its first parameter. Functions are generators, which yield when their goroutine
+    // blocks, so they are called with `yield*`.
+    fn visit_function(&mut self, name: &str, function: &Function) {
+        let mut params_string = "".to_string();
+        if let Some(ref receiver) = function.receiver {
+            write!(params_string, "{}", receiver.name).unwrap();
+            if !function.parameters.is_empty() {
+                write!(params_string, ", ").unwrap();
+            }
+        }
+        let parameters = &function.parameters;
+        for (i,field) in parameters.iter().enumerate() {
+            for (j,id) in field.identifiers.iter().enumerate() {
+                write!(params_string, "{}", id).unwrap();
+                if i < parameters.len() - 1 || j < field.identifiers.len() - 1 {
+                    write!(params_string, ", ").unwrap();
+                }
+            }
+        }
 
-                println!("}}");
-            },
+        writeln!(self.output, "function* {} ( {} ) {{", name, params_string).unwrap();
+
+        // The frame is left however the function ends, for the stack traces of panics
+        self.indent += 1;
+        writeln!(self.output, "{}enter_frame({});", indent(self.indent),
+                 quote_string_literal(&function.trace_name())).unwrap();
+        writeln!(self.output, "{}try {{", indent(self.indent)).unwrap();
+
+        self.indent += 1;
+        // Named results start with their zero value
+        let mut result_names = Vec::new();
+        for field in function.results.iter() {
+            for id in field.identifiers.iter() {
+                let mut zero = String::new();
+                self.visit_var_initialization(&field.kind.evaluated_kind, &mut zero);
+                writeln!(self.output, "{}let {} = {};", indent(self.indent), id, zero).unwrap();
+                result_names.push(id.clone());
+            }
+        }
 
-            _ => {}
+        let body = &function.body;
+        let goto_labels = nested_statements(body).into_iter()
+            .filter_map(|stmt| match stmt.variant {
+                StatementVariant::Goto(ref label) => Some(label.clone()),
+                _ => None,
+            })
+            .collect();
+        let outer_goto_labels = mem::replace(&mut self.goto_labels, goto_labels);
+        let outer_goto_cases = mem::take(&mut self.goto_cases);
+        let outer_jump_targets = mem::take(&mut self.jump_targets);
+        if contains_defer(body) {
+            // The body is left with a break, after which the deferred calls have run
+            if result_names.is_empty() {
+                for _ in function.results.iter() {
+                    let name = format!("ㄭ{}", self.create_id());
+                    writeln!(self.output, "{}let {};", indent(self.indent), name).unwrap();
+                    result_names.push(name);
+                }
+            }
+            writeln!(self.output, "{}const ㄭdefers = [];", indent(self.indent)).unwrap();
+            writeln!(self.output, "{}ㄭbody: try {{", indent(self.indent)).unwrap();
+
+            let outer = self.deferred_results.replace(result_names.clone());
+            self.indent += 1;
+            self.visit_statements(body);
+            self.indent -= 1;
+            self.deferred_results = outer;
+
+            writeln!(self.output, "{}}} finally {{", indent(self.indent)).unwrap();
+            writeln!(self.output, "{}yield* run_deferred(ㄭdefers);", indent(self.indent + 1)).unwrap();
+            writeln!(self.output, "{}}}", indent(self.indent)).unwrap();
+            match result_names.len() {
+                0 => {},
+                1 => writeln!(self.output, "{}return {};", indent(self.indent), result_names[0]).unwrap(),
+                _ => writeln!(self.output, "{}return [{}];", indent(self.indent), result_names.join(", ")).unwrap(),
+            }
+        } else {
+            // Function literals in a function that defers calls return normally
+            let outer = self.deferred_results.take();
+            self.visit_statements(body);
+            self.deferred_results = outer;
         }
+        self.goto_labels = outer_goto_labels;
+        self.goto_cases = outer_goto_cases;
+        self.jump_targets = outer_jump_targets;
+        self.indent -= 1;
+
+        writeln!(self.output, "{}}} finally {{", indent(self.indent)).unwrap();
+        writeln!(self.output, "{}leave_frame();", indent(self.indent + 1)).unwrap();
+        writeln!(self.output, "{}}}", indent(self.indent)).unwrap();
+        self.indent -= 1;
+
+        write!(self.output, "{}}}", indent(self.indent)).unwrap();
     }
 
     fn visit_var_spec(&mut self, var_spec: &VarSpec){
@@ -77,59 +194,73 @@ impl CodeGenVisitor{
                 let mut pre_string = "".to_string();
                 let mut post_string = "".to_string();
 
-                for (name, rhs) in var_spec.names.iter().zip(values.iter()) {
+                let mut rhs_strings = Vec::new();
+                for rhs in values.iter() {
                     let mut new_post = String::new();
                     self.visit_expression(&rhs, &mut pre_string, &mut new_post);
+                    rhs_strings.push(new_post);
+                }
+
+                for (x, name) in var_spec.names.iter().enumerate() {
                     if name != "_" {
-                        writeln!(post_string, "{}var {} = deepCopy({});",
-                            indent(self.indent), name, new_post).unwrap();
+                        writeln!(post_string, "{}{} {} = deepCopy({});",
+                            indent(self.indent), self.declaration_keyword, name, assigned_value(&rhs_strings, var_spec.names.len(), x)).unwrap();
                     }
                 }
 
-                println!("{} \n {}", pre_string, post_string);
+                writeln!(self.output, "{} \n {}", pre_string, post_string).unwrap();
             }
             None => {
                 for name in var_spec.names.iter() {
                     if name != "_" {
-                        print!("{}var {} = ", indent(self.indent), name);
-                        self.visit_var_initialization(&var_spec.evaluated_kind);
-                        println!(";");
+                        let mut zero = String::new();
+                        self.visit_var_initialization(&var_spec.evaluated_kind, &mut zero);
+                        writeln!(self.output, "{}{} {} = {};", indent(self.indent),
+                                 self.declaration_keyword, name, zero).unwrap();
                     }
                 }
             }
         }
     }
 
-    fn visit_var_initialization(&mut self, var_kind: &Kind){
+    fn visit_var_initialization(&mut self, var_kind: &Kind, output: &mut String){
         match &var_kind.resolve() {
-            &Kind::Basic(BasicKind::Int) | &Kind::Basic(BasicKind::Float)| &Kind::Basic(BasicKind::Rune) => print!("0"),
-            &Kind::Basic(BasicKind::Bool) => print!("false"),
-            &Kind::Basic(BasicKind::String) => print!("''"),
+            &Kind::Basic(basic) if is_bigint(basic) => write!(output, "0n").unwrap(),
+            &Kind::Basic(BasicKind::Bool) => write!(output, "false").unwrap(),
+            &Kind::Basic(BasicKind::String) => write!(output, "''").unwrap(),
+            &Kind::Basic(_) => write!(output, "0").unwrap(),
             &Kind::Array(ref kind, ref length) => {
-                print!("makeArray({}, ",length);
-                self.visit_var_initialization(&kind);
-                print!(")");
+                write!(output, "makeArray({}, ",length).unwrap();
+                self.visit_var_initialization(kind, output);
+                write!(output, ")").unwrap();
             }
             &Kind::Slice(..) => {
-                println!("{{", );
+                writeln!(output, "{{", ).unwrap();
 
                 self.indent+=1;
-                println!("{} length: 0,", indent(self.indent));
-                println!("{}capacity: 0,", indent(self.indent));
-                println!("{} contents: []", indent(self.indent));
+                writeln!(output, "{} offset: 0,", indent(self.indent)).unwrap();
+                writeln!(output, "{} length: 0,", indent(self.indent)).unwrap();
+                writeln!(output, "{}capacity: 0,", indent(self.indent)).unwrap();
+                writeln!(output, "{} contents: []", indent(self.indent)).unwrap();
                 self.indent-=1;
-                print!("{}}}", indent(self.indent));
+                write!(output, "{}}}", indent(self.indent)).unwrap();
             }
             &Kind::Struct(ref fields) => {
-                println!("{{");
+                writeln!(output, "{{").unwrap();
                 self.indent+=1;
                 for field in fields.iter(){
-                    print!("{}ㆭ{}:", indent(self.indent), field.name, );
-                    self.visit_var_initialization(&field.kind);
-                    println!(",");
+                    write!(output, "{}ㆭ{}:", indent(self.indent), field.name, ).unwrap();
+                    self.visit_var_initialization(&field.kind, output);
+                    writeln!(output, ",").unwrap();
                 }
                 self.indent-=1;
-                print!("{}}}", indent(self.indent));
+                write!(output, "{}}}", indent(self.indent)).unwrap();
+            }
+            // A nil map
+            &Kind::Map(..) => write!(output, "{{contents: null}}").unwrap(),
+            // A nil function, pointer, interface or channel
+            &Kind::Function(..) | &Kind::Pointer(..) | &Kind::Interface(..) | &Kind::Chan(..) => {
+                write!(output, "null").unwrap()
             }
             _ => {panic!("initializing value not supported")}
         }
@@ -137,42 +268,165 @@ impl CodeGenVisitor{
 
 
     fn visit_statements(&mut self, statements: &[StatementNode]) {
-        for s in statements {
-            self.visit_statement(s);
+        // The statements are split into cases where gotos can jump to
+        let mut starts = statements.iter().enumerate()
+            .filter(|&(_, stmt)| self.is_goto_target(stmt))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        if starts.is_empty() {
+            let outer = mem::replace(&mut self.declaration_keyword, "let");
+            for s in statements {
+                self.visit_statement(s);
+            }
+            self.declaration_keyword = outer;
+            return;
+        }
+        if starts[0] != 0 {
+            starts.insert(0, 0);
+        }
+
+        let case_variable = format!("ㄭ{}", self.create_id());
+        let loop_label = format!("ㄭ{}", self.create_id());
+        for (case, &start) in starts.iter().enumerate() {
+            for label in statement_labels(&statements[start]) {
+                self.goto_cases.insert(label.to_string(), (case_variable.clone(), loop_label.clone(), case));
+            }
+        }
+
+        writeln!(self.output, "{}let {} = 0;", indent(self.indent), case_variable).unwrap();
+        writeln!(self.output, "{}{}: for (;;) {{", indent(self.indent), loop_label).unwrap();
+        writeln!(self.output, "{}switch ({}) {{", indent(self.indent + 1), case_variable).unwrap();
+        let outer = mem::replace(&mut self.declaration_keyword, "var");
+        for (case, &start) in starts.iter().enumerate() {
+            let end = starts.get(case + 1).cloned().unwrap_or(statements.len());
+            writeln!(self.output, "{}case {}:", indent(self.indent + 1), case).unwrap();
+            self.indent += 2;
+            for s in &statements[start..end] {
+                self.visit_statement(s);
+            }
+            self.indent -= 2;
+        }
+        self.declaration_keyword = outer;
+        writeln!(self.output, "{}}}", indent(self.indent + 1)).unwrap();
+        writeln!(self.output, "{}break;", indent(self.indent + 1)).unwrap();
+        writeln!(self.output, "{}}}", indent(self.indent)).unwrap();
+    }
+
+    // The label a loop or switch has in the generated code. Those with a Go label keep it, and
+    // those with goto cases inside them get one, since their breaks and continues can't be
+    // unlabeled there.
+    fn jump_target_label(&mut self, go_label: &Option<String>, nested: &[&StatementNode]) -> Option<String> {
+        if let Some(ref label) = *go_label {
+            Some(label_name(label))
+        } else if nested.iter().any(|stmt| self.is_goto_target(stmt)) {
+            Some(format!("ㄭ{}", self.create_id()))
+        } else {
+            None
         }
     }
 
+    fn is_goto_target(&self, stmt: &StatementNode) -> bool {
+        statement_labels(stmt).iter().any(|label| self.goto_labels.contains(*label))
+    }
+
     fn visit_statement(&mut self, stmt: &StatementNode) {
         match stmt.variant {
             StatementVariant::Empty => {},
-            StatementVariant::Break => {
-                print!("{}", indent(self.indent));
-                println!("break;")
+            StatementVariant::Defer(ref call) => {
+                self.codegen_deferred_call(call, "ㄭdefers.push");
+            },
+            StatementVariant::Go(ref call) => {
+                self.codegen_deferred_call(call, "go_start");
+            },
+            StatementVariant::Send { ref channel, ref value } => {
+                let mut pre = String::new();
+                let mut channel_value = String::new();
+                let mut sent = String::new();
+                self.visit_expression(channel, &mut pre, &mut channel_value);
+                self.visit_expression(value, &mut pre, &mut sent);
+                write!(self.output, "{}", pre).unwrap();
+                writeln!(self.output, "{}yield* chan_send({}, deepCopy({}), {});", indent(self.indent),
+                         channel_value, sent, stmt.span.start.line).unwrap();
+            },
+            StatementVariant::Break(ref label) => {
+                let target = self.jump_targets.iter().rev()
+                    .find(|&(go_label, _, _)| label.is_none() || go_label == label)
+                    .expect("break outside of loop or switch");
+                write!(self.output, "{}", indent(self.indent)).unwrap();
+                match target.1 {
+                    Some(ref js_label) => writeln!(self.output, "break {};", js_label).unwrap(),
+                    None => writeln!(self.output, "break;").unwrap(),
+                }
+            },
+            StatementVariant::Continue(ref label) => {
+                let target = self.jump_targets.iter().rev()
+                    .find(|&&(ref go_label, _, is_loop)| is_loop && (label.is_none() || go_label == label))
+                    .expect("continue outside of loop");
+                write!(self.output, "{}", indent(self.indent)).unwrap();
+                match target.1 {
+                    Some(ref js_label) => writeln!(self.output, "continue {};", js_label).unwrap(),
+                    None => writeln!(self.output, "continue;").unwrap(),
+                }
             },
-            StatementVariant::Continue => {
-                print!("{}", indent(self.indent));
-                println!("continue;")
+            StatementVariant::Goto(ref label) => {
+                let (ref case_variable, ref loop_label, case) = self.goto_cases[label];
+                writeln!(self.output, "{}{} = {};", indent(self.indent), case_variable, case).unwrap();
+                writeln!(self.output, "{}continue {};", indent(self.indent), loop_label).unwrap();
+            },
+            StatementVariant::Labeled { ref label, ref statement } => {
+                match statement.variant {
+                    StatementVariant::For { .. } | StatementVariant::ForRange { .. } |
+                    StatementVariant::Switch { .. } | StatementVariant::TypeSwitch { .. } |
+                    StatementVariant::Select { .. } => {
+                        self.statement_label = Some(label.clone())
+                    }
+                    _ => {},
+                }
+                self.visit_statement(statement);
             },
+            // The clause is emitted without its `break`, so it runs on into the next case
+            StatementVariant::Fallthrough => {},
             StatementVariant::Expression(ref exp) => {
                 let mut pre = String::new();
                 let mut post = String::new();
                 self.visit_expression(exp, &mut pre, &mut post);
-                print!("{}",pre);
-                println!("{}{};", indent(self.indent), &mut post);
+                write!(self.output, "{}",pre).unwrap();
+                writeln!(self.output, "{}{};", indent(self.indent), &mut post).unwrap();
             },
-            StatementVariant::Return(ref exp) => {
-                match exp {
-                    &Some(ref e) => {
-                        let mut pre = String::new();
-                        let mut post = String::new();
-                        self.visit_expression(e, &mut pre, &mut post);
-                        print!("{}",pre);
-                        println!("{}return {};", indent(self.indent), &mut post);
-                    },
-                    &None => {
-                        print!("{}", indent(self.indent));
-                        println!("return;");
+            StatementVariant::Return(ref exps) if self.deferred_results.is_some() => {
+                let result_names = self.deferred_results.clone().unwrap();
+                let mut pre = String::new();
+                let mut post = String::new();
+                let mut temps = Vec::new();
+                for e in exps.iter() {
+                    let temp = format!("temp_{}", self.create_id());
+                    write!(post, "{}var {} = ", indent(self.indent), temp).unwrap();
+                    self.visit_expression(e, &mut pre, &mut post);
+                    writeln!(post, ";").unwrap();
+                    temps.push(temp);
+                }
+                for (x, name) in result_names.iter().enumerate() {
+                    writeln!(post, "{}{} = assign({}, deepCopy({}));", indent(self.indent), name, name,
+                             assigned_value(&temps, result_names.len(), x)).unwrap();
+                }
+                write!(self.output, "{}{}", pre, post).unwrap();
+                writeln!(self.output, "{}break ㄭbody;", indent(self.indent)).unwrap();
+            },
+            StatementVariant::Return(ref exps) => {
+                let mut pre = String::new();
+                let mut post = String::new();
+                // Several results are returned as an array, like the tuples of comma-ok lookups
+                for (i, e) in exps.iter().enumerate() {
+                    if i > 0 {
+                        write!(post, ", ").unwrap();
                     }
+                    self.visit_expression(e, &mut pre, &mut post);
+                }
+                write!(self.output, "{}",pre).unwrap();
+                match exps.len() {
+                    0 => writeln!(self.output, "{}return;", indent(self.indent)).unwrap(),
+                    1 => writeln!(self.output, "{}return {};", indent(self.indent), post).unwrap(),
+                    _ => writeln!(self.output, "{}return [{}];", indent(self.indent), post).unwrap(),
                 }
             },
             StatementVariant::ShortVariableDeclaration { ref identifier_list, ref expression_list, ref is_assigning } => {
@@ -192,14 +446,14 @@ impl CodeGenVisitor{
                 for x in 0..identifier_list.len() {
                     if identifier_list[x] != "_" {
                         if is_assigning[x] {
-                            write!(global_post, "{}{} = deepCopy({});\n", indent(self.indent), identifier_list[x], temps[x]).unwrap();
+                            writeln!(global_post, "{}{} = assign({}, deepCopy({}));", indent(self.indent), identifier_list[x], identifier_list[x], assigned_value(&temps, identifier_list.len(), x)).unwrap();
                         } else {
-                            write!(global_post, "{}var {} = deepCopy({});\n", indent(self.indent), identifier_list[x], temps[x]).unwrap();
+                            writeln!(global_post, "{}{} {} = deepCopy({});", indent(self.indent), self.declaration_keyword, identifier_list[x], assigned_value(&temps, identifier_list.len(), x)).unwrap();
                         }
                     }
                 }
 
-                println!("{}{}", global_pre, global_post);
+                writeln!(self.output, "{}{}", global_pre, global_post).unwrap();
             },
             StatementVariant::VarDeclarations { ref declarations } => {
                 for decl in declarations.iter() {
@@ -207,17 +461,16 @@ impl CodeGenVisitor{
                 }
             },
             StatementVariant::TypeDeclarations { .. } => {},
+            StatementVariant::ConstDeclarations { .. } => {},
             StatementVariant::Assignment { ref lhs, ref rhs } => {
 
                 let mut global_pre = String::new();
                 let mut global_post = String::new();
                 let mut temps = Vec::new();
-                let mut lhs_post_strings = Vec::new();
+                let mut lhs_strings = Vec::new();
 
                 for expr in lhs.iter() {
-                    let mut post = String::new();
-                    self.visit_expression(&expr, &mut global_pre, &mut post);
-                    lhs_post_strings.push(post);
+                    lhs_strings.push(self.visit_assignable(expr, &mut global_pre));
                 }
 
                 for expr in rhs.iter() {
@@ -229,31 +482,30 @@ impl CodeGenVisitor{
                     write!(global_post, ";\n").unwrap();
                 }
 
-                for x in 0..lhs_post_strings.len() {
-                    write!(global_post, "{}{} = deepCopy({});\n", indent(self.indent), lhs_post_strings[x], temps[x]).unwrap();
+                for (x, (_, prefix, suffix)) in lhs_strings.iter().enumerate() {
+                    writeln!(global_post, "{}{}deepCopy({}){};", indent(self.indent), prefix,
+                           assigned_value(&temps, lhs_strings.len(), x), suffix).unwrap();
                 }
 
-                println!("{}{}", global_pre, global_post);
+                writeln!(self.output, "{}{}", global_pre, global_post).unwrap();
             },
             StatementVariant::OpAssignment { ref lhs, ref rhs, ref operator } => {
                 let mut pre_lhs = String::new();
-                let mut post_lhs = String::new();
-                self.visit_expression(&lhs, &mut pre_lhs, &mut post_lhs);
+                let (post_lhs, prefix, suffix) = self.visit_assignable(lhs, &mut pre_lhs);
 
                 let mut pre_rhs = String::new();
                 let mut post_rhs = String::new();
                 self.visit_expression(&rhs, &mut pre_rhs, &mut post_rhs);
 
-                println!("{}", pre_lhs);
-                println!("{}", pre_rhs);
-                print!("{}{} = ", indent(self.indent), post_lhs);
-                print!("{}",generate_binary_op(&operator));
-                println!("({}, {});", post_lhs, post_rhs);
+                writeln!(self.output, "{}", pre_lhs).unwrap();
+                writeln!(self.output, "{}", pre_rhs).unwrap();
+                write!(self.output, "{}{}", indent(self.indent), prefix).unwrap();
+                write!(self.output, "{}", binary_op_call(*operator, &lhs.kind, &post_lhs, &post_rhs,
+                                                         stmt.span.start.line)).unwrap();
+                writeln!(self.output, "{};", suffix).unwrap();
             },
             StatementVariant::Block(ref statements) => {
-                for stmt in statements {
-                    self.visit_statement(stmt);
-                }
+                self.visit_statements(statements);
             },
             StatementVariant::Print { ref exprs } => {
                 self.codegen_print(exprs, false);
@@ -262,6 +514,8 @@ impl CodeGenVisitor{
                 self.codegen_print(exprs, true);
             },
             StatementVariant::For { ref init, ref condition, ref post, ref body } => {
+                let go_label = self.statement_label.take();
+                let js_label = self.jump_target_label(&go_label, &nested_statements(body));
                 self.visit_statement(init);
                 let mut condition_string;
                 if let &Some(ref condition) = condition {
@@ -271,42 +525,135 @@ impl CodeGenVisitor{
                     condition_string = "true".to_string();
                 }
 
-                println!("{}for (;{};(function() {{",indent(self.indent), condition_string);
+                writeln!(self.output, "{}{}for (;{};(yield* (function* () {{",indent(self.indent),
+                         label_prefix(&js_label), condition_string).unwrap();
 
                 self.indent+=1;
-                println!("{}// post:",indent(self.indent));
+                writeln!(self.output, "{}// post:",indent(self.indent)).unwrap();
                 self.visit_statement(post);
                 self.indent-=1;
 
-                println!("{}}}() ) ) {{", indent(self.indent));
+                writeln!(self.output, "{}}}()) ) ) {{", indent(self.indent)).unwrap();
 
                 self.indent+=1;
+                self.jump_targets.push((go_label, js_label, true));
                 self.visit_statements(body);
+                self.jump_targets.pop();
                 self.indent-=1;
 
-                println!("{}}}",indent(self.indent));
+                writeln!(self.output, "{}}}",indent(self.indent)).unwrap();
+
+            },
+            StatementVariant::ForRange { ref key, ref value, ref expr, ref body } => {
+                let go_label = self.statement_label.take();
+                let js_label = self.jump_target_label(&go_label, &nested_statements(body));
+                let mut pre = String::new();
+                let mut post = String::new();
+                self.visit_expression(expr, &mut pre, &mut post);
+                write!(self.output, "{}", pre).unwrap();
+
+                // Channels give the values received until they are closed
+                if expr.kind.is_chan() {
+                    let channel = format!("ㄭ{}", self.create_id());
+                    let received = format!("ㄭ{}", self.create_id());
+                    writeln!(self.output, "{}const {} = {};", indent(self.indent), channel, post).unwrap();
+                    writeln!(self.output, "{}{}for (;;) {{", indent(self.indent), label_prefix(&js_label)).unwrap();
+                    self.indent += 1;
+                    writeln!(self.output, "{}const {} = yield* chan_recv_ok({}, {});", indent(self.indent),
+                             received, channel, expr.span.start.line).unwrap();
+                    writeln!(self.output, "{}if (!{}[1]) {{", indent(self.indent), received).unwrap();
+                    writeln!(self.output, "{}break;", indent(self.indent + 1)).unwrap();
+                    writeln!(self.output, "{}}}", indent(self.indent)).unwrap();
+                    if let Some(key) = key {
+                        if key != "_" {
+                            writeln!(self.output, "{}let {} = {}[0];", indent(self.indent), key, received).unwrap();
+                        }
+                    }
+                    self.jump_targets.push((go_label, js_label, true));
+                    self.visit_statements(body);
+                    self.jump_targets.pop();
+                    self.indent -= 1;
+                    writeln!(self.output, "{}}}", indent(self.indent)).unwrap();
+                    return;
+                }
+
+                let range = format!("ㄭ{}", self.create_id());
+                let index = format!("ㄭ{}", self.create_id());
+                let length = format!("ㄭ{}", self.create_id());
+
+                // The expression is evaluated once; arrays are copied but slices share their contents.
+                // Maps are iterated over their live entries, so deleted ones are skipped.
+                let (collection, key_string, value_string) = match expr.kind.resolve() {
+                    Kind::Map(..) => (
+                        format!("map_entries({})", post),
+                        format!("deepCopy({}[0])", index),
+                        format!("deepCopy({}[1])", index)),
+                    Kind::Basic(BasicKind::String) => (
+                        format!("string_runes({})", post),
+                        format!("{}[{}][0]", range, index),
+                        format!("{}[{}][1]", range, index)),
+                    Kind::Array(..) => (
+                        format!("deepCopy({})", post),
+                        index.clone(),
+                        format!("deepCopy({}[{}])", range, index)),
+                    _ => (
+                        post,
+                        index.clone(),
+                        format!("deepCopy({}.contents[{}.offset + {}])", range, range, index)),
+                };
+
+                writeln!(self.output, "{}var {} = {};", indent(self.indent), range, collection).unwrap();
+                if let Kind::Map(..) = expr.kind.resolve() {
+                    writeln!(self.output, "{}{}for (var {} of {}) {{",
+                             indent(self.indent), label_prefix(&js_label), index, range).unwrap();
+                } else {
+                    writeln!(self.output, "{}{}for (var {} = 0, {} = {}.length; {} < {}; {}++) {{",
+                             indent(self.indent), label_prefix(&js_label), index, length, range, index, length, index).unwrap();
+                }
+
+                self.indent+=1;
+                if let Some(key) = key {
+                    if key != "_" {
+                        writeln!(self.output, "{}let {} = {};", indent(self.indent), key, key_string).unwrap();
+                    }
+                }
+                if let Some(value) = value {
+                    if value != "_" {
+                        writeln!(self.output, "{}let {} = {};", indent(self.indent), value, value_string).unwrap();
+                    }
+                }
+                self.jump_targets.push((go_label, js_label, true));
+                self.visit_statements(body);
+                self.jump_targets.pop();
+                self.indent-=1;
 
+                writeln!(self.output, "{}}}",indent(self.indent)).unwrap();
             },
             StatementVariant::If { ref init, ref condition, ref if_branch, ref else_branch } => {
                 self.visit_statement(init);
                 let mut pre = String::new();
                 let mut post = String::new();
                 self.visit_expression(condition, &mut pre, &mut post);
-                print!("{}",pre);
-                println!("{}if ({}) {{",indent(self.indent),post);
+                write!(self.output, "{}",pre).unwrap();
+                writeln!(self.output, "{}if ({}) {{",indent(self.indent),post).unwrap();
                 self.indent+=1;
                 self.visit_statements(if_branch);
                 self.indent-=1;
-                println!("{}}} else {{",indent(self.indent));
+                writeln!(self.output, "{}}} else {{",indent(self.indent)).unwrap();
                 if let &Some(ref else_branch) = else_branch {
                     self.indent+=1;
                     self.visit_statement(else_branch);
                     self.indent-=1;
                 }
-                println!("{}}}",indent(self.indent));
+                writeln!(self.output, "{}}}",indent(self.indent)).unwrap();
 
             },
             StatementVariant::Switch { ref init, ref expr, ref body } => {
+                let go_label = self.statement_label.take();
+                let nested = body.iter()
+                    .flat_map(|case_clause| nested_statements(&case_clause.statements))
+                    .collect::<Vec<_>>();
+                let js_label = self.jump_target_label(&go_label, &nested);
                 self.visit_statement(init);
 
                     let mut pre;
@@ -315,69 +662,277 @@ impl CodeGenVisitor{
                     pre = String::new();
                     post = String::new();
                     self.visit_expression(expr, &mut pre, &mut post);
-                    print!("{}",pre);
+                    write!(self.output, "{}",pre).unwrap();
                 } else {
                     post = "true".to_string();
                 }
-                println!("{}switch ({}) {{",indent(self.indent),post);
+                // Interfaces are not compared by identity, so each case compares them instead
+                let interface_value = match *expr {
+                    Some(ref expr) if expr.kind.is_interface() => {
+                        let value = format!("ㄭ{}", self.create_id());
+                        writeln!(self.output, "{}const {} = {};", indent(self.indent), value, post).unwrap();
+                        post = "true".to_string();
+                        Some(value)
+                    }
+                    _ => None,
+                };
+                writeln!(self.output, "{}{}switch ({}) {{",indent(self.indent),label_prefix(&js_label),post).unwrap();
                 self.indent+=1;
+                self.jump_targets.push((go_label, js_label, false));
                 for case_clause in body {
                     match &case_clause.switch_case {
                         &SwitchCase::Default => {
-                            println!("{}default:", indent(self.indent));
+                            writeln!(self.output, "{}default:", indent(self.indent)).unwrap();
                         }
                         &SwitchCase::Cases(ref cases) => {
                             for case in cases {
                                 let mut case_code = String::new();
                                 self.codegen_expression_iife(&case, &mut case_code);
-                                println!("{}case {}:", indent(self.indent), case_code);
+                                if let Some(ref value) = interface_value {
+                                    case_code = format!("interface_eq({}, {}, {})", value, case_code,
+                                                        case.span.start.line);
+                                }
+                                writeln!(self.output, "{}case {}:", indent(self.indent), case_code).unwrap();
                             }
                         }
+                        &SwitchCase::Kinds(..) => panic!("Only type switches have kinds in their cases"),
+                        &SwitchCase::Comm(..) => panic!("Only select statements have sends and receives in their cases"),
                     }
                     self.indent+=1;
                     self.visit_statements(&case_clause.statements);
-                    println!("{}break;", indent(self.indent));
+                    if !case_clause.falls_through() {
+                        writeln!(self.output, "{}break;", indent(self.indent)).unwrap();
+                    }
                     self.indent-=1;
                 }
+                self.jump_targets.pop();
                 self.indent-=1;
-                println!("{}}}",indent(self.indent));
+                writeln!(self.output, "{}}}",indent(self.indent)).unwrap();
             },
-            StatementVariant::IncDec { ref expr, is_dec } => {
+            StatementVariant::TypeSwitch { ref init, ref binding, ref expr, ref body } => {
+                let go_label = self.statement_label.take();
+                let nested = body.iter()
+                    .flat_map(|case_clause| nested_statements(&case_clause.statements))
+                    .collect::<Vec<_>>();
+                let js_label = self.jump_target_label(&go_label, &nested);
+                self.visit_statement(init);
+
                 let mut pre = String::new();
                 let mut post = String::new();
                 self.visit_expression(expr, &mut pre, &mut post);
-                print!("{}",pre);
-                let function = 
-                match (is_dec, expr.kind.is_integer()) {
-                    (false, true) => "binary_Add_int",
-                    (true, true) => "binary_Sub_int",
-                    (false, false) => "binary_Add",
-                    (true, false) => "binary_Sub",
+                let value = format!("ㄭ{}", self.create_id());
+                write!(self.output, "{}", pre).unwrap();
+                writeln!(self.output, "{}const {} = {};", indent(self.indent), value, post).unwrap();
+
+                // The cases are tested in order, and the default clause is taken if none match
+                writeln!(self.output, "{}{}switch (true) {{", indent(self.indent), label_prefix(&js_label)).unwrap();
+                self.indent += 1;
+                self.jump_targets.push((go_label, js_label, false));
+                for case_clause in body {
+                    let mut bound = value.clone();
+                    match case_clause.switch_case {
+                        SwitchCase::Default => {
+                            writeln!(self.output, "{}default:", indent(self.indent)).unwrap();
+                        }
+                        SwitchCase::Kinds(ref kinds) => {
+                            for kind in kinds {
+                                let test = self.kind_test(&value, &kind.evaluated_kind);
+                                writeln!(self.output, "{}case {}:", indent(self.indent), test).unwrap();
+                            }
+                            // A case with a single kind gives the variable the value of that kind
+                            if kinds.len() == 1 && !kinds[0].evaluated_kind.is_interface() &&
+                                !matches!(kinds[0].evaluated_kind, Kind::Nil) {
+                                bound = format!("deepCopy({}.value)", value);
+                            }
+                        }
+                        SwitchCase::Cases(..) | SwitchCase::Comm(..) => {
+                            panic!("Type switches only have kinds in their cases")
+                        }
+                    }
+                    // Each clause is a block, since each one has its own variable
+                    writeln!(self.output, "{}{{", indent(self.indent)).unwrap();
+                    self.indent += 1;
+                    if let (Some(_), Some(name)) = (binding, &case_clause.binding) {
+                        writeln!(self.output, "{}{} {} = {};", indent(self.indent),
+                                 self.declaration_keyword, name, bound).unwrap();
+                    }
+                    self.visit_statements(&case_clause.statements);
+                    writeln!(self.output, "{}break;", indent(self.indent)).unwrap();
+                    self.indent -= 1;
+                    writeln!(self.output, "{}}}", indent(self.indent)).unwrap();
+                }
+                self.jump_targets.pop();
+                self.indent -= 1;
+                writeln!(self.output, "{}}}", indent(self.indent)).unwrap();
+            },
+            StatementVariant::Select { ref body } => {
+                let go_label = self.statement_label.take();
+                let nested = body.iter()
+                    .flat_map(|case_clause| nested_statements(&case_clause.statements))
+                    .collect::<Vec<_>>();
+                let js_label = self.jump_target_label(&go_label, &nested);
+
+                // The channels and the values to send are all evaluated before a case is chosen
+                let mut pre = String::new();
+                let mut cases = Vec::new();
+                for case_clause in body {
+                    if let SwitchCase::Comm(ref comm) = case_clause.switch_case {
+                        let mut channel_value = String::new();
+                        if let StatementVariant::Send { ref channel, ref value } = comm.variant {
+                            let mut sent = String::new();
+                            self.visit_expression(channel, &mut pre, &mut channel_value);
+                            self.visit_expression(value, &mut pre, &mut sent);
+                            cases.push(format!("[{}, true, deepCopy({})]", channel_value, sent));
+                        } else {
+                            let channel = comm.received_expression().received_channel();
+                            self.visit_expression(channel, &mut pre, &mut channel_value);
+                            cases.push(format!("[{}, false]", channel_value));
+                        }
+                    }
+                }
+                let has_default = body.iter().any(|case_clause| matches!(case_clause.switch_case, SwitchCase::Default));
+                let status = if cases.is_empty() { "select (no cases)" } else { "select" };
+
+                // The chosen case is given by its index, or -1 for the default clause
+                let selected = format!("ㄭ{}", self.create_id());
+                write!(self.output, "{}", pre).unwrap();
+                writeln!(self.output, "{}const {} = yield* select([{}], {}, {}, {});", indent(self.indent),
+                         selected, cases.join(", "), has_default, quote_string_literal(status),
+                         stmt.span.start.line).unwrap();
+                writeln!(self.output, "{}{}switch ({}[0]) {{", indent(self.indent), label_prefix(&js_label),
+                         selected).unwrap();
+                self.indent += 1;
+                self.jump_targets.push((go_label, js_label, false));
+                let mut case = 0;
+                for case_clause in body {
+                    match case_clause.switch_case {
+                        SwitchCase::Default => {
+                            writeln!(self.output, "{}default:", indent(self.indent)).unwrap();
+                        }
+                        SwitchCase::Comm(..) => {
+                            writeln!(self.output, "{}case {}:", indent(self.indent), case).unwrap();
+                            case += 1;
+                        }
+                        SwitchCase::Cases(..) | SwitchCase::Kinds(..) => {
+                            panic!("Select statements only have sends and receives in their cases")
+                        }
+                    }
+                    // Each clause is a block, since each one can declare its own variables
+                    writeln!(self.output, "{}{{", indent(self.indent)).unwrap();
+                    self.indent += 1;
+                    if let SwitchCase::Comm(ref comm) = case_clause.switch_case {
+                        self.codegen_received(comm, &selected);
+                    }
+                    self.visit_statements(&case_clause.statements);
+                    writeln!(self.output, "{}break;", indent(self.indent)).unwrap();
+                    self.indent -= 1;
+                    writeln!(self.output, "{}}}", indent(self.indent)).unwrap();
+                }
+                self.jump_targets.pop();
+                self.indent -= 1;
+                writeln!(self.output, "{}}}", indent(self.indent)).unwrap();
+            },
+            StatementVariant::IncDec { ref expr, is_dec } => {
+                let mut pre = String::new();
+                let (post, prefix, suffix) = self.visit_assignable(expr, &mut pre);
+                write!(self.output, "{}",pre).unwrap();
+                let op = if is_dec { BinaryOperator::Sub } else { BinaryOperator::Add };
+                let one = match expr.kind.resolve() {
+                    Kind::Basic(basic) if is_bigint(basic) => "1n",
+                    _ => "1",
                 };
-                print!("{}{} = {}({},1);\n",
+                writeln!(self.output, "{}{}{}{};",
                         indent(self.indent),
-                        post,
-                        function,
-                        post);
+                        prefix,
+                        binary_op_call(op, &expr.kind, &post, one, stmt.span.start.line),
+                        suffix).unwrap();
 
             }
         }
     }
 
+    // Assigns or declares the variables of the receive statement of the chosen select case, from
+    // what `select` gave. The expressions on the left are only evaluated once the case is chosen.
+    fn codegen_received(&mut self, comm: &StatementNode, selected: &str) {
+        // Sending cases have nothing to bind
+        if let StatementVariant::Send { .. } = comm.variant {
+            return;
+        }
+        let received = self.received_value(comm.received_expression(), selected);
+        let temp = format!("temp_{}", self.create_id());
+        match comm.variant {
+            StatementVariant::Assignment { ref lhs, .. } => {
+                let mut pre = String::new();
+                let lhs_strings = lhs.iter()
+                    .map(|expr| self.visit_assignable(expr, &mut pre))
+                    .collect::<Vec<_>>();
+                write!(self.output, "{}", pre).unwrap();
+                writeln!(self.output, "{}var {} = {};", indent(self.indent), temp, received).unwrap();
+                for (x, (_, prefix, suffix)) in lhs_strings.iter().enumerate() {
+                    writeln!(self.output, "{}{}deepCopy({}){};", indent(self.indent), prefix,
+                             assigned_value(::std::slice::from_ref(&temp), lhs_strings.len(), x), suffix).unwrap();
+                }
+            }
+            StatementVariant::ShortVariableDeclaration { ref identifier_list, ref is_assigning, .. } => {
+                writeln!(self.output, "{}var {} = {};", indent(self.indent), temp, received).unwrap();
+                for (x, name) in identifier_list.iter().enumerate() {
+                    if name == "_" {
+                        continue;
+                    }
+                    let value = assigned_value(::std::slice::from_ref(&temp), identifier_list.len(), x);
+                    if is_assigning[x] {
+                        writeln!(self.output, "{}{} = assign({}, deepCopy({}));", indent(self.indent),
+                                 name, name, value).unwrap();
+                    } else {
+                        writeln!(self.output, "{}{} {} = deepCopy({});", indent(self.indent),
+                                 self.declaration_keyword, name, value).unwrap();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // What the receive expression of a select case gives, from the array `select` gave
+    fn received_value(&mut self, expr: &Expression, selected: &str) -> String {
+        match expr.variant {
+            ExpressionVariant::Receive { .. } => format!("{}[1]", selected),
+            ExpressionVariant::CommaOk { .. } => format!("[{}[1], {}[2]]", selected, selected),
+            ExpressionVariant::ToInterface { ref expr } if expr.kind.is_interface() => {
+                self.received_value(expr, selected)
+            }
+            ExpressionVariant::ToInterface { ref expr } => {
+                let descriptor = self.type_descriptor(&expr.kind);
+                format!("to_interface({}, {})", descriptor, self.received_value(expr, selected))
+            }
+            _ => panic!("A select case that isn't a send must be a receive"),
+        }
+    }
+
     fn codegen_print(&mut self,
                      exprs: &Vec<Expression>,
                      is_println: bool) {
         let mut pre = String::new();
+        let mut values = Vec::new();
+        for expr in exprs.iter() {
+            let mut value = String::new();
+            self.visit_expression(expr, &mut pre, &mut value);
+            values.push(value);
+        }
+        let post = self.print_calls(exprs, &values, is_println);
+        writeln!(self.output, "{}{}", pre, post).unwrap();
+    }
+
+    // The calls writing the values of the expressions of a print or println statement
+    fn print_calls(&self, exprs: &[Expression], values: &[String], is_println: bool) -> String {
         let mut post = String::new();
-        for (i,expr) in exprs.iter().enumerate() {
+        for (i, (expr, value)) in exprs.iter().zip(values.iter()).enumerate() {
             let function = 
                 match expr.kind.resolve() {
-                    Kind::Basic( BasicKind::Float) => "print_float",
+                    Kind::Basic(basic) if basic.is_float() => "print_float",
                     _ => "print_not_float",
                 };
-            write!(post,"{}{}(", indent(self.indent), function).unwrap();
-            self.visit_expression(expr, &mut pre, &mut post);
-            write!(post,");\n").unwrap();
+            writeln!(post,"{}{}({});", indent(self.indent), function, value).unwrap();
             if is_println && i < exprs.len()-1 { 
                 write!(post,"{}print_not_float(\" \");\n", indent(self.indent)).unwrap();
             }
@@ -385,7 +940,103 @@ impl CodeGenVisitor{
         if is_println {
             write!(post,"{}print_not_float(\"\\n\");\n", indent(self.indent)).unwrap();
         }
-        println!("{}{}", pre, post);
+        post
+    }
+
+    // The function and the arguments of a deferred call are evaluated by the defer statement, into
+    // temporaries that the generator function pushed on `ㄭdefers` calls it with. A go statement
+    // evaluates them the same way, for the goroutine it starts.
+    fn codegen_deferred_call(&mut self, call: &StatementNode, start: &str) {
+        let mut pre = String::new();
+        let body = match call.variant {
+            StatementVariant::Expression(ref exp) => match exp.variant {
+                ExpressionVariant::FunctionCall { ref primary, ref arguments, spread } => {
+                    let mut operands = Vec::new();
+                    let callee = if let ExpressionVariant::Method { ref receiver, ref function, pointer_receiver, .. } = primary.variant {
+                        let mut value = String::new();
+                        self.visit_receiver(receiver, pointer_receiver, &mut pre, &mut value);
+                        operands.push(self.defer_operand(value, &mut pre));
+                        function.clone()
+                    } else {
+                        let mut value = String::new();
+                        self.visit_expression(primary, &mut pre, &mut value);
+                        format!("check_function({}, {})", self.defer_operand(value, &mut pre),
+                                exp.span.start.line)
+                    };
+                    let first = operands.len();
+                    for arg in arguments.iter() {
+                        let mut value = String::new();
+                        self.visit_expression(arg, &mut pre, &mut value);
+                        operands.push(self.defer_operand(value, &mut pre));
+                    }
+                    if let Some(fixed) = primary.packed_arguments(spread) {
+                        let packed = packed_slice(&operands.split_off(first + fixed));
+                        operands.push(packed);
+                    }
+                    format!("{}at_line({});\n{}yield* {}({});\n", indent(self.indent + 1), exp.span.start.line,
+                            indent(self.indent + 1), callee, operands.join(", "))
+                }
+                ExpressionVariant::Delete { ref map, ref key } => {
+                    let mut map_value = String::new();
+                    self.visit_expression(map, &mut pre, &mut map_value);
+                    let map_value = self.defer_operand(map_value, &mut pre);
+                    let mut key_value = String::new();
+                    self.visit_expression(key, &mut pre, &mut key_value);
+                    let key_value = self.defer_operand(key_value, &mut pre);
+                    // The key is checked when the deferred call runs
+                    format!("{}map_delete({}, check_key({}, {}));\n", indent(self.indent + 1), map_value,
+                            key_value, key.span.start.line)
+                }
+                ExpressionVariant::Panic { expr: ref value } => {
+                    let mut panic_value = String::new();
+                    self.visit_expression(value, &mut pre, &mut panic_value);
+                    let panic_value = self.defer_operand(panic_value, &mut pre);
+                    format!("{}go_panic({}, {});\n", indent(self.indent + 1),
+                            panic_text(&panic_value, &value.kind), exp.span.start.line)
+                }
+                ExpressionVariant::Close { ref channel } => {
+                    let mut channel_value = String::new();
+                    self.visit_expression(channel, &mut pre, &mut channel_value);
+                    let channel_value = self.defer_operand(channel_value, &mut pre);
+                    format!("{}chan_close({}, {});\n", indent(self.indent + 1), channel_value,
+                            exp.span.start.line)
+                }
+                _ => panic!("deferring something that isn't a call"),
+            },
+            StatementVariant::Print { ref exprs } | StatementVariant::Println { ref exprs } => {
+                let mut values = Vec::new();
+                for expr in exprs.iter() {
+                    let mut value = String::new();
+                    self.visit_expression(expr, &mut pre, &mut value);
+                    values.push(self.defer_operand(value, &mut pre));
+                }
+                let is_println = matches!(call.variant, StatementVariant::Println { .. });
+                self.indent += 1;
+                let calls = self.print_calls(exprs, &values, is_println);
+                self.indent -= 1;
+                calls
+            }
+            _ => panic!("deferring something that isn't a call"),
+        };
+
+        write!(self.output, "{}", pre).unwrap();
+        write!(self.output, "{}{}(function* () {{\n{}{}}});\n",
+               indent(self.indent), start, body, indent(self.indent)).unwrap();
+    }
+
+    // Map keys are checked when they are used, like in the interpreter
+    fn visit_map_key(&mut self, key: &Expression, pre_string: &mut String, post_string: &mut String) {
+        write!(post_string, "check_key(").unwrap();
+        self.visit_expression(key, pre_string, post_string);
+        write!(post_string, ", {})", key.span.start.line).unwrap();
+    }
+
+    // Keeps a copy of a value for a deferred call. Each one is a new constant, so closures pushed
+    // in a loop do not share them.
+    fn defer_operand(&mut self, value: String, pre: &mut String) -> String {
+        let temp = format!("ㄭ{}", self.create_id());
+        writeln!(pre, "{}const {} = deepCopy({});", indent(self.indent), temp, value).unwrap();
+        temp
     }
 
     // "immediately invoked function expression"
@@ -397,9 +1048,9 @@ impl CodeGenVisitor{
         self.indent+= 1;
         self.visit_expression(exp, &mut new_pre, &mut new_post);
         self.indent-= 1;
-        write!(post_string, "(function() {{\n\
+        write!(post_string, "(yield* (function* () {{\n\
             {}\
-            {}return {};}}())",
+            {}return {};}}()))",
             new_pre,
             indent(self.indent + 1),
             new_post).unwrap();
@@ -419,54 +1070,25 @@ impl CodeGenVisitor{
         match exp.variant {
             ExpressionVariant::RawLiteral{ ref value } => {
                 match exp.kind {
+                    Kind::Basic(_) if is_rune_literal(value) => {
+                        let code_no = parse_rune_literal(value);
+                        write!(post_string, "{}", code_no).unwrap();
+                    },
                     Kind::Basic(BasicKind::Int) | 
                     Kind::Basic(BasicKind::Float) => {
                         write!(post_string, "{}", value).unwrap();
                     },
-                    Kind::Basic(BasicKind::Rune) => {
-                        let code_no = parse_rune_literal(value);
-                        write!(post_string, "{}", code_no).unwrap();
+                    Kind::Basic(basic) if is_bigint(basic) => {
+                        write!(post_string, "{}n", string_to_u64(value)).unwrap();
+                    },
+                    Kind::Basic(BasicKind::Float32) => {
+                        write!(post_string, "Math.fround({})", value).unwrap();
+                    },
+                    Kind::Basic(basic) if basic.is_integer() => {
+                        write!(post_string, "{}", string_to_u64(value)).unwrap();
                     },
                     Kind::Basic(BasicKind::String) => {
-                        let letter = &value[0..1];
-                        match letter {
-                            "`" => { // Raw
-                                let mut new_string = String::new();
-                                for c in value.chars() {
-                                    if c == '`' {
-                                        continue;
-                                    }
-                                    if c == '\\' || c == '"' {
-                                        new_string = format!("{}{}", new_string, "\\");
-                                    }
-                                    new_string = format!("{}{}", new_string, c);
-                                }
-                                write!(post_string, "\"{}\"", new_string).unwrap();
-                            },
-                            "\"" => { // Interpreted
-                                let mut new_string = String::new();
-                                let char_vec: Vec<_> = value.chars().collect::<Vec<_>>();
-                                let mut skip_next_char = false;
-                                for (id, &c) in char_vec.iter().enumerate() {
-                                    if skip_next_char {
-                                        skip_next_char = false;
-                                        continue;
-                                    }
-                                    if c == '\\' 
-                                        && id < char_vec.len()-2
-                                        && char_vec[id+1] == 'a' {
-                                        new_string = format!("{}\\007", new_string);
-                                        skip_next_char = true;
-                                    } else {
-                                        new_string = format!("{}{}", new_string, c);
-                                    }
-                                }
-                                write!(post_string, "{}", new_string).unwrap();
-                            }
-                            _ => {
-                                panic!("A string should be either interpreted or raw");
-                            }
-                        }
+                        write!(post_string, "{}", js_string_literal(&parse_string_literal(value))).unwrap();
                     }
                     _ => {
                         panic!("Invalid type of typecasted expression");
@@ -479,9 +1101,10 @@ impl CodeGenVisitor{
             }
 
             ExpressionVariant::UnaryOperation { ref op, ref rhs } => {
-                write!(post_string, "{}(", generate_unary_op(op)).unwrap();
-                self.visit_expression(rhs, pre_string, post_string);
-                write!(post_string, ")").unwrap();
+                let mut value = format!("{}(", generate_unary_op(op));
+                self.visit_expression(rhs, pre_string, &mut value);
+                write!(value, ")").unwrap();
+                write!(post_string, "{}", fit_kind(&exp.kind, value)).unwrap();
             }
 
             ExpressionVariant::BinaryOperation { ref op, ref lhs, ref rhs } => {
@@ -493,29 +1116,23 @@ impl CodeGenVisitor{
                     self.codegen_expression_iife(rhs,  post_string);
                     write!(post_string, ")").unwrap();
                 } else {
-                    write!(post_string, "{}", generate_binary_op(op)).unwrap();
-
-                    if exp.kind.is_integer() {
-                        match op {
-                            &BinaryOperator::Add |
-                                &BinaryOperator::Sub |
-                                &BinaryOperator::Mul |
-                                &BinaryOperator::Div => {
-                                    write!(post_string, "_int").unwrap();
-                                }
-                            _ => {}
-                        }
+                    let mut lhs_value = String::new();
+                    self.visit_expression(lhs, pre_string, &mut lhs_value);
+                    let mut rhs_value = String::new();
+                    self.visit_expression(rhs, pre_string, &mut rhs_value);
+                    if lhs.kind.is_interface() {
+                        // Comparing values of an uncomparable type panics
+                        let negation = if *op == BinaryOperator::Neq { "!" } else { "" };
+                        write!(post_string, "{}interface_eq({}, {}, {})", negation, lhs_value,
+                               rhs_value, exp.span.start.line).unwrap();
+                        return;
                     }
-
-                    write!(post_string, "(").unwrap();
-                    self.visit_expression(lhs, pre_string, post_string);
-                    write!(post_string, ", ").unwrap();
-                    self.visit_expression(rhs, pre_string, post_string);
-                    write!(post_string, ")").unwrap();
+                    write!(post_string, "{}", binary_op_call(*op, &exp.kind, &lhs_value, &rhs_value,
+                                                             exp.span.start.line)).unwrap();
                 }
             }
 
-            ExpressionVariant::FunctionCall { ref primary, ref arguments } => {
+            ExpressionVariant::FunctionCall { ref primary, ref arguments, spread } => {
                 let tmp_id = self.create_id();
                 let mut new_pre_string = String::new();
                 let mut new_post_string = String::new();
@@ -524,102 +1141,780 @@ impl CodeGenVisitor{
                 write!(post_string, "ⴵ_{}", tmp_id).unwrap();
 
                 // Execute function call outside using different post/prestrings
-                write!(new_post_string, "var ⴵ_{} = ", tmp_id).unwrap();
-
-                // Print primary to new_post_string
-                self.visit_expression(primary, &mut new_pre_string, &mut new_post_string);
+                write!(new_post_string, "var ⴵ_{} = yield* ", tmp_id).unwrap();
+
+                let mut operands = Vec::new();
+                if let ExpressionVariant::Method { ref receiver, ref function, pointer_receiver, .. } = primary.variant {
+                    // Methods are called directly, with the receiver first
+                    write!(new_post_string, "{}(", function).unwrap();
+                    let mut value = String::new();
+                    self.visit_receiver(receiver, pointer_receiver, &mut new_pre_string, &mut value);
+                    operands.push(value);
+                } else {
+                    // Print primary to new_post_string. Function values can be nil.
+                    write!(new_post_string, "check_function(").unwrap();
+                    self.visit_expression(primary, &mut new_pre_string, &mut new_post_string);
+                    write!(new_post_string, ", {})(", exp.span.start.line).unwrap();
+                }
 
                 // Print arguments to new_post_string
-                write!(new_post_string, "(").unwrap();
-                for (i,arg) in arguments.iter().enumerate() {
-                    write!(new_post_string, "deepCopy(").unwrap();
-                    self.visit_expression(arg, &mut new_pre_string, &mut new_post_string);
-                    write!(new_post_string, ")").unwrap();
-                    if i < arguments.len() - 1 {
-                        write!(new_post_string, ", ").unwrap();
-                    }
+                let first = operands.len();
+                for arg in arguments.iter() {
+                    let mut value = String::new();
+                    self.visit_expression(arg, &mut new_pre_string, &mut value);
+                    operands.push(format!("deepCopy({})", value));
+                }
+                if let Some(fixed) = primary.packed_arguments(spread) {
+                    let packed = packed_slice(&operands.split_off(first + fixed));
+                    operands.push(packed);
                 }
-                write!(new_post_string, ");").unwrap();
+                write!(new_post_string, "{});", operands.join(", ")).unwrap();
 
-                // Add all hoisted calls, and the new func call to pre_string.
+                // Add all hoisted calls, and the new func call to pre_string. The line of the call
+                // is kept for stack traces.
                 write!(pre_string, 
-                       "{}{}{}\n", 
+                       "{}{}at_line({});\n{}{}\n", 
                        &mut new_pre_string, 
                        indent(self.indent), 
+                       exp.span.start.line,
+                       indent(self.indent), 
                        &mut new_post_string).unwrap();
             }
 
+            ExpressionVariant::FunctionLiteral { ref function } => {
+                // The body is written on its own and then put in the expression. JavaScript
+                // closures share the variables they capture, like Go closures.
+                let outer = mem::take(&mut self.output);
+                self.visit_function("", function);
+                let literal = mem::replace(&mut self.output, outer);
+                write!(post_string, "({})", literal).unwrap();
+            }
+
+            ExpressionVariant::Method { ref receiver, ref function, pointer_receiver, .. } => {
+                write!(post_string, "bind_method({}, ", function).unwrap();
+                self.visit_receiver(receiver, pointer_receiver, pre_string, post_string);
+                write!(post_string, ")").unwrap();
+            }
+
+            // Interfaces put in other interfaces keep the value they hold
+            ExpressionVariant::ToInterface { ref expr } if expr.kind.is_interface() => {
+                self.visit_expression(expr, pre_string, post_string);
+            }
+
+            ExpressionVariant::ToInterface { ref expr } => {
+                let descriptor = self.type_descriptor(&expr.kind);
+                write!(post_string, "to_interface({}, ", descriptor).unwrap();
+                self.visit_expression(expr, pre_string, post_string);
+                write!(post_string, ")").unwrap();
+            }
+
+            ExpressionVariant::InterfaceMethod { ref receiver, ref name } => {
+                write!(post_string, "interface_method(").unwrap();
+                self.visit_expression(receiver, pre_string, post_string);
+                write!(post_string, ", {}, {})", quote_string_literal(name), exp.span.start.line).unwrap();
+            }
+
+            ExpressionVariant::TypeAssertion { ref expr, ref kind } => {
+                let mut value = String::new();
+                self.visit_expression(expr, pre_string, &mut value);
+                let kind = &kind.evaluated_kind;
+                let interface_name = quote_string_literal(&expr.kind.runtime_name());
+                if kind.is_interface() {
+                    write!(post_string, "interface_assert({}, {}, {}, {}, {})", value,
+                           method_signatures(kind), quote_string_literal(&kind.runtime_name()),
+                           interface_name, exp.span.start.line).unwrap();
+                } else {
+                    write!(post_string, "type_assert({}, {}, {}, {})", value,
+                           self.type_descriptor(kind), interface_name, exp.span.start.line).unwrap();
+                }
+            }
+
             ExpressionVariant::Index { ref primary, ref index } => {
 
                 let mut primary_value = "".to_string();
                 let mut index_value = "".to_string();
                 self.visit_expression(primary, pre_string, &mut primary_value);
+
+                // Missing keys give the zero value of the elements
+                if let Kind::Map(..) = primary.kind.resolve() {
+                    self.visit_map_key(index, pre_string, &mut index_value);
+                    let mut zero = String::new();
+                    self.visit_var_initialization(&exp.kind, &mut zero);
+                    write!(post_string, "map_get({}, {}, {})", primary_value, index_value, zero).unwrap();
+                    return;
+                }
                 self.visit_expression(index, pre_string, &mut index_value);
 
                 match primary.kind {
                     Kind::Slice(..) =>  {
-                        write!(post_string, "{}.contents", primary_value).unwrap();
+                        write!(post_string, "{}.contents[{}.offset + ", primary_value, primary_value).unwrap();
                     },
                     Kind::Array(..) =>  {
-                        write!(post_string, "{}", primary_value).unwrap();
+                        write!(post_string, "{}[", primary_value).unwrap();
                     },
                     _ => panic!("codegening index of something other than slice or array")
                 };
 
-                write!(post_string, "[check_bounds({}, {}.length, {})]",
-                index_value, primary_value, exp.line_number).unwrap();
+                write!(post_string, "check_bounds({}, {}.length, {})]",
+                index_value, primary_value, exp.span.start.line).unwrap();
             }
 
             ExpressionVariant::Selector { ref primary, ref name } => {
-                self.visit_expression(primary, pre_string, post_string);
+                if primary.kind.is_pointer() {
+                    write!(post_string, "dereference(").unwrap();
+                    self.visit_expression(primary, pre_string, post_string);
+                    write!(post_string, ", {})", exp.span.start.line).unwrap();
+                } else {
+                    self.visit_expression(primary, pre_string, post_string);
+                }
                 write!(post_string, ".ㆭ{}", name).unwrap();
             }
 
+            ExpressionVariant::Dereference { ref expr } => {
+                write!(post_string, "dereference(").unwrap();
+                self.visit_expression(expr, pre_string, post_string);
+                write!(post_string, ", {})", exp.span.start.line).unwrap();
+            }
+
+            ExpressionVariant::AddressOf { ref expr } => {
+                self.visit_address(expr, pre_string, post_string);
+            }
+
+            ExpressionVariant::New { ref kind } => {
+                write!(post_string, "new_pointer(").unwrap();
+                self.visit_var_initialization(&kind.evaluated_kind, post_string);
+                write!(post_string, ")").unwrap();
+            }
+
+            ExpressionVariant::Nil => {
+                write!(post_string, "null").unwrap();
+            }
+
             ExpressionVariant::Append { ref lhs, ref rhs } => {
                 write!(post_string, "append(").unwrap();
                 self.visit_expression(lhs, pre_string, post_string);
                 write!(post_string, ", ").unwrap();
                 self.visit_expression(rhs, pre_string, post_string);
+                write!(post_string, ", ").unwrap();
+                match exp.kind.resolve() {
+                    Kind::Slice(ref element) => self.visit_var_initialization(element, post_string),
+                    _ => panic!("codegening append to something other than a slice"),
+                }
+                write!(post_string, ")").unwrap();
+            }
+
+            ExpressionVariant::Slice { ref primary, ref low, ref high, ref max } => {
+                write!(post_string, "slice(").unwrap();
+                self.visit_expression(primary, pre_string, post_string);
+                for index in [low, high, max].iter() {
+                    write!(post_string, ", ").unwrap();
+                    match **index {
+                        Some(ref index) => self.visit_expression(index, pre_string, post_string),
+                        None => write!(post_string, "undefined").unwrap(),
+                    }
+                }
+                write!(post_string, ", {})", primary.span.start.line).unwrap();
+            }
+
+            ExpressionVariant::Len { ref expr } => {
+                if let Kind::Map(..) = expr.kind.resolve() {
+                    write!(post_string, "map_len(").unwrap();
+                } else {
+                    write!(post_string, "len(").unwrap();
+                }
+                self.visit_expression(expr, pre_string, post_string);
+                write!(post_string, ")").unwrap();
+            }
+
+            ExpressionVariant::Cap { ref expr } => {
+                write!(post_string, "cap(").unwrap();
+                self.visit_expression(expr, pre_string, post_string);
                 write!(post_string, ")").unwrap();
             }
 
             ExpressionVariant::TypeCast { ref expr, .. } => {
-                if exp.kind.is_string() && expr.kind.is_integer() {
-                    write!(post_string, "String.fromCharCode(").unwrap();
-                    self.visit_expression(expr, pre_string, post_string);
-                    write!(post_string, ")").unwrap();
-                } else if exp.kind.is_integer() && expr.kind.is_floating_point(){
-                    write!(post_string, "Math.trunc(").unwrap();
+                let (to, from) = (exp.kind.resolve(), expr.kind.resolve());
+                if to.is_string() && from.is_integer() {
+                    write!(post_string, "string_of_int(").unwrap();
                     self.visit_expression(expr, pre_string, post_string);
                     write!(post_string, ")").unwrap();
+                } else if to.is_integer() && from.is_numeric() && !are_identical(&to, &from) {
+                    let mut value = String::new();
+                    self.visit_expression(expr, pre_string, &mut value);
+                    if let Kind::Basic(basic) = to {
+                        write!(post_string, "convert_int({}, {}, {})", value,
+                               basic.integer_bits().unwrap(), !basic.is_unsigned()).unwrap();
+                    }
+                } else if to.is_floating_point() && from.is_numeric() && !are_identical(&to, &from) {
+                    let mut value = String::new();
+                    self.visit_expression(expr, pre_string, &mut value);
+                    write!(post_string, "{}", fit_kind(&to, format!("Number({})", value))).unwrap();
                 } else {
                     // Do nothing at all
                     self.visit_expression(expr, pre_string, post_string);
                 }
             }
+
+            ExpressionVariant::Make { ref kind, ref arguments } if kind.evaluated_kind.is_chan() => {
+                let mut zero = String::new();
+                if let Kind::Chan(ref base) = kind.evaluated_kind.resolve() {
+                    self.visit_var_initialization(base, &mut zero);
+                }
+                write!(post_string, "make_chan(").unwrap();
+                match arguments.first() {
+                    Some(size) => self.visit_expression(size, pre_string, post_string),
+                    None => write!(post_string, "0").unwrap(),
+                }
+                write!(post_string, ", {}, {})", zero, exp.span.start.line).unwrap();
+            }
+
+            ExpressionVariant::Make { ref kind, ref arguments } if kind.evaluated_kind.is_slice() => {
+                let mut length = String::new();
+                self.visit_expression(&arguments[0], pre_string, &mut length);
+                // The capacity is the length if it is left out
+                let mut capacity = String::new();
+                let capacity_line = match arguments.get(1) {
+                    Some(argument) => {
+                        self.visit_expression(argument, pre_string, &mut capacity);
+                        argument.span.start.line
+                    }
+                    None => {
+                        capacity = length.clone();
+                        arguments[0].span.start.line
+                    }
+                };
+                let mut zero = String::new();
+                if let Kind::Slice(ref element) = kind.evaluated_kind.resolve() {
+                    self.visit_var_initialization(element, &mut zero);
+                }
+                write!(post_string, "make_slice({}, {}, {}, {}, {})", length, capacity, zero,
+                       arguments[0].span.start.line, capacity_line).unwrap();
+            }
+
+            ExpressionVariant::Make { ref arguments, .. } => {
+                write!(post_string, "make_map([]").unwrap();
+                for arg in arguments.iter() {
+                    write!(post_string, ", ").unwrap();
+                    self.visit_expression(arg, pre_string, post_string);
+                    write!(post_string, ", {}", exp.span.start.line).unwrap();
+                }
+                write!(post_string, ")").unwrap();
+            }
+
+            ExpressionVariant::CompositeLiteral { ref elements, .. } => {
+                match exp.kind.resolve() {
+                    Kind::Struct(ref fields) => {
+                        // The values are visited in the order they are written, which is the
+                        // order the calls taken out of them are made in
+                        let mut values = HashMap::new();
+                        for element in elements.iter() {
+                            let mut value = String::new();
+                            self.visit_expression(&element.value, pre_string, &mut value);
+                            if let Some(Expression { variant: ExpressionVariant::Identifier { ref name, .. }, .. }) = element.key {
+                                values.insert(name.clone(), value);
+                            }
+                        }
+                        write!(post_string, "{{").unwrap();
+                        for field in fields.iter() {
+                            write!(post_string, "ㆭ{}: ", field.name).unwrap();
+                            match values.get(&field.name) {
+                                Some(value) => write!(post_string, "deepCopy({})", value).unwrap(),
+                                None => self.visit_var_initialization(&field.kind, post_string),
+                            }
+                            write!(post_string, ", ").unwrap();
+                        }
+                        write!(post_string, "}}").unwrap();
+                    }
+                    Kind::Array(ref base, length) => {
+                        self.visit_literal_array(base, length as usize, elements, pre_string, post_string);
+                    }
+                    Kind::Slice(ref base) => {
+                        let length = elements.iter().filter_map(|e| e.key.as_ref())
+                            .map(|key| match key.variant {
+                                ExpressionVariant::RawLiteral { ref value } => value.parse::<usize>().unwrap() + 1,
+                                _ => panic!("The index of a literal element is an int literal"),
+                            })
+                            .max().unwrap_or(0);
+                        write!(post_string, "{{offset: 0, length: {}, capacity: {}, contents: ", length, length).unwrap();
+                        self.visit_literal_array(base, length, elements, pre_string, post_string);
+                        write!(post_string, "}}").unwrap();
+                    }
+                    _ => {
+                        write!(post_string, "make_map([").unwrap();
+                        for (i, element) in elements.iter().enumerate() {
+                            write!(post_string, "[").unwrap();
+                            if let Some(ref key) = element.key {
+                                self.visit_map_key(key, pre_string, post_string);
+                            }
+                            write!(post_string, ", deepCopy(").unwrap();
+                            self.visit_expression(&element.value, pre_string, post_string);
+                            write!(post_string, ")]").unwrap();
+                            if i < elements.len() - 1 {
+                                write!(post_string, ", ").unwrap();
+                            }
+                        }
+                        write!(post_string, "])").unwrap();
+                    }
+                }
+            }
+
+            ExpressionVariant::Panic { ref expr } => {
+                let mut value = String::new();
+                self.visit_expression(expr, pre_string, &mut value);
+                write!(post_string, "go_panic({}, {})", panic_text(&value, &expr.kind),
+                       exp.span.start.line).unwrap();
+            }
+
+            // Receives are taken out of the expression like calls, since they can block
+            ExpressionVariant::Receive { ref channel } => {
+                let mut channel_value = String::new();
+                self.visit_expression(channel, pre_string, &mut channel_value);
+                let tmp_id = self.create_id();
+                writeln!(pre_string, "{}var ⴵ_{} = yield* chan_recv({}, {});", indent(self.indent), tmp_id,
+                         channel_value, exp.span.start.line).unwrap();
+                write!(post_string, "ⴵ_{}", tmp_id).unwrap();
+            }
+
+            ExpressionVariant::Close { ref channel } => {
+                write!(post_string, "chan_close(").unwrap();
+                self.visit_expression(channel, pre_string, post_string);
+                write!(post_string, ", {})", exp.span.start.line).unwrap();
+            }
+
+            ExpressionVariant::Copy { ref dst, ref src } => {
+                write!(post_string, "copy(").unwrap();
+                self.visit_expression(dst, pre_string, post_string);
+                write!(post_string, ", ").unwrap();
+                self.visit_expression(src, pre_string, post_string);
+                write!(post_string, ")").unwrap();
+            }
+
+            ExpressionVariant::Delete { ref map, ref key } => {
+                write!(post_string, "map_delete(").unwrap();
+                self.visit_expression(map, pre_string, post_string);
+                write!(post_string, ", ").unwrap();
+                self.visit_map_key(key, pre_string, post_string);
+                write!(post_string, ")").unwrap();
+            }
+
+            // Gives an array with the element and whether the key was found
+            ExpressionVariant::CommaOk { ref expr } => {
+                if let ExpressionVariant::Index { ref primary, ref index } = expr.variant {
+                    let mut zero = String::new();
+                    self.visit_var_initialization(&expr.kind, &mut zero);
+                    write!(post_string, "map_lookup(").unwrap();
+                    self.visit_expression(primary, pre_string, post_string);
+                    write!(post_string, ", ").unwrap();
+                    self.visit_map_key(index, pre_string, post_string);
+                    write!(post_string, ", {})", zero).unwrap();
+                } else if let ExpressionVariant::TypeAssertion { expr: ref interface, ref kind } = expr.variant {
+                    let mut value = String::new();
+                    self.visit_expression(interface, pre_string, &mut value);
+                    let kind = &kind.evaluated_kind;
+                    if kind.is_interface() {
+                        write!(post_string, "interface_assert_ok({}, {})", value,
+                               method_signatures(kind)).unwrap();
+                    } else {
+                        let mut zero = String::new();
+                        self.visit_var_initialization(kind, &mut zero);
+                        write!(post_string, "type_assert_ok({}, {}, {})", value,
+                               self.type_descriptor(kind), zero).unwrap();
+                    }
+                } else if let ExpressionVariant::Receive { ref channel } = expr.variant {
+                    let mut channel_value = String::new();
+                    self.visit_expression(channel, pre_string, &mut channel_value);
+                    let tmp_id = self.create_id();
+                    writeln!(pre_string, "{}var ⴵ_{} = yield* chan_recv_ok({}, {});", indent(self.indent),
+                             tmp_id, channel_value, expr.span.start.line).unwrap();
+                    write!(post_string, "ⴵ_{}", tmp_id).unwrap();
+                } else {
+                    panic!("Only map lookups, type assertions and receives can give comma-ok values");
+                }
+            }
+        }
+    }
+
+    // The receiver given to a method: a copy of the value, or a pointer for a pointer receiver
+    fn visit_receiver(&mut self,
+                      receiver: &Expression,
+                      pointer_receiver: bool,
+                      pre_string: &mut String,
+                      post_string: &mut String) {
+        match (pointer_receiver, receiver.kind.is_pointer()) {
+            (true, true) => self.visit_expression(receiver, pre_string, post_string),
+            (true, false) => self.visit_address(receiver, pre_string, post_string),
+            (false, true) => {
+                write!(post_string, "deepCopy(dereference(").unwrap();
+                self.visit_expression(receiver, pre_string, post_string);
+                write!(post_string, ", {}))", receiver.span.start.line).unwrap();
+            }
+            (false, false) => {
+                write!(post_string, "deepCopy(").unwrap();
+                self.visit_expression(receiver, pre_string, post_string);
+                write!(post_string, ")").unwrap();
+            }
         }
     }
 
+    // A pointer to an addressable expression. Pointers are Pointer objects whose `get` and `set`
+    // read and write what they point to.
+    fn visit_address(&mut self,
+                     exp: &Expression,
+                     pre_string: &mut String,
+                     post_string: &mut String) {
+        match exp.variant {
+            ExpressionVariant::Identifier { ref name, .. } => {
+                write!(post_string, "new Pointer(() => {}, (v) => {} = v)", name, name).unwrap();
+            }
+            // `&*p` is `p`
+            ExpressionVariant::Dereference { ref expr } => {
+                self.visit_expression(expr, pre_string, post_string);
+            }
+            ExpressionVariant::Selector { ref primary, ref name } => {
+                write!(post_string, "field_pointer(").unwrap();
+                if primary.kind.is_pointer() {
+                    self.visit_expression(primary, pre_string, post_string);
+                } else {
+                    self.visit_address(primary, pre_string, post_string);
+                }
+                write!(post_string, ", 'ㆭ{}', {})", name, exp.span.start.line).unwrap();
+            }
+            ExpressionVariant::Index { ref primary, ref index } => {
+                // The elements of a slice are in its contents, which the pointer keeps
+                if let Kind::Slice(..) = primary.kind.resolve() {
+                    write!(post_string, "slice_element_pointer(").unwrap();
+                    self.visit_expression(primary, pre_string, post_string);
+                } else {
+                    write!(post_string, "array_element_pointer(").unwrap();
+                    self.visit_address(primary, pre_string, post_string);
+                }
+                write!(post_string, ", ").unwrap();
+                self.visit_expression(index, pre_string, post_string);
+                write!(post_string, ", {})", exp.span.start.line).unwrap();
+            }
+            // `&T{}` points to a new variable holding the literal
+            ExpressionVariant::CompositeLiteral { .. } => {
+                write!(post_string, "new_pointer(").unwrap();
+                self.visit_expression(exp, pre_string, post_string);
+                write!(post_string, ")").unwrap();
+            }
+            _ => panic!("Taking the address of something that isn't addressable"),
+        }
+    }
+
+    // The contents of an array or slice literal, whose elements were given their index as their
+    // key by the typechecker
+    fn visit_literal_array(&mut self,
+                           base: &Kind,
+                           length: usize,
+                           elements: &[KeyedElement],
+                           pre_string: &mut String,
+                           post_string: &mut String) {
+        write!(post_string, "literal_array({}, ", length).unwrap();
+        self.visit_var_initialization(base, post_string);
+        write!(post_string, ", [").unwrap();
+        for element in elements.iter() {
+            write!(post_string, "[").unwrap();
+            if let Some(ref key) = element.key {
+                self.visit_expression(key, pre_string, post_string);
+            }
+            write!(post_string, ", deepCopy(").unwrap();
+            self.visit_expression(&element.value, pre_string, post_string);
+            write!(post_string, ")], ").unwrap();
+        }
+        write!(post_string, "])").unwrap();
+    }
+
+    // Gives the code that reads an expression being assigned to, and the code that goes before
+    // and after the assigned value. Map elements can't be assigned to in JS, so they are set
+    // with a call instead.
+    fn visit_assignable(&mut self,
+                        exp: &Expression,
+                        pre_string: &mut String) -> (String, String, String) {
+        if let ExpressionVariant::Index { ref primary, ref index } = exp.variant {
+            if let Kind::Map(..) = primary.kind.resolve() {
+                let mut map = String::new();
+                let mut key = String::new();
+                self.visit_expression(primary, pre_string, &mut map);
+                self.visit_map_key(index, pre_string, &mut key);
+
+                let mut zero = String::new();
+                self.visit_var_initialization(&exp.kind, &mut zero);
+                return (format!("map_get({}, {}, {})", map, key, zero),
+                        format!("map_set({}, {}, ", map, key),
+                        format!(", {})", exp.span.start.line));
+            }
+        }
+
+        // What a pointer points to is set through the pointer
+        if let ExpressionVariant::Dereference { ref expr } = exp.variant {
+            let mut pointer = String::new();
+            self.visit_expression(expr, pre_string, &mut pointer);
+            return (format!("dereference({}, {})", pointer, exp.span.start.line),
+                    format!("pointer_set({}, ", pointer),
+                    format!(", {})", exp.span.start.line));
+        }
+
+        let mut post = String::new();
+        self.visit_expression(exp, pre_string, &mut post);
+        // Arrays are assigned in place, since their slices share them
+        match exp.kind.resolve() {
+            Kind::Array(..) | Kind::Struct(..) => {
+                let prefix = format!("{} = assign({}, ", post, post);
+                (post, prefix, ")".to_string())
+            }
+            _ => {
+                let prefix = format!("{} = ", post);
+                (post, prefix, String::new())
+            }
+        }
+    }
+
+    // The test of a type switch case on the interface value in `value`
+    fn kind_test(&mut self, value: &str, kind: &Kind) -> String {
+        match *kind {
+            Kind::Nil => format!("{} === null", value),
+            _ if kind.is_interface() => format!("has_methods({}, {})", value, method_signatures(kind)),
+            _ => format!("has_type({}, {})", value, self.type_descriptor(kind)),
+        }
+    }
+
+    // The constant holding the descriptor of a type put in interfaces, declared the first time
+    // the type is used. Types are the same when they have the same descriptor.
+    fn type_descriptor(&mut self, kind: &Kind) -> String {
+        let key = type_key(kind);
+        if let Some(name) = self.type_descriptors.get(&key) {
+            return name.clone();
+        }
+        let name = format!("ㄒ{}", self.create_id());
+        self.type_descriptors.insert(key, name.clone());
+
+        // Pointers to a defined type also have its methods with a value receiver
+        let mut methods = Vec::new();
+        for method_name in kind.method_names() {
+            let signature = match kind.method_kind(&method_name) {
+                Some(method_kind) => type_key(&method_kind),
+                None => continue,
+            };
+            let (path, selection) = match kind.lookup(&method_name) {
+                Lookup::Found(path, selection) => (path, selection),
+                _ => continue,
+            };
+            let through_pointer = kind.is_pointer() || path.iter().any(|f| f.kind.is_pointer());
+            let mut bind = match selection {
+                Selection::Method(ref method) if !method.pointer_receiver && through_pointer => {
+                    format!("bound_method_through_pointer({})", method.function)
+                }
+                Selection::Method(ref method) => format!("bound_method({})", method.function),
+                // Methods promoted from an embedded interface are the ones of the value it holds
+                Selection::InterfaceMethod(..) => {
+                    format!("bound_interface_method({}, {})", quote_string_literal(&method_name),
+                            through_pointer)
+                }
+                Selection::Field(..) => continue,
+            };
+            // Promoted methods are bound to the embedded field they come from
+            if !path.is_empty() {
+                let steps = path.iter()
+                    .map(|f| format!("['ㆭ{}', {}]", f.name, f.kind.is_pointer()))
+                    .collect::<Vec<_>>();
+                bind = format!("promoted_method({}, [{}], {})", kind.is_pointer(), steps.join(", "), bind);
+            }
+            methods.push(format!("{}: {{bind: {}, signature: {}}}",
+                                 quote_string_literal(&method_name), bind,
+                                 quote_string_literal(&signature)));
+        }
+        writeln!(self.type_declarations, "const {} = new GoType({}, {}, {{{}}});", name,
+                 quote_string_literal(&kind.runtime_name()), kind.is_comparable(),
+                 methods.join(", ")).unwrap();
+        name
+    }
+
     fn create_id(&mut self) -> String{
         self.id_counter += 1;
         return self.id_counter.to_string()
     }
 }
 
-pub fn codegen(root: &Program) {
-    let mut visitor = CodeGenVisitor{ indent: 0, id_counter: 0, init_functions: Vec::new()  };
+/// Returns the JavaScript program for the given (typechecked) AST
+pub fn codegen(root: &Program) -> String {
+    let mut visitor = CodeGenVisitor{ indent: 0, id_counter: 0, init_functions: Vec::new(),
+                                      output: String::new(), deferred_results: None,
+                                      goto_labels: HashSet::new(), goto_cases: HashMap::new(),
+                                      jump_targets: Vec::new(), statement_label: None,
+                                      declaration_keyword: "let", type_descriptors: HashMap::new(),
+                                      type_declarations: String::new() };
 
     visitor.visit_program(root);
+    visitor.output
+}
+
+// The value given to the `x`th variable on the left of an assignment. When there are more
+// variables than values, the only value is an array with all of them.
+fn assigned_value(values: &[String], count: usize, x: usize) -> String {
+    if values.len() == count {
+        values[x].clone()
+    } else {
+        format!("{}[{}]", values[0], x)
+    }
+}
+
+// The text that panic shows for a value, like the interpreter. Values of defined types show the
+// type, like `main.celsius(20)`.
+// The slice a variadic parameter is given, from the trailing arguments of a call
+fn packed_slice(values: &[String]) -> String {
+    format!("{{offset: 0, length: {}, capacity: {}, contents: [{}]}}", values.len(), values.len(), values.join(", "))
+}
+
+fn panic_text(value: &str, kind: &Kind) -> String {
+    let text = match kind.resolve() {
+        Kind::Basic(basic) if basic.is_float() => format!("format_float({})", value),
+        _ => format!("(\"\" + {})", value),
+    };
+    match *kind {
+        Kind::Defined(ref definition) => {
+            format!("\"main.{}(\" + {} + \")\"", definition.borrow().name, text)
+        }
+        _ => text,
+    }
+}
+
+// A JS string literal holding the UTF-8 bytes of a Go string, one character per byte. The runtime
+// keeps strings that way so that they are measured, sliced and compared by bytes.
+fn js_string_literal(s: &str) -> String {
+    let mut literal = "\"".to_string();
+    for &b in s.as_bytes() {
+        match b {
+            b'"' | b'\\' => {
+                literal.push('\\');
+                literal.push(b as char);
+            }
+            0x20..=0x7e => literal.push(b as char),
+            _ => write!(literal, "\\x{:02x}", b).unwrap(),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+// A string identifying a kind, which is the same for identical kinds. Defined types are told apart
+// by their definition, since types declared in different scopes can have the same name.
+fn type_key(kind: &Kind) -> String {
+    match *kind {
+        Kind::Defined(ref definition) => {
+            format!("{}@{:p}", definition.borrow().name, Rc::as_ptr(definition))
+        }
+        Kind::Array(ref base, size) => format!("[{}]{}", size, type_key(base)),
+        Kind::Slice(ref base) => format!("[]{}", type_key(base)),
+        Kind::Map(ref key, ref value) => format!("map[{}]{}", type_key(key), type_key(value)),
+        Kind::Pointer(ref base) => format!("*{}", type_key(base)),
+        Kind::Struct(ref fields) => {
+            let mut key = "struct{".to_string();
+            for field in fields {
+                if field.embedded {
+                    write!(key, "{};", type_key(&field.kind)).unwrap();
+                } else {
+                    write!(key, "{} {};", field.name, type_key(&field.kind)).unwrap();
+                }
+            }
+            key.push('}');
+            key
+        }
+        Kind::Function(ref params, ref return_kind, variadic) => {
+            let mut params = params.iter().map(type_key).collect::<Vec<_>>();
+            if variadic {
+                let last = params.pop().unwrap();
+                params.push(format!("...{}", &last["[]".len()..]));
+            }
+            match *return_kind {
+                Some(ref k) => format!("func({}) {}", params.join(","), type_key(k)),
+                None => format!("func({})", params.join(",")),
+            }
+        }
+        Kind::Tuple(ref kinds) => {
+            format!("({})", kinds.iter().map(type_key).collect::<Vec<_>>().join(","))
+        }
+        Kind::Interface(ref methods) => {
+            let mut key = "interface{".to_string();
+            for method in methods {
+                write!(key, "{} {};", method.name, type_key(&method.kind)).unwrap();
+            }
+            key.push('}');
+            key
+        }
+        ref k => format!("{}", k),
+    }
+}
+
+// The names and signatures of the methods of an interface kind, which the values in an interface
+// are checked against
+fn method_signatures(kind: &Kind) -> String {
+    let methods = match kind.resolve() {
+        Kind::Interface(methods) => methods,
+        _ => panic!("Only interfaces have a method set"),
+    };
+    let signatures = methods.iter()
+        .map(|m| format!("[{}, {}]", quote_string_literal(&m.name), quote_string_literal(&type_key(&m.kind))))
+        .collect::<Vec<_>>();
+    format!("[{}]", signatures.join(", "))
+}
+
+// The statements and the statements nested in them, not counting the bodies of function literals
+fn nested_statements(statements: &[StatementNode]) -> Vec<&StatementNode> {
+    let mut nested = Vec::new();
+    for stmt in statements {
+        nested.push(stmt);
+        match stmt.variant {
+            StatementVariant::Block(ref body) |
+            StatementVariant::For { ref body, .. } |
+            StatementVariant::ForRange { ref body, .. } => nested.extend(nested_statements(body)),
+            StatementVariant::If { ref if_branch, ref else_branch, .. } => {
+                nested.extend(nested_statements(if_branch));
+                if let Some(ref else_branch) = *else_branch {
+                    nested.extend(nested_statements(::std::slice::from_ref(&**else_branch)));
+                }
+            }
+            StatementVariant::Switch { ref body, .. } |
+            StatementVariant::TypeSwitch { ref body, .. } => {
+                for clause in body {
+                    nested.extend(nested_statements(&clause.statements));
+                }
+            }
+            StatementVariant::Labeled { ref statement, .. } => {
+                nested.extend(nested_statements(::std::slice::from_ref(&**statement)));
+            }
+            _ => {},
+        }
+    }
+    nested
 }
 
-fn print_header() {
-    let mut f = File::open("src/header.js").expect("Header file not found");
-    let mut contents = String::new();
-    f.read_to_string(&mut contents).expect("Error when reading header file");
+// Whether the statements defer calls, not counting the bodies of function literals
+fn contains_defer(statements: &[StatementNode]) -> bool {
+    nested_statements(statements).iter().any(|stmt| matches!(stmt.variant, StatementVariant::Defer(..)))
+}
+
+// The labels on a statement, which can have more than one
+fn statement_labels(mut stmt: &StatementNode) -> Vec<&str> {
+    let mut labels = Vec::new();
+    while let StatementVariant::Labeled { ref label, ref statement } = stmt.variant {
+        labels.push(label.as_str());
+        stmt = statement;
+    }
+    labels
+}
+
+fn label_name(label: &str) -> String {
+    format!("label_{}", label)
+}
 
-    println!("{}", contents);
+fn label_prefix(label: &Option<String>) -> String {
+    label.as_ref().map_or(String::new(), |label| format!("{}: ", label))
 }
 
+// The runtime functions used by the generated code
+const HEADER: &str = include_str!("header.js");
+
 
 fn generate_unary_op(op: &UnaryOperator) -> String {
     let mut op_name = "".to_string();
@@ -632,3 +1927,40 @@ fn generate_binary_op(op: &BinaryOperator) -> String {
     write!(op_name, "binary_{:?}", op).unwrap();
     op_name
 }
+
+// The call of the runtime function for a binary operation (other than && and ||) giving a value
+// of the given kind, which the result is made to fit. Integer division, modulo and shifts are
+// given the line, for their panics.
+fn binary_op_call(op: BinaryOperator, kind: &Kind, lhs: &str, rhs: &str, line_number: u32) -> String {
+    let function = generate_binary_op(&op);
+    let call = match op {
+        BinaryOperator::Mul if kind.is_integer() => {
+            format!("{}_int({}, {})", function, lhs, rhs)
+        }
+        BinaryOperator::Div if kind.is_integer() => {
+            format!("{}_int({}, {}, {})", function, lhs, rhs, line_number)
+        }
+        BinaryOperator::Mod | BinaryOperator::LShift | BinaryOperator::RShift => {
+            format!("{}({}, {}, {})", function, lhs, rhs, line_number)
+        }
+        _ => format!("{}({}, {})", function, lhs, rhs),
+    };
+    fit_kind(kind, call)
+}
+
+// int64 and uint64 values are BigInts, other numbers are JS numbers
+fn is_bigint(basic: BasicKind) -> bool {
+    basic.integer_bits() == Some(64)
+}
+
+// Wraps an integer value around to fit in its kind and rounds a float32 value, like
+// value::fit_kind does in the interpreter
+fn fit_kind(kind: &Kind, value: String) -> String {
+    match kind.resolve() {
+        Kind::Basic(BasicKind::Float32) => format!("Math.fround({})", value),
+        Kind::Basic(basic) if basic.is_integer() => {
+            format!("wrap_int({}, {}, {})", value, basic.integer_bits().unwrap(), !basic.is_unsigned())
+        }
+        _ => value,
+    }
+}
@@ -1,7 +1,1357 @@
-use ast;
+use std::fmt::Write;
+use std::collections::{HashMap, HashSet};
+use crate::util::*;
+use crate::ast::*;
+use crate::kind::*;
+use crate::diagnostics::{Diagnostic, Diagnostics};
 
-pub fn codegen(_root: &ast::Program) {
-    //let mut visitor = CodeGenVisitor{ indent: 0, id_counter: 0, init_functions: Vec::new()  };
+// Unlike the JS backend, C needs every type and function to be declared before it is used, so the
+// output is accumulated in separate buffers and stitched together at the end.
+struct CodeGenVisitor {
+    indent: u32,
+    id_counter: u32,
+    init_functions: Vec<String>,
 
-    //visitor.visit_program(root);
+    // C type names of the arrays and structs seen so far, keyed by their resolved structure
+    type_names: HashMap<String, String>,
+    equality_functions: HashMap<String, String>,
+
+    type_definitions: String,
+    helper_functions: String,
+    prototypes: String,
+    globals: String,
+    global_initialization: String,
+    functions: String,
+    output: String,
+
+    // Top-level functions, which are the only things that can be called. Other function values
+    // are reported as unsupported.
+    function_names: HashSet<String>,
+
+    // The Go label of each loop and switch around the statement, and the labels to jump to on
+    // `break` and `continue`. Switches only have a break label.
+    jump_targets: Vec<(Option<String>, String, Option<String>)>,
+
+    // The Go label of the loop or switch about to be written
+    statement_label: Option<String>,
+
+    // Labels at the start of the clause bodies that `fallthrough` jumps to
+    fallthrough_targets: Vec<String>,
+
+    // Uses of features that the C runtime doesn't have, like maps
+    unsupported: Diagnostics,
+}
+
+impl CodeGenVisitor {
+    fn visit_program(&mut self, root: &Program) {
+        for decl in &root.declarations {
+            if let TopLevelDeclarationVariant::FunctionDeclaration(ref function) = decl.variant {
+                self.function_names.insert(function.name.clone());
+            }
+        }
+
+        for decl in &root.declarations {
+            self.visit_top_level_declaration(decl);
+        }
+
+        self.output.push_str(HEADER);
+        self.output.push('\n');
+
+        writeln!(self.output, "{}", self.type_definitions).unwrap();
+        writeln!(self.output, "{}", self.helper_functions).unwrap();
+        writeln!(self.output, "{}", self.prototypes).unwrap();
+        writeln!(self.output, "{}", self.globals).unwrap();
+
+        writeln!(self.output, "static void gl_initialize_globals(void) {{").unwrap();
+        write!(self.output, "{}", self.global_initialization).unwrap();
+        writeln!(self.output, "}}\n").unwrap();
+
+        writeln!(self.output, "{}", self.functions).unwrap();
+
+        writeln!(self.output, "int main(void) {{").unwrap();
+        writeln!(self.output, "\tgl_initialize_globals();").unwrap();
+        for init_func_name in &self.init_functions {
+            writeln!(self.output, "\t{}();", init_func_name).unwrap();
+        }
+        writeln!(self.output, "\tv_main();").unwrap();
+        writeln!(self.output, "\treturn 0;").unwrap();
+        writeln!(self.output, "}}").unwrap();
+    }
+
+    fn visit_top_level_declaration(&mut self, decl: &TopLevelDeclarationNode) {
+        match decl.variant {
+            TopLevelDeclarationVariant::VarDeclarations { ref declarations } => {
+                for d in declarations.iter() {
+                    self.visit_global_var_spec(d);
+                }
+            }
+
+            TopLevelDeclarationVariant::FunctionDeclaration
+                (Function { ref name, ref receiver, ref parameters, ref results, ref body }) => {
+                if name == "_" {
+                    return;
+                }
+
+                if receiver.is_some() {
+                    self.unsupported.error(decl.span, "methods are not supported by the C backend.");
+                    return;
+                }
+
+                if parameters.last().is_some_and(|f| f.variadic) {
+                    self.unsupported.error(decl.span, "variadic functions are not supported by the C backend.");
+                    return;
+                }
+
+                let result_count: usize = results.iter().map(|f| f.identifiers.len().max(1)).sum();
+                if result_count > 1 {
+                    self.unsupported.error(decl.span, "functions with several results are not \
+                    supported by the C backend.");
+                    return;
+                }
+
+                let func_name = if name == "init" {
+                    let func_name = format!("gl_init_{}", self.create_id());
+                    self.init_functions.push(func_name.clone());
+                    func_name
+                } else {
+                    mangle(name)
+                };
+
+                let mut params_string = String::new();
+                for field in parameters.iter() {
+                    let kind = self.c_type(&field.kind.evaluated_kind);
+                    for id in field.identifiers.iter() {
+                        if !params_string.is_empty() {
+                            write!(params_string, ", ").unwrap();
+                        }
+                        if id == "_" {
+                            write!(params_string, "{} gl_unused_{}", kind, self.create_id()).unwrap();
+                        } else {
+                            write!(params_string, "{} {}", kind, mangle(id)).unwrap();
+                        }
+                    }
+                }
+                if params_string.is_empty() {
+                    params_string = "void".to_string();
+                }
+
+                let return_type = match results.first() {
+                    Some(field) => self.c_type(&field.kind.evaluated_kind),
+                    None => "void".to_string(),
+                };
+
+                let signature = format!("static {} {}({})", return_type, func_name, params_string);
+                writeln!(self.prototypes, "{};", signature).unwrap();
+
+                let mut output = String::new();
+                writeln!(output, "{} {{", signature).unwrap();
+                self.indent += 1;
+                // A named result starts with its zero value
+                for field in results.iter() {
+                    for id in field.identifiers.iter() {
+                        let kind = &field.kind.evaluated_kind;
+                        let c_kind = self.c_type(kind);
+                        writeln!(output, "{}{} {} = {};", indent(self.indent), c_kind, mangle(id),
+                                 zero_initializer(kind)).unwrap();
+                    }
+                }
+                self.visit_statements(body, &mut output);
+                self.indent -= 1;
+                writeln!(output, "}}\n").unwrap();
+
+                self.functions.push_str(&output);
+            },
+
+            TopLevelDeclarationVariant::TypeDeclarations { .. } => {}
+            // Every use of a constant has been replaced by its value
+            TopLevelDeclarationVariant::ConstDeclarations { .. } => {}
+        }
+    }
+
+    fn visit_global_var_spec(&mut self, var_spec: &VarSpec) {
+        if let Some(ref values) = var_spec.rhs {
+            if self.is_unsupported_tuple(values, var_spec.names.len()) {
+                return;
+            }
+        }
+        let kinds = var_spec_kinds(var_spec);
+        let mut initialization = String::new();
+        self.indent += 1;
+        match var_spec.rhs {
+            Some(ref values) => {
+                let mut pre = String::new();
+                let mut assignments = String::new();
+                for ((name, rhs), kind) in var_spec.names.iter().zip(values.iter()).zip(kinds.iter()) {
+                    let mut post = String::new();
+                    self.visit_expression(rhs, &mut pre, &mut post);
+                    if name != "_" {
+                        let c_kind = self.c_type(kind);
+                        writeln!(self.globals, "static {} {};", c_kind, mangle(name)).unwrap();
+                        writeln!(assignments, "{}{} = {};", indent(self.indent), mangle(name), post).unwrap();
+                    } else {
+                        writeln!(assignments, "{}(void)({});", indent(self.indent), post).unwrap();
+                    }
+                }
+                write!(initialization, "{}{}", pre, assignments).unwrap();
+            }
+            None => {
+                for (name, kind) in var_spec.names.iter().zip(kinds.iter()) {
+                    if name != "_" {
+                        let c_kind = self.c_type(kind);
+                        writeln!(self.globals, "static {} {};", c_kind, mangle(name)).unwrap();
+                    }
+                }
+            }
+        }
+        self.indent -= 1;
+        self.global_initialization.push_str(&initialization);
+    }
+
+    fn visit_var_spec(&mut self, var_spec: &VarSpec, output: &mut String) {
+        if let Some(ref values) = var_spec.rhs {
+            if self.is_unsupported_tuple(values, var_spec.names.len()) {
+                return;
+            }
+        }
+        let kinds = var_spec_kinds(var_spec);
+        match var_spec.rhs {
+            Some(ref values) => {
+                let mut pre = String::new();
+                let mut declarations = String::new();
+                for ((name, rhs), kind) in var_spec.names.iter().zip(values.iter()).zip(kinds.iter()) {
+                    let mut post = String::new();
+                    self.visit_expression(rhs, &mut pre, &mut post);
+                    if name != "_" {
+                        let c_kind = self.c_type(kind);
+                        writeln!(declarations, "{}{} {} = {};",
+                                 indent(self.indent), c_kind, mangle(name), post).unwrap();
+                    } else {
+                        writeln!(declarations, "{}(void)({});", indent(self.indent), post).unwrap();
+                    }
+                }
+                write!(output, "{}{}", pre, declarations).unwrap();
+            }
+            None => {
+                for (name, kind) in var_spec.names.iter().zip(kinds.iter()) {
+                    if name != "_" {
+                        let c_kind = self.c_type(kind);
+                        writeln!(output, "{}{} {} = {};",
+                                 indent(self.indent), c_kind, mangle(name), zero_initializer(kind)).unwrap();
+                    }
+                }
+            }
+        }
+    }
+
+    fn visit_statements(&mut self, statements: &[StatementNode], output: &mut String) {
+        for s in statements {
+            self.visit_statement(s, output);
+        }
+    }
+
+    fn visit_block(&mut self, statements: &[StatementNode], output: &mut String) {
+        writeln!(output, "{}{{", indent(self.indent)).unwrap();
+        self.indent += 1;
+        self.visit_statements(statements, output);
+        self.indent -= 1;
+        writeln!(output, "{}}}", indent(self.indent)).unwrap();
+    }
+
+    fn visit_case_clause(&mut self, body: &[CaseClause], i: usize, case_labels: &[String],
+                         output: &mut String) {
+        writeln!(output, "{}{{", indent(self.indent)).unwrap();
+        self.indent += 1;
+        if i > 0 && body[i - 1].falls_through() {
+            writeln!(output, "{}{}: ;", indent(self.indent), case_labels[i]).unwrap();
+        }
+        if body[i].falls_through() {
+            self.fallthrough_targets.push(case_labels[i + 1].clone());
+        }
+        self.visit_statements(&body[i].statements, output);
+        if body[i].falls_through() {
+            self.fallthrough_targets.pop();
+        }
+        self.indent -= 1;
+        writeln!(output, "{}}}", indent(self.indent)).unwrap();
+    }
+
+    fn visit_statement(&mut self, stmt: &StatementNode, output: &mut String) {
+        match stmt.variant {
+            StatementVariant::Empty => {},
+            StatementVariant::Break(ref label) => {
+                let label = self.jump_targets.iter().rev()
+                    .find(|&(go_label, _, _)| label.is_none() || go_label == label)
+                    .expect("break outside of loop or switch").1.clone();
+                writeln!(output, "{}goto {};", indent(self.indent), label).unwrap();
+            },
+            StatementVariant::Continue(ref label) => {
+                let label = self.jump_targets.iter().rev()
+                    .filter(|&(go_label, _, _)| label.is_none() || go_label == label)
+                    .filter_map(|(_, _, continue_label)| continue_label.clone())
+                    .next()
+                    .expect("continue outside of loop");
+                writeln!(output, "{}goto {};", indent(self.indent), label).unwrap();
+            },
+            StatementVariant::Goto(ref label) => {
+                writeln!(output, "{}goto {};", indent(self.indent), label_name(label)).unwrap();
+            },
+            StatementVariant::Labeled { ref label, ref statement } => {
+                writeln!(output, "{}{}: ;", indent(self.indent), label_name(label)).unwrap();
+                match statement.variant {
+                    StatementVariant::For { .. } | StatementVariant::ForRange { .. } |
+                    StatementVariant::Switch { .. } => self.statement_label = Some(label.clone()),
+                    _ => {},
+                }
+                self.visit_statement(statement, output);
+            },
+            StatementVariant::Fallthrough => {
+                let label = self.fallthrough_targets.last()
+                    .expect("fallthrough outside of switch").clone();
+                writeln!(output, "{}goto {};", indent(self.indent), label).unwrap();
+            },
+            StatementVariant::Expression(ref exp) => {
+                let mut pre = String::new();
+                let mut post = String::new();
+                self.visit_expression(exp, &mut pre, &mut post);
+                output.push_str(&pre);
+                if !post.is_empty() {
+                    writeln!(output, "{}(void)({});", indent(self.indent), post).unwrap();
+                }
+            },
+            StatementVariant::Return(ref exps) => {
+                // Functions with several results are reported, so there is at most one value
+                match exps.first() {
+                    Some(e) => {
+                        let mut pre = String::new();
+                        let mut post = String::new();
+                        self.visit_expression(e, &mut pre, &mut post);
+                        output.push_str(&pre);
+                        writeln!(output, "{}return {};", indent(self.indent), post).unwrap();
+                    },
+                    None => {
+                        writeln!(output, "{}return;", indent(self.indent)).unwrap();
+                    }
+                }
+            },
+            StatementVariant::Defer(..) => {
+                self.unsupported.error(stmt.span, "defer is not supported by the C backend.");
+            },
+            StatementVariant::Go(..) | StatementVariant::Send { .. } | StatementVariant::Select { .. } => {
+                self.unsupported.error(stmt.span, "goroutines and channels are not supported by the C backend.");
+            },
+            StatementVariant::TypeSwitch { .. } => {
+                self.unsupported.error(stmt.span, "interfaces are not supported by the C backend.");
+            },
+            StatementVariant::ShortVariableDeclaration { ref identifier_list, ref expression_list, ref is_assigning } => {
+                if self.is_unsupported_tuple(expression_list, identifier_list.len()) {
+                    return;
+                }
+                let mut pre = String::new();
+                let mut assignments = String::new();
+                let mut temps = Vec::new();
+
+                for expr in expression_list.iter() {
+                    let mut post = String::new();
+                    self.visit_expression(expr, &mut pre, &mut post);
+                    let temp = self.create_temp();
+                    let c_kind = self.c_type(&expr.kind);
+                    writeln!(pre, "{}{} {} = {};", indent(self.indent), c_kind, temp, post).unwrap();
+                    temps.push(temp);
+                }
+
+                for (i, id) in identifier_list.iter().enumerate() {
+                    if id == "_" {
+                        writeln!(assignments, "{}(void){};", indent(self.indent), temps[i]).unwrap();
+                    } else if is_assigning[i] {
+                        writeln!(assignments, "{}{} = {};", indent(self.indent), mangle(id), temps[i]).unwrap();
+                    } else {
+                        let c_kind = self.c_type(&expression_list[i].kind);
+                        writeln!(assignments, "{}{} {} = {};",
+                                 indent(self.indent), c_kind, mangle(id), temps[i]).unwrap();
+                    }
+                }
+
+                write!(output, "{}{}", pre, assignments).unwrap();
+            },
+            StatementVariant::VarDeclarations { ref declarations } => {
+                for decl in declarations.iter() {
+                    self.visit_var_spec(decl, output);
+                }
+            },
+            StatementVariant::TypeDeclarations { .. } => {},
+            StatementVariant::ConstDeclarations { .. } => {},
+            StatementVariant::Assignment { ref lhs, ref rhs } => {
+                if self.is_unsupported_tuple(rhs, lhs.len()) {
+                    return;
+                }
+                // Index operands on the left and all expressions on the right are evaluated before
+                // any assignment is carried out.
+                let mut pre = String::new();
+                let mut lvalues = Vec::new();
+                for expr in lhs.iter() {
+                    lvalues.push(self.visit_lvalue(expr, &mut pre));
+                }
+
+                let mut temps = Vec::new();
+                for expr in rhs.iter() {
+                    let mut post = String::new();
+                    self.visit_expression(expr, &mut pre, &mut post);
+                    let temp = self.create_temp();
+                    let c_kind = self.c_type(&expr.kind);
+                    writeln!(pre, "{}{} {} = {};", indent(self.indent), c_kind, temp, post).unwrap();
+                    temps.push(temp);
+                }
+
+                output.push_str(&pre);
+                for (lvalue, temp) in lvalues.iter().zip(temps.iter()) {
+                    match lvalue {
+                        Some(lvalue) => {
+                            writeln!(output, "{}{} = {};", indent(self.indent), lvalue, temp).unwrap();
+                        }
+                        None => {
+                            writeln!(output, "{}(void){};", indent(self.indent), temp).unwrap();
+                        }
+                    }
+                }
+            },
+            StatementVariant::OpAssignment { ref lhs, ref rhs, operator } => {
+                let mut pre = String::new();
+                let lvalue = self.visit_lvalue(lhs, &mut pre)
+                    .expect("cannot op-assign to the blank identifier");
+                let mut post = String::new();
+                self.visit_expression(rhs, &mut pre, &mut post);
+                let value = binary_operation(operator, &lhs.kind, &lvalue, &post, stmt.span.start.line, self);
+
+                output.push_str(&pre);
+                writeln!(output, "{}{} = {};", indent(self.indent), lvalue, value).unwrap();
+            },
+            StatementVariant::Block(ref statements) => {
+                self.visit_block(statements, output);
+            },
+            StatementVariant::Print { ref exprs } => {
+                self.codegen_print(exprs, false, output);
+            },
+            StatementVariant::Println { ref exprs } => {
+                self.codegen_print(exprs, true, output);
+            },
+            StatementVariant::For { ref init, ref condition, ref post, ref body } => {
+                let go_label = self.statement_label.take();
+                let break_label = format!("gl_break_{}", self.create_id());
+                let continue_label = format!("gl_continue_{}", self.create_id());
+
+                writeln!(output, "{}{{", indent(self.indent)).unwrap();
+                self.indent += 1;
+                self.visit_statement(init, output);
+                writeln!(output, "{}for (;;) {{", indent(self.indent)).unwrap();
+                self.indent += 1;
+
+                if let Some(condition) = condition {
+                    let mut pre = String::new();
+                    let mut cond = String::new();
+                    self.visit_expression(condition, &mut pre, &mut cond);
+                    output.push_str(&pre);
+                    writeln!(output, "{}if (!({})) break;", indent(self.indent), cond).unwrap();
+                }
+
+                self.jump_targets.push((go_label, break_label.clone(), Some(continue_label.clone())));
+                self.visit_block(body, output);
+                self.jump_targets.pop();
+
+                writeln!(output, "{}{}: ;", indent(self.indent), continue_label).unwrap();
+                self.visit_statement(post, output);
+
+                self.indent -= 1;
+                writeln!(output, "{}}}", indent(self.indent)).unwrap();
+                writeln!(output, "{}{}: ;", indent(self.indent), break_label).unwrap();
+                self.indent -= 1;
+                writeln!(output, "{}}}", indent(self.indent)).unwrap();
+            },
+            StatementVariant::ForRange { ref key, ref value, ref expr, ref body } => {
+                let go_label = self.statement_label.take();
+                let break_label = format!("gl_break_{}", self.create_id());
+                let continue_label = format!("gl_continue_{}", self.create_id());
+
+                writeln!(output, "{}{{", indent(self.indent)).unwrap();
+                self.indent += 1;
+
+                // The expression is evaluated once, which also copies arrays
+                let mut pre = String::new();
+                let mut post = String::new();
+                self.visit_expression(expr, &mut pre, &mut post);
+                output.push_str(&pre);
+                let range = self.create_temp();
+                let range_kind = self.c_type(&expr.kind);
+                writeln!(output, "{}{} {} = {};", indent(self.indent), range_kind, range, post).unwrap();
+
+                let index = self.create_temp();
+                writeln!(output, "{}int32_t {} = 0;", indent(self.indent), index).unwrap();
+                let (element_kind, element) = match expr.kind.resolve() {
+                    Kind::Basic(BasicKind::String) => {
+                        // Runes are decoded even if they aren't used, to know where the next one is
+                        let width = self.create_temp();
+                        let rune = self.create_temp();
+                        writeln!(output, "{}int32_t {} = 0;", indent(self.indent), width).unwrap();
+                        writeln!(output, "{}for (;; {} += {}) {{", indent(self.indent), index, width).unwrap();
+                        writeln!(output, "{}if (!({} < {}.length)) break;", indent(self.indent + 1), index, range).unwrap();
+                        writeln!(output, "{}int32_t {} = gl_decode_rune({}, {}, &{});",
+                                 indent(self.indent + 1), rune, range, index, width).unwrap();
+                        ("int32_t".to_string(), rune)
+                    }
+                    Kind::Array(ref base, size) => {
+                        writeln!(output, "{}for (;; {}++) {{", indent(self.indent), index).unwrap();
+                        writeln!(output, "{}if (!({} < {})) break;", indent(self.indent + 1), index, size).unwrap();
+                        (self.c_type(base), format!("{}.elements[{}]", range, index))
+                    }
+                    Kind::Slice(ref base) => {
+                        // The length is fixed when the loop starts
+                        let length = self.create_temp();
+                        let element = self.c_type(base);
+                        writeln!(output, "{}int32_t {} = {}.length;", indent(self.indent), length, range).unwrap();
+                        writeln!(output, "{}for (;; {}++) {{", indent(self.indent), index).unwrap();
+                        writeln!(output, "{}if (!({} < {})) break;", indent(self.indent + 1), index, length).unwrap();
+                        (element.clone(), format!("(({}*){}.data)[{}]", element, range, index))
+                    }
+                    Kind::Map(..) => {
+                        self.unsupported.error(expr.span, "maps are not supported by the C backend.");
+                        writeln!(output, "{}for (;;) {{", indent(self.indent)).unwrap();
+                        writeln!(output, "{}break;", indent(self.indent + 1)).unwrap();
+                        ("void *".to_string(), "0".to_string())
+                    }
+                    Kind::Chan(..) => {
+                        self.unsupported.error(expr.span, "goroutines and channels are not supported by the C backend.");
+                        writeln!(output, "{}for (;;) {{", indent(self.indent)).unwrap();
+                        writeln!(output, "{}break;", indent(self.indent + 1)).unwrap();
+                        ("void *".to_string(), "0".to_string())
+                    }
+                    _ => panic!("ranging over something other than a string, array or slice"),
+                };
+                self.indent += 1;
+
+                if let Some(key) = key {
+                    if key != "_" {
+                        writeln!(output, "{}int32_t {} = {};", indent(self.indent), mangle(key), index).unwrap();
+                    }
+                }
+                if let Some(value) = value {
+                    if value != "_" {
+                        writeln!(output, "{}{} {} = {};", indent(self.indent), element_kind, mangle(value), element).unwrap();
+                    }
+                }
+
+                self.jump_targets.push((go_label, break_label.clone(), Some(continue_label.clone())));
+                self.visit_block(body, output);
+                self.jump_targets.pop();
+
+                writeln!(output, "{}{}: ;", indent(self.indent), continue_label).unwrap();
+                self.indent -= 1;
+                writeln!(output, "{}}}", indent(self.indent)).unwrap();
+                writeln!(output, "{}{}: ;", indent(self.indent), break_label).unwrap();
+                self.indent -= 1;
+                writeln!(output, "{}}}", indent(self.indent)).unwrap();
+            },
+            StatementVariant::If { ref init, ref condition, ref if_branch, ref else_branch } => {
+                writeln!(output, "{}{{", indent(self.indent)).unwrap();
+                self.indent += 1;
+                self.visit_statement(init, output);
+
+                let mut pre = String::new();
+                let mut cond = String::new();
+                self.visit_expression(condition, &mut pre, &mut cond);
+                output.push_str(&pre);
+
+                writeln!(output, "{}if ({})", indent(self.indent), cond).unwrap();
+                self.visit_block(if_branch, output);
+                if let Some(else_branch) = else_branch {
+                    writeln!(output, "{}else {{", indent(self.indent)).unwrap();
+                    self.indent += 1;
+                    self.visit_statement(else_branch, output);
+                    self.indent -= 1;
+                    writeln!(output, "{}}}", indent(self.indent)).unwrap();
+                }
+
+                self.indent -= 1;
+                writeln!(output, "{}}}", indent(self.indent)).unwrap();
+            },
+            StatementVariant::Switch { ref init, ref expr, ref body } => {
+                let go_label = self.statement_label.take();
+                let break_label = format!("gl_break_{}", self.create_id());
+
+                writeln!(output, "{}{{", indent(self.indent)).unwrap();
+                self.indent += 1;
+                self.visit_statement(init, output);
+
+                let tag = self.create_temp();
+                let tag_kind = match expr {
+                    Some(expr) => {
+                        let mut pre = String::new();
+                        let mut post = String::new();
+                        self.visit_expression(expr, &mut pre, &mut post);
+                        let c_kind = self.c_type(&expr.kind);
+                        output.push_str(&pre);
+                        writeln!(output, "{}{} {} = {};", indent(self.indent), c_kind, tag, post).unwrap();
+                        expr.kind.clone()
+                    }
+                    None => {
+                        writeln!(output, "{}bool {} = true;", indent(self.indent), tag).unwrap();
+                        Kind::Basic(BasicKind::Bool)
+                    }
+                };
+
+                // Find out which clause matches first, evaluating the cases in order and stopping
+                // at the first match. The clause bodies are emitted afterwards.
+                let matched = self.create_temp();
+                writeln!(output, "{}int {} = -1;", indent(self.indent), matched).unwrap();
+                for (i, case_clause) in body.iter().enumerate() {
+                    if let SwitchCase::Cases(ref cases) = case_clause.switch_case {
+                        for case in cases {
+                            writeln!(output, "{}if ({} < 0) {{", indent(self.indent), matched).unwrap();
+                            self.indent += 1;
+                            let mut pre = String::new();
+                            let mut post = String::new();
+                            self.visit_expression(case, &mut pre, &mut post);
+                            let equal = self.equality(&tag_kind, &tag, &post);
+                            output.push_str(&pre);
+                            writeln!(output, "{}if ({}) {} = {};", indent(self.indent), equal, matched, i).unwrap();
+                            self.indent -= 1;
+                            writeln!(output, "{}}}", indent(self.indent)).unwrap();
+                        }
+                    }
+                }
+
+                // A clause that is fallen into from the previous one gets a label to jump to
+                let case_labels = body.iter().map(|_| format!("gl_case_{}", self.create_id()))
+                    .collect::<Vec<_>>();
+
+                self.jump_targets.push((go_label, break_label.clone(), None));
+                let mut first = true;
+                for (i, case_clause) in body.iter().enumerate() {
+                    if let SwitchCase::Cases(..) = case_clause.switch_case {
+                        writeln!(output, "{}{}if ({} == {})",
+                                 indent(self.indent), if first { "" } else { "else " }, matched, i).unwrap();
+                        self.visit_case_clause(body, i, &case_labels, output);
+                        first = false;
+                    }
+                }
+                for (i, case_clause) in body.iter().enumerate() {
+                    if let SwitchCase::Default = case_clause.switch_case {
+                        if !first {
+                            writeln!(output, "{}else", indent(self.indent)).unwrap();
+                        }
+                        self.visit_case_clause(body, i, &case_labels, output);
+                    }
+                }
+                self.jump_targets.pop();
+
+                writeln!(output, "{}{}: ;", indent(self.indent), break_label).unwrap();
+                self.indent -= 1;
+                writeln!(output, "{}}}", indent(self.indent)).unwrap();
+            },
+            StatementVariant::IncDec { ref expr, is_dec } => {
+                let mut pre = String::new();
+                let lvalue = self.visit_lvalue(expr, &mut pre)
+                    .expect("cannot increment the blank identifier");
+                let op = if is_dec { BinaryOperator::Sub } else { BinaryOperator::Add };
+                let one = if expr.kind.is_floating_point() { "1.0" } else { "1" };
+                let value = binary_operation(op, &expr.kind, &lvalue, one, stmt.span.start.line, self);
+
+                output.push_str(&pre);
+                writeln!(output, "{}{} = {};", indent(self.indent), lvalue, value).unwrap();
+            }
+        }
+    }
+
+    fn codegen_print(&mut self, exprs: &[Expression], is_println: bool, output: &mut String) {
+        let mut pre = String::new();
+        let mut post = String::new();
+        for (i, expr) in exprs.iter().enumerate() {
+            let function =
+                match expr.kind.resolve() {
+                    Kind::Basic(BasicKind::Float) => "gl_print_float",
+                    Kind::Basic(BasicKind::String) => "gl_print_string",
+                    Kind::Basic(BasicKind::Bool) => "gl_print_bool",
+                    _ => "gl_print_int",
+                };
+            let mut value = String::new();
+            self.visit_expression(expr, &mut pre, &mut value);
+            writeln!(post, "{}{}({});", indent(self.indent), function, value).unwrap();
+            if is_println && i < exprs.len() - 1 {
+                writeln!(post, "{}putchar(' ');", indent(self.indent)).unwrap();
+            }
+        }
+        if is_println {
+            writeln!(post, "{}putchar('\\n');", indent(self.indent)).unwrap();
+        }
+        write!(output, "{}{}", pre, post).unwrap();
+    }
+
+    // Returns the C lvalue for an assignable expression, or None for the blank identifier.
+    // Indices are evaluated into temporaries right away so that later assignments in the same
+    // statement cannot change which element is assigned to.
+    fn visit_lvalue(&mut self, exp: &Expression, pre_string: &mut String) -> Option<String> {
+        match exp.variant {
+            ExpressionVariant::Identifier { ref name, .. } => {
+                if name == "_" {
+                    None
+                } else {
+                    Some(mangle(name))
+                }
+            }
+            // Map elements are left to visit_expression, which reports them
+            ExpressionVariant::Index { ref primary, ref index } if !is_map(&primary.kind) => {
+                let primary_value = self.visit_lvalue(primary, pre_string)
+                    .expect("cannot index the blank identifier");
+                let mut index_value = String::new();
+                self.visit_expression(index, pre_string, &mut index_value);
+                let temp = self.create_temp();
+                writeln!(pre_string, "{}int32_t {} = {};", indent(self.indent), temp, index_value).unwrap();
+                Some(self.index_expression(&primary.kind, &primary_value, &temp, exp.span.start.line))
+            }
+            ExpressionVariant::Selector { ref primary, ref name } => {
+                let primary_value = self.visit_lvalue(primary, pre_string)
+                    .expect("cannot select from the blank identifier");
+                Some(format!("{}.{}", primary_value, field_name(name)))
+            }
+            _ => {
+                let mut post = String::new();
+                self.visit_expression(exp, pre_string, &mut post);
+                Some(post)
+            }
+        }
+    }
+
+    fn index_expression(&mut self, primary_kind: &Kind, primary: &str, index: &str, line_number: u32) -> String {
+        match primary_kind.resolve() {
+            Kind::Slice(ref base) => {
+                let element = self.c_type(base);
+                format!("(({}*){}.data)[gl_check_bounds({}, {}.length, {})]",
+                        element, primary, index, primary, line_number)
+            },
+            Kind::Array(_, size) => {
+                format!("{}.elements[gl_check_bounds({}, {}, {})]", primary, index, size, line_number)
+            },
+            _ => panic!("codegening index of something other than slice or array")
+        }
+    }
+
+    // Same convention as in the JS backend:
+    // each line in pre_string is indented and ends with a semicolon and a newline
+    // post_string is not indented or anything
+    fn visit_expression(&mut self,
+                        exp: &Expression,
+                        pre_string: &mut String,
+                        post_string: &mut String) {
+
+        match exp.variant {
+            _ if is_sized_numeric(&exp.kind) => {
+                self.unsupported.error(exp.span, "sized numeric types are not supported by the C backend.");
+                write!(post_string, "0").unwrap();
+            }
+
+            ExpressionVariant::RawLiteral { ref value } => {
+                match exp.kind {
+                    Kind::Basic(_) if is_rune_literal(value) => {
+                        write!(post_string, "{}", parse_rune_literal(value)).unwrap();
+                    },
+                    Kind::Basic(BasicKind::Int) | Kind::Basic(BasicKind::Int32) => {
+                        let n = string_to_int(value);
+                        if n > i32::MAX as u32 {
+                            write!(post_string, "(int32_t){}u", n).unwrap();
+                        } else {
+                            write!(post_string, "{}", n).unwrap();
+                        }
+                    },
+                    Kind::Basic(BasicKind::Float) => {
+                        write!(post_string, "{}", value).unwrap();
+                    },
+                    Kind::Basic(BasicKind::String) => {
+                        let s = parse_string_literal(value);
+                        write!(post_string, "gl_string_literal(\"{}\", {})", escape_c_string(&s), s.len()).unwrap();
+                    },
+                    _ => {
+                        panic!("Invalid type of literal expression");
+                    }
+                }
+            }
+
+            ExpressionVariant::Identifier { .. } if is_function(&exp.kind) => {
+                self.unsupported_function(exp, post_string);
+            }
+
+            ExpressionVariant::FunctionLiteral { .. } | ExpressionVariant::Method { .. } => {
+                self.unsupported_function(exp, post_string);
+            }
+
+            ExpressionVariant::Panic { .. } => {
+                self.unsupported.error(exp.span, "panic is not supported by the C backend.");
+                write!(post_string, "0").unwrap();
+            }
+
+            ExpressionVariant::Slice { .. } => {
+                self.unsupported.error(exp.span, "slice expressions are not supported by the C backend.");
+                write!(post_string, "0").unwrap();
+            }
+
+            ExpressionVariant::CompositeLiteral { .. } if !matches!(exp.kind.resolve(), Kind::Map(..)) => {
+                self.unsupported.error(exp.span, "composite literals are not supported by the C backend.");
+                write!(post_string, "0").unwrap();
+            }
+
+            ExpressionVariant::Make { ref kind, .. } if kind.evaluated_kind.is_slice() => {
+                self.unsupported.error(exp.span, "make of slices is not supported by the C backend.");
+                write!(post_string, "0").unwrap();
+            }
+
+            ExpressionVariant::Copy { .. } => {
+                self.unsupported.error(exp.span, "copy is not supported by the C backend.");
+                write!(post_string, "0").unwrap();
+            }
+
+            ExpressionVariant::Nil if is_function(&exp.kind) => {
+                self.unsupported_function(exp, post_string);
+            }
+
+            ExpressionVariant::New { .. } | ExpressionVariant::Dereference { .. } |
+            ExpressionVariant::AddressOf { .. } | ExpressionVariant::Nil => {
+                self.unsupported_pointer(exp, post_string);
+            }
+
+            ExpressionVariant::Selector { ref primary, .. } if primary.kind.is_pointer() => {
+                self.unsupported_pointer(exp, post_string);
+            }
+
+            ExpressionVariant::Identifier { ref name, .. } => {
+                match name.as_str() {
+                    "true" | "false" => write!(post_string, "{}", name).unwrap(),
+                    _ => write!(post_string, "{}", mangle(name)).unwrap(),
+                }
+            }
+
+            ExpressionVariant::UnaryOperation { op, ref rhs } => {
+                let mut value = String::new();
+                self.visit_expression(rhs, pre_string, &mut value);
+                match op {
+                    UnaryOperator::Plus => write!(post_string, "({})", value).unwrap(),
+                    UnaryOperator::Neg => {
+                        if rhs.kind.is_integer() {
+                            write!(post_string, "gl_neg({})", value).unwrap();
+                        } else {
+                            write!(post_string, "(-({}))", value).unwrap();
+                        }
+                    }
+                    UnaryOperator::BwCompl => write!(post_string, "(~({}))", value).unwrap(),
+                    UnaryOperator::Not => write!(post_string, "(!({}))", value).unwrap(),
+                }
+            }
+
+            ExpressionVariant::BinaryOperation { op, ref lhs, ref rhs } => {
+                let mut lhs_value = String::new();
+                self.visit_expression(lhs, pre_string, &mut lhs_value);
+
+                if op == BinaryOperator::Or || op == BinaryOperator::And {
+                    let mut rhs_pre = String::new();
+                    let mut rhs_value = String::new();
+                    self.indent += 1;
+                    self.visit_expression(rhs, &mut rhs_pre, &mut rhs_value);
+                    self.indent -= 1;
+                    let c_op = if op == BinaryOperator::Or { "||" } else { "&&" };
+
+                    if rhs_pre.is_empty() {
+                        write!(post_string, "({} {} {})", lhs_value, c_op, rhs_value).unwrap();
+                    } else {
+                        // The right hand side has function calls which must only be evaluated if
+                        // the left hand side doesn't short circuit.
+                        let temp = self.create_temp();
+                        writeln!(pre_string, "{}bool {} = {};", indent(self.indent), temp, lhs_value).unwrap();
+                        writeln!(pre_string, "{}if ({}{}) {{", indent(self.indent),
+                                 if op == BinaryOperator::Or { "!" } else { "" }, temp).unwrap();
+                        pre_string.push_str(&rhs_pre);
+                        writeln!(pre_string, "{}{} = {};", indent(self.indent + 1), temp, rhs_value).unwrap();
+                        writeln!(pre_string, "{}}}", indent(self.indent)).unwrap();
+                        write!(post_string, "{}", temp).unwrap();
+                    }
+                } else {
+                    let mut rhs_value = String::new();
+                    self.visit_expression(rhs, pre_string, &mut rhs_value);
+                    let value = binary_operation(op, &lhs.kind, &lhs_value, &rhs_value, exp.span.start.line, self);
+                    write!(post_string, "{}", value).unwrap();
+                }
+            }
+
+            // Calls to variadic functions are reported with their declaration
+            ExpressionVariant::FunctionCall { ref primary, ref arguments, .. } => {
+                let mut call = String::new();
+                match primary.variant {
+                    ExpressionVariant::Identifier { ref name, .. } if self.function_names.contains(name) => {
+                        write!(call, "{}", mangle(name)).unwrap();
+                    }
+                    _ => self.visit_expression(primary, pre_string, &mut call),
+                }
+
+                write!(call, "(").unwrap();
+                for (i, arg) in arguments.iter().enumerate() {
+                    if i > 0 {
+                        write!(call, ", ").unwrap();
+                    }
+                    self.visit_expression(arg, pre_string, &mut call);
+                }
+                write!(call, ")").unwrap();
+
+                // Calls are hoisted out of the expression so that they happen in order. Calls
+                // giving several results are only used as statements here.
+                if let Kind::Void | Kind::Tuple(..) = exp.kind {
+                    writeln!(pre_string, "{}{};", indent(self.indent), call).unwrap();
+                } else {
+                    let temp = self.create_temp();
+                    let c_kind = self.c_type(&exp.kind);
+                    writeln!(pre_string, "{}{} {} = {};", indent(self.indent), c_kind, temp, call).unwrap();
+                    write!(post_string, "{}", temp).unwrap();
+                }
+            }
+
+            ExpressionVariant::Index { ref primary, .. } if is_map(&primary.kind) => {
+                self.unsupported_map(exp, post_string);
+            }
+
+            ExpressionVariant::Len { ref expr } if is_map(&expr.kind) => {
+                self.unsupported_map(exp, post_string);
+            }
+
+            ExpressionVariant::Len { ref expr } | ExpressionVariant::Cap { ref expr } if expr.kind.is_chan() => {
+                self.unsupported_channel(exp, post_string);
+            }
+
+            ExpressionVariant::Make { ref kind, .. } if kind.evaluated_kind.is_chan() => {
+                self.unsupported_channel(exp, post_string);
+            }
+
+            ExpressionVariant::CommaOk { ref expr } if is_receive(expr) => {
+                self.unsupported_channel(exp, post_string);
+            }
+
+            ExpressionVariant::Receive { .. } | ExpressionVariant::Close { .. } => {
+                self.unsupported_channel(exp, post_string);
+            }
+
+            ExpressionVariant::CommaOk { ref expr } if is_type_assertion(expr) => {
+                self.unsupported_interface(exp, post_string);
+            }
+
+            ExpressionVariant::Make { .. } | ExpressionVariant::Delete { .. } |
+            ExpressionVariant::CompositeLiteral { .. } | ExpressionVariant::CommaOk { .. } => {
+                self.unsupported_map(exp, post_string);
+            }
+
+            ExpressionVariant::ToInterface { .. } | ExpressionVariant::InterfaceMethod { .. } |
+            ExpressionVariant::TypeAssertion { .. } => {
+                self.unsupported_interface(exp, post_string);
+            }
+
+            ExpressionVariant::Index { ref primary, ref index } => {
+                let mut primary_value = String::new();
+                let mut index_value = String::new();
+                self.visit_expression(primary, pre_string, &mut primary_value);
+                self.visit_expression(index, pre_string, &mut index_value);
+                let value = self.index_expression(&primary.kind, &primary_value, &index_value, exp.span.start.line);
+                write!(post_string, "{}", value).unwrap();
+            }
+
+            ExpressionVariant::Selector { ref primary, ref name } => {
+                self.visit_expression(primary, pre_string, post_string);
+                write!(post_string, ".{}", field_name(name)).unwrap();
+            }
+
+            ExpressionVariant::Append { ref lhs, ref rhs } => {
+                let element_kind = match lhs.kind.resolve() {
+                    Kind::Slice(ref base) => (**base).clone(),
+                    _ => panic!("appending to something other than a slice"),
+                };
+                let element = self.c_type(&element_kind);
+
+                let mut slice_value = String::new();
+                self.visit_expression(lhs, pre_string, &mut slice_value);
+                let slice = self.create_temp();
+                writeln!(pre_string, "{}gl_slice {} = {};", indent(self.indent), slice, slice_value).unwrap();
+
+                let mut element_value = String::new();
+                self.visit_expression(rhs, pre_string, &mut element_value);
+                let temp = self.create_temp();
+                writeln!(pre_string, "{}{} {} = {};", indent(self.indent), element, temp, element_value).unwrap();
+
+                writeln!(pre_string, "{}{} = gl_append_slot({}, sizeof({}));",
+                         indent(self.indent), slice, slice, element).unwrap();
+                writeln!(pre_string, "{}(({}*){}.data)[{}.length - 1] = {};",
+                         indent(self.indent), element, slice, slice, temp).unwrap();
+                write!(post_string, "{}", slice).unwrap();
+            }
+
+            ExpressionVariant::Len { ref expr } | ExpressionVariant::Cap { ref expr } => {
+                let mut value = String::new();
+                self.visit_expression(expr, pre_string, &mut value);
+                match expr.kind.resolve() {
+                    // The calls that kept the length from being folded are already in pre_string
+                    Kind::Array(_, size) => write!(post_string, "{}", size).unwrap(),
+                    Kind::Slice(..) => {
+                        if let ExpressionVariant::Cap { .. } = exp.variant {
+                            write!(post_string, "({}).capacity", value).unwrap();
+                        } else {
+                            write!(post_string, "({}).length", value).unwrap();
+                        }
+                    }
+                    _ => write!(post_string, "({}).length", value).unwrap(),
+                }
+            }
+
+            ExpressionVariant::TypeCast { ref expr, .. } => {
+                let mut value = String::new();
+                self.visit_expression(expr, pre_string, &mut value);
+                if exp.kind.is_string() && expr.kind.is_integer() {
+                    write!(post_string, "gl_string_from_rune({})", value).unwrap();
+                } else if exp.kind.is_integer() && expr.kind.is_floating_point() {
+                    write!(post_string, "gl_float_to_int({})", value).unwrap();
+                } else if exp.kind.is_floating_point() && expr.kind.is_integer() {
+                    write!(post_string, "((double)({}))", value).unwrap();
+                } else {
+                    write!(post_string, "{}", value).unwrap();
+                }
+            }
+        }
+    }
+
+    // Whether the values assigned to `count` variables all come from one expression, either a
+    // comma-ok lookup or a call to a function with several results, which are reported as
+    // unsupported
+    fn is_unsupported_tuple(&mut self, exprs: &[Expression], count: usize) -> bool {
+        if exprs.len() == count {
+            return false;
+        }
+        // The function itself has already been reported
+        if let ExpressionVariant::CommaOk { ref expr } = exprs[0].variant {
+            if is_type_assertion(expr) {
+                self.unsupported.error(exprs[0].span, "interfaces are not supported by the C backend.");
+            } else if is_receive(expr) {
+                self.unsupported.error(exprs[0].span, "goroutines and channels are not supported by the C backend.");
+            } else {
+                self.unsupported.error(exprs[0].span, "maps are not supported by the C backend.");
+            }
+        }
+        true
+    }
+
+    // Reports a map operation and stands in for its value, so that the rest of the program can
+    // still be checked
+    fn unsupported_map(&mut self, exp: &Expression, post_string: &mut String) {
+        self.unsupported.error(exp.span, "maps are not supported by the C backend.");
+        write!(post_string, "0").unwrap();
+    }
+
+    fn unsupported_function(&mut self, exp: &Expression, post_string: &mut String) {
+        self.unsupported.error(exp.span, "function values are not supported by the C backend.");
+        write!(post_string, "0").unwrap();
+    }
+
+    fn unsupported_pointer(&mut self, exp: &Expression, post_string: &mut String) {
+        self.unsupported.error(exp.span, "pointers are not supported by the C backend.");
+        write!(post_string, "0").unwrap();
+    }
+
+    fn unsupported_interface(&mut self, exp: &Expression, post_string: &mut String) {
+        self.unsupported.error(exp.span, "interfaces are not supported by the C backend.");
+        write!(post_string, "0").unwrap();
+    }
+
+    fn unsupported_channel(&mut self, exp: &Expression, post_string: &mut String) {
+        self.unsupported.error(exp.span, "goroutines and channels are not supported by the C backend.");
+        write!(post_string, "0").unwrap();
+    }
+
+    // Returns a C expression comparing two values of the given kind
+    fn equality(&mut self, kind: &Kind, a: &str, b: &str) -> String {
+        match kind.resolve() {
+            Kind::Basic(BasicKind::String) => format!("gl_string_eq({}, {})", a, b),
+            Kind::Array(..) | Kind::Struct(..) => {
+                let function = self.equality_function(kind);
+                format!("{}({}, {})", function, a, b)
+            }
+            _ => format!("({} == {})", a, b),
+        }
+    }
+
+    fn equality_function(&mut self, kind: &Kind) -> String {
+        let key = type_key(kind);
+        if let Some(name) = self.equality_functions.get(&key) {
+            return name.clone();
+        }
+
+        let c_kind = self.c_type(kind);
+        let name = format!("gl_equal_{}", self.create_id());
+        self.equality_functions.insert(key, name.clone());
+
+        let mut body = String::new();
+        match kind.resolve() {
+            Kind::Array(ref base, size) => {
+                let equal = self.equality(base, "a.elements[i]", "b.elements[i]");
+                writeln!(body, "\tfor (int32_t i = 0; i < {}; i++) {{", size).unwrap();
+                writeln!(body, "\t\tif (!{}) return false;", equal).unwrap();
+                writeln!(body, "\t}}").unwrap();
+            }
+            Kind::Struct(ref fields) => {
+                for (i, field) in fields.iter().enumerate() {
+                    // Blank fields are ignored when comparing structs
+                    if field.name == "_" {
+                        continue;
+                    }
+                    let name = struct_field_name(&field.name, i);
+                    let equal = self.equality(&field.kind,
+                                              &format!("a.{}", name),
+                                              &format!("b.{}", name));
+                    writeln!(body, "\tif (!{}) return false;", equal).unwrap();
+                }
+            }
+            _ => unreachable!(),
+        }
+
+        writeln!(self.helper_functions, "static bool {}({} a, {} b) {{", name, c_kind, c_kind).unwrap();
+        write!(self.helper_functions, "{}", body).unwrap();
+        writeln!(self.helper_functions, "\treturn true;\n}}\n").unwrap();
+        name
+    }
+
+    // Returns the C type used to represent a kind, defining it first if necessary
+    fn c_type(&mut self, kind: &Kind) -> String {
+        match kind.resolve() {
+            Kind::Basic(BasicKind::Int) | Kind::Basic(BasicKind::Int32) => "int32_t".to_string(),
+            Kind::Basic(BasicKind::Float) => "double".to_string(),
+            Kind::Basic(BasicKind::Bool) => "bool".to_string(),
+            Kind::Basic(BasicKind::String) => "gl_string".to_string(),
+            // The sized numeric kinds are reported as unsupported where they are used
+            Kind::Basic(BasicKind::Float32) => "float".to_string(),
+            Kind::Basic(basic) => format!("{}_t", basic),
+            Kind::Slice(..) => "gl_slice".to_string(),
+            // Only nil maps can be made, since the operations on maps are reported as unsupported
+            Kind::Map(..) => "void *".to_string(),
+            // Function values, pointers, interfaces and channels are reported as unsupported where
+            // they are used
+            Kind::Function(..) | Kind::Pointer(..) | Kind::Interface(..) | Kind::Chan(..) => "void *".to_string(),
+            Kind::Void => "void".to_string(),
+            resolved @ Kind::Array(..) | resolved @ Kind::Struct(..) => {
+                let key = type_key(&resolved);
+                if let Some(name) = self.type_names.get(&key) {
+                    return name.clone();
+                }
+
+                // Define the types that this one depends on first
+                let mut definition = String::new();
+                let name;
+                match resolved {
+                    Kind::Array(ref base, size) => {
+                        let element = self.c_type(base);
+                        name = format!("gl_array_{}", self.create_id());
+                        // C doesn't allow arrays of size 0
+                        writeln!(definition, "typedef struct {{ {} elements[{}]; }} {};",
+                                 element, if size == 0 { 1 } else { size }, name).unwrap();
+                    }
+                    Kind::Struct(ref fields) => {
+                        let mut members = String::new();
+                        for (i, field) in fields.iter().enumerate() {
+                            let field_kind = self.c_type(&field.kind);
+                            write!(members, "{} {}; ", field_kind, struct_field_name(&field.name, i)).unwrap();
+                        }
+                        if fields.is_empty() {
+                            // C doesn't allow empty structs
+                            members = "char unused; ".to_string();
+                        }
+                        name = format!("gl_struct_{}", self.create_id());
+                        writeln!(definition, "typedef struct {{ {}}} {};", members, name).unwrap();
+                    }
+                    _ => unreachable!(),
+                }
+
+                self.type_names.insert(key, name.clone());
+                self.type_definitions.push_str(&definition);
+                name
+            }
+            k => panic!("no C type for {}", k),
+        }
+    }
+
+    fn create_temp(&mut self) -> String {
+        format!("gl_temp_{}", self.create_id())
+    }
+
+    fn create_id(&mut self) -> String {
+        self.id_counter += 1;
+        self.id_counter.to_string()
+    }
+}
+
+// Returns the C expression for a binary operation (other than && and ||) on values of the given
+// kind.
+fn binary_operation(op: BinaryOperator, kind: &Kind, a: &str, b: &str, line_number: u32,
+                    visitor: &mut CodeGenVisitor) -> String {
+    use crate::ast::BinaryOperator::*;
+    if kind.is_string() {
+        return match op {
+            Add => format!("gl_string_concat({}, {})", a, b),
+            Eq => format!("gl_string_eq({}, {})", a, b),
+            Neq => format!("(!gl_string_eq({}, {}))", a, b),
+            Lt => format!("(gl_string_compare({}, {}) < 0)", a, b),
+            Leq => format!("(gl_string_compare({}, {}) <= 0)", a, b),
+            Gt => format!("(gl_string_compare({}, {}) > 0)", a, b),
+            Geq => format!("(gl_string_compare({}, {}) >= 0)", a, b),
+            _ => panic!("invalid operation {:?} on strings", op),
+        };
+    }
+
+    let integer = kind.is_integer();
+    match op {
+        Eq => visitor.equality(kind, a, b),
+        Neq => format!("(!{})", visitor.equality(kind, a, b)),
+        Lt => format!("({} < {})", a, b),
+        Leq => format!("({} <= {})", a, b),
+        Gt => format!("({} > {})", a, b),
+        Geq => format!("({} >= {})", a, b),
+
+        Add if integer => format!("gl_add({}, {})", a, b),
+        Sub if integer => format!("gl_sub({}, {})", a, b),
+        Mul if integer => format!("gl_mul({}, {})", a, b),
+        Div if integer => format!("gl_div({}, {}, {})", a, b, line_number),
+        Add => format!("({} + {})", a, b),
+        Sub => format!("({} - {})", a, b),
+        Mul => format!("({} * {})", a, b),
+        Div => format!("({} / {})", a, b),
+
+        Mod => format!("gl_mod({}, {}, {})", a, b, line_number),
+        BwOr => format!("({} | {})", a, b),
+        BwXor => format!("({} ^ {})", a, b),
+        BwAnd => format!("({} & {})", a, b),
+        BwAndNot => format!("({} & ~{})", a, b),
+        LShift => format!("gl_shl({}, {}, {})", a, b, line_number),
+        RShift => format!("gl_shr({}, {}, {})", a, b, line_number),
+
+        Or | And => panic!("short circuiting operators are handled separately"),
+    }
+}
+
+// The kind of each variable declared by a var spec
+fn var_spec_kinds(var_spec: &VarSpec) -> Vec<Kind> {
+    match var_spec.rhs {
+        Some(ref values) => values.iter().map(|e| e.kind.clone()).collect(),
+        None => var_spec.names.iter().map(|_| var_spec.evaluated_kind.clone()).collect(),
+    }
+}
+
+// A string that identifies a type by its structure, with all defined types resolved
+fn type_key(kind: &Kind) -> String {
+    match kind.resolve() {
+        Kind::Array(ref base, size) => format!("[{}]{}", size, type_key(base)),
+        Kind::Slice(ref base) => format!("[]{}", type_key(base)),
+        Kind::Struct(ref fields) => {
+            let mut key = "struct{".to_string();
+            for field in fields {
+                write!(key, "{} {};", field.name, type_key(&field.kind)).unwrap();
+            }
+            key.push('}');
+            key
+        }
+        k => format!("{}", k),
+    }
+}
+
+fn is_map(kind: &Kind) -> bool {
+    matches!(kind.resolve(), Kind::Map(..))
+}
+
+fn is_type_assertion(exp: &Expression) -> bool {
+    matches!(exp.variant, ExpressionVariant::TypeAssertion { .. })
+}
+
+fn is_receive(exp: &Expression) -> bool {
+    matches!(exp.variant, ExpressionVariant::Receive { .. })
+}
+
+fn is_function(kind: &Kind) -> bool {
+    matches!(kind.resolve(), Kind::Function(..))
+}
+
+// Whether the kind is one of the numeric kinds other than int, rune (int32) and float64
+fn is_sized_numeric(kind: &Kind) -> bool {
+    match kind.resolve() {
+        Kind::Basic(BasicKind::Int) | Kind::Basic(BasicKind::Int32) | Kind::Basic(BasicKind::Float) => false,
+        resolved => resolved.is_numeric(),
+    }
+}
+
+fn zero_initializer(kind: &Kind) -> &'static str {
+    match kind.resolve() {
+        Kind::Basic(BasicKind::String) | Kind::Slice(..) | Kind::Array(..) | Kind::Struct(..) => "{0}",
+        _ => "0",
+    }
 }
+
+// Renamed identifiers look like `name·3`, which is not a valid C identifier. The prefix keeps user
+// identifiers from clashing with C keywords and the runtime.
+fn mangle(name: &str) -> String {
+    format!("v_{}", name.replace("·", "_"))
+}
+
+fn label_name(name: &str) -> String {
+    format!("l_{}", name)
+}
+
+fn field_name(name: &str) -> String {
+    format!("f_{}", name)
+}
+
+// Blank fields can appear several times in the same struct, so they get numbered
+fn struct_field_name(name: &str, index: usize) -> String {
+    if name == "_" {
+        format!("f__{}", index)
+    } else {
+        field_name(name)
+    }
+}
+
+fn escape_c_string(s: &str) -> String {
+    let mut ret = String::new();
+    for b in s.bytes() {
+        match b {
+            b'"' => ret.push_str("\\\""),
+            b'\\' => ret.push_str("\\\\"),
+            b'?' => ret.push_str("\\?"), // avoid trigraphs
+            0x20..=0x7e => ret.push(b as char),
+            _ => write!(ret, "\\{:03o}", b).unwrap(),
+        }
+    }
+    ret
+}
+
+/// Returns the C program for the given (typechecked) AST, or the uses of features that the C
+/// backend doesn't support
+pub fn codegen(root: &Program) -> Result<String, Vec<Diagnostic>> {
+    let mut visitor = CodeGenVisitor {
+        indent: 0,
+        id_counter: 0,
+        init_functions: Vec::new(),
+        type_names: HashMap::new(),
+        equality_functions: HashMap::new(),
+        type_definitions: String::new(),
+        helper_functions: String::new(),
+        prototypes: String::new(),
+        globals: String::new(),
+        global_initialization: String::new(),
+        functions: String::new(),
+        output: String::new(),
+        function_names: HashSet::new(),
+        jump_targets: Vec::new(),
+        statement_label: None,
+        fallthrough_targets: Vec::new(),
+        unsupported: Diagnostics::new(),
+    };
+
+    visitor.visit_program(root);
+    visitor.unsupported.result()?;
+    Ok(visitor.output)
+}
+
+// The runtime functions used by the generated code
+const HEADER: &str = include_str!("header.c");
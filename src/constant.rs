@@ -0,0 +1,691 @@
+use std::cmp::Ordering;
+use std::fmt;
+use crate::ast::{BinaryOperator, UnaryOperator};
+use crate::kind::BasicKind;
+use crate::util;
+
+// The largest shift allowed in a constant expression, like the limit the Go compiler has
+const MAX_SHIFT: u32 = 10000;
+
+/// Arbitrary-precision integer used for integer constants
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigInt {
+    negative: bool,
+    // Little-endian digits in base 2^32, without trailing zeros. Zero has no digits.
+    digits: Vec<u32>,
+}
+
+/// The exact value of a floating-point constant, with a positive denominator and no common
+/// factors
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rational {
+    numerator: BigInt,
+    denominator: BigInt,
+}
+
+/// The value of a constant expression. Runes are integers; whether a constant is a rune is
+/// part of its kind.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constant {
+    Bool(bool),
+    String(String),
+    Int(BigInt),
+    Float(Rational),
+}
+
+
+fn trim(digits: &mut Vec<u32>) {
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+}
+
+fn magnitude_cmp(a: &[u32], b: &[u32]) -> Ordering {
+    if a.len() != b.len() {
+        return a.len().cmp(&b.len());
+    }
+    for i in (0..a.len()).rev() {
+        if a[i] != b[i] {
+            return a[i].cmp(&b[i]);
+        }
+    }
+    Ordering::Equal
+}
+
+fn magnitude_add(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut ret = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0u64;
+    for i in 0..a.len().max(b.len()) {
+        let sum = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        ret.push(sum as u32);
+        carry = sum >> 32;
+    }
+    ret.push(carry as u32);
+    trim(&mut ret);
+    ret
+}
+
+// a must not be smaller than b
+fn magnitude_sub(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut ret = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (i, &digit) in a.iter().enumerate() {
+        let mut difference = digit as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+        if difference < 0 {
+            difference += 1 << 32;
+            borrow = 1;
+        }
+        ret.push(difference as u32);
+    }
+    trim(&mut ret);
+    ret
+}
+
+fn magnitude_mul(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut ret = vec![0u32; a.len() + b.len()];
+    for i in 0..a.len() {
+        let mut carry = 0u64;
+        for j in 0..b.len() {
+            let product = a[i] as u64 * b[j] as u64 + ret[i + j] as u64 + carry;
+            ret[i + j] = product as u32;
+            carry = product >> 32;
+        }
+        ret[i + b.len()] = carry as u32;
+    }
+    trim(&mut ret);
+    ret
+}
+
+fn magnitude_bits(a: &[u32]) -> u32 {
+    match a.last() {
+        Some(last) => (a.len() as u32 - 1) * 32 + (32 - last.leading_zeros()),
+        None => 0,
+    }
+}
+
+fn magnitude_shl(a: &[u32], shift: u32) -> Vec<u32> {
+    if a.is_empty() {
+        return Vec::new();
+    }
+    let (words, bits) = ((shift / 32) as usize, shift % 32);
+    let mut ret = vec![0u32; words];
+    let mut carry = 0u32;
+    for &digit in a {
+        if bits == 0 {
+            ret.push(digit);
+        } else {
+            ret.push(digit << bits | carry);
+            carry = digit >> (32 - bits);
+        }
+    }
+    ret.push(carry);
+    trim(&mut ret);
+    ret
+}
+
+fn magnitude_shr(a: &[u32], shift: u32) -> Vec<u32> {
+    let (words, bits) = ((shift / 32) as usize, shift % 32);
+    if words >= a.len() {
+        return Vec::new();
+    }
+    let mut ret = Vec::with_capacity(a.len() - words);
+    for i in words..a.len() {
+        let high = if bits == 0 { 0 } else { a.get(i + 1).map_or(0, |d| d << (32 - bits)) };
+        ret.push(a[i] >> bits | high);
+    }
+    trim(&mut ret);
+    ret
+}
+
+// Schoolbook division one bit at a time; constants are small enough for it not to matter
+fn magnitude_div_rem(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    assert!(!b.is_empty(), "division by zero");
+    let mut quotient = vec![0u32; a.len()];
+    let mut remainder = Vec::new();
+    for bit in (0..magnitude_bits(a)).rev() {
+        remainder = magnitude_shl(&remainder, 1);
+        if a[(bit / 32) as usize] >> (bit % 32) & 1 == 1 {
+            if remainder.is_empty() {
+                remainder.push(1);
+            } else {
+                remainder[0] |= 1;
+            }
+        }
+        if magnitude_cmp(&remainder, b) != Ordering::Less {
+            remainder = magnitude_sub(&remainder, b);
+            quotient[(bit / 32) as usize] |= 1 << (bit % 32);
+        }
+    }
+    trim(&mut quotient);
+    (quotient, remainder)
+}
+
+
+impl BigInt {
+    fn new(negative: bool, mut digits: Vec<u32>) -> BigInt {
+        trim(&mut digits);
+        BigInt { negative: negative && !digits.is_empty(), digits }
+    }
+
+    pub fn zero() -> BigInt {
+        BigInt::new(false, Vec::new())
+    }
+
+    pub fn from_i64(value: i64) -> BigInt {
+        let magnitude = value.unsigned_abs();
+        BigInt::new(value < 0, vec![magnitude as u32, (magnitude >> 32) as u32])
+    }
+
+    /// Parses a decimal, octal or hexadecimal integer literal
+    pub fn parse_literal(text: &str) -> BigInt {
+        let (radix, digits) =
+            if text.starts_with("0x") || text.starts_with("0X") {
+                (16, &text[2..])
+            } else if text.len() > 1 && text.starts_with('0') {
+                (8, &text[1..])
+            } else {
+                (10, text)
+            };
+        let mut ret = Vec::new();
+        for c in digits.chars() {
+            let digit = c.to_digit(radix).expect("invalid digit in integer literal");
+            ret = magnitude_add(&magnitude_mul(&ret, &[radix]), &[digit]);
+        }
+        BigInt::new(false, ret)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        if self.digits.len() > 2 {
+            return None;
+        }
+        let magnitude = self.digits.iter().rev().fold(0u64, |acc, &d| acc << 32 | d as u64);
+        if self.negative {
+            if magnitude <= 1 << 63 { Some((magnitude as i64).wrapping_neg()) } else { None }
+        } else {
+            if magnitude < 1 << 63 { Some(magnitude as i64) } else { None }
+        }
+    }
+
+    pub fn neg(&self) -> BigInt {
+        BigInt::new(!self.negative, self.digits.clone())
+    }
+
+    pub fn add(&self, other: &BigInt) -> BigInt {
+        if self.negative == other.negative {
+            return BigInt::new(self.negative, magnitude_add(&self.digits, &other.digits));
+        }
+        match magnitude_cmp(&self.digits, &other.digits) {
+            Ordering::Less => BigInt::new(other.negative,
+                                          magnitude_sub(&other.digits, &self.digits)),
+            _ => BigInt::new(self.negative, magnitude_sub(&self.digits, &other.digits)),
+        }
+    }
+
+    pub fn sub(&self, other: &BigInt) -> BigInt {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &BigInt) -> BigInt {
+        BigInt::new(self.negative != other.negative, magnitude_mul(&self.digits, &other.digits))
+    }
+
+    /// Division truncated towards zero, like Go's `/` and `%`. The divisor must not be zero.
+    pub fn div_rem(&self, other: &BigInt) -> (BigInt, BigInt) {
+        let (quotient, remainder) = magnitude_div_rem(&self.digits, &other.digits);
+        (BigInt::new(self.negative != other.negative, quotient),
+         BigInt::new(self.negative, remainder))
+    }
+
+    pub fn shl(&self, shift: u32) -> BigInt {
+        BigInt::new(self.negative, magnitude_shl(&self.digits, shift))
+    }
+
+    /// Arithmetic shift, which rounds towards negative infinity
+    pub fn shr(&self, shift: u32) -> BigInt {
+        if self.negative {
+            // -((|a| - 1) >> n) - 1
+            let one = BigInt::from_i64(1);
+            let shifted = self.neg().sub(&one);
+            BigInt::new(false, magnitude_shr(&shifted.digits, shift)).neg().sub(&one)
+        } else {
+            BigInt::new(false, magnitude_shr(&self.digits, shift))
+        }
+    }
+
+    pub fn not(&self) -> BigInt {
+        self.neg().sub(&BigInt::from_i64(1))
+    }
+
+    // Two's complement digits, with enough of them to keep the sign bit
+    fn twos_complement(&self, length: usize) -> Vec<u32> {
+        let mut digits = self.digits.clone();
+        digits.resize(length, 0);
+        if self.negative {
+            for d in digits.iter_mut() {
+                *d = !*d;
+            }
+            for d in digits.iter_mut() {
+                let (sum, overflow) = d.overflowing_add(1);
+                *d = sum;
+                if !overflow {
+                    break;
+                }
+            }
+        }
+        digits
+    }
+
+    fn from_twos_complement(mut digits: Vec<u32>) -> BigInt {
+        if digits.last().is_some_and(|d| d >> 31 == 1) {
+            for d in digits.iter_mut() {
+                *d = !*d;
+            }
+            BigInt::new(false, digits).add(&BigInt::from_i64(1)).neg()
+        } else {
+            BigInt::new(false, digits)
+        }
+    }
+
+    fn bitwise<F: Fn(u32, u32) -> u32>(&self, other: &BigInt, f: F) -> BigInt {
+        let length = self.digits.len().max(other.digits.len()) + 1;
+        let a = self.twos_complement(length);
+        let b = other.twos_complement(length);
+        BigInt::from_twos_complement(a.iter().zip(b.iter()).map(|(&x, &y)| f(x, y)).collect())
+    }
+
+    pub fn and(&self, other: &BigInt) -> BigInt { self.bitwise(other, |a, b| a & b) }
+    pub fn or(&self, other: &BigInt) -> BigInt { self.bitwise(other, |a, b| a | b) }
+    pub fn xor(&self, other: &BigInt) -> BigInt { self.bitwise(other, |a, b| a ^ b) }
+    pub fn and_not(&self, other: &BigInt) -> BigInt { self.bitwise(other, |a, b| a & !b) }
+
+    fn gcd(&self, other: &BigInt) -> BigInt {
+        let (mut a, mut b) = (self.abs(), other.abs());
+        while !b.is_zero() {
+            let remainder = a.div_rem(&b).1;
+            a = b;
+            b = remainder;
+        }
+        a
+    }
+
+    fn abs(&self) -> BigInt {
+        BigInt::new(false, self.digits.clone())
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &BigInt) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => magnitude_cmp(&self.digits, &other.digits),
+            (true, true) => magnitude_cmp(&other.digits, &self.digits),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // Nine decimal digits at a time
+        let mut chunks = Vec::new();
+        let mut rest = self.digits.clone();
+        while !rest.is_empty() {
+            let (quotient, remainder) = magnitude_div_rem(&rest, &[1000000000]);
+            chunks.push(remainder.first().cloned().unwrap_or(0));
+            rest = quotient;
+        }
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", chunks.pop().unwrap())?;
+        for chunk in chunks.iter().rev() {
+            write!(f, "{:09}", chunk)?;
+        }
+        Ok(())
+    }
+}
+
+
+// Multiplies by 2^exponent without overflowing the intermediate powers
+fn scale(mut value: f64, mut exponent: i64) -> f64 {
+    while exponent > 1000 {
+        value *= 2f64.powi(1000);
+        exponent -= 1000;
+    }
+    while exponent < -1000 {
+        value *= 2f64.powi(-1000);
+        exponent += 1000;
+    }
+    value * 2f64.powi(exponent as i32)
+}
+
+impl Rational {
+    fn new(numerator: BigInt, denominator: BigInt) -> Rational {
+        let gcd = numerator.gcd(&denominator);
+        let (mut numerator, mut denominator) =
+            if gcd.is_zero() {
+                (numerator, denominator)
+            } else {
+                (numerator.div_rem(&gcd).0, denominator.div_rem(&gcd).0)
+            };
+        if denominator.is_negative() {
+            numerator = numerator.neg();
+            denominator = denominator.neg();
+        }
+        Rational { numerator, denominator }
+    }
+
+    pub fn from_int(value: &BigInt) -> Rational {
+        Rational::new(value.clone(), BigInt::from_i64(1))
+    }
+
+    /// Parses a float literal of the form `1.5`, `1.` or `.5`
+    pub fn parse_literal(text: &str) -> Rational {
+        let (whole, fraction) = match text.find('.') {
+            Some(i) => (&text[..i], &text[i + 1..]),
+            None => (text, ""),
+        };
+        let mut numerator = BigInt::zero();
+        let mut denominator = BigInt::from_i64(1);
+        let ten = BigInt::from_i64(10);
+        for c in whole.chars().chain(fraction.chars()) {
+            let digit = c.to_digit(10).expect("invalid digit in float literal");
+            numerator = numerator.mul(&ten).add(&BigInt::from_i64(digit as i64));
+        }
+        for _ in fraction.chars() {
+            denominator = denominator.mul(&ten);
+        }
+        Rational::new(numerator, denominator)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.numerator.is_zero()
+    }
+
+    /// The value as an integer, if it is one
+    pub fn to_integer(&self) -> Option<BigInt> {
+        if self.denominator == BigInt::from_i64(1) {
+            Some(self.numerator.clone())
+        } else {
+            None
+        }
+    }
+
+    pub fn neg(&self) -> Rational {
+        Rational::new(self.numerator.neg(), self.denominator.clone())
+    }
+
+    pub fn add(&self, other: &Rational) -> Rational {
+        Rational::new(self.numerator.mul(&other.denominator)
+                          .add(&other.numerator.mul(&self.denominator)),
+                      self.denominator.mul(&other.denominator))
+    }
+
+    pub fn sub(&self, other: &Rational) -> Rational {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &Rational) -> Rational {
+        Rational::new(self.numerator.mul(&other.numerator),
+                      self.denominator.mul(&other.denominator))
+    }
+
+    /// The divisor must not be zero
+    pub fn div(&self, other: &Rational) -> Rational {
+        Rational::new(self.numerator.mul(&other.denominator),
+                      self.denominator.mul(&other.numerator))
+    }
+
+    /// The closest float64, or None if the value is too large for one
+    pub fn to_f64(&self) -> Option<f64> {
+        if self.is_zero() {
+            return Some(0.0);
+        }
+        let numerator = &self.numerator.digits;
+        let denominator = &self.denominator.digits;
+
+        // Scale the quotient so that it has between 54 and 56 bits, which leaves at least one
+        // bit to round with
+        let shift = 55 - (magnitude_bits(numerator) as i64 - magnitude_bits(denominator) as i64);
+        let (quotient, remainder) =
+            if shift >= 0 {
+                magnitude_div_rem(&magnitude_shl(numerator, shift as u32), denominator)
+            } else {
+                magnitude_div_rem(numerator, &magnitude_shl(denominator, (-shift) as u32))
+            };
+        let quotient = quotient.iter().rev().fold(0u64, |acc, &d| acc << 32 | d as u64);
+
+        let extra = 64 - quotient.leading_zeros() - 53;
+        let mut mantissa = quotient >> extra;
+        let dropped = quotient & ((1 << extra) - 1);
+        let half = 1 << (extra - 1);
+        if dropped > half || (dropped == half && (!remainder.is_empty() || mantissa & 1 == 1)) {
+            mantissa += 1;
+        }
+
+        let value = scale(mantissa as f64, extra as i64 - shift);
+        if value.is_infinite() {
+            return None;
+        }
+        Some(if self.numerator.is_negative() { -value } else { value })
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Rational) -> Ordering {
+        self.numerator.mul(&other.denominator).cmp(&other.numerator.mul(&self.denominator))
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Rational) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.to_integer(), self.to_f64()) {
+            (Some(integer), _) => write!(f, "{}", integer),
+            (None, Some(float)) => write!(f, "{}", float),
+            (None, None) => write!(f, "{}/{}", self.numerator, self.denominator),
+        }
+    }
+}
+
+
+fn invalid_operation<T: fmt::Debug>(op: T, value: &Constant) -> String {
+    format!("invalid operation {:?} on constant {}.", op, value)
+}
+
+impl Constant {
+    /// The value of a literal of the given kind
+    pub fn parse_literal(kind: BasicKind, text: &str) -> Constant {
+        match kind {
+            BasicKind::Rune => {
+                Constant::Int(BigInt::from_i64(util::parse_rune_literal(text) as i64))
+            }
+            BasicKind::String => Constant::String(util::parse_string_literal(text)),
+            BasicKind::Bool => Constant::Bool(text == "true"),
+            _ if kind.is_float() => Constant::Float(Rational::parse_literal(text)),
+            _ => Constant::Int(BigInt::parse_literal(text)),
+        }
+    }
+
+    /// Converts a numeric constant to a float constant
+    pub fn to_float(&self) -> Constant {
+        match *self {
+            Constant::Int(ref i) => Constant::Float(Rational::from_int(i)),
+            ref other => other.clone(),
+        }
+    }
+
+    /// Checks that the constant can be represented by a value of the given kind and returns
+    /// the value it gets, like what happens when an untyped constant is given a type
+    pub fn convert(&self, kind: BasicKind) -> Result<Constant, String> {
+        let bits = match kind.integer_bits() {
+            Some(bits) => bits,
+            None => return self.convert_non_integer(kind),
+        };
+        let integer = match *self {
+            Constant::Int(ref i) => i.clone(),
+            Constant::Float(ref r) => {
+                match r.to_integer() {
+                    Some(i) => i,
+                    None => return Err(format!("constant {} truncated to integer.", r)),
+                }
+            }
+            _ => return Err(format!("cannot convert constant {} to type {}.", self, kind)),
+        };
+        let one = BigInt::from_i64(1);
+        let (min, max) = if kind.is_unsigned() {
+            (BigInt::zero(), one.shl(bits).sub(&one))
+        } else {
+            (one.shl(bits - 1).neg(), one.shl(bits - 1).sub(&one))
+        };
+        if integer < min || integer > max {
+            return Err(format!("constant {} overflows {}.", integer, kind));
+        }
+        Ok(Constant::Int(integer))
+    }
+
+    fn convert_non_integer(&self, kind: BasicKind) -> Result<Constant, String> {
+        match (kind, self) {
+            (_, &Constant::Int(..)) | (_, &Constant::Float(..)) if kind.is_float() => {
+                let float = self.to_float();
+                let fits = match float {
+                    Constant::Float(ref r) => match r.to_f64() {
+                        Some(f) => kind != BasicKind::Float32 || f.abs() <= f32::MAX as f64,
+                        None => false,
+                    },
+                    _ => unreachable!(),
+                };
+                if fits {
+                    Ok(float)
+                } else {
+                    Err(format!("constant {} overflows {}.", self, kind))
+                }
+            }
+            (BasicKind::String, &Constant::String(..)) |
+            (BasicKind::Bool, &Constant::Bool(..)) => Ok(self.clone()),
+            _ => Err(format!("cannot convert constant {} to type {}.", self, kind)),
+        }
+    }
+
+    pub fn unary_operation(&self, op: UnaryOperator) -> Result<Constant, String> {
+        match (op, self) {
+            (UnaryOperator::Plus, &Constant::Int(..)) |
+            (UnaryOperator::Plus, &Constant::Float(..)) => Ok(self.clone()),
+            (UnaryOperator::Neg, Constant::Int(i)) => Ok(Constant::Int(i.neg())),
+            (UnaryOperator::Neg, Constant::Float(r)) => Ok(Constant::Float(r.neg())),
+            (UnaryOperator::BwCompl, Constant::Int(i)) => Ok(Constant::Int(i.not())),
+            (UnaryOperator::Not, &Constant::Bool(b)) => Ok(Constant::Bool(!b)),
+            _ => Err(invalid_operation(op, self)),
+        }
+    }
+
+    /// Both operands must already be of the same kind of constant. Integer constants use
+    /// integer division.
+    pub fn binary_operation(&self, op: BinaryOperator, other: &Constant)
+        -> Result<Constant, String> {
+        use crate::ast::BinaryOperator::*;
+        let ordering = match (self, other) {
+            (Constant::Int(a), Constant::Int(b)) => Some(a.cmp(b)),
+            (Constant::Float(a), Constant::Float(b)) => Some(a.cmp(b)),
+            (Constant::String(a), Constant::String(b)) => Some(a.cmp(b)),
+            (&Constant::Bool(a), &Constant::Bool(b)) => Some(a.cmp(&b)),
+            _ => None,
+        };
+        match (op, ordering) {
+            (Eq, Some(o)) => return Ok(Constant::Bool(o == Ordering::Equal)),
+            (Neq, Some(o)) => return Ok(Constant::Bool(o != Ordering::Equal)),
+            (Lt, Some(o)) => return Ok(Constant::Bool(o == Ordering::Less)),
+            (Leq, Some(o)) => return Ok(Constant::Bool(o != Ordering::Greater)),
+            (Gt, Some(o)) => return Ok(Constant::Bool(o == Ordering::Greater)),
+            (Geq, Some(o)) => return Ok(Constant::Bool(o != Ordering::Less)),
+            _ => {}
+        }
+
+        match (op, self, other) {
+            (Or, &Constant::Bool(a), &Constant::Bool(b)) => Ok(Constant::Bool(a || b)),
+            (And, &Constant::Bool(a), &Constant::Bool(b)) => Ok(Constant::Bool(a && b)),
+            (Add, Constant::String(a), Constant::String(b)) => {
+                Ok(Constant::String(format!("{}{}", a, b)))
+            }
+
+            (Add, Constant::Int(a), Constant::Int(b)) => Ok(Constant::Int(a.add(b))),
+            (Sub, Constant::Int(a), Constant::Int(b)) => Ok(Constant::Int(a.sub(b))),
+            (Mul, Constant::Int(a), Constant::Int(b)) => Ok(Constant::Int(a.mul(b))),
+            (Div, &Constant::Int(_), &Constant::Int(ref b)) |
+            (Mod, &Constant::Int(_), &Constant::Int(ref b)) if b.is_zero() => {
+                Err("division by zero.".to_string())
+            }
+            (Div, Constant::Int(a), Constant::Int(b)) => {
+                Ok(Constant::Int(a.div_rem(b).0))
+            }
+            (Mod, Constant::Int(a), Constant::Int(b)) => {
+                Ok(Constant::Int(a.div_rem(b).1))
+            }
+            (BwOr, Constant::Int(a), Constant::Int(b)) => Ok(Constant::Int(a.or(b))),
+            (BwXor, Constant::Int(a), Constant::Int(b)) => Ok(Constant::Int(a.xor(b))),
+            (BwAnd, Constant::Int(a), Constant::Int(b)) => Ok(Constant::Int(a.and(b))),
+            (BwAndNot, Constant::Int(a), Constant::Int(b)) => {
+                Ok(Constant::Int(a.and_not(b)))
+            }
+            (LShift, &Constant::Int(ref a), &Constant::Int(ref b)) |
+            (RShift, &Constant::Int(ref a), &Constant::Int(ref b)) => {
+                let shift = match b.to_i64() {
+                    Some(s) if s >= 0 && s <= MAX_SHIFT as i64 => s as u32,
+                    _ => return Err(format!("invalid shift count {}.", b)),
+                };
+                Ok(Constant::Int(if op == LShift { a.shl(shift) } else { a.shr(shift) }))
+            }
+
+            (Add, Constant::Float(a), Constant::Float(b)) => {
+                Ok(Constant::Float(a.add(b)))
+            }
+            (Sub, Constant::Float(a), Constant::Float(b)) => {
+                Ok(Constant::Float(a.sub(b)))
+            }
+            (Mul, Constant::Float(a), Constant::Float(b)) => {
+                Ok(Constant::Float(a.mul(b)))
+            }
+            (Div, &Constant::Float(_), Constant::Float(b)) if b.is_zero() => {
+                Err("division by zero.".to_string())
+            }
+            (Div, Constant::Float(a), Constant::Float(b)) => {
+                Ok(Constant::Float(a.div(b)))
+            }
+            _ => Err(invalid_operation(op, self)),
+        }
+    }
+}
+
+impl fmt::Display for Constant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Constant::Bool(b) => write!(f, "{}", b),
+            Constant::String(ref s) => write!(f, "{:?}", s),
+            Constant::Int(ref i) => write!(f, "{}", i),
+            Constant::Float(ref r) => write!(f, "{}", r),
+        }
+    }
+}
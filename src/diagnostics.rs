@@ -0,0 +1,76 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use crate::span;
+use crate::span::{Source, Span};
+
+/// An error found while compiling or running a program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub span: Span,
+    pub message: String,
+    // Keeps the file registered so that the error can quote it
+    source: Option<Source>,
+}
+
+impl Diagnostic {
+    pub fn new(span: Span, message: &str) -> Diagnostic {
+        Diagnostic { span, message: message.to_string(), source: span::source_file(span.file) }
+    }
+}
+
+/// Formats the diagnostic like `Error: prog.go:4:10: message`, followed by the offending line
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", span::format_error(self.span, &self.message))
+    }
+}
+
+/// Collects the errors found by the weeder and the typechecker so that they can all be reported
+/// at once. Cloning gives another handle to the same list, the same way the symbol tables share
+/// their id counter.
+#[derive(Debug, Clone)]
+pub struct Diagnostics {
+    errors: Rc<RefCell<Vec<Diagnostic>>>,
+}
+
+impl Default for Diagnostics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Diagnostics {
+    pub fn new() -> Diagnostics {
+        Diagnostics {
+            errors: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    /// Records an error and keeps going
+    pub fn error(&self, span: Span, message: &str) {
+        self.errors.borrow_mut().push(Diagnostic::new(span, message));
+    }
+
+    pub fn has_errors(&self) -> bool {
+        !self.errors.borrow().is_empty()
+    }
+
+    /// Returns the errors sorted by position, without duplicates
+    pub fn sorted_errors(&self) -> Vec<Diagnostic> {
+        let mut errors = self.errors.borrow().clone();
+        // The sort is stable so errors at the same place keep the order they were found in
+        errors.sort_by_key(|e| (e.span.file, e.span.start));
+        errors.dedup();
+        errors
+    }
+
+    /// `Ok` if nothing was recorded, otherwise the sorted errors
+    pub fn result(&self) -> Result<(), Vec<Diagnostic>> {
+        if self.has_errors() {
+            Err(self.sorted_errors())
+        } else {
+            Ok(())
+        }
+    }
+}
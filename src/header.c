@@ -0,0 +1,261 @@
+// Runtime for the C backend. Everything that the generated code needs is in this file so that the
+// output of `codegen_c` is a single standalone C99 file.
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <stdint.h>
+#include <stdbool.h>
+#include <string.h>
+#include <math.h>
+
+// Strings
+
+typedef struct {
+    const char *data;
+    int32_t length;
+} gl_string;
+
+static gl_string gl_string_literal(const char *data, int32_t length) {
+    gl_string ret = { data, length };
+    return ret;
+}
+
+static gl_string gl_string_concat(gl_string a, gl_string b) {
+    if (a.length == 0) return b;
+    if (b.length == 0) return a;
+    char *data = malloc((size_t)a.length + (size_t)b.length);
+    memcpy(data, a.data, a.length);
+    memcpy(data + a.length, b.data, b.length);
+    gl_string ret = { data, a.length + b.length };
+    return ret;
+}
+
+static int gl_string_compare(gl_string a, gl_string b) {
+    int32_t min = a.length < b.length ? a.length : b.length;
+    int c = min ? memcmp(a.data, b.data, min) : 0;
+    if (c != 0) return c;
+    return (a.length > b.length) - (a.length < b.length);
+}
+
+static bool gl_string_eq(gl_string a, gl_string b) {
+    return a.length == b.length && (a.length == 0 || memcmp(a.data, b.data, a.length) == 0);
+}
+
+// Encodes a rune as UTF-8. Invalid code points become U+FFFD, like in Go.
+static gl_string gl_string_from_rune(int32_t r) {
+    char *data = malloc(4);
+    int32_t length;
+    if (r < 0 || r > 0x10FFFF || (r >= 0xD800 && r <= 0xDFFF)) {
+        r = 0xFFFD;
+    }
+    if (r < 0x80) {
+        data[0] = (char)r;
+        length = 1;
+    } else if (r < 0x800) {
+        data[0] = (char)(0xC0 | (r >> 6));
+        data[1] = (char)(0x80 | (r & 0x3F));
+        length = 2;
+    } else if (r < 0x10000) {
+        data[0] = (char)(0xE0 | (r >> 12));
+        data[1] = (char)(0x80 | ((r >> 6) & 0x3F));
+        data[2] = (char)(0x80 | (r & 0x3F));
+        length = 3;
+    } else {
+        data[0] = (char)(0xF0 | (r >> 18));
+        data[1] = (char)(0x80 | ((r >> 12) & 0x3F));
+        data[2] = (char)(0x80 | ((r >> 6) & 0x3F));
+        data[3] = (char)(0x80 | (r & 0x3F));
+        length = 4;
+    }
+    gl_string ret = { data, length };
+    return ret;
+}
+
+// Decodes the rune that starts at `index` and stores its length in bytes in `width`. Invalid UTF-8
+// decodes to U+FFFD with a width of 1, like in Go.
+static int32_t gl_decode_rune(gl_string s, int32_t index, int32_t *width) {
+    const unsigned char *p = (const unsigned char *)s.data + index;
+    int32_t left = s.length - index;
+    int32_t n, r, min;
+    if (p[0] < 0x80) {
+        *width = 1;
+        return p[0];
+    } else if ((p[0] & 0xE0) == 0xC0) {
+        n = 2; r = p[0] & 0x1F; min = 0x80;
+    } else if ((p[0] & 0xF0) == 0xE0) {
+        n = 3; r = p[0] & 0x0F; min = 0x800;
+    } else if ((p[0] & 0xF8) == 0xF0) {
+        n = 4; r = p[0] & 0x07; min = 0x10000;
+    } else {
+        *width = 1;
+        return 0xFFFD;
+    }
+    if (n > left) {
+        *width = 1;
+        return 0xFFFD;
+    }
+    for (int32_t i = 1; i < n; i++) {
+        if ((p[i] & 0xC0) != 0x80) {
+            *width = 1;
+            return 0xFFFD;
+        }
+        r = (r << 6) | (p[i] & 0x3F);
+    }
+    if (r < min || r > 0x10FFFF || (r >= 0xD800 && r <= 0xDFFF)) {
+        *width = 1;
+        return 0xFFFD;
+    }
+    *width = n;
+    return r;
+}
+
+// Slices
+//
+// All slices share the same representation; the generated code casts `data` to the right element
+// type when indexing.
+
+typedef struct {
+    void *data;
+    int32_t length;
+    int32_t capacity;
+} gl_slice;
+
+// Returns a slice with one more element, reusing the backing storage if there is room left.
+// The new element is left for the caller to fill in. The growth policy matches the interpreter
+// and the JS backend so that aliasing between slices behaves identically.
+static gl_slice gl_append_slot(gl_slice s, size_t element_size) {
+    if (s.length + 1 <= s.capacity) {
+        s.length += 1;
+        return s;
+    }
+    int32_t capacity = s.length == 0 ? 1 : s.length * 2;
+    void *data = calloc((size_t)capacity, element_size);
+    if (s.length > 0) {
+        memcpy(data, s.data, (size_t)s.length * element_size);
+    }
+    gl_slice ret = { data, s.length + 1, capacity };
+    return ret;
+}
+
+// Runtime errors
+//
+// They are reported like Go panics, without the stack trace since the calls are not kept track of.
+
+static void gl_runtime_error(const char *message, int line_number) {
+    fprintf(stderr, "panic: runtime error: %s [line %d]\n", message, line_number);
+    exit(2);
+}
+
+static int32_t gl_check_bounds(int32_t index, int32_t length, int line_number) {
+    char message[64];
+    if (index < 0) {
+        snprintf(message, sizeof message, "index out of range [%d]", index);
+        gl_runtime_error(message, line_number);
+    }
+    if (index >= length) {
+        snprintf(message, sizeof message, "index out of range [%d] with length %d", index, length);
+        gl_runtime_error(message, line_number);
+    }
+    return index;
+}
+
+// Integer arithmetic
+//
+// Go integers wrap around on overflow, which is undefined behaviour for signed integers in C, so
+// the arithmetic is done on unsigned integers.
+
+static int32_t gl_add(int32_t a, int32_t b) { return (int32_t)((uint32_t)a + (uint32_t)b); }
+static int32_t gl_sub(int32_t a, int32_t b) { return (int32_t)((uint32_t)a - (uint32_t)b); }
+static int32_t gl_mul(int32_t a, int32_t b) { return (int32_t)((uint32_t)a * (uint32_t)b); }
+static int32_t gl_neg(int32_t a) { return (int32_t)(0u - (uint32_t)a); }
+
+static int32_t gl_div(int32_t a, int32_t b, int line_number) {
+    if (b == 0) {
+        gl_runtime_error("integer divide by zero", line_number);
+    }
+    if (b == -1) return gl_neg(a);
+    return a / b;
+}
+
+static int32_t gl_mod(int32_t a, int32_t b, int line_number) {
+    if (b == 0) {
+        gl_runtime_error("integer divide by zero", line_number);
+    }
+    if (b == -1) return 0;
+    return a % b;
+}
+
+// Shifting by the width of the kind or more gives 0, or -1 when shifting a negative value right
+static int32_t gl_shl(int32_t a, int32_t b, int line_number) {
+    if (b < 0) {
+        gl_runtime_error("negative shift amount", line_number);
+    }
+    if (b >= 32) return 0;
+    return (int32_t)((uint32_t)a << b);
+}
+
+static int32_t gl_shr(int32_t a, int32_t b, int line_number) {
+    if (b < 0) {
+        gl_runtime_error("negative shift amount", line_number);
+    }
+    if (b >= 32) return a < 0 ? -1 : 0;
+    return a >> b;
+}
+
+// Conversions from float to int truncate towards zero, and the ones that do not fit are wrapped
+// around like in the interpreter and the JS backend. Their remainder modulo 2^32 is the same as
+// the one of the remainder modulo 2^64 that they take, and fmod computes it exactly.
+static int32_t gl_float_to_int(double f) {
+    if (!isfinite(f)) return 0;
+    return (int32_t)(uint32_t)(int64_t)fmod(trunc(f), 4294967296.0);
+}
+
+// Printing
+
+static void gl_print_int(int32_t a) {
+    printf("%d", a);
+}
+
+static void gl_print_bool(bool a) {
+    fputs(a ? "true" : "false", stdout);
+}
+
+static void gl_print_string(gl_string a) {
+    fwrite(a.data, 1, (size_t)a.length, stdout);
+}
+
+// Same format as `value::format_float`: +1.234560e+002
+static void gl_print_float(double a) {
+    if (isnan(a)) {
+        fputs("NaN", stdout);
+    } else if (isinf(a)) {
+        fputs(a > 0 ? "+Inf" : "-Inf", stdout);
+    } else {
+        char base[64];
+        snprintf(base, sizeof base, "%.6e", a);
+        char *e = strchr(base, 'e');
+        *e = '\0';
+        const char *mantissa = base;
+        const char *exponent = e + 1;
+        if (*mantissa == '-') {
+            putchar('-');
+            mantissa++;
+        } else {
+            putchar('+');
+        }
+        fputs(mantissa, stdout);
+        putchar('e');
+        if (*exponent == '-' || *exponent == '+') {
+            putchar(*exponent);
+            exponent++;
+        } else {
+            putchar('+');
+        }
+        for (size_t len = strlen(exponent); len < 3; len++) {
+            putchar('0');
+        }
+        fputs(exponent, stdout);
+    }
+}
+
+//============================== END OF HEADER ================================//
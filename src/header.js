@@ -45,8 +45,11 @@ function binary_BwOr(a,b) {
 function binary_BwXor(a,b) {
     return a ^ b
 }
-function binary_Mod(a,b) {
-    return (a%b) | 0 // Note: modulo is only performed on ints in Go
+function binary_Mod(a,b,line_number) {
+    if (b == 0) {
+        runtime_error("integer divide by zero", line_number);
+    }
+    return a%b // Note: modulo is only performed on ints in Go
 }
 function binary_BwAnd(a,b) {
     return a & b
@@ -54,30 +57,86 @@ function binary_BwAnd(a,b) {
 function binary_BwAndNot(a,b) {
     return a & (~b)
 }
-function binary_LShift(a,b) {
-    return a << b
+// Shifting by the width of the kind or more gives 0, or -1 when shifting a negative value right
+function binary_LShift(a,b,line_number) {
+    if (b < 0) {
+        runtime_error("negative shift amount", line_number);
+    }
+    if (typeof(a) === 'bigint') {
+        return b >= 64n ? 0n : a << b
+    }
+    return b >= 32 ? 0 : a << b
 }
-function binary_RShift(a,b) {
-    return a >> b
+function binary_RShift(a,b,line_number) {
+    if (b < 0) {
+        runtime_error("negative shift amount", line_number);
+    }
+    if (typeof(a) === 'bigint') {
+        return a >> (b >= 64n ? 64n : b)
+    }
+    // Unlike >>, this works for uint32 values too
+    return Math.floor(a / 2**Math.min(b, 32))
 }
 
-function binary_Add_int(a,b) {
-    return (a+b) | 0
+function binary_Mul_int(a,b) {
+    if (typeof(a) === 'bigint') {
+        return a*b
+    }
+    return Math.imul(a,b)
+}
+function binary_Div_int(a,b,line_number) {
+    if (b == 0) {
+        runtime_error("integer divide by zero", line_number);
+    }
+    if (typeof(a) === 'bigint') {
+        return a/b
+    }
+    return Math.trunc(a/b)
 }
 
-function binary_Sub_int(a,b) {
-    return (a-b) | 0
+// Integers of up to 32 bits are numbers, and int64 and uint64 values are BigInts. The results of
+// integer operations are wrapped around to fit in the bits of their kind.
+function wrap_int(a, bits, signed) {
+    if (typeof(a) === 'bigint') {
+        return signed ? BigInt.asIntN(bits, a) : BigInt.asUintN(bits, a);
+    }
+    const shift = 32 - bits;
+    return signed ? (a << shift) >> shift : (a << shift) >>> shift;
 }
 
-function binary_Mul_int(a,b) {
-    return Math.imul(a,b)
+// Converts a number or a BigInt to an integer kind. Floats are truncated, and like in the
+// interpreter the ones that do not fit are wrapped around.
+function convert_int(a, bits, signed) {
+    if (typeof(a) === 'number') {
+        a = Number.isFinite(a) ? Math.trunc(a) % 18446744073709551616 : 0;
+        if (bits < 64) {
+            return wrap_int(a, bits, signed);
+        }
+        a = BigInt(a);
+    }
+    if (bits === 64) {
+        return wrap_int(a, bits, signed);
+    }
+    return Number(wrap_int(a, bits, signed));
 }
-function binary_Div_int(a,b) {
-    if (b == 0) {
-        console.error("Error: division by zero.");
-        exit(1);
+
+// Strings are kept as their UTF-8 bytes, one character per byte, so that they are measured,
+// sliced and compared by bytes like in Go. They are only decoded by range loops and panics.
+function encode_string(s) {
+    return Buffer.from(s, 'utf8').toString('latin1');
+}
+
+function decode_string(s) {
+    return Buffer.from(s, 'latin1').toString('utf8');
+}
+
+// string(i) for an integer i, which gives "\uFFFD" if i is not a valid code point
+function string_of_int(a) {
+    const c = Number(a);
+    if (c < 0 || c > 0x10FFFF || (c >= 0xD800 && c <= 0xDFFF)) {
+        return encode_string("\uFFFD");
     }
-    return (a/b) | 0
+    return encode_string(String.fromCodePoint(c));
 }
 
 
@@ -100,6 +159,10 @@ function unary_Not(a) {
 
 
 function print_float(a) {
+    process.stdout.write(format_float(a));
+}
+
+function format_float(a) {
     if (Number.isFinite(a)) {
         let out = a.toExponential(6);
         if (out[0] !== '-') {
@@ -117,21 +180,24 @@ function print_float(a) {
         while (exponent.length < 3) {
             exponent = "0" + exponent;
         }
-        process.stdout.write(beforeExponent + exponent);
+        return beforeExponent + exponent;
     } else if (Number.isNaN(a)) {
-        process.stdout.write("NaN");
+        return "NaN";
     } else if (a == Infinity) {
-        process.stdout.write("+Inf");
+        return "+Inf";
     } else if (a == -Infinity) {
-        process.stdout.write("-Inf");
+        return "-Inf";
     } else {
-        console.error("Error: Trying to print out something that isn't a float as a float.");
-        exit(1);
+        throw new Error("Trying to print out something that isn't a float as a float.");
     }
 }
 
 function print_not_float(a) {
-    // TODO: check if this needs further splitting
+    // Strings are written as the bytes they hold
+    if (typeof(a) === 'string') {
+        process.stdout.write(Buffer.from(a, 'latin1'));
+        return;
+    }
     process.stdout.write(""+a);
 }
 
@@ -141,9 +207,26 @@ function print_not_float(a) {
 function deepEq(a,b) {
     var type = typeof(a);
 
+    if (a instanceof Pointer) {
+        return pointer_eq(a, b);
+    }
+
+    if (a instanceof Interface) {
+        return b instanceof Interface && a.type === b.type && deepEq(a.value, b.value);
+    }
+
+    // Channels are equal when they were made by the same call to make
+    if (a instanceof Channel) {
+        return a === b;
+    }
+
+    // Functions are shared, and a nil function is null
     if (type === 'number' || 
+            type === 'bigint' ||
             type === 'string' ||
-            type === 'boolean') {
+            type === 'boolean' ||
+            type === 'function' ||
+            a === null) {
         return a === b;
     }
 
@@ -156,49 +239,501 @@ function deepEq(a,b) {
         return true;
     }
 
-    console.error("Error: doing comparison of things that aren't number/string/boolean/object");
-    process.exit(1);
+    throw new Error("doing comparison of things that aren't number/string/boolean/object");
 }
 
 //------------------------------------------------
 // Expressions
 
-function append(slice, object) {
+// A slice is the elements of its contents from its offset on, which slices of the same array or
+// slice share. The new contents of a full slice hold zero values past its length, since the slice
+// can be extended over them.
+function append(slice, object, zero) {
     let ret = {
+        offset: slice.offset,
         length: slice.length,
         capacity: slice.capacity, // I "simulate" the capacity
         contents: slice.contents,
     };
     if (ret.length+1 > ret.capacity) {
-        ret.contents = deepCopy(ret.contents);
-        ret.capacity = ret.capacity? ret.capacity * 2: 1;
+        ret.contents = [];
+        for (let i = 0; i < slice.length; i++) {
+            ret.contents.push(deepCopy(slice.contents[slice.offset + i]));
+        }
+        ret.offset = 0;
+        ret.capacity = ret.length? ret.length * 2: 1;
         while (ret.contents.length < ret.capacity) {
-            ret.contents.push(undefined);
+            ret.contents.push(deepCopy(zero));
         }
     }
-    ret.contents[ret.length] = deepCopy(object);
+    ret.contents[ret.offset + ret.length] = deepCopy(object);
     ret.length++;
     return ret;
 }
 
+// The contents of an array or slice literal, given as pairs of an index and a value. The elements
+// that are left out are zero values.
+function literal_array(length, zero, elements) {
+    const ret = makeArray(length, zero);
+    for (const [index, value] of elements) {
+        ret[index] = value;
+    }
+    return ret;
+}
+
+// The size given to make as a number, or -1 if it is out of range. int64 and uint64 sizes are
+// BigInts, so they are only converted once they are known to fit, with the same limit as the
+// interpreter.
+function make_size(size) {
+    return size >= 0 && size <= 2147483647 ? Number(size) : -1;
+}
+
+// make([]T, length, capacity). The elements past the length are zero values as well, since the
+// slice can be extended over them.
+function make_slice(length, capacity, zero, length_line, capacity_line) {
+    length = make_size(length);
+    capacity = make_size(capacity);
+    if (length < 0) {
+        runtime_error("makeslice: len out of range", length_line);
+    }
+    if (capacity < length) {
+        runtime_error("makeslice: cap out of range", capacity_line);
+    }
+    return {offset: 0, length: length, capacity: capacity, contents: makeArray(capacity, zero)};
+}
+
+// The elements are read before any is written, so that slices sharing their elements copy them
+// like Go does when they overlap
+function copy(dst, src) {
+    const count = Math.min(dst.length, src.length);
+    const values = [];
+    for (let i = 0; i < count; i++) {
+        values.push(deepCopy(src.contents[src.offset + i]));
+    }
+    for (let i = 0; i < count; i++) {
+        dst.contents[dst.offset + i] = values[i];
+    }
+    return count;
+}
+
+// a[low:high:max], where the indices left out are undefined. The slice of an array shares the
+// JS array, and strings are sliced by bytes.
+function slice(a, low, high, max, line_number) {
+    const is_string = typeof(a) === 'string';
+    const length = a.length;
+    const capacity = is_string || Array.isArray(a) ? length : a.capacity;
+    const bound = is_string || Array.isArray(a) ? " with length " : " with capacity ";
+    const out_of_range = (text) => runtime_error("slice bounds out of range " + text, line_number);
+    low = low === undefined ? 0 : low;
+    high = high === undefined ? length : high;
+    if (max !== undefined) {
+        if (max < 0) {
+            out_of_range("[::" + max + "]");
+        }
+        if (max > capacity) {
+            out_of_range("[::" + max + "]" + bound + capacity);
+        }
+        if (high < 0) {
+            out_of_range("[:" + high + ":]");
+        }
+        if (high > max) {
+            out_of_range("[:" + high + ":" + max + "]");
+        }
+        if (low < 0) {
+            out_of_range("[" + low + "::]");
+        }
+        if (low > high) {
+            out_of_range("[" + low + ":" + high + ":]");
+        }
+    } else {
+        if (high < 0) {
+            out_of_range("[:" + high + "]");
+        }
+        if (high > capacity) {
+            out_of_range("[:" + high + "]" + bound + capacity);
+        }
+        if (low < 0) {
+            out_of_range("[" + low + ":]");
+        }
+        if (low > high) {
+            out_of_range("[" + low + ":" + high + "]");
+        }
+        max = capacity;
+    }
+    if (is_string) {
+        return a.substring(low, high);
+    }
+    if (Array.isArray(a)) {
+        return {offset: low, length: high - low, capacity: max - low, contents: a};
+    }
+    return {offset: a.offset + low, length: high - low, capacity: max - low, contents: a.contents};
+}
+
+// Arrays are JS arrays, slices are objects with a length and strings have a character per byte.
+// Channels give the number of values in their buffer, and a nil channel is null.
+function len(a) {
+    if (a === null) {
+        return 0;
+    }
+    if (a instanceof Channel) {
+        return queue_length(a.buffer);
+    }
+    return a.length;
+}
+
+function cap(a) {
+    if (Array.isArray(a)) {
+        return a.length;
+    }
+    if (a === null) {
+        return 0;
+    }
+    return a.capacity;
+}
+
+// The byte offset and the code point of each rune in a string, for range loops. Each byte that
+// isn't part of a valid UTF-8 sequence is a rune of its own, U+FFFD.
+function string_runes(s) {
+    let ret = [];
+    let offset = 0;
+    while (offset < s.length) {
+        const [c, width] = decode_rune(s, offset);
+        ret.push([offset, c]);
+        offset += width;
+    }
+    return ret;
+}
+
+// The code point of the rune starting at a byte of a string, and the number of bytes it takes
+function decode_rune(s, offset) {
+    const b = s.charCodeAt(offset);
+    if (b < 0x80) {
+        return [b, 1];
+    }
+    // The number of continuation bytes, the bits of the first byte, and the smallest code point
+    // that needs that many bytes
+    const [count, bits, min] = b >= 0xC2 && b < 0xE0 ? [1, b & 0x1F, 0x80] :
+        b >= 0xE0 && b < 0xF0 ? [2, b & 0x0F, 0x800] :
+        b >= 0xF0 && b < 0xF5 ? [3, b & 0x07, 0x10000] : [0, 0, 0];
+    if (count === 0 || offset + count >= s.length) {
+        return [0xFFFD, 1];
+    }
+    let c = bits;
+    for (let i = 1; i <= count; i++) {
+        const next = s.charCodeAt(offset + i);
+        if ((next & 0xC0) !== 0x80) {
+            return [0xFFFD, 1];
+        }
+        c = (c << 6) | (next & 0x3F);
+    }
+    if (c < min || c > 0x10FFFF || (c >= 0xD800 && c <= 0xDFFF)) {
+        return [0xFFFD, 1];
+    }
+    return [c, count + 1];
+}
+
+// Maps are objects whose contents are a JS Map, or null for a nil map. Arrays and structs are
+// keyed by their JSON, and each entry keeps the Go key with its value for range loops.
+function map_key(k) {
+    if (k instanceof Channel) {
+        return k;
+    }
+    if (typeof(k) === 'object') {
+        return JSON.stringify(k, (field, v) => typeof(v) === 'bigint' ? v + 'n' : v);
+    }
+    return k;
+}
+
+// The type of a value in an interface in a map key that can't be hashed, like a slice. The types
+// of map keys are comparable, so only interfaces can hold those.
+function unhashable_type(k) {
+    if (k instanceof Interface) {
+        return k.type.comparable ? unhashable_type(k.value) : k.type;
+    }
+    // Arrays and structs, which are the other objects that keys can be
+    if (typeof(k) === 'object' && k !== null && !(k instanceof Pointer) && !(k instanceof Channel)) {
+        for (const v of Object.values(k)) {
+            const type = unhashable_type(v);
+            if (type !== null) {
+                return type;
+            }
+        }
+    }
+    return null;
+}
+
+// Go panics on the map keys it can't hash
+function check_key(k, line_number) {
+    const type = unhashable_type(k);
+    if (type !== null) {
+        runtime_error("hash of unhashable type " + type.name, line_number);
+    }
+    return k;
+}
+
+function make_map(pairs, size, line_number) {
+    if (size !== undefined && make_size(size) < 0) {
+        runtime_error("makemap: size out of range", line_number);
+    }
+    let m = {contents: new Map()};
+    for (const pair of pairs) {
+        m.contents.set(map_key(pair[0]), pair);
+    }
+    return m;
+}
+
+function map_get(m, k, zero) {
+    const entry = m.contents === null ? undefined : m.contents.get(map_key(k));
+    return entry === undefined ? zero : entry[1];
+}
+
+// The element and whether the key was found, for comma-ok assignments
+function map_lookup(m, k, zero) {
+    const entry = m.contents === null ? undefined : m.contents.get(map_key(k));
+    return entry === undefined ? [zero, false] : [entry[1], true];
+}
+
+function map_set(m, k, v, line_number) {
+    if (m.contents === null) {
+        runtime_error("assignment to entry in nil map", line_number);
+    }
+    m.contents.set(map_key(k), [deepCopy(k), v]);
+    return v;
+}
+
+function map_delete(m, k) {
+    if (m.contents !== null) {
+        m.contents.delete(map_key(k));
+    }
+}
+
+function map_len(m) {
+    return m.contents === null ? 0 : m.contents.size;
+}
+
+function map_entries(m) {
+    return m.contents === null ? [] : m.contents.values();
+}
+
 function check_bounds(a, length, line_number) {
     if (a < 0) {
-        console.error("Error: line "+line_number+": trying to index an array or slice with negative number.");
-        process.exit(1);
+        runtime_error("index out of range [" + a + "]", line_number);
     }
     if (a >= length) {
-        console.error("Error: line "+line_number+": index " + a +" out of range. Should be in range of "+length);
-        process.exit(1);
+        runtime_error("index out of range [" + a + "] with length " + length, line_number);
     }
     return a;
 }
 
+function check_function(f, line_number) {
+    if (f === null) {
+        runtime_error("invalid memory address or nil pointer dereference", line_number);
+    }
+    return f;
+}
+
+// A method value calls the method with a copy of the receiver it was made with
+function bind_method(f, receiver) {
+    return function* (...args) {
+        return yield* f(deepCopy(receiver), ...args);
+    };
+}
+
+// Interfaces hold a value with the descriptor of its type, or are null when nil. The values are
+// never changed, so copies of an interface share them.
+function Interface(type, value) {
+    this.type = type;
+    this.value = value;
+}
+
+// There is one descriptor for each type put in an interface, with its name for panics and its
+// methods. Each method binds a receiver, and has a signature to compare with the ones of other
+// interfaces.
+function GoType(name, comparable, methods) {
+    this.name = name;
+    this.comparable = comparable;
+    this.methods = methods;
+}
+
+function bound_method(f) {
+    return (receiver, line_number) => bind_method(f, receiver);
+}
+
+// Methods with a value receiver are also methods of pointers, called on what they point to
+function bound_method_through_pointer(f) {
+    return (p, line_number) => bind_method(f, deepCopy(dereference(p, line_number)));
+}
+
+// The method of the value held by an embedded interface, or by the one a pointer points to
+function bound_interface_method(name, through_pointer) {
+    return (receiver, line_number) => {
+        if (through_pointer) {
+            receiver = dereference(receiver, line_number);
+        }
+        return interface_method(receiver, name, line_number);
+    };
+}
+
+// A method promoted from an embedded field, which is found by following the path of embedded
+// fields. Each step is a field and whether it is a pointer. Once a pointer is on the way, the
+// fields are pointed to so that pointer methods can change them.
+function promoted_method(through_pointer, path, bind) {
+    return (receiver, line_number) => {
+        let pointer = through_pointer;
+        for (const [field, is_pointer] of path) {
+            if (!pointer) {
+                receiver = receiver[field];
+            } else if (is_pointer) {
+                receiver = dereference(receiver, line_number)[field];
+            } else {
+                receiver = field_pointer(receiver, field, line_number);
+            }
+            pointer = pointer || is_pointer;
+        }
+        return bind(receiver, line_number);
+    };
+}
+
+function to_interface(type, v) {
+    return new Interface(type, deepCopy(v));
+}
+
+function interface_method(x, name, line_number) {
+    if (x === null) {
+        runtime_error("invalid memory address or nil pointer dereference", line_number);
+    }
+    return x.type.methods[name].bind(x.value, line_number);
+}
+
+// Interfaces are equal when they hold equal values of the same type, which must be comparable
+function interface_eq(a, b, line_number) {
+    if (a !== null && b !== null && a.type === b.type && !a.type.comparable) {
+        runtime_error("comparing uncomparable type " + a.type.name, line_number);
+    }
+    return deepEq(a, b);
+}
+
+function has_type(x, type) {
+    return x !== null && x.type === type;
+}
+
+// The first of the methods, given as names and signatures, that the value in the interface
+// doesn't have
+function missing_method(x, methods) {
+    for (const [name, signature] of methods) {
+        const method = x.type.methods[name];
+        if (method === undefined || method.signature !== signature) {
+            return name;
+        }
+    }
+    return null;
+}
+
+function has_methods(x, methods) {
+    return x !== null && missing_method(x, methods) === null;
+}
+
+function type_assert(x, type, interface_name, line_number) {
+    if (x === null) {
+        go_panic("interface conversion: " + interface_name + " is nil, not " + type.name, line_number);
+    }
+    if (x.type !== type) {
+        go_panic("interface conversion: " + interface_name + " is " + x.type.name + ", not " +
+                 type.name, line_number);
+    }
+    return x.value;
+}
+
+function interface_assert(x, methods, name, interface_name, line_number) {
+    if (x === null) {
+        go_panic("interface conversion: " + interface_name + " is nil, not " + name, line_number);
+    }
+    const missing = missing_method(x, methods);
+    if (missing !== null) {
+        go_panic("interface conversion: " + x.type.name + " is not " + name + ": missing method " +
+                 missing, line_number);
+    }
+    return x;
+}
+
+// The value and whether the assertion holds, for comma-ok assignments
+function type_assert_ok(x, type, zero) {
+    return has_type(x, type) ? [x.value, true] : [zero, false];
+}
+
+function interface_assert_ok(x, methods) {
+    return has_methods(x, methods) ? [x, true] : [null, false];
+}
+
+// Pointers have a `get` and a `set` function for what they point to. Pointers to fields and
+// array elements go through the pointer to the struct or array, so they see it being replaced;
+// they keep it with the field or index to be compared. Pointers to variables can only be
+// compared by identity, so taking the address of a variable twice gives different pointers.
+function Pointer(get, set, base, key) {
+    this.get = get;
+    this.set = set;
+    this.base = base;
+    this.key = key;
+}
+
+function pointer_eq(a, b) {
+    if (a === b) {
+        return true;
+    }
+    if (a === null || b === null || a.base === undefined || a.key !== b.key) {
+        return false;
+    }
+    return a.base instanceof Pointer ? pointer_eq(a.base, b.base) : a.base === b.base;
+}
+
+// The pointer given by new, to a variable that nothing else refers to
+function new_pointer(v) {
+    return new Pointer(() => v, (w) => v = w);
+}
+
+function pointer_set(p, v, line_number) {
+    dereference(p, line_number);
+    p.set(assign(p.get(), v));
+    return v;
+}
+
+function dereference(p, line_number) {
+    if (p === null) {
+        runtime_error("invalid memory address or nil pointer dereference", line_number);
+    }
+    return p.get();
+}
+
+function field_pointer(p, field, line_number) {
+    dereference(p, line_number);
+    return new Pointer(() => p.get()[field], (v) => p.get()[field] = v, p, field);
+}
+
+function array_element_pointer(p, i, line_number) {
+    check_bounds(i, dereference(p, line_number).length, line_number);
+    return new Pointer(() => p.get()[i], (v) => p.get()[i] = v, p, i);
+}
+
+function slice_element_pointer(s, i, line_number) {
+    check_bounds(i, s.length, line_number);
+    const contents = s.contents;
+    const j = s.offset + i;
+    return new Pointer(() => contents[j], (v) => contents[j] = v, contents, j);
+}
+
 function deepCopy(a) {
     let b;
     const type = typeof(a);
+    // Functions, pointers and channels are shared, and a nil one is null
     if (type === 'number' || 
+            type === 'bigint' ||
             type === 'string' ||
-            type === 'boolean') {
+            type === 'boolean' ||
+            type === 'function' ||
+            a === null ||
+            a instanceof Pointer ||
+            a instanceof Interface ||
+            a instanceof Channel) {
         return a;
     }
     if (type === 'object') {
@@ -222,6 +757,29 @@ function deepCopy(a) {
     }
 }
 
+// Gives what a variable holding `old` holds after `v` is assigned to it. Arrays, and the structs
+// that can hold them, are changed in place, since the slices of an array share its JS array.
+function assign(old, v) {
+    if (Array.isArray(old)) {
+        for (let i = 0; i < v.length; i++) {
+            old[i] = assign(old[i], v[i]);
+        }
+        return old;
+    }
+    if (is_struct(old)) {
+        for (let field in v) {
+            old[field] = assign(old[field], v[field]);
+        }
+        return old;
+    }
+    return v;
+}
+
+// Slices and maps are plain objects as well, but they have contents, and struct fields are
+// prefixed so none is called that
+function is_struct(a) {
+    return a !== null && typeof(a) === 'object' && a.constructor === Object && !('contents' in a);
+}
 
 function makeArray(length, example) {
     let ret = [];
@@ -236,4 +794,373 @@ var _;
 
 //============================== END OF HEADER ================================//
 
+// The GoLite functions being run, with the line each one is at, for the stack traces of panics.
+// Functions enter a frame when they start and calls set the line of the caller's frame. Each
+// goroutine has its own stack, which is the call stack while it runs.
+let call_stack = [];
+
+function enter_frame(name) {
+    call_stack.push({name: name, line: 0});
+}
+
+function leave_frame() {
+    call_stack.pop();
+}
+
+function at_line(line_number) {
+    if (call_stack.length > 0) {
+        call_stack[call_stack.length - 1].line = line_number;
+    }
+}
+
+// Panics are thrown, so that the calls deferred by the functions they go through still run, and
+// are reported once they get to the top of the program. They keep the stack trace from where they
+// started, innermost function first.
+function Panic(message, line_number) {
+    this.message = message;
+    this.line = line_number;
+    this.frames = call_stack.map((frame) => [frame.name, frame.line]).reverse();
+    if (this.frames.length > 0) {
+        this.frames[0][1] = line_number;
+    }
+}
+
+function runtime_error(message, line_number) {
+    throw new Panic("runtime error: " + message, line_number);
+}
+
+function go_panic(text, line_number) {
+    throw new Panic(text, line_number);
+}
+
+// Goroutines are generators, which yield when they block on a channel. Only one of them runs at a
+// time, until it blocks or returns. The next one is picked at random among the runnable ones, from
+// the seed given with --seed, the same way as the interpreter, so that a run can be repeated.
+function Goroutine(id, generator) {
+    this.id = id;
+    this.generator = generator;
+    this.stack = [];
+    this.status = 'runnable';
+    // The status of the goroutine in stack traces and the line it is blocked at
+    this.waiting = null;
+}
+
+const goroutines = [];
+let current_goroutine = null;
+// xorshift32, the same generator as the interpreter
+let random = 1;
+
+function choose(n) {
+    if (n <= 1) {
+        return 0;
+    }
+    random ^= random << 13;
+    random ^= random >>> 17;
+    random ^= random << 5;
+    random >>>= 0;
+    return random % n;
+}
+
+// The runnable goroutines, counted in a Fenwick tree over their ids. Picking the one with a given
+// rank among them, in the order of their ids like the interpreter, then doesn't go through all the
+// goroutines, of which there can be many blocked ones. The element for id `i` counts the runnable
+// goroutines with ids in `(i - lowbit(i), i]`.
+const runnable_tree = [];
+let runnable_count = 0;
+
+// The number of runnable goroutines with ids up to `id`
+function runnable_prefix(id) {
+    let count = 0;
+    while (id > 0) {
+        count += runnable_tree[id - 1];
+        id &= id - 1;
+    }
+    return count;
+}
+
+// The id of the runnable goroutine with `rank` runnable ones before it
+function nth_runnable(rank) {
+    let id = 0;
+    let step = 1;
+    while (step * 2 <= runnable_tree.length) {
+        step *= 2;
+    }
+    for (; step > 0; step >>= 1) {
+        if (id + step <= runnable_tree.length && runnable_tree[id + step - 1] <= rank) {
+            id += step;
+            rank -= runnable_tree[id - 1];
+        }
+    }
+    return id + 1;
+}
+
+function set_status(goroutine, status) {
+    const change = (status === 'runnable') - (goroutine.status === 'runnable');
+    goroutine.status = status;
+    if (change !== 0) {
+        runnable_count += change;
+        for (let id = goroutine.id; id <= runnable_tree.length; id += id & -id) {
+            runnable_tree[id - 1] += change;
+        }
+    }
+}
+
+function go_start(f) {
+    const id = goroutines.length + 1;
+    goroutines.push(new Goroutine(id, f()));
+    // The new goroutine is runnable, and covers the ones before it down to `id - lowbit(id)`
+    runnable_tree.push(runnable_prefix(id - 1) - runnable_prefix(id - (id & -id)) + 1);
+    runnable_count++;
+}
+
+function seed() {
+    for (const arg of process.argv) {
+        if (arg.startsWith("--seed=")) {
+            return parseInt(arg.substring("--seed=".length), 10) >>> 0;
+        }
+    }
+    return 0;
+}
+
+function report(kind, e, goroutine, status) {
+    let report = kind + ": " + decode_string(e.message) + " [line " + e.line + "]\n\ngoroutine " + goroutine + " [" +
+        status + "]:";
+    for (const [name, line_number] of e.frames) {
+        report += "\n" + name + "()\n\t" + source_file + ":" + line_number;
+    }
+    console.error(report);
+    process.exit(2);
+}
+
+// Runs the goroutines until main returns, and reports a panic or a deadlock like Go does, exiting
+// with the same status
+function run_program(program) {
+    random = (seed() ^ 0x2545F491) >>> 0;
+    if (random === 0) {
+        random = 1;
+    }
+    go_start(program);
+    const main = goroutines[0];
+    current_goroutine = main;
+    for (;;) {
+        call_stack = current_goroutine.stack;
+        let step;
+        try {
+            step = current_goroutine.generator.next();
+        } catch (e) {
+            if (!(e instanceof Panic)) {
+                throw e;
+            }
+            report("panic", e, current_goroutine.id, "running");
+        }
+        if (step.done) {
+            if (current_goroutine === main) {
+                return;
+            }
+            set_status(current_goroutine, 'done');
+        }
+        if (runnable_count === 0) {
+            call_stack = main.stack;
+            const e = new Panic("all goroutines are asleep - deadlock!", main.waiting[1]);
+            report("fatal error", e, 1, main.waiting[0]);
+        }
+        current_goroutine = goroutines[nth_runnable(choose(runnable_count)) - 1];
+    }
+}
+
+// Deferred calls run from the last one, even after one of them fails; the last error is the one
+// that goes on
+function* run_deferred(defers) {
+    let error = null;
+    while (defers.length > 0) {
+        try {
+            yield* defers.pop()();
+        } catch (e) {
+            error = e;
+        }
+    }
+    if (error !== null) {
+        throw error;
+    }
+}
+
+// Channels are shared by their copies, and a nil channel is null. Goroutines blocked on a channel
+// wait in its queues until one of the cases of their select fires; the entries of their other
+// cases are then stale, and are dropped when they come up.
+function Channel(capacity, zero) {
+    this.buffer = new Queue();
+    this.capacity = capacity;
+    this.closed = false;
+    // What receiving from the channel gives once it is closed
+    this.zero = zero;
+    this.receivers = new Queue();
+    this.senders = new Queue();
+}
+
+// A first-in first-out queue. Arrays shift by moving all of their elements, which is slow on the
+// queue of a channel that many goroutines wait on, so the queue only moves them once the ones
+// taken out are half of the array.
+function Queue() {
+    this.items = [];
+    this.head = 0;
+}
+
+function queue_length(queue) {
+    return queue.items.length - queue.head;
+}
+
+function queue_push(queue, item) {
+    queue.items.push(item);
+}
+
+function queue_shift(queue) {
+    const item = queue.items[queue.head];
+    queue.head++;
+    if (queue.head * 2 >= queue.items.length) {
+        queue.items = queue.items.slice(queue.head);
+        queue.head = 0;
+    }
+    return item;
+}
+
+function make_chan(size, zero, line_number) {
+    size = make_size(size);
+    if (size < 0) {
+        runtime_error("makechan: size out of range", line_number);
+    }
+    return new Channel(size, zero);
+}
+
+// The entries of a blocked select are stale once one of its cases fired
+function waiting(queue) {
+    while (queue_length(queue) > 0 && queue.items[queue.head].waiter.fired !== null) {
+        queue_shift(queue);
+    }
+    return queue_length(queue) > 0;
+}
+
+function fire(entry, fired) {
+    entry.waiter.fired = [entry.index].concat(fired);
+    set_status(entry.waiter.goroutine, 'runnable');
+}
+
+// Cases are the channel, whether it is a send and the value to send
+function case_ready([ch, is_send]) {
+    if (ch === null) {
+        return false;
+    }
+    if (is_send) {
+        return ch.closed || waiting(ch.receivers) || queue_length(ch.buffer) < ch.capacity;
+    }
+    return queue_length(ch.buffer) > 0 || waiting(ch.senders) || ch.closed;
+}
+
+// Sends or receives without blocking, once the case is ready. Gives the value received, whether
+// it was sent and whether the channel was closed.
+function complete_case([ch, is_send, v]) {
+    if (is_send) {
+        if (ch.closed) {
+            return [undefined, false, true];
+        }
+        // A waiting receiver gets the value directly
+        if (waiting(ch.receivers)) {
+            fire(queue_shift(ch.receivers), [v, true, false]);
+        } else {
+            queue_push(ch.buffer, v);
+        }
+        return [undefined, true, false];
+    }
+    if (queue_length(ch.buffer) > 0) {
+        const v = queue_shift(ch.buffer);
+        // The first waiting sender gets its value in the buffer
+        if (waiting(ch.senders)) {
+            const sender = queue_shift(ch.senders);
+            queue_push(ch.buffer, sender.value);
+            fire(sender, [undefined, true, false]);
+        }
+        return [v, true, false];
+    }
+    if (waiting(ch.senders)) {
+        const sender = queue_shift(ch.senders);
+        fire(sender, [undefined, true, false]);
+        return [sender.value, true, false];
+    }
+    return [deepCopy(ch.zero), false, false];
+}
+
+// Carries out one of the cases that can go on, chosen at random, or the default case, given as -1,
+// if none can. Otherwise the goroutine blocks until one can. Gives the index of the case with the
+// value received and whether it was sent.
+function* select(cases, has_default, status, line_number) {
+    const ready = [];
+    for (let i = 0; i < cases.length; i++) {
+        if (case_ready(cases[i])) {
+            ready.push(i);
+        }
+    }
+    let chosen;
+    if (ready.length > 0) {
+        const i = ready[choose(ready.length)];
+        chosen = [i].concat(complete_case(cases[i]));
+    } else if (has_default) {
+        return [-1, undefined, false];
+    } else {
+        const goroutine = current_goroutine;
+        // fired gets the index of the case that went on, with the value received, whether it was
+        // sent and whether the channel was closed
+        const waiter = {goroutine: goroutine, fired: null};
+        for (let i = 0; i < cases.length; i++) {
+            const [ch, is_send, v] = cases[i];
+            // Nil channels block forever
+            if (ch === null) {
+                continue;
+            }
+            if (is_send) {
+                queue_push(ch.senders, {waiter: waiter, index: i, value: v});
+            } else {
+                queue_push(ch.receivers, {waiter: waiter, index: i});
+            }
+        }
+        set_status(goroutine, 'blocked');
+        goroutine.waiting = [status, line_number];
+        yield;
+        chosen = waiter.fired;
+    }
+    if (chosen[3]) {
+        go_panic("send on closed channel", line_number);
+    }
+    return chosen.slice(0, 3);
+}
+
+function* chan_send(ch, v, line_number) {
+    yield* select([[ch, true, v]], false, ch === null ? "chan send (nil chan)" : "chan send",
+                  line_number);
+}
 
+// The value received and whether it was sent, for comma-ok assignments
+function* chan_recv_ok(ch, line_number) {
+    const received = yield* select([[ch, false]], false,
+                                   ch === null ? "chan receive (nil chan)" : "chan receive", line_number);
+    return [received[1], received[2]];
+}
+
+function* chan_recv(ch, line_number) {
+    return (yield* chan_recv_ok(ch, line_number))[0];
+}
+
+// The goroutines receiving from a closed channel get the zero value, and those sending on it panic
+function chan_close(ch, line_number) {
+    if (ch === null) {
+        go_panic("close of nil channel", line_number);
+    }
+    if (ch.closed) {
+        go_panic("close of closed channel", line_number);
+    }
+    ch.closed = true;
+    while (waiting(ch.receivers)) {
+        fire(queue_shift(ch.receivers), [deepCopy(ch.zero), false, false]);
+    }
+    while (waiting(ch.senders)) {
+        fire(queue_shift(ch.senders), [undefined, false, true]);
+    }
+}
@@ -1,33 +1,135 @@
-use ast;
-use ast::*;
-use value;
-use value::Value;
-use value::builtins;
+use crate::ast;
+use crate::ast::*;
+use crate::kind;
+use crate::kind::Kind;
+use crate::value;
+use crate::value::Value;
+use crate::value::Variable;
+use crate::value::builtins;
+use crate::scheduler;
+use crate::scheduler::Scheduler;
 use std::collections::HashMap;
 use std::cell::RefCell;
+use std::fmt;
+use std::future::Future;
+use std::io::Write;
+use std::pin::Pin;
+use std::rc::Rc;
+use crate::span;
+use crate::span::Span;
+use crate::util;
 
 #[derive(Debug)]
 pub enum Declaration<'b> {
-    Variable(Value),
+    Variable(Variable),
     Function(&'b ast::Function),
 }
 
-#[derive(Debug)]
 pub struct Env<'a,'b> {
     parent: Option<&'a Env<'a,'b>>,
-    entries: RefCell<HashMap<String, Declaration<'b> >>
+    entries: RefCell<HashMap<String, Declaration<'b> >>,
+    // Where print and println write to, shared by all the scopes
+    output: Rc<RefCell<&'b mut dyn Write>>,
+    // The calls deferred by the function the scope is in, shared by its scopes
+    defers: Rc<RefCell<Vec<Deferred>>>,
+    // Runs the goroutines, shared by all of them
+    scheduler: Rc<Scheduler<Deferred>>,
+}
+
+/// A call put off by a defer statement until its function returns. The function and its
+/// arguments are evaluated by the defer statement.
+enum Deferred {
+    Call(value::Function, Box<[Value]>, Span),
+    Delete(Value, Value, Span),
+    Print(Vec<u8>, Span),
+    Panic(Box<Panic>),
+    Close(Value, Span),
+}
+
+/// Runtime errors and calls to panic stop the program and are passed up with `?`. The panic is
+/// boxed so that the results of everything that can panic stay small.
+pub type RuntimeResult<T> = Result<T, Box<Panic>>;
+
+/// What the interpreter functions that call themselves give. A goroutine can block on a channel
+/// anywhere in them, so they are async, and their futures are boxed since they can't hold
+/// themselves.
+pub type Interpretation<'x, T> = Pin<Box<dyn Future<Output = RuntimeResult<T>> + 'x>>;
+
+/// A panic going up the calls. It keeps the functions it went out of, for the stack trace.
+#[derive(Debug, Clone)]
+pub struct Panic {
+    /// What the program was stopped with, like `runtime error: integer divide by zero`
+    pub message: String,
+    /// Where the panic started
+    pub span: Span,
+    /// The functions the panic went out of, innermost first, with the line each one was at
+    pub frames: Vec<(String, Span)>,
+    /// The goroutine that panicked, 1 being the main one
+    pub goroutine: usize,
+    // What the goroutine was doing, like `chan receive` for a deadlock
+    status: &'static str,
+    // Fatal errors, like deadlocks, stop the program without running the deferred calls
+    fatal: bool,
+    // Where the function the panic is in was
+    position: Span,
+}
+
+impl Panic {
+    fn new(span: Span, message: &str) -> Box<Panic> {
+        Box::new(Panic {
+            message: message.to_string(), span, frames: Vec::new(), goroutine: 1,
+            status: "running", fatal: false, position: span,
+        })
+    }
+
+    fn fatal(span: Span, message: &str, status: &'static str) -> Box<Panic> {
+        let mut panic = Panic::new(span, message);
+        panic.fatal = true;
+        panic.status = status;
+        panic
+    }
+
+    fn runtime_error(span: Span, message: &str) -> Box<Panic> {
+        Panic::new(span, &format!("runtime error: {}", message))
+    }
+}
+
+/// Formats the panic like Go does, with a stack trace of the GoLite functions:
+///
+/// ```text
+/// panic: runtime error: integer divide by zero [line 4]
+///
+/// goroutine 1 [running]:
+/// main.divide()
+///     prog.go:4
+/// main.main()
+///     prog.go:8
+/// ```
+///
+/// A deadlock is a fatal error instead, with what the main goroutine was waiting on.
+impl fmt::Display for Panic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} [line {}]\n\ngoroutine {} [{}]:", if self.fatal { "fatal error" } else { "panic" },
+               self.message, self.span.start.line, self.goroutine, self.status)?;
+        for &(ref name, span) in &self.frames {
+            write!(f, "\n{}()\n\t{}", name, span::format_line(span))?;
+        }
+        Ok(())
+    }
 }
 
 
 // Adds appropriate entries to env, and returns a list of init functions
-fn init_top_level<'a,'b>(env: &Env<'a, 'b>, root: &'b Program) -> Box<[&'b ast::Function]> {
+async fn init_top_level<'a,'b>(env: &Env<'a, 'b>, root: &'b Program) -> RuntimeResult<Box<[&'b ast::Function]>> {
     let mut init_functions = Vec::<&'b ast::Function>::new();
     for decl in &root.declarations {
         match &decl.variant {
             TopLevelDeclarationVariant::VarDeclarations{declarations} => {
-                interpret_var_declarations(declarations, env);
+                interpret_var_declarations(declarations, env).await?;
             },
             TopLevelDeclarationVariant::TypeDeclarations{..} => {},
+            // Uses of constants were replaced by their values
+            TopLevelDeclarationVariant::ConstDeclarations{..} => {},
             TopLevelDeclarationVariant::FunctionDeclaration(function) => {
                 let name = &function.name;
                 if name == "init" {
@@ -38,63 +140,89 @@ fn init_top_level<'a,'b>(env: &Env<'a, 'b>, root: &'b Program) -> Box<[&'b ast::
             }
         }
     }
-    return init_functions.into();
+    Ok(init_functions.into())
 }
 
 
-pub fn env_get_value(env: &Env, s: &str) -> Option<Value> {
+// TODO: functions are strictly top-level in this subset, so I probably want to
+// have a separate hashmap for functions, that is global, instead of one for each scope...
+// This code here is strictly because it was easy to copy and paste...
+pub fn env_get_function<'a,'b>(env: &'a Env<'a,'b>, s: &str) -> Option<&'b ast::Function> {
     //println!("{:?}", env.entries.borrow().keys());
     if let Some(declaration) = env.entries.borrow().get(s) {
         match declaration {
-            Declaration::Variable(v) => {return Some(v.clone());}
-            Declaration::Function(_) => {todo!("Haven't implemented taking functions as values");}
+            Declaration::Variable(_) => {panic!("not a function")},
+            Declaration::Function(f) => {return Some(*f);}
         }
     } else if let Some(parent) = env.parent {
-        env_get_value(parent, s)
+        env_get_function(parent, s)
     } else {
         None
     }
 }
 
-// TODO: functions are strictly top-level in this subset, so I probably want to
-// have a separate hashmap for functions, that is global, instead of one for each scope...
-// This code here is strictly because it was easy to copy and paste...
-pub fn env_get_function<'a,'b>(env: &'a Env<'a,'b>, s: &str) -> Option<&'b ast::Function> {
-    //println!("{:?}", env.entries.borrow().keys());
+pub fn env_get_variable(env: &Env, s: &str) -> Variable {
     if let Some(declaration) = env.entries.borrow().get(s) {
         match declaration {
-            Declaration::Variable(_) => {panic!("not a function")},
-            Declaration::Function(f) => {return Some(*f);}
+            Declaration::Variable(v) => v.clone(),
+            Declaration::Function(_) => panic!("not a variable"),
         }
     } else if let Some(parent) = env.parent {
-        env_get_function(parent, s)
+        env_get_variable(parent, s)
     } else {
-        None
+        panic!("Variable does not exist in scope");
     }
 }
 
-pub fn check_bounds(a: i32, length: usize, line_number: u32) {
+pub fn check_bounds(a: i32, length: usize, span: Span) -> RuntimeResult<()> {
     if a < 0 {
-        eprintln!("Error: line {}: trying to index an array or slice with negative number.", line_number);
-        std::process::exit(1);
+        return Err(Panic::runtime_error(span, &format!("index out of range [{}]", a)));
     }
     if a as usize >= length {
-        eprintln!("Error: line {}: index {} out of range. Should be in range of {}", line_number, a, length);
-        std::process::exit(1);
+        return Err(Panic::runtime_error(span, &format!("index out of range [{}] with length {}",
+                                                       a, length)));
+    }
+    Ok(())
+}
+
+/// Go panics on the map keys it can't hash, see value::unhashable_kind
+fn check_key(key: &Value, span: Span) -> RuntimeResult<()> {
+    match value::unhashable_kind(key) {
+        Some(kind) => Err(Panic::runtime_error(span, &format!("hash of unhashable type {}",
+                                                              kind.runtime_name()))),
+        None => Ok(()),
+    }
+}
+
+/// Stores a value where another one was. The elements of arrays are changed rather than replaced,
+/// since the slices of the array share them.
+fn assign(base: &mut Value, value: Value) {
+    match (base, value) {
+        (&mut Value::Array(ref array), Value::Array(new)) => {
+            for (element, v) in array.contents.iter().zip(new.contents.iter()) {
+                assign(&mut element.borrow_mut(), v.borrow().clone());
+            }
+        }
+        (&mut Value::Struct(ref mut fields), Value::Struct(new)) => {
+            for (name, v) in new {
+                assign(fields.get_mut(&name).unwrap(), v);
+            }
+        }
+        (base, value) => *base = value,
     }
 }
 
 pub fn env_declare_var(env: &Env, s: &str, v: Value) {
-    env.entries.borrow_mut().insert(s.to_string(), Declaration::Variable(v));
+    env.entries.borrow_mut().insert(s.to_string(), Declaration::Variable(Rc::new(RefCell::new(v))));
 }
 
 pub fn env_set_var(env: &Env, s: &str, v: Value) {
     if let Some(declaration) = env.entries.borrow_mut().get_mut(s) {
         match declaration {
             Declaration::Variable(ref mut lv) => {
-                *lv = v;
+                assign(&mut lv.borrow_mut(), v);
             },
-            Declaration::Function(_) => todo!()
+            Declaration::Function(_) => panic!("Functions cannot be assigned to")
         }
     } else if let Some(parent) = env.parent {
         env_set_var(parent, s, v)
@@ -103,7 +231,8 @@ pub fn env_set_var(env: &Env, s: &str, v: Value) {
     }
 }
 
-pub fn compute_binary_operation_int(op: BinaryOperator, l: i32, r: i32) -> Value {
+/// The result is wrapped around to fit the kind of the operands by the caller
+pub fn compute_binary_operation_int(op: BinaryOperator, l: i128, r: i128) -> Value {
     use self::BinaryOperator::*;
     match op {
         Eq => Value::Bool(l == r),
@@ -125,8 +254,10 @@ pub fn compute_binary_operation_int(op: BinaryOperator, l: i32, r: i32) -> Value
         BwAnd => Value::Int(l & r),
         BwAndNot => Value::Int(l & (! r)),
 
-        LShift => Value::Int(l<<r),
-        RShift => Value::Int(l>>r),
+        // The count is never negative here. Shifting by the width of the kind or more gives 0,
+        // or -1 when shifting a negative value right.
+        LShift => Value::Int(if r >= 64 { 0 } else { l.wrapping_shl(r as u32) }),
+        RShift => Value::Int(l >> r.min(127)),
 
         _ => panic!("Should not have been computing this, bc or/and are shortcircuiting"),
     }
@@ -152,7 +283,7 @@ pub fn compute_binary_operation_float(op: BinaryOperator, l: f64, r: f64) -> Val
     }
 }
 
-pub fn compute_binary_operation_string(op: BinaryOperator, l: &str, r: &str) -> Value {
+pub fn compute_binary_operation_string(op: BinaryOperator, l: &[u8], r: &[u8]) -> Value {
     use self::BinaryOperator::*;
     match op {
         Eq => Value::Bool(l == r),
@@ -164,8 +295,8 @@ pub fn compute_binary_operation_string(op: BinaryOperator, l: &str, r: &str) ->
         Geq => Value::Bool(l >= r),
 
         Add => {
-            let mut result = l.to_string();
-            result.push_str(&r);
+            let mut result = l.to_vec();
+            result.extend_from_slice(r);
             Value::String(result)
         }
 
@@ -173,22 +304,38 @@ pub fn compute_binary_operation_string(op: BinaryOperator, l: &str, r: &str) ->
     }
 }
 
-pub fn compute_binary_operation(op: BinaryOperator, lv: Value, rv: Value) -> Value {
+/// `kind` is the kind of the operands, which the result of arithmetic is made to fit
+pub fn compute_binary_operation(op: BinaryOperator, lv: Value, rv: Value, kind: &Kind, span: Span)
+    -> RuntimeResult<Value> {
     match (op, lv, rv) {
+        (BinaryOperator::Div, Value::Int(_), Value::Int(0)) |
+        (BinaryOperator::Mod, Value::Int(_), Value::Int(0)) => {
+            Err(Panic::runtime_error(span, "integer divide by zero"))
+        },
+        (BinaryOperator::LShift, Value::Int(_), Value::Int(ri)) |
+        (BinaryOperator::RShift, Value::Int(_), Value::Int(ri)) if ri < 0 => {
+            Err(Panic::runtime_error(span, "negative shift amount"))
+        },
         (_, Value::Int(li), Value::Int(ri)) => {
-            compute_binary_operation_int(op, li, ri)
+            Ok(value::fit_kind(compute_binary_operation_int(op, li, ri), kind))
         },
         (_, Value::Float(li), Value::Float(ri)) => {
-            compute_binary_operation_float(op, li, ri)
+            Ok(value::fit_kind(compute_binary_operation_float(op, li, ri), kind))
         },
         (_, Value::String(li), Value::String(ri)) => {
-            compute_binary_operation_string(op, &li, &ri)
+            Ok(compute_binary_operation_string(op, &li, &ri))
+        },
+        (BinaryOperator::Eq, Value::Interface(Some(ref a)), Value::Interface(Some(ref b))) |
+        (BinaryOperator::Neq, Value::Interface(Some(ref a)), Value::Interface(Some(ref b)))
+            if kind::are_identical(&a.kind, &b.kind) && !a.kind.is_comparable() => {
+            Err(Panic::runtime_error(span, &format!("comparing uncomparable type {}",
+                                                    a.kind.runtime_name())))
         },
         (BinaryOperator::Eq, lv, rv) => {
-            Value::Bool(lv == rv)
+            Ok(Value::Bool(lv == rv))
         },
         (BinaryOperator::Neq, lv, rv) => {
-            Value::Bool(lv != rv)
+            Ok(Value::Bool(lv != rv))
         },
         _ => {
             panic!("Other operations not supported");
@@ -196,107 +343,366 @@ pub fn compute_binary_operation(op: BinaryOperator, lv: Value, rv: Value) -> Val
     }
 }
 
-pub fn interpret_expression(expression_node: &Expression, env: & Env) -> Value {
-    match &expression_node.variant {
-        ExpressionVariant::RawLiteral{value} => {value::parse_with_kind(&value, &expression_node.kind)}
-        ExpressionVariant::BinaryOperation { op, lhs, rhs } => {
-            if let BinaryOperator::Or = op {
-                //special case, short circuiting
-                let l_ref = interpret_reference_expr(lhs, env);
-                let lv = l_ref.get_value(env);
+pub fn interpret_expression<'x>(expression_node: &'x Expression, env: &'x Env) -> Interpretation<'x, Value> {
+    Box::pin(async move {
+        let value = match &expression_node.variant {
+            ExpressionVariant::RawLiteral{value} => {value::parse_with_kind(&value, &expression_node.kind)}
+            ExpressionVariant::BinaryOperation { op, lhs, rhs } => {
+                if let BinaryOperator::Or = op {
+                    //special case, short circuiting
+                    let l_ref = interpret_reference_expr(lhs, env).await?;
+                    let lv = l_ref.get_value(env)?;
+
+                    if lv.get_boolean().unwrap() {
+                        lv
+                    } else {
+                        let r_ref = interpret_reference_expr(rhs, env).await?;
+                        let rv = r_ref.get_value(env)?;
+                        rv
+                    }
 
-                if lv.get_boolean().unwrap() {
-                    lv
-                } else {
-                    let r_ref = interpret_reference_expr(rhs, env);
-                    let rv = r_ref.get_value(env);
-                    rv
-                }
+                } else if let BinaryOperator::And = op {
+                    //special case, short circuiting
+                    let l_ref = interpret_reference_expr(lhs, env).await?;
+                    let lv = l_ref.get_value(env)?;
 
-            } else if let BinaryOperator::And = op {
-                //special case, short circuiting
-                let l_ref = interpret_reference_expr(lhs, env);
-                let lv = l_ref.get_value(env);
+                    if lv.get_boolean().unwrap() {
+                        let r_ref = interpret_reference_expr(rhs, env).await?;
+                        let rv = r_ref.get_value(env)?;
+                        rv
+                    } else {
+                        lv
+                    }
 
-                if lv.get_boolean().unwrap() {
-                    let r_ref = interpret_reference_expr(rhs, env);
-                    let rv = r_ref.get_value(env);
-                    rv
                 } else {
-                    lv
+                    let l_ref = interpret_reference_expr(lhs, env).await?;
+                    let r_ref = interpret_reference_expr(rhs, env).await?;
+                    let lv = l_ref.get_value(env)?;
+                    let rv = r_ref.get_value(env)?;
+                    compute_binary_operation(*op, lv, rv, &lhs.kind, expression_node.span)?
                 }
 
-            } else {
-                let l_ref = interpret_reference_expr(lhs, env);
-                let r_ref = interpret_reference_expr(rhs, env);
-                let lv = l_ref.get_value(env);
-                let rv = r_ref.get_value(env);
-                compute_binary_operation(*op, lv, rv)
             }
-
-        }
-        ExpressionVariant::UnaryOperation { op, rhs } => {
-            let rv = interpret_expression(rhs, env);
-            match op {
-                UnaryOperator::Plus => builtins::plus(&rv),
-                UnaryOperator::Neg => builtins::neg(&rv),
-                UnaryOperator::BwCompl => builtins::bw_compl(&rv),
-                UnaryOperator::Not => builtins::not(&rv),
+            ExpressionVariant::UnaryOperation { op, rhs } => {
+                let rv = interpret_expression(rhs, env).await?;
+                match op {
+                    UnaryOperator::Plus => builtins::plus(&rv),
+                    UnaryOperator::Neg => builtins::neg(&rv, &expression_node.kind),
+                    UnaryOperator::BwCompl => builtins::bw_compl(&rv, &expression_node.kind),
+                    UnaryOperator::Not => builtins::not(&rv),
+                }
             }
-        }
-        ExpressionVariant::Identifier{..} |
-        ExpressionVariant::Index { .. } |
-        ExpressionVariant::Selector { .. } => {
-            let r = interpret_reference_expr(expression_node, env);
-            r.get_value(env)
-        }
-        ExpressionVariant::FunctionCall { primary, arguments } => {
-            let f;
-            match &primary.variant {
-                ExpressionVariant::Identifier{name, ..} => {
-                    f = env_get_function(env, name).unwrap();
-                },
-                _ => todo!("Haven't implemented taking an expression as a function.")
+            ExpressionVariant::Identifier{..} |
+            ExpressionVariant::Index { .. } |
+            ExpressionVariant::Selector { .. } |
+            ExpressionVariant::Dereference { .. } => {
+                let r = interpret_reference_expr(expression_node, env).await?;
+                r.get_value(env)?
+            }
+            ExpressionVariant::Slice { primary, low, high, max } => {
+                interpret_slice_expression(primary, low, high, max, env).await?
+            }
+            ExpressionVariant::FunctionCall { primary, arguments, spread } => {
+                let (f, evaled_args) = interpret_call_operands(primary, arguments, *spread, env).await?;
+                call_function(&f, env, evaled_args, expression_node.span).await?
+            }
+            ExpressionVariant::AddressOf { expr } => {
+                let reference = interpret_reference_expr(expr, env).await?;
+                Value::Pointer(Some(address_of(reference, env)?))
+            }
+            ExpressionVariant::New { kind } => {
+                // Each call gives a new variable that only the pointer refers to
+                let variable = Rc::new(RefCell::new(value::zero_value(&kind.evaluated_kind)));
+                Value::Pointer(Some(value::Pointer { place: value::Place::Variable(variable), path: Vec::new() }))
+            }
+            ExpressionVariant::Nil => value::zero_value(&expression_node.kind),
+            ExpressionVariant::Panic { expr } => {
+                let v = interpret_expression(expr, env).await?;
+                return Err(Panic::new(expression_node.span, &panic_value_text(&v, &expr.kind)));
+            }
+            ExpressionVariant::FunctionLiteral { function } => {
+                Value::Function(value::Function::Closure(function.clone(), capture_variables(env)))
+            }
+            ExpressionVariant::Method { receiver, function, pointer_receiver, .. } => {
+                // The receiver is copied, or its address is taken for a pointer receiver
+                let receiver_value = if *pointer_receiver && !receiver.kind.is_pointer() {
+                    let reference = interpret_reference_expr(receiver, env).await?;
+                    Value::Pointer(Some(address_of(reference, env)?))
+                } else if !*pointer_receiver && receiver.kind.is_pointer() {
+                    let pointer = dereference(interpret_expression(receiver, env).await?, receiver.span)?;
+                    let value = get_reference_value(&pointer.place.cell().borrow(), &pointer.path)?;
+                    value
+                } else {
+                    interpret_expression(receiver, env).await?
+                };
+                Value::Function(value::Function::Method(function.clone(), Box::new(receiver_value)))
+            }
+            ExpressionVariant::ToInterface { expr } => {
+                let v = interpret_expression(expr, env).await?;
+                to_interface(v, &expr.kind)
+            }
+            ExpressionVariant::InterfaceMethod { receiver, name } => {
+                interface_method(interpret_expression(receiver, env).await?, name, expression_node.span)?
+            }
+            ExpressionVariant::TypeAssertion { expr, kind } => {
+                let v = interpret_expression(expr, env).await?;
+                match assert_kind(v, &expr.kind, &kind.evaluated_kind) {
+                    Ok(v) => v,
+                    Err(message) => return Err(Panic::new(expression_node.span, &message)),
+                }
+            }
+            ExpressionVariant::Append { lhs, rhs } => {
+                let l_ref = interpret_reference_expr(lhs, env).await?;
+                let r_ref = interpret_reference_expr(rhs, env).await?;
+                let lv = l_ref.get_value(env)?;
+                let rv = r_ref.get_value(env)?;
+                let element_kind = match expression_node.kind.resolve() {
+                    Kind::Slice(element) => *element,
+                    _ => panic!("Appending to something that isn't a slice."),
+                };
+                builtins::append(lv, rv, &element_kind)
+            }
+            ExpressionVariant::Len { expr } => {
+                let v = interpret_expression(expr, env).await?;
+                builtins::len(&v)
+            }
+            ExpressionVariant::Cap { expr } => {
+                let v = interpret_expression(expr, env).await?;
+                builtins::cap(&v)
+            }
+            ExpressionVariant::TypeCast {expr, ..} => {
+                let kind = &expression_node.kind;
+                let v = interpret_expression(expr, env).await?;
+                builtins::cast(kind, &v)
+            }
+            ExpressionVariant::Make { kind, arguments } if kind.evaluated_kind.is_slice() => {
+                let length = match interpret_expression(&arguments[0], env).await?.get_size() {
+                    Some(length) => length,
+                    None => return Err(Panic::runtime_error(arguments[0].span, "makeslice: len out of range")),
+                };
+                let capacity = match arguments.get(1) {
+                    Some(argument) => match interpret_expression(argument, env).await?.get_size() {
+                        Some(capacity) if capacity >= length => capacity,
+                        _ => return Err(Panic::runtime_error(argument.span, "makeslice: cap out of range")),
+                    },
+                    None => length,
+                };
+                builtins::make_slice(&expression_node.kind, length, capacity)
+            }
+            ExpressionVariant::Make { kind, arguments } => {
+                let mut size = 0;
+                for argument in arguments {
+                    size = match interpret_expression(argument, env).await?.get_size() {
+                        Some(size) => size,
+                        None => {
+                            let message = if kind.evaluated_kind.is_chan() { "makechan" } else { "makemap" };
+                            return Err(Panic::runtime_error(argument.span, &format!("{}: size out of range", message)));
+                        }
+                    };
+                }
+                match kind.evaluated_kind.resolve() {
+                    Kind::Chan(base) => {
+                        let zero = value::zero_value(&base);
+                        Value::Channel(Some(scheduler::Channel::new(size, zero)))
+                    }
+                    _ => builtins::make_map(),
+                }
             }
+            ExpressionVariant::Receive { channel } => {
+                let cv = interpret_expression(channel, env).await?;
+                receive(&cv, env, expression_node.span).await?.0
+            }
+            ExpressionVariant::Copy { dst, src } => {
+                let dv = interpret_expression(dst, env).await?;
+                let sv = interpret_expression(src, env).await?;
+                builtins::copy(&dv, &sv)
+            }
+            ExpressionVariant::Close { channel } => {
+                let cv = interpret_expression(channel, env).await?;
+                close_channel(&cv, env, expression_node.span)?;
+                Value::Void
+            }
+            ExpressionVariant::CompositeLiteral { elements, .. } => {
+                interpret_composite_literal(&expression_node.kind, elements, env).await?
+            }
+            ExpressionVariant::Delete { map, key } => {
+                let mv = interpret_expression(map, env).await?;
+                let kv = interpret_expression(key, env).await?;
+                check_key(&kv, key.span)?;
+                builtins::map_delete(&mv, &kv);
+                Value::Void
+            }
+            ExpressionVariant::CommaOk { expr } => {
+                let (primary, index) = match expr.variant {
+                    ExpressionVariant::Index { ref primary, ref index } => (primary, index),
+                    ExpressionVariant::TypeAssertion { expr: ref interface, ref kind } => {
+                        let v = interpret_expression(interface, env).await?;
+                        return Ok(match assert_kind(v, &interface.kind, &kind.evaluated_kind) {
+                            Ok(v) => Value::Tuple(vec![v, Value::Bool(true)]),
+                            Err(_) => Value::Tuple(vec![value::zero_value(&expr.kind), Value::Bool(false)]),
+                        });
+                    }
+                    ExpressionVariant::Receive { ref channel } => {
+                        let cv = interpret_expression(channel, env).await?;
+                        let (v, ok) = receive(&cv, env, expr.span).await?;
+                        return Ok(Value::Tuple(vec![v, Value::Bool(ok)]));
+                    }
+                    _ => panic!("Only map lookups, type assertions and receives can give comma-ok values"),
+                };
+                let mv = interpret_expression(primary, env).await?;
+                let kv = interpret_expression(index, env).await?;
+                check_key(&kv, index.span)?;
+                let found = match mv {
+                    Value::Map(ref m) => builtins::map_lookup(m, &kv),
+                    _ => panic!("Trying to look up a key in something that isn't a map."),
+                };
+                match found {
+                    Some(v) => Value::Tuple(vec![v, Value::Bool(true)]),
+                    None => Value::Tuple(vec![value::zero_value(&expr.kind), Value::Bool(false)]),
+                }
+            }
+        };
+        Ok(value)
+    })
+}
+
+/// Puts a value of kind `kind` in an interface. Interfaces put in other interfaces keep the value
+/// they hold.
+fn to_interface(v: Value, kind: &Kind) -> Value {
+    if kind.is_interface() {
+        v
+    } else {
+        Value::Interface(Some(value::Interface { kind: kind.clone(), value: Box::new(v) }))
+    }
+}
 
-            let refs: Vec<Reference> = arguments.iter().map(|arg| {
-                interpret_reference_expr(arg, env)
-            }).collect();
+/// Whether the value held by an interface has the kind `kind`. A nil interface has none.
+fn has_kind(interface: &Value, kind: &Kind) -> bool {
+    match (interface, kind) {
+        (Value::Interface(None), Kind::Nil) => true,
+        (Value::Interface(Some(interface)), _) if kind.is_interface() => {
+            interface.kind.missing_method(&interface_methods(kind)).is_none()
+        }
+        (Value::Interface(Some(interface)), _) => kind::are_identical(&interface.kind, kind),
+        _ => false,
+    }
+}
 
-            let evaled_args: Vec<Value> = refs.into_iter().map(|r| {
-                r.get_value(env)
-            }).collect();
+fn interface_methods(kind: &Kind) -> Vec<kind::InterfaceMethod> {
+    match kind.resolve() {
+        Kind::Interface(methods) => methods,
+        _ => panic!("Only interfaces have a method set"),
+    }
+}
 
-            interpret_function(f, env, evaled_args.into())
+/// The method `name` of the value held by an interface, bound to its receiver
+fn interface_method(interface: Value, name: &str, span: Span) -> RuntimeResult<Value> {
+    let interface = match interface {
+        Value::Interface(Some(interface)) => interface,
+        Value::Interface(None) => return Err(Panic::runtime_error(
+            span, "invalid memory address or nil pointer dereference")),
+        _ => panic!("Calling an interface method on something that isn't an interface"),
+    };
+    let (path, selection) = match interface.kind.lookup(name) {
+        kind::Lookup::Found(path, selection) => (path, selection),
+        _ => panic!("The value of an interface has its methods"),
+    };
+    // Promoted methods are called on the embedded field they come from. Once a pointer is
+    // on the way, the fields are pointed to so that pointer methods can change them.
+    let mut receiver_value = *interface.value;
+    let mut through_pointer = interface.kind.is_pointer();
+    for field in path {
+        receiver_value = if through_pointer {
+            let mut pointer = dereference(receiver_value, span)?;
+            pointer.path.push(ReferenceModifier::Selector(field.name.clone()));
+            if field.kind.is_pointer() {
+                get_reference_value(&pointer.place.cell().borrow(), &pointer.path)?
+            } else {
+                Value::Pointer(Some(pointer))
+            }
+        } else {
+            match receiver_value {
+                Value::Struct(mut fields) => fields.remove(&field.name).unwrap(),
+                _ => panic!("Only structs have embedded fields"),
+            }
+        };
+        through_pointer = through_pointer || field.kind.is_pointer();
+    }
+    let method = match selection {
+        kind::Selection::Method(method) => method,
+        // Methods promoted from an embedded interface are the ones of the value it holds
+        kind::Selection::InterfaceMethod(_) if through_pointer => {
+            let pointer = dereference(receiver_value, span)?;
+            let value = get_reference_value(&pointer.place.cell().borrow(), &pointer.path)?;
+            return interface_method(value, name, span);
         }
-        ExpressionVariant::Append { lhs, rhs } => {
-            let l_ref = interpret_reference_expr(lhs, env);
-            let r_ref = interpret_reference_expr(rhs, env);
-            let lv = l_ref.get_value(env);
-            let rv = r_ref.get_value(env);
-            builtins::append(lv,rv)
+        kind::Selection::InterfaceMethod(_) => return interface_method(receiver_value, name, span),
+        kind::Selection::Field(_) => panic!("The value of an interface has its methods"),
+    };
+    // Methods with a value receiver are called on what a pointer points to
+    let receiver_value = if !method.pointer_receiver && through_pointer {
+        let pointer = dereference(receiver_value, span)?;
+        let value = get_reference_value(&pointer.place.cell().borrow(), &pointer.path)?;
+        value
+    } else {
+        receiver_value
+    };
+    Ok(Value::Function(value::Function::Method(method.function, Box::new(receiver_value))))
+}
+
+/// Takes the value of kind `kind` out of an interface of kind `interface_kind`, or gives an
+/// interface of kind `kind` holding the same value. Gives Go's panic message if it can't.
+fn assert_kind(v: Value, interface_kind: &Kind, kind: &Kind) -> Result<Value, String> {
+    if has_kind(&v, kind) {
+        return Ok(match v {
+            Value::Interface(Some(interface)) if !kind.is_interface() => *interface.value,
+            v => v,
+        });
+    }
+    Err(match v {
+        Value::Interface(Some(ref interface)) if kind.is_interface() => {
+            let missing = interface.kind.missing_method(&interface_methods(kind)).unwrap();
+            format!("interface conversion: {} is not {}: missing method {}",
+                    interface.kind.runtime_name(), kind.runtime_name(), missing)
+        }
+        Value::Interface(Some(ref interface)) => {
+            format!("interface conversion: {} is {}, not {}", interface_kind.runtime_name(),
+                    interface.kind.runtime_name(), kind.runtime_name())
         }
-        ExpressionVariant::TypeCast {expr, ..} => {
-            let kind = &expression_node.kind;
-            let v = interpret_expression(expr, env);
-            builtins::cast(kind, &v)
+        _ => format!("interface conversion: {} is nil, not {}", interface_kind.runtime_name(),
+                     kind.runtime_name()),
+    })
+}
+
+/// Evaluates the expressions assigned to `count` values. A single expression can give all of
+/// them at once as a tuple.
+async fn interpret_assigned_expressions(exprs: &[Expression], count: usize, env: &Env<'_, '_>)
+    -> RuntimeResult<Vec<Value>> {
+    let mut values = Vec::new();
+    for expr in exprs {
+        values.push(interpret_expression(expr, env).await?);
+    }
+    if values.len() != count {
+        match values.pop() {
+            Some(Value::Tuple(values)) => return Ok(values),
+            _ => panic!("Only a tuple can give several values"),
         }
     }
+    Ok(values)
 }
 
 
-pub fn interpret_var_declarations(declarations: &[VarSpec], env: &Env) {
+pub async fn interpret_var_declarations(declarations: &[VarSpec], env: &Env<'_, '_>) -> RuntimeResult<()> {
     for var_spec in declarations {
-        for (i,name) in var_spec.names.iter().enumerate() {
-            let rv;
-            if let Some(ref exprs) = var_spec.rhs {
-                rv = interpret_expression(&exprs[i], env);
-            } else {
-                rv = value::zero_value(&var_spec.evaluated_kind);
-            }
-            env_declare_var(&env, name, rv);
+        let values = match var_spec.rhs {
+            Some(ref exprs) => interpret_assigned_expressions(exprs, var_spec.names.len(), env).await?,
+            None => vec![value::zero_value(&var_spec.evaluated_kind); var_spec.names.len()],
+        };
+        for (name, rv) in var_spec.names.iter().zip(values) {
+            env_declare_var(env, name, rv);
         }
     }
+    Ok(())
 }
 
 // Different "signals" that may be returned from a statement to control the program flow
@@ -304,8 +710,11 @@ pub fn interpret_var_declarations(declarations: &[VarSpec], env: &Env) {
 pub enum Signal {
     None,
     Return(Value),
-    Break,
-    Continue,
+    // The label of the loop or switch, if it isn't the innermost one
+    Break(Option<String>),
+    Continue(Option<String>),
+    // Goes up to the statements with the label
+    Goto(String),
 }
 
 impl Signal {
@@ -317,15 +726,29 @@ impl Signal {
     }
 }
 
+// Whether a break or continue is for the loop or switch with the given label
+fn is_jump_target(signal_label: &Option<String>, label: Option<&String>) -> bool {
+    match signal_label {
+        None => true,
+        Some(l) => Some(l) == label,
+    }
+}
+
 pub enum ReferenceBase {
     Identifier(String),
+    // What a pointer points to
+    Place(value::Place),
     Value(Value),
     Underscore,
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub enum ReferenceModifier {
     Selector(String),
-    Index(i32),
+    // The span of the index expression is kept for the out of range error
+    Index(i32, Span),
+    // The key, the value of missing keys, and the span of the key for the nil map error
+    Key(Value, Value, Span),
 }
 
 pub struct Reference {
@@ -333,65 +756,83 @@ pub struct Reference {
     modifier_stack: Vec<ReferenceModifier>,
 }
 
-fn get_reference_value(base: &Value, modifier_stack: &[ReferenceModifier]) -> Value {
+fn get_reference_value(base: &Value, modifier_stack: &[ReferenceModifier]) -> RuntimeResult<Value> {
     if modifier_stack.len() == 0 {
-        return base.clone();
+        return Ok(base.clone());
     }
     match (base, &modifier_stack[0]) {
-        (Value::Array(ref array), ReferenceModifier::Index(ii))  => {
-            let i = *ii;
-            // TODO: display the correct line number
-            check_bounds(i, array.len(), 0);
-            get_reference_value(& array[i as usize], &modifier_stack[1..])
-        }
-        (Value::Slice(ref slice), ReferenceModifier::Index(ii))  => {
-            let i = *ii;
-            // TODO: display the correct line number
-            check_bounds(i, slice.length, 0);
-            get_reference_value(& *slice.contents[i as usize].borrow(), &modifier_stack[1..])
-        }
-        (_, ReferenceModifier::Index(_))  => panic!("Trying to index something that isn't an array or slice."),
+        (Value::Array(ref array), ReferenceModifier::Index(i, span))  => {
+            let i = *i;
+            check_bounds(i, array.len(), *span)?;
+            get_reference_value(&array.contents[i as usize].borrow(), &modifier_stack[1..])
+        }
+        (Value::Slice(ref slice), ReferenceModifier::Index(i, span))  => {
+            let i = *i;
+            check_bounds(i, slice.length, *span)?;
+            get_reference_value(&slice.element(i as usize).borrow(), &modifier_stack[1..])
+        }
+        (_, ReferenceModifier::Index(..))  => panic!("Trying to index something that isn't an array or slice."),
         (Value::Struct(ref hm), ReferenceModifier::Selector(s))  =>  {
             get_reference_value(hm.get(s).unwrap(), &modifier_stack[1..])
         }
-        (_, ReferenceModifier::Selector(_))  => panic!("Trying to get a field of something that isn't a struct.")
+        (_, ReferenceModifier::Selector(_))  => panic!("Trying to get a field of something that isn't a struct."),
+        (Value::Map(ref map), ReferenceModifier::Key(key, zero, _))  =>  {
+            match builtins::map_lookup(map, key) {
+                Some(element) => get_reference_value(&element, &modifier_stack[1..]),
+                None => get_reference_value(zero, &modifier_stack[1..]),
+            }
+        }
+        (_, ReferenceModifier::Key(..))  => panic!("Trying to look up a key in something that isn't a map.")
     }
 }
 
-fn set_reference_value(base: &mut Value, modifier_stack: &[ReferenceModifier], value: Value) {
+fn set_reference_value(base: &mut Value, modifier_stack: &[ReferenceModifier], value: Value)
+    -> RuntimeResult<()> {
     if modifier_stack.len() == 0 {
-        *base = value;
-        return;
+        assign(base, value);
+        return Ok(());
     }
     match (base, &modifier_stack[0]) {
-        (Value::Array(ref mut array), ReferenceModifier::Index(ii))  => {
-            let i = *ii;
-            // TODO: display the correct line number
-            check_bounds(i, array.len(), 0);
-            set_reference_value(&mut array[i as usize], &modifier_stack[1..], value)
-        }
-        (Value::Slice(ref mut slice), ReferenceModifier::Index(ii))  => {
-            let i = *ii;
-            // TODO: display the correct line number
-            check_bounds(i, slice.length, 0);
-            set_reference_value(&mut *slice.contents[i as usize].borrow_mut(), &modifier_stack[1..], value)
-        }
-        (_, ReferenceModifier::Index(_))  => panic!("Trying to index something that isn't an array or slice."),
+        (Value::Array(ref array), ReferenceModifier::Index(i, span))  => {
+            let i = *i;
+            check_bounds(i, array.len(), *span)?;
+            set_reference_value(&mut array.contents[i as usize].borrow_mut(), &modifier_stack[1..], value)
+        }
+        (Value::Slice(ref slice), ReferenceModifier::Index(i, span))  => {
+            let i = *i;
+            check_bounds(i, slice.length, *span)?;
+            set_reference_value(&mut slice.element(i as usize).borrow_mut(), &modifier_stack[1..], value)
+        }
+        (_, ReferenceModifier::Index(..))  => panic!("Trying to index something that isn't an array or slice."),
         (Value::Struct(ref mut hm), ReferenceModifier::Selector(s))  =>  {
             set_reference_value(hm.get_mut(s).unwrap(), &modifier_stack[1..], value)
         }
-        (_, ReferenceModifier::Selector(_))  => panic!("Trying to get a field of something that isn't a struct.")
+        (_, ReferenceModifier::Selector(_))  => panic!("Trying to get a field of something that isn't a struct."),
+        (Value::Map(ref map), ReferenceModifier::Key(key, zero, span))  =>  {
+            if map.entries.is_none() {
+                return Err(Panic::runtime_error(*span, "assignment to entry in nil map"));
+            }
+            // Elements can't be borrowed from the map, so they are changed and put back
+            let mut element = builtins::map_lookup(map, key).unwrap_or_else(|| zero.clone());
+            set_reference_value(&mut element, &modifier_stack[1..], value)?;
+            builtins::map_insert(map, key.clone(), element);
+            Ok(())
+        }
+        (_, ReferenceModifier::Key(..))  => panic!("Trying to look up a key in something that isn't a map.")
     }
 }
 
 
 impl Reference {
-    pub fn get_value(&self, env: &Env) -> Value {
+    pub fn get_value(&self, env: &Env) -> RuntimeResult<Value> {
         let modifier_stack = &self.modifier_stack;
         match &self.base {
             ReferenceBase::Identifier(ref s) => {
                 env_get_reference_value(env, s, modifier_stack).unwrap()
             },
+            ReferenceBase::Place(place) => {
+                get_reference_value(&place.cell().borrow(), modifier_stack)
+            },
             ReferenceBase::Value(base) => {
                 get_reference_value(&base, modifier_stack)
             },
@@ -400,19 +841,23 @@ impl Reference {
             },
         }
     }
-    pub fn set_value(&self, env: &Env, value: Value) {
+    pub fn set_value(&self, env: &Env, value: Value) -> RuntimeResult<()> {
         let modifier_stack = &self.modifier_stack;
         match &self.base {
             ReferenceBase::Identifier(ref s) => {
-                env_set_reference_value(env, s, modifier_stack, value);
+                env_set_reference_value(env, s, modifier_stack, value)
+            },
+            ReferenceBase::Place(place) => {
+                set_reference_value(&mut place.cell().borrow_mut(), modifier_stack, value)
             },
             ReferenceBase::Value(base) => {
                 let mut copy = base.clone();
-                set_reference_value(&mut copy, modifier_stack, value);
+                set_reference_value(&mut copy, modifier_stack, value)
             }
             ReferenceBase::Underscore => {
                 if modifier_stack.len() == 0 {
                     // Do nothing.
+                    Ok(())
                 }
                 else {
                     panic!("Cannot do stuff with underscore");
@@ -422,21 +867,25 @@ impl Reference {
         }
     }
     
-    pub fn map_value<F>(&self, env: &Env, f: F)
-    where F: FnOnce(&mut Value) {
+    pub fn map_value<F>(&self, env: &Env, f: F) -> RuntimeResult<()>
+    where F: FnOnce(&mut Value) -> RuntimeResult<()> {
         let modifier_stack = &self.modifier_stack;
         match &self.base {
             ReferenceBase::Identifier(ref s) => {
-                env_map_reference_value(env, s, modifier_stack, f);
+                env_map_reference_value(env, s, modifier_stack, f)
+            },
+            ReferenceBase::Place(place) => {
+                map_reference_value(&mut place.cell().borrow_mut(), modifier_stack, f)
             },
             ReferenceBase::Value(base) => {
                 let mut copy = base.clone();
-                map_reference_value(&mut copy, modifier_stack, f);
+                map_reference_value(&mut copy, modifier_stack, f)
             }
             ReferenceBase::Underscore => {
                 if modifier_stack.len() == 0 {
                     // Do nothing.
                     // TODO: should we panic here?
+                    Ok(())
                 }
                 else {
                     panic!("Cannot do stuff with underscore");
@@ -446,14 +895,15 @@ impl Reference {
     }
 }
 
-pub fn env_set_reference_value(env: &Env, ident: &str, modifier_stack: &[ReferenceModifier], value: Value) {
+pub fn env_set_reference_value(env: &Env, ident: &str, modifier_stack: &[ReferenceModifier], value: Value)
+    -> RuntimeResult<()> {
     //println!("{:?}", env.entries.borrow().keys());
     if let Some(ref mut declaration) = env.entries.borrow_mut().get_mut(ident) {
         match declaration {
             Declaration::Variable(ref mut v) => {
-                set_reference_value(v, modifier_stack, value);
+                set_reference_value(&mut v.borrow_mut(), modifier_stack, value)
             }
-            Declaration::Function(_) => {todo!("Haven't implemented taking functions as values");}
+            Declaration::Function(_) => panic!("Functions cannot be assigned to")
         }
     } else if let Some(parent) = env.parent {
         env_set_reference_value(parent, ident, modifier_stack, value)
@@ -462,14 +912,15 @@ pub fn env_set_reference_value(env: &Env, ident: &str, modifier_stack: &[Referen
     }
 }
 
-pub fn env_map_reference_value<F>(env: &Env, ident: &str, modifier_stack: &[ReferenceModifier], f: F) 
-where F: FnOnce(&mut Value) {
+pub fn env_map_reference_value<F>(env: &Env, ident: &str, modifier_stack: &[ReferenceModifier], f: F)
+    -> RuntimeResult<()>
+where F: FnOnce(&mut Value) -> RuntimeResult<()> {
     if let Some(ref mut declaration) = env.entries.borrow_mut().get_mut(ident) {
         match declaration {
             Declaration::Variable(ref mut v) => {
-                map_reference_value(v, modifier_stack, f);
+                map_reference_value(&mut v.borrow_mut(), modifier_stack, f)
             }
-            Declaration::Function(_) => {todo!("Haven't implemented taking functions as values");}
+            Declaration::Function(_) => panic!("Functions cannot be assigned to")
         }
     } else if let Some(parent) = env.parent {
         env_map_reference_value(parent, ident, modifier_stack, f)
@@ -479,39 +930,52 @@ where F: FnOnce(&mut Value) {
 }
 
 fn map_reference_value<F>(base: &mut Value, modifier_stack: &[ReferenceModifier], f: F)
-where F: FnOnce(&mut Value) {
+    -> RuntimeResult<()>
+where F: FnOnce(&mut Value) -> RuntimeResult<()> {
     if modifier_stack.len() == 0 {
-        f(base);
-        return;
+        return f(base);
     }
     match (base, &modifier_stack[0]) {
-        (Value::Array(ref mut array), ReferenceModifier::Index(ii))  => {
-            let i = *ii;
-            // TODO: display the correct line number
-            check_bounds(i, array.len(), 0);
-            map_reference_value(&mut array[i as usize], &modifier_stack[1..], f)
-        }
-        (Value::Slice(ref mut slice), ReferenceModifier::Index(ii))  => {
-            let i = *ii;
-            // TODO: display the correct line number
-            check_bounds(i, slice.length, 0);
-            map_reference_value(&mut *slice.contents[i as usize].borrow_mut(), &modifier_stack[1..], f)
-        }
-        (_, ReferenceModifier::Index(_))  => panic!("Trying to index something that isn't an array or slice."),
+        (Value::Array(ref array), ReferenceModifier::Index(i, span))  => {
+            let i = *i;
+            check_bounds(i, array.len(), *span)?;
+            map_reference_value(&mut array.contents[i as usize].borrow_mut(), &modifier_stack[1..], f)
+        }
+        (Value::Slice(ref slice), ReferenceModifier::Index(i, span))  => {
+            let i = *i;
+            check_bounds(i, slice.length, *span)?;
+            map_reference_value(&mut slice.element(i as usize).borrow_mut(), &modifier_stack[1..], f)
+        }
+        (_, ReferenceModifier::Index(..))  => panic!("Trying to index something that isn't an array or slice."),
         (Value::Struct(ref mut hm), ReferenceModifier::Selector(s))  =>  {
             map_reference_value(hm.get_mut(s).unwrap(), &modifier_stack[1..], f)
         }
-        (_, ReferenceModifier::Selector(_))  => panic!("Trying to get a field of something that isn't a struct.")
+        (_, ReferenceModifier::Selector(_))  => panic!("Trying to get a field of something that isn't a struct."),
+        (Value::Map(ref map), ReferenceModifier::Key(key, zero, span))  =>  {
+            if map.entries.is_none() {
+                return Err(Panic::runtime_error(*span, "assignment to entry in nil map"));
+            }
+            let mut element = builtins::map_lookup(map, key).unwrap_or_else(|| zero.clone());
+            map_reference_value(&mut element, &modifier_stack[1..], f)?;
+            builtins::map_insert(map, key.clone(), element);
+            Ok(())
+        }
+        (_, ReferenceModifier::Key(..))  => panic!("Trying to look up a key in something that isn't a map.")
     }
 }
 
-pub fn env_get_reference_value(env: &Env, ident: &str, modifier_stack: &[ReferenceModifier]) -> Option<Value> {
+pub fn env_get_reference_value(env: &Env, ident: &str, modifier_stack: &[ReferenceModifier])
+    -> Option<RuntimeResult<Value>> {
     if let Some(ref declaration) = env.entries.borrow().get(ident) {
         match declaration {
             Declaration::Variable(ref v) => {
-                Some(get_reference_value(v, modifier_stack))
+                Some(get_reference_value(&v.borrow(), modifier_stack))
+            }
+            // Top-level functions are values that find the function by name when called
+            Declaration::Function(_) => {
+                let f = Value::Function(value::Function::TopLevel(ident.to_string()));
+                Some(get_reference_value(&f, modifier_stack))
             }
-            Declaration::Function(_) => {todo!("Haven't implemented taking functions as values");}
         }
     } else if let Some(parent) = env.parent {
         env_get_reference_value(parent, ident, modifier_stack)
@@ -520,344 +984,1011 @@ pub fn env_get_reference_value(env: &Env, ident: &str, modifier_stack: &[Referen
     }
 }
 
-pub fn interpret_reference_expr(expr: &Expression, env: &Env) -> Reference {
-    match expr.variant {
-        ExpressionVariant::Identifier{ref name, ..} => {
-            if name == "_" {
+pub fn interpret_reference_expr<'x>(expr: &'x Expression, env: &'x Env) -> Interpretation<'x, Reference> {
+    Box::pin(async move {
+        let reference = match expr.variant {
+            ExpressionVariant::Identifier{ref name, ..} => {
+                if name == "_" {
+                    Reference {
+                        base: ReferenceBase::Underscore,
+                        modifier_stack: Vec::new(),
+                    }
+                } else {
+                    Reference {
+                        base: ReferenceBase::Identifier(name.clone()),
+                        modifier_stack: Vec::new(),
+                    }
+                }
+            },
+            ExpressionVariant::Index{ref primary,ref  index} => {
+                let mut reference = interpret_reference_expr(primary, env).await?;
+                if let Kind::Map(..) = primary.kind.resolve() {
+                    let key = interpret_expression(index, env).await?;
+                    check_key(&key, index.span)?;
+                    let zero = value::zero_value(&expr.kind);
+                    reference.modifier_stack.push(ReferenceModifier::Key(key, zero, index.span));
+                } else {
+                    let i = interpret_expression(index, env).await?.get_integer().unwrap();
+                    reference.modifier_stack.push(ReferenceModifier::Index(i, index.span));
+                }
+                reference
+            }
+            ExpressionVariant::Selector{ref primary,ref name} => {
+                // The fields of a struct can be selected through a pointer to it
+                let mut reference = if primary.kind.is_pointer() {
+                    let pointer = dereference(interpret_expression(primary, env).await?, expr.span)?;
+                    Reference {
+                        base: ReferenceBase::Place(pointer.place),
+                        modifier_stack: pointer.path,
+                    }
+                } else {
+                    interpret_reference_expr(primary, env).await?
+                };
+                reference.modifier_stack.push(ReferenceModifier::Selector(name.clone()));
+                reference
+            },
+            ExpressionVariant::Dereference{ref expr} => {
+                let pointer = dereference(interpret_expression(expr, env).await?, expr.span)?;
                 Reference {
-                    base: ReferenceBase::Underscore,
-                    modifier_stack: Vec::new(),
+                    base: ReferenceBase::Place(pointer.place),
+                    modifier_stack: pointer.path,
                 }
-            } else {
+            },
+            _ => {
                 Reference {
-                    base: ReferenceBase::Identifier(name.clone()),
+                    base: ReferenceBase::Value(interpret_expression(expr, env).await?),
                     modifier_stack: Vec::new(),
                 }
             }
-        },
-        ExpressionVariant::Index{ref primary,ref  index} => {
-            let mut reference = interpret_reference_expr(&primary, env);
-            let i = interpret_expression(&*index, env).get_integer().unwrap();
-            reference.modifier_stack.push(ReferenceModifier::Index(i));
-            reference
-        }
-        ExpressionVariant::Selector{ref primary,ref name} => {
-            let mut reference = interpret_reference_expr(&primary, env);
-            reference.modifier_stack.push(ReferenceModifier::Selector(name.clone()));
-            reference
-        },
+        };
+        Ok(reference)
+    })
+}
+
+/// Builds the value of a composite literal. The typechecker gave each element of a struct, array
+/// or slice literal its key, and whatever the elements leave out has its zero value.
+async fn interpret_composite_literal(kind: &Kind, elements: &[KeyedElement], env: &Env<'_, '_>) -> RuntimeResult<Value> {
+    match kind.resolve() {
+        Kind::Struct(ref fields) => {
+            let mut literal = value::zero_struct(fields);
+            for element in elements {
+                let v = interpret_expression(&element.value, env).await?;
+                if let (Value::Struct(ref mut values), ExpressionVariant::Identifier { name, .. }) =
+                    (&mut literal, &element_key(element).variant) {
+                    values.insert(name.clone(), v);
+                }
+            }
+            Ok(literal)
+        }
+        Kind::Array(ref base, length) => {
+            let values = literal_values(base, length as usize, elements, env).await?;
+            Ok(Value::Array(value::Array::new(values)))
+        }
+        Kind::Slice(ref base) => {
+            let length = elements.iter().map(|e| literal_index(e) + 1).max().unwrap_or(0);
+            let values = literal_values(base, length, elements, env).await?;
+            Ok(Value::Slice(value::Slice::new(values)))
+        }
         _ => {
-            Reference {
-                base: ReferenceBase::Value(interpret_expression(expr, env)),
-                modifier_stack: Vec::new(),
+            let map = builtins::make_map();
+            if let Value::Map(ref m) = map {
+                for element in elements {
+                    let k = interpret_expression(element_key(element), env).await?;
+                    check_key(&k, element_key(element).span)?;
+                    let v = interpret_expression(&element.value, env).await?;
+                    builtins::map_insert(m, k, v);
+                }
             }
+            Ok(map)
         }
     }
 }
 
-#[must_use]
-pub fn interpret_statement(statement: &StatementNode, env: & Env) -> Signal {
-    match &statement.variant {
-        StatementVariant::Empty => {
-            return Signal::None;
-        },
-        StatementVariant::Block(statement_node_vec) => {
-            let block_env = create_child_env(env);
-            for sn in statement_node_vec {
-                let s = interpret_statement(sn, &block_env);
-                if !s.is_none() {
-                    return s;
-                }
-            }
-            return Signal::None;
-        },
-        StatementVariant::Expression(expression_node) => {
-            interpret_expression(&expression_node, env);
-            return Signal::None;
-        },
-        StatementVariant::Assignment{lhs, rhs} => {
+/// The elements of an array or slice literal, in order of their indices
+async fn literal_values(kind: &Kind, length: usize, elements: &[KeyedElement], env: &Env<'_, '_>) -> RuntimeResult<Vec<Value>> {
+    let mut values = (0..length).map(|_| value::zero_value(kind)).collect::<Vec<_>>();
+    for element in elements {
+        values[literal_index(element)] = interpret_expression(&element.value, env).await?;
+    }
+    Ok(values)
+}
 
-            let mut references: Vec<Reference> = Vec::new();
-            for le in lhs {
-                let l_ref = interpret_reference_expr(le, env);
-                references.push(l_ref);
-            }
+fn element_key(element: &KeyedElement) -> &Expression {
+    element.key.as_ref().expect("The typechecker gives each literal element a key")
+}
 
-            let mut values: Vec<Value> = Vec::new();
-            for re in rhs {
-                let r_val = interpret_expression(re, env);
-                values.push(r_val);
-            }
-            assert!(references.len() == values.len());
+/// The index the typechecker gave an element of an array or slice literal
+fn literal_index(element: &KeyedElement) -> usize {
+    match element_key(element).variant {
+        ExpressionVariant::RawLiteral { ref value } => value.parse().unwrap(),
+        _ => panic!("The index of a literal element is an int literal"),
+    }
+}
 
-            for (l_ref, r_val) in references.into_iter().zip(values.into_iter()) {
-                l_ref.set_value(env, r_val);
+/// Evaluates `a[low:high]` or `a[low:high:max]`. Slicing an array or a slice gives a slice that
+/// shares its elements, and slicing a string gives the bytes between the indices.
+async fn interpret_slice_expression(primary: &Expression, low: &Option<Box<Expression>>,
+                              high: &Option<Box<Expression>>, max: &Option<Box<Expression>>,
+                              env: &Env<'_, '_>) -> RuntimeResult<Value> {
+    let is_array = matches!(primary.kind.resolve(), Kind::Array(..));
+    // The slice of an array refers to the array itself, not a copy of it
+    let v = if is_array {
+        let reference = interpret_reference_expr(primary, env).await?;
+        let pointer = address_of(reference, env)?;
+        let mut contents = None;
+        map_reference_value(&mut pointer.place.cell().borrow_mut(), &pointer.path, |v| {
+            if let Value::Array(ref array) = *v {
+                contents = Some(array.contents.clone());
             }
-            return Signal::None;
-        },
-        StatementVariant::OpAssignment{lhs, rhs, operator} => {
-            let l_ref = interpret_reference_expr(lhs, env);
-            let rval = interpret_expression(rhs, env);
-
-            l_ref.map_value(env, |lval| {
-                let result = compute_binary_operation(*operator, lval.clone(), rval);
-                *lval = result;
-            });
-            return Signal::None;
-        },
-        StatementVariant::VarDeclarations{declarations} => {
-            interpret_var_declarations(declarations, env);
-            return Signal::None;
-        },
-        StatementVariant::TypeDeclarations{..} => {
-            // nothing, we completely erase all types
-            return Signal::None;
-        },
-        StatementVariant::ShortVariableDeclaration{identifier_list, expression_list, is_assigning} => {
-            let mut temp: Vec<Value> = Vec::new();
-            for ee in expression_list {
-                let ev = interpret_expression(ee, env);
-                temp.push(ev);
-            }
-            assert!(identifier_list.len() == temp.len());
-            assert!(is_assigning.len() == temp.len());
-            for (i,ev) in temp.into_iter().enumerate() {
-                let name = &identifier_list[i];
-                if is_assigning[i] {
-
-                    env_set_var(&env, name, ev);
-                } else {
-                    env_declare_var(&env, name, ev);
-                }
+            Ok(())
+        })?;
+        let contents = contents.expect("Slicing something that isn't an array.");
+        Value::Slice(value::Slice { offset: 0, length: contents.len(), capacity: contents.len(), contents })
+    } else {
+        interpret_expression(primary, env).await?
+    };
+    let low = slice_index(low, env).await?;
+    let high = slice_index(high, env).await?;
+    let max = slice_index(max, env).await?;
+
+    let (length, capacity) = match v {
+        Value::String(ref s) => (s.len(), s.len()),
+        Value::Slice(ref slice) => (slice.length, slice.capacity),
+        _ => panic!("Slicing something that isn't an array, a slice or a string."),
+    };
+    // Arrays and strings have no capacity past their length, so Go reports their length
+    let bound = match v {
+        Value::Slice(_) if !is_array => "capacity",
+        _ => "length",
+    };
+    let out_of_range = |text: String| -> RuntimeResult<Value> {
+        Err(Panic::runtime_error(primary.span, &format!("slice bounds out of range {}", text)))
+    };
+    let low_value = low.unwrap_or(0);
+    let high_value = high.unwrap_or(length as i32);
+    let max_value = match max {
+        Some(max) => {
+            if max < 0 {
+                return out_of_range(format!("[::{}]", max));
+            }
+            if max as usize > capacity {
+                return out_of_range(format!("[::{}] with {} {}", max, bound, capacity));
+            }
+            if high_value < 0 {
+                return out_of_range(format!("[:{}:]", high_value));
+            }
+            if high_value > max {
+                return out_of_range(format!("[:{}:{}]", high_value, max));
+            }
+            if low_value < 0 {
+                return out_of_range(format!("[{}::]", low_value));
             }
-            return Signal::None;
+            if low_value > high_value {
+                return out_of_range(format!("[{}:{}:]", low_value, high_value));
+            }
+            max
+        }
+        None => {
+            if high_value < 0 {
+                return out_of_range(format!("[:{}]", high_value));
+            }
+            if high_value as usize > capacity {
+                return out_of_range(format!("[:{}] with {} {}", high_value, bound, capacity));
+            }
+            if low_value < 0 {
+                return out_of_range(format!("[{}:]", low_value));
+            }
+            if low_value > high_value {
+                return out_of_range(format!("[{}:{}]", low_value, high_value));
+            }
+            capacity as i32
+        }
+    };
+    let (low, high, max) = (low_value as usize, high_value as usize, max_value as usize);
+
+    Ok(match v {
+        // Strings are sliced by bytes, like in Go
+        Value::String(s) => Value::String(s[low..high].to_vec()),
+        Value::Slice(slice) => Value::Slice(value::Slice {
+            offset: slice.offset + low,
+            length: high - low,
+            capacity: max - low,
+            contents: slice.contents,
+        }),
+        _ => unreachable!(),
+    })
+}
+
+/// The value of one of the indices of a slice expression, if it is there
+async fn slice_index(index: &Option<Box<Expression>>, env: &Env<'_, '_>) -> RuntimeResult<Option<i32>> {
+    match index {
+        Some(index) => Ok(interpret_expression(index, env).await?.get_integer()),
+        None => Ok(None),
+    }
+}
+
+/// The pointer to what an addressable reference refers to
+fn address_of(reference: Reference, env: &Env) -> RuntimeResult<value::Pointer> {
+    let mut pointer = value::Pointer {
+        place: match reference.base {
+            ReferenceBase::Identifier(ref name) => value::Place::Variable(env_get_variable(env, name)),
+            ReferenceBase::Place(place) => place,
+            ReferenceBase::Value(v) => value::Place::Variable(Rc::new(RefCell::new(v))),
+            ReferenceBase::Underscore => panic!("Cannot take the address of underscore."),
         },
-        StatementVariant::IncDec{is_dec, expr} => {
-            let is_dec = *is_dec;
-            let r = interpret_reference_expr(expr, env);
-
-            r.map_value(env, |v| {
-                match v {
-                    Value::Int(i) => {
-                        *v = Value::Int(if is_dec { *i-1 } else { *i+1 });
-                    },
-                    Value::Float(f) => {
-                        *v = Value::Float(if is_dec { *f-1. } else { *f+1. });
-                    },
-                    _ => panic!("Shouldn't inc/dec this"),
+        path: Vec::new(),
+    };
+    for modifier in reference.modifier_stack {
+        // Pointers to the elements of a slice point into its contents, which outlive the slice
+        if let ReferenceModifier::Index(i, span) = modifier {
+            let mut element = None;
+            map_reference_value(&mut pointer.place.cell().borrow_mut(), &pointer.path, |v| {
+                if let Value::Slice(ref slice) = *v {
+                    check_bounds(i, slice.length, span)?;
+                    element = Some(value::Place::Element(slice.contents.clone(), slice.offset + i as usize));
                 }
-            });
-            return Signal::None;
-        },
-        StatementVariant::Print{exprs} => {
-            let refs: Vec<Reference> = exprs.iter().map(|expr| {
-                interpret_reference_expr(expr, env)
-            }).collect();
-
-            for v_ref in refs {
-                let value = v_ref.get_value(env);
-                print!("{}",value);
+                Ok(())
+            })?;
+            if let Some(place) = element {
+                pointer = value::Pointer { place, path: Vec::new() };
+                continue;
             }
-            return Signal::None;
-        },
-        StatementVariant::Println{exprs} => {
-            let len = exprs.len();
+        }
+        pointer.path.push(modifier);
+    }
+    Ok(pointer)
+}
 
-            let refs: Vec<Reference> = exprs.iter().map(|expr| {
-                interpret_reference_expr(expr, env)
-            }).collect();
+/// The pointer that a pointer value holds, or an error if it is nil
+fn dereference(pointer: Value, span: Span) -> RuntimeResult<value::Pointer> {
+    match pointer {
+        Value::Pointer(Some(pointer)) => Ok(pointer),
+        Value::Pointer(None) => Err(Panic::runtime_error(span, "invalid memory address or nil pointer dereference")),
+        _ => panic!("Dereferencing something that isn't a pointer."),
+    }
+}
 
-            for (i,refv) in refs.into_iter().enumerate() {
-                let value = refv.get_value(env);
-                print!("{}",value);
-                if i<len-1 {
-                    print!(" ");
-                }
+/// Writes the text printed by the program
+fn write_output(env: &Env, text: &[u8], span: Span) -> RuntimeResult<()> {
+    env.output.borrow_mut().write_all(text).map_err(|e| {
+        Panic::new(span, &format!("could not write the output: {}", e))
+    })
+}
+
+/// The channel a channel value holds, or None for a nil channel
+fn channel_of(v: &Value) -> Option<&scheduler::Channel> {
+    match v {
+        Value::Channel(channel) => channel.as_ref(),
+        _ => panic!("Using something that isn't a channel as one"),
+    }
+}
+
+/// The panic that stops a goroutine which was waiting on a channel. `status` is what Go shows the
+/// goroutine doing if it deadlocked.
+fn interrupted(interruption: scheduler::Interruption, status: &'static str, span: Span) -> Box<Panic> {
+    match interruption {
+        scheduler::Interruption::Closed => Panic::new(span, "send on closed channel"),
+        scheduler::Interruption::Deadlock => {
+            Panic::fatal(span, "all goroutines are asleep - deadlock!", status)
+        }
+    }
+}
+
+async fn send(channel: &Value, v: Value, env: &Env<'_, '_>, span: Span) -> RuntimeResult<()> {
+    let channel = channel_of(channel);
+    let status = if channel.is_some() { "chan send" } else { "chan send (nil chan)" };
+    env.scheduler.send(channel, v).await.map_err(|e| interrupted(e, status, span))
+}
+
+/// The value received from a channel, and whether it was sent rather than given by the channel
+/// being closed
+async fn receive(channel: &Value, env: &Env<'_, '_>, span: Span) -> RuntimeResult<(Value, bool)> {
+    let channel = channel_of(channel);
+    let status = if channel.is_some() { "chan receive" } else { "chan receive (nil chan)" };
+    env.scheduler.receive(channel).await.map_err(|e| interrupted(e, status, span))
+}
+
+fn close_channel(channel: &Value, env: &Env, span: Span) -> RuntimeResult<()> {
+    match channel_of(channel) {
+        Some(channel) => env.scheduler.close(channel).map_err(|message| Panic::new(span, message)),
+        None => Err(Panic::new(span, "close of nil channel")),
+    }
+}
+
+/// What the receive expression of a select case gives, once the case received `v`
+fn received_value(expr: &Expression, v: Value, ok: bool) -> Value {
+    match expr.variant {
+        ExpressionVariant::Receive { .. } => v,
+        ExpressionVariant::CommaOk { .. } => Value::Tuple(vec![v, Value::Bool(ok)]),
+        ExpressionVariant::ToInterface { ref expr } => to_interface(received_value(expr, v, ok), &expr.kind),
+        _ => panic!("A select case that isn't a send must be a receive"),
+    }
+}
+
+/// Assigns or declares the variables of the receive statement of the chosen select case. The
+/// expressions on the left are only evaluated once the case is chosen.
+async fn bind_received(comm: &StatementNode, v: Value, ok: bool, env: &Env<'_, '_>) -> RuntimeResult<()> {
+    // Sending cases have nothing to bind
+    if let StatementVariant::Send { .. } = comm.variant {
+        return Ok(());
+    }
+    let values = match received_value(comm.received_expression(), v, ok) {
+        Value::Tuple(values) => values,
+        v => vec![v],
+    };
+    match comm.variant {
+        StatementVariant::Assignment { ref lhs, .. } => {
+            let mut references = Vec::new();
+            for le in lhs {
+                references.push(interpret_reference_expr(le, env).await?);
             }
-            print!("\n");
-            return Signal::None;
-        },
-        StatementVariant::If{init, condition, if_branch, else_branch} => {
-            let is = interpret_statement(init, env);
-            if !is.is_none() {
-                return is;
+            for (l_ref, r_val) in references.into_iter().zip(values) {
+                l_ref.set_value(env, r_val)?;
             }
+        }
+        StatementVariant::ShortVariableDeclaration { ref identifier_list, ref is_assigning, .. } => {
+            declare_short_variables(identifier_list, is_assigning, values, env);
+        }
+        _ => {}
+    }
+    Ok(())
+}
 
-            let cv = interpret_expression(condition, env);
-            if let Value::Bool(b) = cv {
-                if b {
-                    let new_env = create_child_env(env);
-                    for sn in if_branch {
-                        let s = interpret_statement(sn, &new_env);
-                        if !s.is_none() {
-                            return s;
-                        }
-                    }
-                    return Signal::None;
-                } else if let Some(s) = else_branch{
-                    return interpret_statement(&(*s), env);
-                } else {
-                    // condition was false and there is no else branch
-                    return Signal::None;
+/// Runs statements until one of them gives a signal. A goto to one of their labels is followed,
+/// and other signals are given back.
+async fn interpret_statements(statements: &[StatementNode], env: &Env<'_, '_>) -> RuntimeResult<Signal> {
+    let mut i = 0;
+    while i < statements.len() {
+        match interpret_statement(&statements[i], env).await? {
+            Signal::None => i += 1,
+            Signal::Goto(label) => {
+                match statements.iter().position(|stmt| stmt.has_label(&label)) {
+                    Some(target) => i = target,
+                    None => return Ok(Signal::Goto(label)),
                 }
+            },
+            s => return Ok(s),
+        }
+    }
+    Ok(Signal::None)
+}
 
-            } else {
-                panic!("Condition passed to if statement is not a boolean type.");
-            }
-        },
-        StatementVariant::For{init, condition, post, body} => {
-            let is = interpret_statement(init, env);
-            if !is.is_none() {
-                return is;
-            }
-            'external: loop {
-                let new_env = create_child_env(env);
+pub fn interpret_statement<'x>(statement: &'x StatementNode, env: &'x Env) -> Interpretation<'x, Signal> {
+    interpret_labeled_statement(statement, None, env)
+}
+
+/// Runs a statement with its label, if it has one. Loops and switches need it to know which
+/// breaks and continues are theirs.
+fn interpret_labeled_statement<'x>(statement: &'x StatementNode, label: Option<&'x String>, env: &'x Env)
+    -> Interpretation<'x, Signal> {
+    Box::pin(async move {
+        match &statement.variant {
+            StatementVariant::Empty => {
+                Ok(Signal::None)
+            },
+            StatementVariant::Block(statement_node_vec) => {
+                let block_env = create_child_env(env);
+                interpret_statements(statement_node_vec, &block_env).await
+            },
+            StatementVariant::Expression(expression_node) => {
+                interpret_expression(expression_node, env).await?;
+                Ok(Signal::None)
+            },
+            StatementVariant::Assignment{lhs, rhs} => {
+
+                let mut references: Vec<Reference> = Vec::new();
+                for le in lhs {
+                    let l_ref = interpret_reference_expr(le, env).await?;
+                    references.push(l_ref);
+                }
+
+                let values = interpret_assigned_expressions(rhs, references.len(), env).await?;
+                assert!(references.len() == values.len());
+
+                for (l_ref, r_val) in references.into_iter().zip(values.into_iter()) {
+                    l_ref.set_value(env, r_val)?;
+                }
+                Ok(Signal::None)
+            },
+            StatementVariant::OpAssignment{lhs, rhs, operator} => {
+                let l_ref = interpret_reference_expr(lhs, env).await?;
+                let rval = interpret_expression(rhs, env).await?;
+
+                l_ref.map_value(env, |lval| {
+                    let result = compute_binary_operation(*operator, lval.clone(), rval, &lhs.kind,
+                                                          statement.span)?;
+                    *lval = result;
+                    Ok(())
+                })?;
+                Ok(Signal::None)
+            },
+            StatementVariant::VarDeclarations{declarations} => {
+                interpret_var_declarations(declarations, env).await?;
+                Ok(Signal::None)
+            },
+            StatementVariant::TypeDeclarations{..} => {
+                // nothing, we completely erase all types
+                Ok(Signal::None)
+            },
+            StatementVariant::ConstDeclarations{..} => {
+                // nothing, constants were folded by the typechecker
+                Ok(Signal::None)
+            },
+            StatementVariant::ShortVariableDeclaration{identifier_list, expression_list, is_assigning} => {
+                let temp = interpret_assigned_expressions(expression_list, identifier_list.len(), env).await?;
+                declare_short_variables(identifier_list, is_assigning, temp, env);
+                Ok(Signal::None)
+            },
+            StatementVariant::IncDec{is_dec, expr} => {
+                let is_dec = *is_dec;
+                let r = interpret_reference_expr(expr, env).await?;
+
+                r.map_value(env, |v| {
+                    let result = match v {
+                        Value::Int(i) => {
+                            Value::Int(if is_dec { *i-1 } else { *i+1 })
+                        },
+                        Value::Float(f) => {
+                            Value::Float(if is_dec { *f-1. } else { *f+1. })
+                        },
+                        _ => panic!("Shouldn't inc/dec this"),
+                    };
+                    *v = value::fit_kind(result, &expr.kind);
+                    Ok(())
+                })?;
+                Ok(Signal::None)
+            },
+            StatementVariant::Print{exprs} => {
+                let text = print_text(exprs, false, env).await?;
+                write_output(env, &text, statement.span)?;
+                Ok(Signal::None)
+            },
+            StatementVariant::Println{exprs} => {
+                let text = print_text(exprs, true, env).await?;
+                write_output(env, &text, statement.span)?;
+                Ok(Signal::None)
+            },
+            StatementVariant::Defer(call) => {
+                let deferred = defer_call(call, env).await?;
+                env.defers.borrow_mut().push(deferred);
+                Ok(Signal::None)
+            },
+            StatementVariant::Go(call) => {
+                // The goroutine runs the call like a deferred one, in the global scope
+                let deferred = defer_call(call, env).await?;
+                env.scheduler.spawn(deferred);
+                Ok(Signal::None)
+            },
+            StatementVariant::Send{channel, value} => {
+                let cv = interpret_expression(channel, env).await?;
+                let v = interpret_expression(value, env).await?;
+                send(&cv, v, env, statement.span).await?;
+                Ok(Signal::None)
+            },
+            StatementVariant::If{init, condition, if_branch, else_branch} => {
+                let is = interpret_statement(init, env).await?;
+                if !is.is_none() {
+                    return Ok(is);
+                }
 
-                let looping =
-                if let Some(cond) = condition {
-                    let cv = interpret_expression(cond, env);
-                    if let Value::Bool(b) = cv {
-                        b
+                let cv = interpret_expression(condition, env).await?;
+                if let Value::Bool(b) = cv {
+                    if b {
+                        let new_env = create_child_env(env);
+                        interpret_statements(if_branch, &new_env).await
+                    } else if let Some(s) = else_branch{
+                        interpret_statement(s, env).await
                     } else {
-                        panic!("Condition passed to if statement is not a boolean type.");
+                        // condition was false and there is no else branch
+                        Ok(Signal::None)
                     }
+
                 } else {
-                    true
-                };
+                    panic!("Condition passed to if statement is not a boolean type.");
+                }
+            },
+            StatementVariant::For{init, condition, post, body} => {
+                let is = interpret_statement(init, env).await?;
+                if !is.is_none() {
+                    return Ok(is);
+                }
+                'external: loop {
+                    let new_env = create_child_env(env);
+
+                    let looping =
+                    if let Some(cond) = condition {
+                        let cv = interpret_expression(cond, env).await?;
+                        if let Value::Bool(b) = cv {
+                            b
+                        } else {
+                            panic!("Condition passed to if statement is not a boolean type.");
+                        }
+                    } else {
+                        true
+                    };
 
-                if looping {
-                    for sn in body {
-                        let s = interpret_statement(sn, &new_env);
+                    if looping {
+                        // A continue still runs the post statement
+                        let s = interpret_statements(body, &new_env).await?;
                         match s {
                             Signal::None => {},
-                            Signal::Return(_) => {
-                                return s;
-                            },
-                            Signal::Break => {
+                            Signal::Break(ref l) if is_jump_target(l, label) => {
                                 break 'external;
                             },
-                            Signal::Continue => {
-                                continue 'external;
+                            Signal::Continue(ref l) if is_jump_target(l, label) => {},
+                            _ => {
+                                return Ok(s);
                             },
                         }
+                        let ps = interpret_statement(post, env).await?;
+                        if !ps.is_none() {
+                            return Ok(ps);
+                        }
+                    } else {
+                        break;
                     }
-                    let ps = interpret_statement(post, &env);
-                    if !ps.is_none() {
-                        return ps;
+                }
+                Ok(Signal::None)
+            },     
+            StatementVariant::ForRange{key, value, expr, body} => {
+                // The expression is evaluated once. Arrays are copied by this, but the elements of a
+                // slice are read as the loop goes. The keys of a map are the ones it has when the loop
+                // starts, and those deleted during the loop are skipped.
+                let rv = interpret_expression(expr, env).await?;
+                // Channels give the values received until they are closed
+                if let Value::Channel(_) = rv {
+                    loop {
+                        let (v, ok) = receive(&rv, env, expr.span).await?;
+                        if !ok {
+                            break;
+                        }
+                        let new_env = create_child_env(env);
+                        match key {
+                            Some(name) if name != "_" => env_declare_var(&new_env, name, v),
+                            _ => {},
+                        }
+                        let s = interpret_statements(body, &new_env).await?;
+                        match s {
+                            Signal::None => {},
+                            Signal::Break(ref l) if is_jump_target(l, label) => break,
+                            Signal::Continue(ref l) if is_jump_target(l, label) => {},
+                            _ => return Ok(s),
+                        }
                     }
-                } else {
-                    break;
+                    return Ok(Signal::None);
                 }
-            }
-            return Signal::None;
-        },     
-        StatementVariant::Switch{init, expr, body} => {
-            // TODO: check order of evaluation when using init statement
-            let is = interpret_statement(init, env);
-            if !is.is_none() {
-                // Technically it should alway be None...
-                return is;
-            }
+                let runes: Vec<(usize, char)> = match &rv {
+                    Value::String(s) => util::decode_runes(s),
+                    _ => Vec::new(),
+                };
+                let keys: Vec<Value> = match &rv {
+                    Value::Map(m) => builtins::map_keys(m),
+                    _ => Vec::new(),
+                };
+                let count = match &rv {
+                    Value::String(_) => runes.len(),
+                    Value::Array(a) => a.len(),
+                    Value::Slice(s) => s.length,
+                    Value::Map(_) => keys.len(),
+                    _ => panic!("Cannot range over {:?}", rv),
+                };
 
-            let lv = if let Some(exp_n) = expr {
-                interpret_expression(&*exp_n, env)
-            } else {
-                Value::Bool(true)
-            };
-            let mut found = false;
-            'external: for CaseClause{line_number: _, switch_case, statements} in body {
-                match switch_case {
-                    SwitchCase::Default => {},
-                    SwitchCase::Cases(vec_expr) => {
-                        for expr in vec_expr {
-                            let rv = interpret_expression(&expr, env);
+                'range: for i in 0..count {
+                    let (kv, vv) = match &rv {
+                        Value::String(_) => {
+                            let (offset, c) = runes[i];
+                            (Value::Int(offset as i128), Value::Int(c as i128))
+                        },
+                        Value::Array(a) => (Value::Int(i as i128), a.contents[i].borrow().clone()),
+                        Value::Slice(s) => (Value::Int(i as i128), s.element(i).borrow().clone()),
+                        Value::Map(m) => match builtins::map_lookup(m, &keys[i]) {
+                            Some(v) => (keys[i].clone(), v),
+                            None => continue 'range,
+                        },
+                        _ => unreachable!(),
+                    };
+
+                    let new_env = create_child_env(env);
+                    match key {
+                        Some(name) if name != "_" => env_declare_var(&new_env, name, kv),
+                        _ => {},
+                    }
+                    match value {
+                        Some(name) if name != "_" => env_declare_var(&new_env, name, vv),
+                        _ => {},
+                    }
 
+                    let s = interpret_statements(body, &new_env).await?;
+                    match s {
+                        Signal::None => {},
+                        Signal::Break(ref l) if is_jump_target(l, label) => {
+                            break 'range;
+                        },
+                        Signal::Continue(ref l) if is_jump_target(l, label) => {
+                            continue 'range;
+                        },
+                        _ => {
+                            return Ok(s);
+                        },
+                    }
+                }
+                Ok(Signal::None)
+            },
+            StatementVariant::Switch{init, expr, body} => {
+                // TODO: check order of evaluation when using init statement
+                let is = interpret_statement(init, env).await?;
+                if !is.is_none() {
+                    // Technically it should alway be None...
+                    return Ok(is);
+                }
+
+                let lv = if let Some(exp_n) = expr {
+                    interpret_expression(exp_n, env).await?
+                } else {
+                    Value::Bool(true)
+                };
+                // The first clause with a matching case is run, or the default clause if none match
+                let mut selected = None;
+                'search: for (i, case_clause) in body.iter().enumerate() {
+                    if let SwitchCase::Cases(vec_expr) = &case_clause.switch_case {
+                        for expr in vec_expr {
+                            let rv = interpret_expression(expr, env).await?;
                             if lv == rv {
-                                found = true;
-
-                                let new_env = create_child_env(env);
-                                for stmt in statements {
-                                    let s = interpret_statement(stmt, &new_env);
-                                    match s {
-                                        Signal::None => {},
-                                        Signal::Return(_) => {
-                                            return s;
-                                        },
-                                        Signal::Break => {
-                                            break 'external;
-                                        },
-                                        Signal::Continue => {
-                                            return s;
-                                        },
-                                    }
-                                }
-                                break 'external;
+                                selected = Some(i);
+                                break 'search;
                             }
                         }
                     }
                 }
-            }
+                if selected.is_none() {
+                    selected = body.iter().position(|case_clause| match case_clause.switch_case {
+                        SwitchCase::Default => true,
+                        SwitchCase::Cases(..) | SwitchCase::Kinds(..) | SwitchCase::Comm(..) => false,
+                    });
+                }
+
+                if let Some(mut i) = selected {
+                    'external: loop {
+                        let new_env = create_child_env(env);
+                        let s = interpret_statements(&body[i].statements, &new_env).await?;
+                        match s {
+                            Signal::None => {},
+                            Signal::Break(ref l) if is_jump_target(l, label) => {
+                                break 'external;
+                            },
+                            _ => {
+                                return Ok(s);
+                            },
+                        }
+                        if !body[i].falls_through() {
+                            break 'external;
+                        }
+                        i += 1;
+                    }
+                }
+                Ok(Signal::None)
+            },
+            StatementVariant::TypeSwitch{init, binding, expr, body} => {
+                let is = interpret_statement(init, env).await?;
+                if !is.is_none() {
+                    return Ok(is);
+                }
 
+                let v = interpret_expression(expr, env).await?;
+                // The first clause with the kind of the value is run, or the default clause
+                let selected = body.iter().find(|case_clause| match case_clause.switch_case {
+                    SwitchCase::Kinds(ref kinds) => {
+                        kinds.iter().any(|kind| has_kind(&v, &kind.evaluated_kind))
+                    }
+                    _ => false,
+                }).or_else(|| body.iter().find(|case_clause| matches!(case_clause.switch_case, SwitchCase::Default)));
 
-            if !found {
-                'external: for CaseClause{line_number: _, switch_case, statements} in body {
-                    match switch_case {
-                        SwitchCase::Cases{..} => {},
-                        SwitchCase::Default => {
-                            let new_env = create_child_env(env);
-                            for stmt in statements {
-                                let s = interpret_statement(stmt, &new_env);
-                                match s {
-                                    Signal::None => {},
-                                    Signal::Return(_) => {
-                                        return s;
-                                    },
-                                    Signal::Break => {
-                                        break 'external;
-                                    },
-                                    Signal::Continue => {
-                                        return s;
-                                    },
-                                }
+                if let Some(case_clause) = selected {
+                    let new_env = create_child_env(env);
+                    if let (Some(_), Some(name)) = (binding, &case_clause.binding) {
+                        // A case with a single kind gives the variable the value of that kind
+                        let bound = match case_clause.switch_case {
+                            SwitchCase::Kinds(ref kinds) if kinds.len() == 1 &&
+                                !matches!(kinds[0].evaluated_kind, Kind::Nil) => {
+                                assert_kind(v.clone(), &expr.kind, &kinds[0].evaluated_kind).unwrap()
+                            }
+                            _ => v.clone(),
+                        };
+                        env_declare_var(&new_env, name, bound);
+                    }
+                    let s = interpret_statements(&case_clause.statements, &new_env).await?;
+                    match s {
+                        Signal::Break(ref l) if is_jump_target(l, label) => {},
+                        _ => return Ok(s),
+                    }
+                }
+                Ok(Signal::None)
+            },
+            StatementVariant::Select{body} => {
+                // The channels and the values to send are all evaluated before a case is chosen
+                let mut cases = Vec::new();
+                let mut clauses = Vec::new();
+                for (i, case_clause) in body.iter().enumerate() {
+                    if let SwitchCase::Comm(ref comm) = case_clause.switch_case {
+                        cases.push(match comm.variant {
+                            StatementVariant::Send { ref channel, ref value } => {
+                                let cv = interpret_expression(channel, env).await?;
+                                let v = interpret_expression(value, env).await?;
+                                scheduler::Case::Send(channel_of(&cv).cloned(), v)
                             }
+                            _ => {
+                                let channel = comm.received_expression().received_channel();
+                                let cv = interpret_expression(channel, env).await?;
+                                scheduler::Case::Receive(channel_of(&cv).cloned())
+                            }
+                        });
+                        clauses.push(i);
+                    }
+                }
+                let has_default = body.iter().any(|case_clause| matches!(case_clause.switch_case, SwitchCase::Default));
+                let status = if cases.is_empty() { "select (no cases)" } else { "select" };
+                let chosen = env.scheduler.select(&cases, has_default).await
+                    .map_err(|e| interrupted(e, status, statement.span))?;
+
+                let new_env = create_child_env(env);
+                let case_clause = match chosen {
+                    Some((i, v, ok)) => {
+                        let case_clause = &body[clauses[i]];
+                        if let SwitchCase::Comm(ref comm) = case_clause.switch_case {
+                            bind_received(comm, v, ok, &new_env).await?;
                         }
+                        case_clause
                     }
+                    None => body.iter().find(|case_clause| matches!(case_clause.switch_case, SwitchCase::Default)).unwrap(),
+                };
+                let s = interpret_statements(&case_clause.statements, &new_env).await?;
+                match s {
+                    Signal::Break(ref l) if is_jump_target(l, label) => {},
+                    _ => return Ok(s),
                 }
-            }
-            return Signal::None;
-        },
+                Ok(Signal::None)
+            },
 
-        StatementVariant::Break => {
-            return Signal::Break;
-        },
+            StatementVariant::Break(l) => {
+                Ok(Signal::Break(l.clone()))
+            },
 
-        StatementVariant::Continue => {
-            return Signal::Continue;
-        },
+            StatementVariant::Continue(l) => {
+                Ok(Signal::Continue(l.clone()))
+            },
 
-        StatementVariant::Return(opt_expr) => {
-            if let Some(expr) = opt_expr {
-                let v = interpret_expression(&*expr, env);
-                return Signal::Return(v);
-            } else {
-                return Signal::Return(Value::Void);
-            }
-        },
-    }
+            StatementVariant::Goto(l) => {
+                Ok(Signal::Goto(l.clone()))
+            },
+
+            // The label is only needed by loops and switches
+            StatementVariant::Labeled{label, statement} => {
+                interpret_labeled_statement(statement, Some(label), env).await
+            },
+
+            // The switch moves on to the next clause after one that ends in `fallthrough`
+            StatementVariant::Fallthrough => {
+                Ok(Signal::None)
+            },
+
+            StatementVariant::Return(exprs) => {
+                // A single expression can also be a call giving all the results as a tuple
+                let mut values = Vec::new();
+                for expr in exprs {
+                    values.push(interpret_expression(expr, env).await?);
+                }
+                Ok(Signal::Return(match values.len() {
+                    0 => Value::Void,
+                    1 => values.pop().unwrap(),
+                    _ => Value::Tuple(values),
+                }))
+            },
+        }
 
+    })
 }
 
 pub fn create_child_env<'a,'b>(env: &'a Env<'a,'b>) -> Env<'a,'b> {
     return Env{
         parent: Some(env),
         entries: RefCell::new(HashMap::new()),
+        output: env.output.clone(),
+        defers: env.defers.clone(),
+        scheduler: env.scheduler.clone(),
+    };
+}
+
+/// Declares the variables of a short variable declaration, or assigns those already declared
+fn declare_short_variables(identifier_list: &[String], is_assigning: &[bool], values: Vec<Value>, env: &Env) {
+    assert!(identifier_list.len() == values.len());
+    assert!(is_assigning.len() == values.len());
+    for (i,ev) in values.into_iter().enumerate() {
+        let name = &identifier_list[i];
+        if is_assigning[i] {
+            env_set_var(env, name, ev);
+        } else {
+            env_declare_var(env, name, ev);
+        }
+    }
+}
+
+/// Evaluates the function and the arguments of a deferred call, or of the call a go statement
+/// starts
+async fn defer_call(call: &StatementNode, env: &Env<'_, '_>) -> RuntimeResult<Deferred> {
+    Ok(match &call.variant {
+        StatementVariant::Expression(expr) => match &expr.variant {
+            ExpressionVariant::FunctionCall { primary, arguments, spread } => {
+                let (f, evaled_args) = interpret_call_operands(primary, arguments, *spread, env).await?;
+                Deferred::Call(f, evaled_args, expr.span)
+            }
+            ExpressionVariant::Delete { map, key } => {
+                let mv = interpret_expression(map, env).await?;
+                let kv = interpret_expression(key, env).await?;
+                Deferred::Delete(mv, kv, call.span)
+            }
+            ExpressionVariant::Panic { expr: value } => {
+                let v = interpret_expression(value, env).await?;
+                Deferred::Panic(Panic::new(expr.span, &panic_value_text(&v, &value.kind)))
+            }
+            ExpressionVariant::Close { channel } => {
+                Deferred::Close(interpret_expression(channel, env).await?, expr.span)
+            }
+            _ => panic!("Deferring something that isn't a call"),
+        },
+        StatementVariant::Print{exprs} => Deferred::Print(print_text(exprs, false, env).await?, call.span),
+        StatementVariant::Println{exprs} => Deferred::Print(print_text(exprs, true, env).await?, call.span),
+        _ => panic!("Deferring something that isn't a call"),
+    })
+}
+
+async fn run_deferred(deferred: Deferred, env: &Env<'_, '_>) -> RuntimeResult<()> {
+    match deferred {
+        Deferred::Call(f, args, span) => call_function(&f, env, args, span).await.map(|_| ()),
+        Deferred::Delete(m, k, span) => {
+            check_key(&k, span)?;
+            builtins::map_delete(&m, &k);
+            Ok(())
+        },
+        Deferred::Print(text, span) => write_output(env, &text, span),
+        Deferred::Panic(panic) => Err(panic),
+        Deferred::Close(channel, span) => close_channel(&channel, env, span),
+    }
+}
+
+/// The function value and the arguments of a call, in the order Go evaluates them. The trailing
+/// arguments of a variadic function are packed into a new slice.
+async fn interpret_call_operands(primary: &Expression, arguments: &[Expression], spread: bool, env: &Env<'_, '_>)
+    -> RuntimeResult<(value::Function, Box<[Value]>)> {
+    let f = match interpret_expression(primary, env).await? {
+        Value::Function(f) => f,
+        _ => panic!("Calling something that isn't a function"),
     };
+
+    let mut refs = Vec::new();
+    for arg in arguments {
+        refs.push(interpret_reference_expr(arg, env).await?);
+    }
+
+    let mut evaled_args = refs.into_iter().map(|r| {
+        r.get_value(env)
+    }).collect::<RuntimeResult<Vec<Value>>>()?;
+
+    if let Some(fixed) = primary.packed_arguments(spread) {
+        let packed = evaled_args.split_off(fixed);
+        evaled_args.push(Value::Slice(value::Slice::new(packed)));
+    }
+
+    Ok((f, evaled_args.into()))
+}
+
+/// How panic shows a value. Values of defined types show the type, like `main.celsius(20)`.
+fn panic_value_text(v: &Value, kind: &Kind) -> String {
+    match *kind {
+        Kind::Defined(ref definition) => format!("main.{}({})", definition.borrow().name, v),
+        _ => format!("{}", v),
+    }
+}
+
+/// What a print or println statement writes
+async fn print_text(exprs: &[Expression], is_println: bool, env: &Env<'_, '_>) -> RuntimeResult<Vec<u8>> {
+    let mut refs = Vec::new();
+    for expr in exprs {
+        refs.push(interpret_reference_expr(expr, env).await?);
+    }
+
+    let mut text = Vec::new();
+    for (i,refv) in refs.into_iter().enumerate() {
+        // Strings are written as the bytes they hold
+        match refv.get_value(env)? {
+            Value::String(s) => text.extend_from_slice(&s),
+            value => write!(text, "{}", value).unwrap(),
+        }
+        if is_println && i<exprs.len()-1 {
+            text.push(b' ');
+        }
+    }
+    if is_println {
+        text.push(b'\n');
+    }
+    Ok(text)
+}
+
+/// The scope of the global variables and functions, which all the others end in
+fn root_env<'a,'b>(env: &'a Env<'a,'b>) -> &'a Env<'a,'b> {
+    match env.parent {
+        Some(parent) => root_env(parent),
+        None => env,
+    }
+}
+
+/// The variables that a function literal made in the given scope can refer to, apart from the
+/// global ones. Identifiers are renamed to be unique, so the scopes can be flattened.
+fn capture_variables(env: &Env) -> HashMap<String, Variable> {
+    let mut captured = HashMap::new();
+    let mut scope = Some(env);
+    while let Some(e) = scope {
+        if e.parent.is_none() {
+            break;
+        }
+        for (name, declaration) in e.entries.borrow().iter() {
+            if let Declaration::Variable(ref v) = *declaration {
+                captured.entry(name.clone()).or_insert_with(|| v.clone());
+            }
+        }
+        scope = e.parent;
+    }
+    captured
+}
+
+/// Calls a function value. The body runs in a scope of its own, which only sees the global scope
+/// and, for closures, the variables they captured.
+fn call_function<'x>(f: &'x value::Function, env: &'x Env, args: Box<[Value]>, span: Span)
+    -> Interpretation<'x, Value> {
+    Box::pin(async move {
+        let root = root_env(env);
+        let result = match f {
+            value::Function::Nil => {
+                Err(Panic::runtime_error(span, "invalid memory address or nil pointer dereference"))
+            }
+            value::Function::TopLevel(name) => {
+                interpret_function(env_get_function(root, name).unwrap(), root, args).await
+            }
+            value::Function::Method(name, receiver) => {
+                let mut args: Vec<Value> = args.into();
+                args.insert(0, (**receiver).clone());
+                interpret_function(env_get_function(root, name).unwrap(), root, args.into()).await
+            }
+            value::Function::Closure(function, captured) => {
+                let closure_env = Env {
+                    parent: Some(root),
+                    entries: RefCell::new(captured.iter()
+                        .map(|(name, v)| (name.clone(), Declaration::Variable(v.clone())))
+                        .collect()),
+                    output: env.output.clone(),
+                    defers: env.defers.clone(),
+                    scheduler: env.scheduler.clone(),
+                };
+                interpret_function(function, &closure_env, args).await
+            }
+        };
+        // A panic goes on from the call
+        result.map_err(|mut panic| {
+            panic.position = span;
+            panic
+        })
+    })
 }
 
-pub fn interpret_function<'a,'b>(f: &ast::Function, tl_env: &'a Env<'a,'b>, args: Box<[Value]>) -> Value {
+pub async fn interpret_function<'a,'b>(f: &ast::Function, tl_env: &'a Env<'a,'b>, args: Box<[Value]>)
+    -> RuntimeResult<Value> {
     let mut env = create_child_env(tl_env);
+    // Each call has its own deferred calls
+    env.defers = Rc::new(RefCell::new(Vec::new()));
 
     let mut flattened_parameters = Vec::new();
+    if let Some(ref receiver) = f.receiver {
+        flattened_parameters.push(receiver.name.clone());
+    }
     for field in &f.parameters {
         for ident in &field.identifiers {
             flattened_parameters.push(ident.clone());
@@ -868,34 +1999,112 @@ pub fn interpret_function<'a,'b>(f: &ast::Function, tl_env: &'a Env<'a,'b>, args
     let args_huh: Vec<Value> = args.into(); // WHY? is it a bug that I need to do this?
     for (arg, param) in args_huh.into_iter().zip(flattened_parameters.into_iter()) {
         // Why is arg a &Value and not a Value when I use into_iter directly on the Box<[Value]>??????
-        env.entries.borrow_mut().insert(param, Declaration::Variable(arg));
+        env_declare_var(&env, &param, arg);
+    }
+
+    for field in &f.results {
+        for ident in &field.identifiers {
+            env_declare_var(&env, ident, value::zero_value(&field.kind.evaluated_kind));
+        }
+    }
+
+    let result = interpret_body(&f.body, &mut env).await;
+    run_deferred_calls(f, &env, result).await.map_err(|mut panic| {
+        panic.frames.push((f.trace_name(), panic.position));
+        panic
+    })
+}
+
+async fn interpret_body(body: &[StatementNode], env: &mut Env<'_, '_>) -> RuntimeResult<Value> {
+    match interpret_statements(body, env).await? {
+        Signal::None => Ok(Value::Void),
+        Signal::Return(v) => Ok(v),
+        Signal::Continue(..) | Signal::Break(..) | Signal::Goto(..)
+            => {panic!("continue, break or goto outside of its target")},
+    }
+}
+
+/// Runs the calls deferred by a function, the last one first, after its body returned or
+/// stopped at a runtime error. They still run after an error in one of them, and the last error
+/// is the one reported. Deferred calls can change the named results, so those are what the
+/// function gives back.
+async fn run_deferred_calls(f: &ast::Function, env: &Env<'_, '_>, result: RuntimeResult<Value>)
+    -> RuntimeResult<Value> {
+    // Fatal errors don't run them
+    if env.defers.borrow().is_empty() || result.as_ref().err().is_some_and(|panic| panic.fatal) {
+        return result;
     }
 
-    for statement_node in &f.body {
-        let s = interpret_statement(statement_node,&mut env);
-        match s {
-            Signal::None => {},
-            Signal::Return(v) => {return v},
-            Signal::Continue | Signal::Break
-                => {panic!("continue or break outside of loop")},
+    let result_names: Vec<&String> = f.results.iter()
+        .flat_map(|field| field.identifiers.iter())
+        .collect();
+    if let Ok(ref v) = result {
+        match *v {
+            Value::Tuple(ref values) => {
+                for (name, v) in result_names.iter().zip(values.iter()) {
+                    assign(&mut env_get_variable(env, name).borrow_mut(), v.clone());
+                }
+            }
+            Value::Void => {},
+            ref v => if let Some(name) = result_names.first() {
+                assign(&mut env_get_variable(env, name).borrow_mut(), v.clone());
+            },
+        }
+    }
+
+    let mut result = result;
+    loop {
+        // The deferred call can defer calls of its own, in its own frame
+        let deferred = env.defers.borrow_mut().pop();
+        let outcome = match deferred {
+            Some(deferred) => run_deferred(deferred, env).await,
+            None => break,
+        };
+        if let Err(e) = outcome {
+            let fatal = e.fatal;
+            result = Err(e);
+            if fatal {
+                break;
+            }
         }
     }
-    return Value::Void;
+
+    if result.is_err() || result_names.is_empty() {
+        return result;
+    }
+    let mut values = result_names.iter()
+        .map(|name| env_get_variable(env, name).borrow().clone())
+        .collect::<Vec<Value>>();
+    Ok(if values.len() == 1 { values.pop().unwrap() } else { Value::Tuple(values) })
 }
 
 
-pub fn interpret<'b>(root: &'b Program){
-    let mut env = Env {parent:None, entries:RefCell::new(HashMap::new())};
+/// Runs the (typechecked) program, writing what it prints to `output`. Stops at the first
+/// runtime error, in any goroutine. The seed decides which goroutine runs when one blocks.
+pub fn interpret<'b>(root: &'b Program, output: &'b mut dyn Write, seed: u32) -> RuntimeResult<()> {
+    let scheduler = Rc::new(Scheduler::new(seed));
+    let env = Env {
+        parent: None,
+        entries: RefCell::new(HashMap::new()),
+        output: Rc::new(RefCell::new(output)),
+        defers: Rc::new(RefCell::new(Vec::new())),
+        scheduler: scheduler.clone(),
+    };
     {
-        env.entries.borrow_mut().insert("true".to_string(),Declaration::Variable(Value::Bool(true)));
-        env.entries.borrow_mut().insert("false".to_string(),Declaration::Variable(Value::Bool(false)));
+        env_declare_var(&env, "true", Value::Bool(true));
+        env_declare_var(&env, "false", Value::Bool(false));
     }
 
-    let init_functions = init_top_level(&env, root);
+    // The program ends when main returns, whatever the other goroutines are doing
+    scheduler.run(Box::pin(interpret_main(root, &env)), |deferred| Box::pin(run_deferred(deferred, &env)))
+}
+
+async fn interpret_main<'a,'b>(root: &'b Program, env: &'a Env<'a,'b>) -> RuntimeResult<()> {
+    let init_functions = init_top_level(env, root).await?;
 
     for f in init_functions.iter() {
         let empty_args: Box<[Value]> = Vec::new().into();
-        interpret_function(f, &mut env, empty_args);
+        interpret_function(f, env, empty_args).await?;
     }
 
     let main = 
@@ -906,7 +2115,7 @@ pub fn interpret<'b>(root: &'b Program){
     };
 
     let empty_args: Box<[Value]> = Vec::new().into();
-    interpret_function(main, &env, empty_args);
-
+    interpret_function(main, env, empty_args).await?;
 
+    Ok(())
 }
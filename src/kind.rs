@@ -1,28 +1,120 @@
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::fmt;
+use std::collections::HashMap;
+use crate::span::Span;
 
 #[repr(C)]
 #[derive(Debug,Copy,Clone,Eq,PartialEq)]
 pub enum BasicKind {
     Int = 0,
     Float = 1,
+    // Only the kind of untyped rune constants. Typed runes are int32, see `typed`.
     Rune = 2,
     String = 3,
-    Bool = 4
+    Bool = 4,
+    // The sized numeric kinds only come from type names, so the parser never makes literals of
+    // them. `byte` is uint8.
+    Int8 = 5,
+    Int16 = 6,
+    Int32 = 7,
+    Int64 = 8,
+    Uint8 = 9,
+    Uint16 = 10,
+    Uint32 = 11,
+    Uint64 = 12,
+    Uintptr = 13,
+    Float32 = 14,
+}
+
+impl BasicKind {
+    /// The number of bits of an integer kind, whose values wrap around to fit in them. Like
+    /// runes, `int` and `uintptr` are 32 bits wide in GoLite.
+    pub fn integer_bits(self) -> Option<u32> {
+        use self::BasicKind::*;
+        match self {
+            Int8 | Uint8 => Some(8),
+            Int16 | Uint16 => Some(16),
+            Int | Rune | Int32 | Uint32 | Uintptr => Some(32),
+            Int64 | Uint64 => Some(64),
+            Float | Float32 | String | Bool => None,
+        }
+    }
+
+    pub fn is_unsigned(self) -> bool {
+        use self::BasicKind::*;
+        matches!(self, Uint8 | Uint16 | Uint32 | Uint64 | Uintptr)
+    }
+
+    pub fn is_integer(self) -> bool {
+        self.integer_bits().is_some()
+    }
+
+    pub fn is_float(self) -> bool {
+        self == BasicKind::Float || self == BasicKind::Float32
+    }
+
+    /// The kind that values of this kind have once they are typed. `rune` is an alias of int32,
+    /// like `byte` is of uint8.
+    pub fn typed(self) -> BasicKind {
+        if self == BasicKind::Rune { BasicKind::Int32 } else { self }
+    }
 }
 
 #[derive(Debug,Clone)]
 pub struct Field {
     pub name: String,
-    pub kind: Kind
+    pub kind: Kind,
+    /// A field declared as just a type `T` or `*T`, which is named `T`. The fields and methods of
+    /// the embedded value are promoted to the struct.
+    pub embedded: bool,
+}
+
+/// What a selector finds in a value: a field, a method with its receiver, or the method of an
+/// interface, which is called on the value the interface holds
+#[derive(Debug,Clone)]
+pub enum Selection {
+    Field(Kind),
+    Method(Method),
+    InterfaceMethod(Kind),
+}
+
+/// The result of looking up a selector, see Kind::lookup
+#[derive(Debug,Clone)]
+pub enum Lookup {
+    /// The embedded fields leading to what was found, from the outermost one, and what it is
+    Found(Vec<Field>, Selection),
+    /// Several fields or methods with the name were found at the shallowest depth
+    Ambiguous,
+    Missing,
 }
 
 #[derive(Debug,Clone)]
 pub struct Definition {
-    pub line_number: u32,
+    pub span: Span,
     pub name: String,
-    pub kind: Kind
+    pub kind: Kind,
+    /// The methods declared on the type so far, by name
+    pub methods: HashMap<String, Method>,
+}
+
+#[derive(Debug,Clone)]
+pub struct Method {
+    /// The renamed function implementing the method, which takes the receiver first
+    pub function: String,
+    pub params: Vec<Kind>,
+    pub return_kind: Option<Kind>,
+    pub variadic: bool,
+    /// Methods with a pointer receiver can change the value they are called on
+    pub pointer_receiver: bool,
+}
+
+/// A method that the values of an interface kind have
+#[derive(Debug,Clone)]
+pub struct InterfaceMethod {
+    pub name: String,
+    /// The kind of the method without its receiver, a function kind
+    pub kind: Kind,
 }
 
 #[derive(Debug,Clone)]
@@ -33,8 +125,26 @@ pub enum Kind {
     Array(Box<Kind>,u32),
     Slice(Box<Kind>),
     Struct(Vec<Field>),
+    Map(Box<Kind>, Box<Kind>),
+    Pointer(Box<Kind>),
+    /// The methods of the interface, sorted by name. Any kind that has them can be used as the
+    /// interface, which keeps the kind of its value at runtime.
+    Interface(Vec<InterfaceMethod>),
+    /// The kind of the values sent on the channel
+    Chan(Box<Kind>),
+    /// The parameters, the return kind, which is a tuple for several results, and whether the last
+    /// parameter is variadic. A variadic parameter has the kind of the slice it is given as.
+    Function(Vec<Kind>, Option<Box<Kind>>, bool),
+    /// The kind of expressions that give several values at once, like a comma-ok lookup. These
+    /// can only appear on the right of assignments.
+    Tuple(Vec<Kind>),
     Underscore,
     Void,
+    /// The kind of constants that did not get a type yet, like `1 << 40`. These are converted
+    /// to a typed kind wherever they are used.
+    Untyped(BasicKind),
+    /// The kind of `nil` until it is given the pointer, function or interface kind it is used as
+    Nil,
 }
 
 impl fmt::Display for BasicKind {
@@ -46,6 +156,16 @@ impl fmt::Display for BasicKind {
                    BasicKind::Rune => "rune",
                    BasicKind::String => "string",
                    BasicKind::Bool => "bool",
+                   BasicKind::Int8 => "int8",
+                   BasicKind::Int16 => "int16",
+                   BasicKind::Int32 => "int32",
+                   BasicKind::Int64 => "int64",
+                   BasicKind::Uint8 => "uint8",
+                   BasicKind::Uint16 => "uint16",
+                   BasicKind::Uint32 => "uint32",
+                   BasicKind::Uint64 => "uint64",
+                   BasicKind::Uintptr => "uintptr",
+                   BasicKind::Float32 => "float32",
                })
     }
 }
@@ -64,13 +184,59 @@ impl fmt::Display for Kind {
             Slice(ref k) => write!(f, "[]{}", k),
             Struct(ref fields) => {
                 write!(f, "struct {{ ")?;
-                for &Field{ref name, ref kind} in fields {
-                    write!(f, "{} {}; ", name, kind)?;
+                for &Field{ref name, ref kind, embedded} in fields {
+                    if embedded {
+                        write!(f, "{}; ", kind)?;
+                    } else {
+                        write!(f, "{} {}; ", name, kind)?;
+                    }
                 }
                 write!(f, "}}")
             },
+            Map(ref key, ref value) => write!(f, "map[{}]{}", key, value),
+            Pointer(ref k) => write!(f, "*{}", k),
+            Chan(ref k) => write!(f, "chan {}", k),
+            Interface(ref methods) if methods.is_empty() => write!(f, "interface {{}}"),
+            Interface(ref methods) => {
+                write!(f, "interface {{ ")?;
+                for method in methods {
+                    // Methods are written like function types without `func`
+                    write!(f, "{}{}; ", method.name, &method.kind.to_string()["func".len()..])?;
+                }
+                write!(f, "}}")
+            },
+            Function(ref params, ref return_kind, variadic) => {
+                write!(f, "func(")?;
+                for (i, param) in params.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    match *param {
+                        Slice(ref base) if variadic && i + 1 == params.len() => write!(f, "...{}", base)?,
+                        _ => write!(f, "{}", param)?,
+                    }
+                }
+                write!(f, ")")?;
+                match *return_kind {
+                    Some(ref k) => write!(f, " {}", k),
+                    None => Ok(()),
+                }
+            },
+            Tuple(ref kinds) => {
+                write!(f, "(")?;
+                for (i, kind) in kinds.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", kind)?;
+                }
+                write!(f, ")")
+            },
             Underscore => write!(f, "_"),
-            Void => write!(f, "void")
+            Void => write!(f, "void"),
+            Untyped(BasicKind::Float) => write!(f, "untyped float"),
+            Untyped(b) => write!(f, "untyped {}", b),
+            Nil => write!(f, "untyped nil"),
         }
     }
 }
@@ -79,6 +245,8 @@ impl fmt::Display for Kind {
 pub fn are_identical(a: &Kind, b: &Kind) -> bool {
     use self::Kind::*;
     match (a, b) {
+        // Undefined is the kind of expressions that already failed to typecheck
+        (&Undefined, _) | (_, &Undefined) => true,
         (&Basic(a_kind), &Basic(b_kind)) => a_kind == b_kind,
         (&Defined(ref a), &Defined(ref b)) => {
             Rc::ptr_eq(a,b)
@@ -92,10 +260,41 @@ pub fn are_identical(a: &Kind, b: &Kind) -> bool {
         (&Struct(ref a_fields), &Struct(ref b_fields)) => {
             a_fields.len() == b_fields.len() &&
                 a_fields.iter().zip(b_fields).all(|(a_field, b_field)| {
-                    &a_field.name == &b_field.name &&
+                    a_field.name == b_field.name && a_field.embedded == b_field.embedded &&
                         are_identical(&a_field.kind,&b_field.kind)
                 })
         },
+        (Map(a_key, a_value), Map(b_key, b_value)) => {
+            are_identical(a_key, b_key) && are_identical(a_value, b_value)
+        },
+        (Pointer(a_base), Pointer(b_base)) => {
+            are_identical(a_base, b_base)
+        },
+        (Chan(a_base), Chan(b_base)) => {
+            are_identical(a_base, b_base)
+        },
+        (Interface(a_methods), Interface(b_methods)) => {
+            a_methods.len() == b_methods.len() &&
+                a_methods.iter().zip(b_methods).all(|(a_method, b_method)| {
+                    a_method.name == b_method.name && are_identical(&a_method.kind, &b_method.kind)
+                })
+        },
+        (&Function(ref a_params, ref a_return, a_variadic),
+         &Function(ref b_params, ref b_return, b_variadic)) => {
+            a_params.len() == b_params.len() && a_variadic == b_variadic &&
+                a_params.iter().zip(b_params).all(|(a, b)| are_identical(a, b)) &&
+                match (a_return, b_return) {
+                    (Some(a), Some(b)) => are_identical(a, b),
+                    (None, None) => true,
+                    _ => false,
+                }
+        },
+        (Tuple(a_kinds), Tuple(b_kinds)) => {
+            a_kinds.len() == b_kinds.len() &&
+                a_kinds.iter().zip(b_kinds).all(|(a, b)| are_identical(a, b))
+        },
+        (&Untyped(a_kind), &Untyped(b_kind)) => a_kind == b_kind,
+        (&Nil, &Nil) => true,
         (&Underscore, &Void) => false, // Ugly hack #1
         (&Underscore, _) => true,      // Ugly hack #2
         _ => false
@@ -103,6 +302,14 @@ pub fn are_identical(a: &Kind, b: &Kind) -> bool {
 }
 
 
+impl Method {
+    /// The kind of the method without its receiver
+    pub fn kind(&self) -> Kind {
+        Kind::Function(self.params.clone(), self.return_kind.clone().map(Box::new), self.variadic)
+    }
+}
+
+
 impl Kind {
     pub fn resolve<'a>(&'a self) -> Kind {
         match self {
@@ -114,6 +321,199 @@ impl Kind {
         }
     }
 
+    /// Whether this is the kind of something that failed to typecheck. Checks on undefined kinds
+    /// are skipped so that each mistake is only reported once.
+    pub fn is_undefined(&self) -> bool {
+        matches!(self.resolve(), Kind::Undefined)
+    }
+
+    /// The method with the given name of a defined type, or of a pointer to one
+    pub fn method(&self, name: &str) -> Option<Method> {
+        let definition = match *self {
+            Kind::Defined(ref d) => d.clone(),
+            Kind::Pointer(ref base) => match **base {
+                Kind::Defined(ref d) => d.clone(),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let definition = definition.borrow();
+        definition.methods.get(name).cloned()
+    }
+
+    /// Finds the field or method that selecting `name` gives in a value of this kind, or in what
+    /// it points to. Like in Go, the ones of a struct hide the ones promoted from its embedded
+    /// fields, which are searched one depth at a time.
+    pub fn lookup(&self, name: &str) -> Lookup {
+        let mut current = vec![(Vec::new(), self.clone())];
+        // Each defined type is only searched at the shallowest depth it is embedded at, which
+        // also stops at types that embed a pointer to themselves. It is searched once for each
+        // time it is embedded at that depth, so that a field promoted through two of them is
+        // ambiguous.
+        let mut searched: Vec<Rc<RefCell<Definition>>> = Vec::new();
+        while !current.is_empty() {
+            let mut found = Vec::new();
+            let mut next = Vec::new();
+            let mut embedded = Vec::new();
+            for (path, kind) in current {
+                if let Some(method) = kind.method(name) {
+                    found.push((path.clone(), Selection::Method(method)));
+                }
+                let fields = match kind.resolve() {
+                    Kind::Interface(methods) => {
+                        if let Some(method) = methods.into_iter().find(|m| m.name == name) {
+                            found.push((path, Selection::InterfaceMethod(method.kind)));
+                        }
+                        continue;
+                    }
+                    Kind::Struct(fields) => fields,
+                    Kind::Pointer(base) => match base.resolve() {
+                        Kind::Struct(fields) => fields,
+                        _ => continue,
+                    },
+                    _ => continue,
+                };
+                for field in fields {
+                    if field.name == name {
+                        found.push((path.clone(), Selection::Field(field.kind.clone())));
+                    }
+                    if !field.embedded {
+                        continue;
+                    }
+                    if let Some(definition) = field.kind.definition() {
+                        if searched.iter().any(|d| Rc::ptr_eq(d, &definition)) {
+                            continue;
+                        }
+                        embedded.push(definition);
+                    }
+                    let mut embedded_path = path.clone();
+                    embedded_path.push(field.clone());
+                    next.push((embedded_path, field.kind.clone()));
+                }
+            }
+            searched.extend(embedded);
+            match found.len() {
+                0 => current = next,
+                1 => {
+                    let (path, selection) = found.pop().unwrap();
+                    return Lookup::Found(path, selection);
+                }
+                _ => return Lookup::Ambiguous,
+            }
+        }
+        Lookup::Missing
+    }
+
+    /// The defined type of this kind or of what it points to
+    fn definition(&self) -> Option<Rc<RefCell<Definition>>> {
+        match *self {
+            Kind::Defined(ref d) => Some(d.clone()),
+            Kind::Pointer(ref base) => match **base {
+                Kind::Defined(ref d) => Some(d.clone()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The names of the methods of this kind and the ones promoted from its embedded fields,
+    /// sorted. Some of them may not be in its method set, see method_kind.
+    pub fn method_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut searched: Vec<Rc<RefCell<Definition>>> = Vec::new();
+        let mut current = vec![self.clone()];
+        while let Some(kind) = current.pop() {
+            if let Some(definition) = kind.definition() {
+                if searched.iter().any(|d| Rc::ptr_eq(d, &definition)) {
+                    continue;
+                }
+                names.extend(definition.borrow().methods.keys().cloned());
+                searched.push(definition);
+            }
+            let base = match kind.resolve() {
+                Kind::Pointer(base) => base.resolve(),
+                resolved => resolved,
+            };
+            match base {
+                Kind::Struct(fields) => {
+                    current.extend(fields.into_iter().filter(|f| f.embedded).map(|f| f.kind));
+                }
+                Kind::Interface(methods) if !kind.is_pointer() => {
+                    names.extend(methods.into_iter().map(|m| m.name));
+                }
+                _ => {}
+            }
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// The kind of the method with the given name among the ones that values of this kind have,
+    /// for interfaces. Methods with a pointer receiver are only had through a pointer, which can
+    /// also be an embedded field the method is promoted from.
+    pub fn method_kind(&self, name: &str) -> Option<Kind> {
+        if let Kind::Interface(ref methods) = self.resolve() {
+            return methods.iter().find(|m| m.name == name).map(|m| m.kind.clone());
+        }
+        match self.lookup(name) {
+            Lookup::Found(ref path, Selection::Method(ref method))
+                if method.pointer_receiver && !self.is_pointer() &&
+                    !path.iter().any(|f| f.kind.is_pointer()) => None,
+            Lookup::Found(_, Selection::Method(method)) => Some(method.kind()),
+            Lookup::Found(_, Selection::InterfaceMethod(kind)) => Some(kind),
+            _ => None,
+        }
+    }
+
+    /// The first of the methods of an interface that values of this kind don't have, if they
+    /// can't be used as that interface
+    pub fn missing_method(&self, methods: &[InterfaceMethod]) -> Option<String> {
+        methods.iter()
+            .find(|m| !self.method_kind(&m.name).is_some_and(|k| are_identical(&k, &m.kind)))
+            .map(|m| m.name.clone())
+    }
+
+    pub fn is_interface(&self) -> bool {
+        matches!(self.resolve(), Kind::Interface(..))
+    }
+
+    /// The name Go gives the kind at runtime, in the messages of failed type assertions
+    pub fn runtime_name(&self) -> String {
+        match *self {
+            Kind::Defined(ref definition) => format!("main.{}", definition.borrow().name),
+            Kind::Array(ref k, s) => format!("[{}]{}", s, k.runtime_name()),
+            Kind::Slice(ref k) => format!("[]{}", k.runtime_name()),
+            Kind::Map(ref key, ref value) => {
+                format!("map[{}]{}", key.runtime_name(), value.runtime_name())
+            }
+            Kind::Pointer(ref k) => format!("*{}", k.runtime_name()),
+            Kind::Chan(ref k) => format!("chan {}", k.runtime_name()),
+            _ => self.to_string(),
+        }
+    }
+
+    pub fn is_chan(&self) -> bool {
+        matches!(self.resolve(), Kind::Chan(..))
+    }
+
+    pub fn is_slice(&self) -> bool {
+        matches!(self.resolve(), Kind::Slice(..))
+    }
+
+    pub fn is_pointer(&self) -> bool {
+        matches!(self.resolve(), Kind::Pointer(..))
+    }
+
+    /// Whether `nil` can be used as a value of this kind
+    pub fn is_nillable(&self) -> bool {
+        matches!(self.resolve(), Kind::Pointer(..) | Kind::Function(..) | Kind::Interface(..) | Kind::Chan(..))
+    }
+
+    pub fn is_untyped(&self) -> bool {
+        matches!(*self, Kind::Untyped(..))
+    }
+
     pub fn is_comparable(&self) -> bool {
         match self.resolve() {
             Kind::Struct(ref fields) => {
@@ -127,7 +527,7 @@ impl Kind {
             Kind::Array(ref kind, ..) => {
                 return kind.is_comparable()
             },
-            Kind::Slice(..) => false,
+            Kind::Slice(..) | Kind::Map(..) | Kind::Function(..) | Kind::Nil => false,
             _ => true
         }
     }
@@ -135,34 +535,29 @@ impl Kind {
     pub fn is_ordered(&self) -> bool {
         match self.resolve() {
             Kind::Basic(BasicKind::Bool) | Kind::Slice(..) | Kind::Array(..)
-            | Kind::Struct(..) => false,
+            | Kind::Struct(..) | Kind::Map(..) | Kind::Function(..) | Kind::Pointer(..)
+            | Kind::Interface(..) | Kind::Chan(..) | Kind::Nil => false,
             _ => true
         }
     }
 
     pub fn is_numeric(&self) -> bool {
         match self.resolve() {
-            Kind::Basic(t) => {
-                t == BasicKind::Int || t == BasicKind::Rune || t == BasicKind::Float
-            }
+            Kind::Basic(t) => t.is_integer() || t.is_float(),
             _ => false
         }
     }
 
     pub fn is_integer(&self) -> bool {
         match self.resolve() {
-            Kind::Basic(t) => {
-                t == BasicKind::Int || t == BasicKind::Rune
-            }
+            Kind::Basic(t) => t.is_integer(),
             _ => false
         }
     }
 
     pub fn is_floating_point(&self) -> bool {
         match self.resolve() {
-            Kind::Basic(t) => {
-                t == BasicKind::Float
-            }
+            Kind::Basic(t) => t.is_float(),
             _ => false
         }
     }
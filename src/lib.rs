@@ -0,0 +1,153 @@
+//! The GoLite compiler as a library. Each phase of the pipeline is a function that returns the
+//! errors it found, as a `Vec<Diagnostic>`, instead of printing them, so that tools can run the
+//! compiler in-process:
+//!
+//! ```ignore
+//! let mut program = golite::parse_source(&source, "prog.go")?;
+//! golite::weed(&program)?;
+//! golite::typecheck(&mut program)?;
+//! if let Err(panic) = golite::interpret(&program, &mut std::io::stdout()) {
+//!     eprintln!("{}", panic);
+//! }
+//! ```
+//!
+//! The errors are sorted by position and their `Display` implementation formats them the way the
+//! command line compiler prints them. Running a program that panics gives a boxed `Panic` instead,
+//! which formats like the report of a Go program.
+
+use std::io::Write;
+use std::os::raw::c_char;
+
+pub mod ast;
+mod ast_constructors;
+mod weed;
+pub mod kind;
+mod symbol_table;
+mod typecheck;
+mod constant;
+mod util;
+mod codegen;
+mod codegen_c;
+mod value;
+mod interpret;
+mod scheduler;
+pub mod span;
+pub mod diagnostics;
+
+pub use crate::ast::Program;
+pub use crate::diagnostics::Diagnostic;
+pub use crate::interpret::Panic;
+use crate::diagnostics::Diagnostics;
+
+// The scanner and the parser, from bisonparser.c
+extern "C" {
+    #[link_name = "scan"]
+    fn c_scan(source: *const c_char, length: u32, file: u32);
+    fn list_tokens(source: *const c_char, length: u32, file: u32);
+    // The program is only ever handled as an opaque pointer on the C side
+    #[allow(improper_ctypes)]
+    #[link_name = "parse"]
+    fn c_parse(source: *const c_char, length: u32, file: u32) -> *mut Program;
+}
+
+// Scanning and parsing stop at the first syntax error, but the AST constructors can record a few
+// more before that
+fn syntax_errors() -> Result<(), Vec<Diagnostic>> {
+    let mut errors = ast_constructors::take_syntax_errors();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        errors.sort_by_key(|e| (e.span.file, e.span.start));
+        Err(errors)
+    }
+}
+
+/// Runs the scanner alone
+pub fn scan(source: &str, file_name: &str) -> Result<(), Vec<Diagnostic>> {
+    let file = span::add_source_file(file_name, source);
+    unsafe { c_scan(source.as_ptr() as *const c_char, source.len() as u32, file.id()) };
+    syntax_errors()
+}
+
+/// Runs the scanner alone and lists the tokens, one per line
+pub fn tokens(source: &str, file_name: &str) -> Result<String, Vec<Diagnostic>> {
+    let file = span::add_source_file(file_name, source);
+    unsafe { list_tokens(source.as_ptr() as *const c_char, source.len() as u32, file.id()) };
+    let tokens = ast_constructors::take_tokens();
+    syntax_errors().map(|()| tokens)
+}
+
+/// Parses a program. `file_name` is only used in error messages.
+pub fn parse(source: &str, file_name: &str) -> Result<Program, Vec<Diagnostic>> {
+    parse_source(source, file_name)
+}
+
+/// Parses a program from a string. `file_name` is only used in error messages. The parser keeps no
+/// state between calls, so any number of sources can be parsed in the same process. The source is
+/// kept with the program and the errors for quoting, and is freed along with them.
+pub fn parse_source(source: &str, file_name: &str) -> Result<Program, Vec<Diagnostic>> {
+    let file = span::add_source_file(file_name, source);
+    let root = unsafe { c_parse(source.as_ptr() as *const c_char, source.len() as u32, file.id()) };
+    let program = unsafe { ast_constructors::from_raw_or_none(root) };
+    syntax_errors()?;
+    match program {
+        Some(mut program) => {
+            program.source = Some(file);
+            Ok(*program)
+        }
+        None => unreachable!("the parser failed without reporting an error"),
+    }
+}
+
+/// Checks the rules that the grammar doesn't enforce, like where `break` and `_` can be used
+pub fn weed(program: &Program) -> Result<(), Vec<Diagnostic>> {
+    let diagnostics = Diagnostics::new();
+    weed::weed_ast(program, &diagnostics);
+    diagnostics.result()
+}
+
+// Expects the program to have been weeded
+fn check(program: &mut Program, print_table: bool, obfuscate: bool) -> Result<String, Vec<Diagnostic>> {
+    let diagnostics = Diagnostics::new();
+    weed::weed_terminating_statements(program, &diagnostics);
+    let table = typecheck::typecheck(program, print_table, obfuscate, &diagnostics);
+    diagnostics.result().map(|()| table)
+}
+
+/// Typechecks a weeded program. Identifiers are renamed so that each declaration has a unique
+/// name, which the later phases rely on.
+pub fn typecheck(program: &mut Program) -> Result<(), Vec<Diagnostic>> {
+    check(program, false, false).map(|_| ())
+}
+
+/// Typechecks a weeded program, renaming the identifiers to meaningless names
+pub fn obfuscate(program: &mut Program) -> Result<(), Vec<Diagnostic>> {
+    check(program, false, true).map(|_| ())
+}
+
+/// Typechecks a weeded program and returns its scopes and their symbols
+pub fn symbol_table(program: &mut Program) -> Result<String, Vec<Diagnostic>> {
+    check(program, true, false)
+}
+
+/// Runs a typechecked program, writing what it prints to `output`. A runtime error or a call to
+/// panic stops the program and is returned.
+pub fn interpret(program: &Program, output: &mut dyn Write) -> Result<(), Box<Panic>> {
+    interpret::interpret(program, output, 0)
+}
+
+/// Runs a typechecked program like `interpret`, with a seed for choosing which goroutine runs
+/// when one blocks. The same seed gives the same run.
+pub fn interpret_with_seed(program: &Program, output: &mut dyn Write, seed: u32) -> Result<(), Box<Panic>> {
+    interpret::interpret(program, output, seed)
+}
+
+/// Compiles a typechecked program to JavaScript
+pub fn codegen(program: &Program) -> Result<String, Vec<Diagnostic>> {
+    Ok(codegen::codegen(program))
+}
+
+/// Compiles a typechecked program to C
+pub fn codegen_c(program: &Program) -> Result<String, Vec<Diagnostic>> {
+    codegen_c::codegen(program)
+}
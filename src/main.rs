@@ -1,114 +1,160 @@
+extern crate golite;
+
 use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
 use std::process::exit;
+use std::thread;
+
+use golite::Diagnostic;
+// The pretty printer is only used by the command line, and finds the AST at the crate root
+use golite::ast;
 
-mod ast;
-mod ast_constructors;
-mod weed;
 mod pretty;
-mod kind;
-mod symbol_table;
-mod typecheck;
-mod util;
-mod codegen;
-mod codegen_c;
-mod value;
-mod interpret;
 
+// The interpreter recurses as deep as the program it runs, so the compiler runs on a thread with
+// a bigger stack than the main one. It is only reserved, and used as the program goes deeper.
+const STACK_SIZE: usize = 1 << 30;
+
+/// Reads the `--max-errors=N` option. At most 10 errors are printed by default and 0 means that
+/// they are all printed.
+fn error_limit(argv: &[String]) -> usize {
+    for arg in argv {
+        if let Some(limit) = arg.strip_prefix("--max-errors=") {
+            match limit.parse() {
+                Ok(limit) => return limit,
+                Err(_) => {
+                    eprintln!("Error: invalid value for --max-errors");
+                    exit(1);
+                }
+            }
+        }
+    }
+    10
+}
 
+/// Reads the `--seed=N` option, which picks the order the goroutines of the interpreter run in
+fn seed(argv: &[String]) -> u32 {
+    for arg in argv {
+        if let Some(value) = arg.strip_prefix("--seed=") {
+            match value.parse() {
+                Ok(seed) => return seed,
+                Err(_) => {
+                    eprintln!("Error: invalid value for --seed");
+                    exit(1);
+                }
+            }
+        }
+    }
+    0
+}
 
-pub use ast_constructors::*;
-pub use weed::*;
-pub use pretty::*;
-//pub use symbol_table::*;
-//pub use symbol_table::*;
-//pub use typecheck::*;
+/// Prints the errors of a failed phase and exits, or returns the result of the phase
+fn or_report<T>(result: Result<T, Vec<Diagnostic>>, limit: usize) -> T {
+    match result {
+        Ok(t) => t,
+        Err(errors) => {
+            for (i, e) in errors.iter().enumerate() {
+                if limit != 0 && i == limit {
+                    eprintln!("Error: too many errors ({} more not shown)", errors.len() - i);
+                    break;
+                }
+                eprintln!("{}", e);
+            }
+            exit(1);
+        }
+    }
+}
 
-extern "C" {
-    fn scan();
-    fn print_tokens();
-    fn parse() -> *mut ast::Program;
+/// Reads the program from the file given after the mode, or from stdin if there is none. Also
+/// returns the name to use in error messages.
+fn read_source(argv: &[String]) -> (String, String) {
+    match argv.iter().find(|arg| !arg.starts_with("--")) {
+        Some(path) => match fs::read_to_string(path) {
+            Ok(source) => (source, path.clone()),
+            Err(e) => {
+                eprintln!("Error: could not read {}: {}", path, e);
+                exit(1);
+            }
+        },
+        None => {
+            let mut source = String::new();
+            if io::stdin().read_to_string(&mut source).is_err() {
+                eprintln!("Error: could not read the source as UTF-8");
+                exit(1);
+            }
+            (source, "<stdin>".to_string())
+        }
+    }
 }
 
 
 fn main() {
+    let compiler = thread::Builder::new().stack_size(STACK_SIZE).spawn(run)
+        .expect("could not start the compiler");
+    // The panic was already reported by the thread
+    if compiler.join().is_err() {
+        exit(101);
+    }
+}
 
+fn run() {
     let argv = env::args().collect::<Vec<String>>();
     if argv.len() < 2 {
         eprintln!("Error: requires mode");
         exit(1);
     }
-    if &argv[1] == "scan" {
-        unsafe {
-            scan();
-        }
+    let limit = error_limit(&argv[2..]);
+    let mode = &argv[1];
+    let (source, file_name) = read_source(&argv[2..]);
+
+    if mode == "scan" {
+        or_report(golite::scan(&source, &file_name), limit);
         println!("OK");
-    } else if &argv[1] == "tokens" {
-        unsafe {
-            print_tokens();
-        }
-    } else if &argv[1] == "parse" {
-        let ast = unsafe { from_raw_or_none(parse()) };
-        match ast {
-            Some(ast) => {
-                weed::weed_ast(&ast);
-                println!("OK");
-            },
-            None =>  eprintln!("Error: AST error")
-        }
-    } else if &argv[1] == "print" {
-        let ast = unsafe { Box::from_raw(parse()) };
+        return;
+    } else if mode == "tokens" {
+        print!("{}", or_report(golite::tokens(&source, &file_name), limit));
+        return;
+    }
+
+    let mut ast = or_report(golite::parse_source(&source, &file_name), limit);
+    if mode == "print" {
         println!("{:?}", ast);
-    } else if &argv[1] == "pretty" {
-        let ast = unsafe { Box::from_raw(parse()) };
-        weed::weed_ast(&ast);
+        return;
+    }
+
+    or_report(golite::weed(&ast), limit);
+    if mode == "parse" {
+        println!("OK");
+    } else if mode == "pretty" {
         pretty::pretty_print_program(&ast)
-    } else if &argv[1] == "rename" {
-        let mut ast = unsafe { Box::from_raw(parse()) };
-        weed::weed_ast(&ast);
-        weed::weed_terminating_statements(&ast);
-        typecheck::typecheck(&mut ast, false, false );
+    } else if mode == "rename" {
+        or_report(golite::typecheck(&mut ast), limit);
         pretty::pretty_print_program(&ast)
-    } else if &argv[1] == "obfuscate" {
-        let mut ast = unsafe { Box::from_raw(parse()) };
-        weed::weed_ast(&ast);
-        weed::weed_terminating_statements(&ast);
-        typecheck::typecheck(&mut ast, false, true );
+    } else if mode == "obfuscate" {
+        or_report(golite::obfuscate(&mut ast), limit);
         pretty::pretty_print_program(&ast)
-    } else if &argv[1] == "symbol" {
-        let mut ast = unsafe { Box::from_raw(parse()) };
-        weed::weed_ast(&ast);
-        weed::weed_terminating_statements(&ast);
-        typecheck::typecheck(&mut ast, true, false);
-    } else if &argv[1] == "typecheck" {
-        let mut ast = unsafe { Box::from_raw(parse()) };
-        weed::weed_ast(&ast);
-        weed::weed_terminating_statements(&ast);
-        typecheck::typecheck(&mut ast, false, false);
+    } else if mode == "symbol" {
+        print!("{}", or_report(golite::symbol_table(&mut ast), limit));
+    } else if mode == "typecheck" {
+        or_report(golite::typecheck(&mut ast), limit);
         print!("OK");
-    } else if &argv[1] == "interpret" {
-        let mut ast = unsafe { Box::from_raw(parse()) };
-        weed::weed_ast(&ast);
-        weed::weed_terminating_statements(&ast);
-        typecheck::typecheck(&mut ast, false, false);
-
-        interpret::interpret(&mut ast);
-
-    } else if &argv[1] == "codegen" {
-        let mut ast = unsafe { Box::from_raw(parse()) };
-        weed::weed_ast(&ast);
-        weed::weed_terminating_statements(&ast);
-        typecheck::typecheck(&mut ast, false, false);
-
-        codegen::codegen(&mut ast);
-
-    } else if &argv[1] == "codegen_c" {
-        let mut ast = unsafe { Box::from_raw(parse()) };
-        weed::weed_ast(&ast);
-        weed::weed_terminating_statements(&ast);
-        typecheck::typecheck(&mut ast, false, false);
-
-        codegen_c::codegen(&mut ast);
-
+    } else if mode == "interpret" {
+        or_report(golite::typecheck(&mut ast), limit);
+        let stdout = io::stdout();
+        let result = golite::interpret_with_seed(&ast, &mut stdout.lock(), seed(&argv[2..]));
+        if let Err(panic) = result {
+            // Like Go, what was printed comes first and a panic exits with status 2
+            io::stdout().flush().ok();
+            eprintln!("{}", panic);
+            exit(2);
+        }
+    } else if mode == "codegen" {
+        or_report(golite::typecheck(&mut ast), limit);
+        print!("{}", or_report(golite::codegen(&ast), limit));
+    } else if mode == "codegen_c" {
+        or_report(golite::typecheck(&mut ast), limit);
+        print!("{}", or_report(golite::codegen_c(&ast), limit));
     } else {
         eprintln!("Error: invalid mode");
         exit(1);
@@ -8,25 +8,60 @@
  */
 
 /* Code section */
+%code requires {
+#include "ast.h"
+
+// Locations are full spans instead of bison's default line/column pairs
+#define YYLTYPE Span
+
+#define YYLLOC_DEFAULT(Current, Rhs, N)                             \
+    do {                                                            \
+        if (N) {                                                    \
+            (Current).file = YYRHSLOC(Rhs, 1).file;                 \
+            (Current).start = YYRHSLOC(Rhs, 1).start;               \
+            (Current).end = YYRHSLOC(Rhs, N).end;                   \
+        } else {                                                    \
+            (Current).file = YYRHSLOC(Rhs, 0).file;                 \
+            (Current).start = (Current).end = YYRHSLOC(Rhs, 0).end; \
+        }                                                           \
+    } while (0)
+
+// Everything that one run of the scanner and the parser needs, so that several sources can be
+// parsed at the same time. The scanner is given this as its extra data.
+typedef struct {
+    // The file being scanned and the position of the next character in it
+    uint32_t file;
+    Position position;
+    int last_token;
+    // Set by the scanner when it stops on a character it doesn't know
+    int lexical_error;
+    Program *root;
+} ParserState;
+
+// The type of flex's reentrant scanners, which lex.yy.c only defines later
+#ifndef YY_TYPEDEF_YY_SCANNER_T
+#define YY_TYPEDEF_YY_SCANNER_T
+typedef void *yyscan_t;
+#endif
+}
+
 %{
 #include <stdio.h>
 #include <stdlib.h>
-#include "ast.h"
 
-// Reference to the line number generated by the scanner
-extern int yylineno;
-
-Program *root;
 // Reference to the yylex function to quiet a compiler warning
 int yylex();
 
 // The yyerror function is called on any syntax error. When using %error-verbose, this function
 // takes a single argument (a string) which contains a detailed explanation of the error.
 // Otherwise it takes no arguments and it is up to the user to format the error using a minimal
-// description provided in yytext.
-void yyerror(const char *s) {
-	fprintf(stderr, "Error: (line %d) %s\n", yylineno, s);
-	exit(1);
+// description provided in yytext. The parser gives up after this call and yyparse returns 1.
+// Being a pure parser, it also gets the location of the error and the parameters of yyparse.
+void yyerror(Span *location, yyscan_t scanner, ParserState *state, const char *s) {
+	// The scanner already reported why it stopped
+	if (!state->lexical_error) {
+		report_syntax_error(*location, s);
+	}
 }
 %}
 
@@ -37,6 +72,7 @@ void yyerror(const char *s) {
 %union {
     char *text;
     AstKindNode *ast_kind;
+    AstKindNodeVec *ast_kind_vec;
     ExpressionNode *expr;
     ExpressionNodeVec *expression_vec;
     StringVec *string_vec;
@@ -55,6 +91,9 @@ void yyerror(const char *s) {
     Program *program;
     Field *field;
     FieldVec *field_vec;
+    KeyedElement *keyed_element;
+    KeyedElementVec *keyed_element_vec;
+    Receiver *receiver;
 }
 
 /* Token directives define the token types to be returned by the scanner (excluding character
@@ -98,6 +137,14 @@ void yyerror(const char *s) {
 %token tPRINT;
 %token tPRINTLN;
 %token tAPPEND;
+%token tLEN;
+%token tCAP;
+%token tMAKE;
+%token tDELETE;
+%token tNEW;
+%token tPANIC;
+%token tCLOSE;
+%token tCOPY;
 
 // Double character operators
 
@@ -176,6 +223,9 @@ void yyerror(const char *s) {
 %type <var_spec> VarSpec
 %type <var_spec_vec> VarSpecs
 %type <var_spec_vec> VarDecl
+%type <var_spec> ConstSpec
+%type <var_spec_vec> ConstSpecs
+%type <var_spec_vec> ConstDecl
 
 %type <string_vec> identifier_list
 
@@ -189,6 +239,15 @@ void yyerror(const char *s) {
 %type <ast_kind> SliceType
 %type <ast_kind> ArrayType
 %type <ast_kind> StructType
+%type <ast_kind> MapType
+%type <ast_kind> PointerType
+%type <ast_kind> FuncType
+%type <ast_kind> InterfaceType
+%type <ast_kind> ChanType
+%type <field_vec> MethodSpecs
+%type <field> MethodSpec
+%type <ast_kind_vec> TypeList
+%type <field_vec> FuncTypeParameters
 
 %type <field_vec> FieldDecls
 %type <field> FieldDecl
@@ -197,6 +256,17 @@ void yyerror(const char *s) {
 %type <expr> Expression
 %type <expr> UnaryExpr
 %type <expr> AppendExpr
+%type <expr> LenExpr
+%type <expr> CapExpr
+%type <expr> MakeExpr
+%type <expr> DeleteExpr
+%type <expr> NewExpr
+%type <expr> PanicExpr
+%type <expr> CloseExpr
+%type <expr> CopyExpr
+%type <expr> CompositeLit
+%type <ast_kind> LiteralType
+%type <expr> FunctionLit
 %type <expr> PrimaryExpr
 %type <expr> Literal
 
@@ -206,18 +276,32 @@ void yyerror(const char *s) {
 %type <expression_vec> OptionalExpressionList
 %type <expression_vec> Arguments
 
+%type <keyed_element_vec> LiteralValue
+%type <keyed_element_vec> KeyedElementList
+%type <keyed_element> KeyedElement
+%type <expr> Element
+
 // Statements
 %type <stmt> Statement
+%type <stmt> NonEmptyStatement
 
 %type <stmt> SimpleStmt
+%type <stmt> NonEmptySimpleStmt
 %type <stmt> ReturnStmt
 %type <stmt> BreakStmt
 %type <stmt> ContinueStmt
+%type <stmt> FallthroughStmt
+%type <stmt> GotoStmt
+%type <stmt> LabeledStmt
 %type <stmt> IfStmt
 %type <stmt> SwitchStmt
 %type <stmt> ForStmt
 %type <stmt> PrintStmt
 %type <stmt> PrintlnStmt
+%type <stmt> DeferStmt
+%type <stmt> GoStmt
+%type <stmt> SendStmt
+%type <stmt> SelectStmt
 
 %type <stmt> ElseStmt
 
@@ -228,11 +312,16 @@ void yyerror(const char *s) {
 %type <stmt> ShortVarDecl
 
 %type <stmt_vec> StatementList
+%type <stmt_vec> Statements
 %type <stmt_vec> Block
 
 // Case Clause
 %type <case_clause> CaseClause
 %type <case_clause_vec> CaseClauses
+%type <case_clause> TypeCaseClause
+%type <case_clause_vec> TypeCaseClauses
+%type <case_clause> CommClause
+%type <case_clause_vec> CommClauses
 
 %type <program> Program
 %type <text> PackageClause
@@ -241,11 +330,14 @@ void yyerror(const char *s) {
 
 // Function Declarations
 %type <top_level_decl> FunctionDecl
-%type <ast_kind> FuncResult
+%type <field_vec> FuncResult
+%type <field_vec> ResultList
+%type <field> ResultDecl
 %type <field_vec> FuncParameterList
 %type <field_vec> FuncParameters
 %type <field_vec> OptionalFuncParameterList
 %type <field> FuncParameterDecl
+%type <receiver> Receiver
 
 
 %token UNARY_PREC
@@ -288,19 +380,32 @@ void yyerror(const char *s) {
 /* Generate detailed error messages */
 %error-verbose
 
+/* Keep the state of the parser on the stack and the state of the scanner in the ParserState, instead
+ * of in globals, so that the parser is reentrant */
+%define api.pure full
+%lex-param {yyscan_t scanner}
+%parse-param {yyscan_t scanner} {ParserState *state}
+
 /* The second section of a bison file contains the productions. Note that rules with the
  * same LHS may be joined together and separated with a pipe.
  */
 
+/* The GLR parser splits on these and keeps whichever parse survives: an identifier
+ * followed by ',' may start an identifier list or an expression list (once in statements
+ * that may be labeled, once in those that may not), `switch x :=` may start a type
+ * switch guard or a short variable declaration, and an identifier followed by '{' may be
+ * the type of a composite literal or an operand before a block.
+ */
 %glr-parser
-%expect-rr 1
+%expect 1
+%expect-rr 7
 
 %%
 
 // FILE STRUCTURE
 // =============================
 
-Program : PackageClause ';' TopLevelDecls { $$ = make_program($1, $3); root = $$; }
+Program : PackageClause ';' TopLevelDecls { $$ = make_program(@1, $1, $3); state->root = $$; }
     ;
 
 // PRIMARY PROGRAM STRUCTURE
@@ -322,8 +427,9 @@ TopLevelDecls : /*empty*/                  { $$ = make_top_level_declaration_vec
 // DECLARATION STRUCTURE
 // ============================
 
-TopLevelDecl : TypeDecl     { $$ = make_type_top_level_declaration(yylineno, $1); }
-    | VarDecl               { $$ = make_var_top_level_declaration(yylineno, $1); }
+TopLevelDecl : TypeDecl     { $$ = make_type_top_level_declaration(@$, $1); }
+    | VarDecl               { $$ = make_var_top_level_declaration(@$, $1); }
+    | ConstDecl             { $$ = make_const_top_level_declaration(@$, $1); }
     | FunctionDecl          { }
     ;
 
@@ -351,9 +457,9 @@ VarSpecs : /*empty*/
         }
     ;
 
-VarSpec : identifier_list Type      { $$ = make_var_spec(yylineno, $1, $2, NULL); }
-    | identifier_list Type '=' expression_list { $$ = make_var_spec(yylineno, $1, $2, $4); }
-    | identifier_list '=' expression_list      { $$ = make_var_spec(yylineno, $1, NULL, $3); }
+VarSpec : identifier_list Type      { $$ = make_var_spec(@$, $1, $2, NULL); }
+    | identifier_list Type '=' expression_list { $$ = make_var_spec(@$, $1, $2, $4); }
+    | identifier_list '=' expression_list      { $$ = make_var_spec(@$, $1, NULL, $3); }
     ;
 
 identifier_list : tIDENTIFIER
@@ -381,6 +487,34 @@ expression_list : Expression
     ;
 
 
+// CONSTANT DECLARATIONS
+// ============================
+
+ConstDecl : tCONST ConstSpec
+        {
+        $$ = make_var_spec_vec();
+        var_spec_vec_push($$, $2);
+        }
+    | tCONST '(' ConstSpecs ')'
+        { $$ = $3; }
+    ;
+
+ConstSpecs : /*empty*/
+        { $$ = make_var_spec_vec(); }
+    | ConstSpecs ConstSpec ';'
+        {
+        $$ = $1;
+        var_spec_vec_push($$, $2);
+        }
+    ;
+
+// Leaving out the expressions repeats the previous ones in the group
+ConstSpec : identifier_list        { $$ = make_var_spec(@$, $1, NULL, NULL); }
+    | identifier_list Type '=' expression_list { $$ = make_var_spec(@$, $1, $2, $4); }
+    | identifier_list '=' expression_list      { $$ = make_var_spec(@$, $1, NULL, $3); }
+    ;
+
+
 // TYPE DECLARATIONS
 // ============================
 TypeDecl : tTYPE TypeSpec
@@ -401,7 +535,7 @@ TypeSpecs : /*empty*/
         }
     ;
 
-TypeSpec : tIDENTIFIER Type  { $$ = make_type_spec(yylineno, $1, $2); }
+TypeSpec : tIDENTIFIER Type  { $$ = make_type_spec(@$, $1, $2); }
     ;
 
 
@@ -410,14 +544,46 @@ TypeSpec : tIDENTIFIER Type  { $$ = make_type_spec(yylineno, $1, $2); }
 // ============================
 
 FunctionDecl : tFUNC tIDENTIFIER FuncParameters FuncResult Block    
-             { $$ = make_function_top_level_declaration(yylineno, $2, $3, $4, $5); }
+             { $$ = make_function_top_level_declaration(@$, $2, $3, $4, $5); }
+    | tFUNC Receiver tIDENTIFIER FuncParameters FuncResult Block
+             { $$ = make_method_top_level_declaration(@$, $2, $3, $4, $5, $6); }
+    ;
+
+// The receiver of a method is a type name, or a pointer to one
+Receiver: '(' tIDENTIFIER TypeName ')'     { $$ = make_receiver(@$, $2, $3, 0); }
+    | '(' tIDENTIFIER '*' TypeName ')'     { $$ = make_receiver(@$, $2, $4, 1); }
+    | '(' TypeName ')'                     { $$ = make_receiver(@$, NULL, $2, 0); }
+    | '(' '*' TypeName ')'                 { $$ = make_receiver(@$, NULL, $3, 1); }
     ;
 
 FuncParameters: '('  OptionalFuncParameterList  ')' { $$ = $2; }
     ;
 
-FuncResult: /*empty*/  { $$ = NULL; }
-    | Type
+// A parenthesized result list is not parsed as a Type so that `(int)` is not ambiguous. Names and
+// types are grouped by make_function_top_level_declaration.
+FuncResult: /*empty*/  { $$ = make_field_vec(); }
+    | TypeName         { $$ = make_field_vec(); field_vec_push($$, make_result_field(@$, NULL, $1)); }
+    | TypeLit          { $$ = make_field_vec(); field_vec_push($$, make_result_field(@$, NULL, $1)); }
+    | '(' ResultList ')' { $$ = $2; }
+    ;
+
+ResultList: ResultDecl
+                 {
+                 $$ = make_field_vec();
+                 field_vec_push($$, $1);
+                 }
+    | ResultList ',' ResultDecl
+                 {
+                 $$ = $1;
+                 field_vec_push($$, $3);
+                 }
+    ;
+
+ResultDecl: Type              { $$ = make_result_field(@$, NULL, $1); }
+    | tIDENTIFIER Type        { $$ = make_result_field(@$, $1, $2); }
+    | tELLIPSIS Type          { $$ = make_variadic_result_field(@$, NULL, $2); }
+    | tIDENTIFIER tELLIPSIS Type
+                              { $$ = make_variadic_result_field(@$, $1, $3); }
     ;
 
 FuncParameterList: FuncParameterDecl            
@@ -436,7 +602,8 @@ OptionalFuncParameterList: /*empty*/ { $$ = make_field_vec();}
     | FuncParameterList
     ;
 
-FuncParameterDecl: identifier_list Type { $$ = make_field(yylineno, $1, $2); }
+FuncParameterDecl: identifier_list Type { $$ = make_field(@$, $1, $2); }
+    | identifier_list tELLIPSIS Type      { $$ = make_variadic_field(@$, $1, $3); }
     ;
 
 
@@ -450,30 +617,35 @@ Type : TypeName
     ;
 
 // Basic types are just identifiers
-TypeName : tIDENTIFIER                      { $$ = make_identifier_kind(yylineno, $1); }
+TypeName : tIDENTIFIER                      { $$ = make_identifier_kind(@$, $1); }
     ;
 
 TypeLit : ArrayType
     | StructType
     | SliceType
+    | MapType
+    | FuncType
+    | PointerType
+    | InterfaceType
+    | ChanType
     ;
 
 
 // SLICES
 
-SliceType : '[' ']' Type                    { $$ = make_slice_kind(yylineno, $3); }
+SliceType : '[' ']' Type                    { $$ = make_slice_kind(@$, $3); }
     ;
 
 
 // ARRAYS
 
-ArrayType : '[' tINTVAL ']' Type            { $$ = make_array_kind(yylineno, $4, $2); }
+ArrayType : '[' tINTVAL ']' Type            { $$ = make_array_kind(@$, $4, $2); }
     ;
 
 
 // STRUCTS
 
-StructType : tSTRUCT '{'  FieldDecls '}'    { $$ = make_struct_kind(yylineno, $3); }
+StructType : tSTRUCT '{'  FieldDecls '}'    { $$ = make_struct_kind(@$, $3); }
     ;
 
 FieldDecls : /*empty*/                         { $$ = make_field_vec(); }
@@ -484,7 +656,75 @@ FieldDecls : /*empty*/                         { $$ = make_field_vec(); }
                 }
     ;
 
-FieldDecl : identifier_list Type            { $$ = make_field(yylineno, $1, $2); }
+FieldDecl : identifier_list Type            { $$ = make_field(@$, $1, $2); }
+    // Like an unnamed result, an embedded field has no identifiers. It is named after its type.
+    | TypeName                              { $$ = make_result_field(@$, NULL, $1); }
+    | '*' TypeName                          { $$ = make_result_field(@$, NULL, make_pointer_kind(@$, $2)); }
+    ;
+
+
+// FUNCTIONS
+
+FuncType : tFUNC FuncTypeParameters FuncResult  { $$ = make_function_kind(@$, $2, $3); }
+    ;
+
+// Like results, the parameters of a function type don't need names
+FuncTypeParameters : '(' ')'                { $$ = make_field_vec(); }
+    | '(' ResultList ')'                    { $$ = $2; }
+    ;
+
+
+// MAPS
+
+MapType : tMAP '[' Type ']' Type            { $$ = make_map_kind(@$, $3, $5); }
+    ;
+
+
+// POINTERS
+
+PointerType : '*' Type                      { $$ = make_pointer_kind(@$, $2); }
+    ;
+
+
+// INTERFACES
+
+// The last method can be on the line of the closing brace, like in `interface{ String() string }`
+InterfaceType : tINTERFACE '{' MethodSpecs '}'  { $$ = make_interface_kind(@$, $3); }
+    | tINTERFACE '{' MethodSpecs MethodSpec '}'
+                {
+                field_vec_push($3, $4);
+                $$ = make_interface_kind(@$, $3);
+                }
+    ;
+
+MethodSpecs : /*empty*/                     { $$ = make_field_vec(); }
+            | MethodSpecs MethodSpec ';'
+                {
+                $$ = $1;
+                field_vec_push($$, $2);
+                }
+    ;
+
+MethodSpec : tIDENTIFIER FuncTypeParameters FuncResult
+                { $$ = make_result_field(@$, $1, make_function_kind(@$, $2, $3)); }
+    ;
+
+
+// CHANNELS
+
+ChanType : tCHAN Type                       { $$ = make_chan_kind(@$, $2); }
+    ;
+
+TypeList : Type
+                {
+                $$ = make_kind_vec();
+                kind_vec_push($$, $1);
+                }
+         | TypeList ',' Type
+                {
+                $$ = $1;
+                kind_vec_push($$, $3);
+                }
     ;
 
 
@@ -492,26 +732,40 @@ FieldDecl : identifier_list Type            { $$ = make_field(yylineno, $1, $2);
 // STATEMENT STRUCTURE
 // ============================
 
-// Need to double check if we support fallthrough 
-Statement : VarDecl                         { $$ = make_var_declaration_statement(yylineno, $1); }
-    | TypeDecl                              { $$ = make_type_declaration_statement(yylineno, $1); }
-    | SimpleStmt
+Statement : NonEmptyStatement
+    | EmptyStmt
+    ;
+
+NonEmptyStatement : VarDecl                 { $$ = make_var_declaration_statement(@$, $1); }
+    | TypeDecl                              { $$ = make_type_declaration_statement(@$, $1); }
+    | ConstDecl                             { $$ = make_const_declaration_statement(@$, $1); }
+    | NonEmptySimpleStmt
     | ReturnStmt
     | BreakStmt
     | ContinueStmt
-    | Block                                 { $$ = make_block_statement(yylineno, $1); }
+    | FallthroughStmt
+    | GotoStmt
+    | LabeledStmt
+    | Block                                 { $$ = make_block_statement(@$, $1); }
     | IfStmt
     | SwitchStmt
     | ForStmt
     | PrintStmt
     | PrintlnStmt
+    | DeferStmt
+    | GoStmt
+    | SelectStmt
     ;
 
 SimpleStmt : EmptyStmt
-    | ExpressionStmt
+    | NonEmptySimpleStmt
+    ;
+
+NonEmptySimpleStmt : ExpressionStmt
     | IncDecStmt
     | Assignment
     | ShortVarDecl
+    | SendStmt
     ;
 
 
@@ -519,30 +773,40 @@ SimpleStmt : EmptyStmt
 // ============================
 
 
-EmptyStmt: /*empty*/               { $$ = make_empty_statement(yylineno); }
+EmptyStmt: /*empty*/               { $$ = make_empty_statement(@$); }
     ;
 
 Block : '{' StatementList '}'   { $$ = $2; }
     ;
 
-StatementList: /*empty*/                       { $$ = make_statement_vec(); }
-             | StatementList Statement ';'  
-             { 
+// Like in Go, the semicolon can be left out after the last statement, which is how blocks fit
+// on one line
+StatementList: Statements
+             | Statements NonEmptyStatement
+             {
                 $$ = $1;
                 statement_vec_push($$, $2);
              }
              ;
 
-ExpressionStmt : Expression { $$ = make_expression_statement(yylineno, $1); }
+Statements: /*empty*/                       { $$ = make_statement_vec(); }
+          | Statements Statement ';'  
+          { 
+             $$ = $1;
+             statement_vec_push($$, $2);
+          }
+          ;
+
+ExpressionStmt : Expression { $$ = make_expression_statement(@$, $1); }
     ;
 
 
 Assignment: expression_list '=' expression_list
-                { $$ = make_assignment_statement(yylineno, $1, $3); }
+                { $$ = make_assignment_statement(@$, $1, $3); }
           | Expression add_assign_op Expression
-                { $$ = make_op_assignment_statement(yylineno, $1, $3, $2); }
+                { $$ = make_op_assignment_statement(@$, $1, $3, $2); }
           | Expression mul_assign_op Expression
-                { $$ = make_op_assignment_statement(yylineno, $1, $3, $2); }
+                { $$ = make_op_assignment_statement(@$, $1, $3, $2); }
     ;
 
 
@@ -565,44 +829,67 @@ mul_assign_op : tTIMESASSIGN      { $$ = opMul; }
 // declaration statements are just Declarations; they were done earlier
 
 ShortVarDecl : identifier_list tDEFINE expression_list
-             { $$ = make_short_var_declaration_statement(yylineno, $1, $3); }
+             { $$ = make_short_var_declaration_statement(@$, $1, $3); }
     ;
 
-IncDecStmt: Expression tINC { $$ = make_inc_dec_statement(yylineno, 0, $1); }
-    | Expression tDEC       { $$ = make_inc_dec_statement(yylineno, 1, $1); }
+IncDecStmt: Expression tINC { $$ = make_inc_dec_statement(@$, 0, $1); }
+    | Expression tDEC       { $$ = make_inc_dec_statement(@$, 1, $1); }
     ;
 
-PrintStmt: tPRINT '(' OptionalExpressionList ')' { $$ = make_print_statement(yylineno, $3); }
+PrintStmt: tPRINT '(' OptionalExpressionList ')' { $$ = make_print_statement(@$, $3); }
     ;
 
-PrintlnStmt: tPRINTLN '(' OptionalExpressionList ')' { $$ = make_println_statement(yylineno, $3); }
+PrintlnStmt: tPRINTLN '(' OptionalExpressionList ')' { $$ = make_println_statement(@$, $3); }
     ;
 
-ReturnStmt: tRETURN             { $$ = make_return_statement(yylineno, NULL); }
-          | tRETURN Expression  { $$ = make_return_statement(yylineno, $2); }
+// Print statements can be deferred like calls, since they are not expressions here
+DeferStmt: tDEFER ExpressionStmt   { $$ = make_defer_statement(@$, $2); }
+         | tDEFER PrintStmt        { $$ = make_defer_statement(@$, $2); }
+         | tDEFER PrintlnStmt      { $$ = make_defer_statement(@$, $2); }
+         ;
+
+// Like deferred calls, the arguments of a go statement are evaluated by the calling goroutine
+GoStmt: tGO ExpressionStmt         { $$ = make_go_statement(@$, $2); }
+      | tGO PrintStmt              { $$ = make_go_statement(@$, $2); }
+      | tGO PrintlnStmt            { $$ = make_go_statement(@$, $2); }
+      ;
+
+SendStmt: Expression tARROW Expression  { $$ = make_send_statement(@$, $1, $3); }
+        ;
+
+ReturnStmt: tRETURN                  { $$ = make_return_statement(@$, make_expression_vec()); }
+          | tRETURN expression_list  { $$ = make_return_statement(@$, $2); }
           ;
 
 
 IfStmt: tIF SimpleStmt ';' Expression Block ElseStmt 
-            { $$ = make_if_statement(yylineno, $2, $4, $5, $6); }
+            { $$ = make_if_statement(@$, $2, $4, $5, $6); }
       | tIF Expression Block ElseStmt
-            { $$ = make_if_statement(yylineno, make_empty_statement(yylineno), $2, $3, $4); }
+            { $$ = make_if_statement(@$, make_empty_statement(@1), $2, $3, $4); }
       ;
 
 ElseStmt: /*empty*/        { $$ = NULL; }
         | tELSE IfStmt  { $$ = $2; }
-        | tELSE Block   { $$ = make_block_statement(yylineno, $2); }
+        | tELSE Block   { $$ = make_block_statement(@$, $2); }
         ;
 
 
 SwitchStmt: tSWITCH SimpleStmt ';' Expression '{' CaseClauses '}'
-                { $$ = make_switch_statement(yylineno, $2, $4, $6); }
+                { $$ = make_switch_statement(@$, $2, $4, $6); }
           | tSWITCH Expression '{' CaseClauses '}'
-                { $$ = make_switch_statement(yylineno, make_empty_statement(yylineno), $2, $4); }
+                { $$ = make_switch_statement(@$, make_empty_statement(@1), $2, $4); }
           | tSWITCH SimpleStmt ';' '{' CaseClauses '}'
-                { $$ = make_switch_statement(yylineno, $2, NULL, $5); }
+                { $$ = make_switch_statement(@$, $2, NULL, $5); }
           | tSWITCH '{' CaseClauses '}'
-                { $$ = make_switch_statement(yylineno, make_empty_statement(yylineno), NULL, $3); }
+                { $$ = make_switch_statement(@$, make_empty_statement(@1), NULL, $3); }
+          | tSWITCH SimpleStmt ';' tIDENTIFIER tDEFINE PrimaryExpr '.' '(' tTYPE ')' '{' TypeCaseClauses '}'
+                { $$ = make_type_switch_statement(@$, $2, $4, $6, $12); }
+          | tSWITCH tIDENTIFIER tDEFINE PrimaryExpr '.' '(' tTYPE ')' '{' TypeCaseClauses '}'
+                { $$ = make_type_switch_statement(@$, make_empty_statement(@1), $2, $4, $10); }
+          | tSWITCH SimpleStmt ';' PrimaryExpr '.' '(' tTYPE ')' '{' TypeCaseClauses '}'
+                { $$ = make_type_switch_statement(@$, $2, NULL, $4, $10); }
+          | tSWITCH PrimaryExpr '.' '(' tTYPE ')' '{' TypeCaseClauses '}'
+                { $$ = make_type_switch_statement(@$, make_empty_statement(@1), NULL, $2, $8); }
     ;
 
 CaseClauses: /*empty*/                 { $$ = make_case_clause_vec(); }
@@ -613,29 +900,75 @@ CaseClauses: /*empty*/                 { $$ = make_case_clause_vec(); }
                 }
            ;
 
-CaseClause: tCASE expression_list ':' StatementList    { $$ = make_case_clause(yylineno, $2, $4); }
-          | tDEFAULT ':' StatementList    { $$ = make_case_clause(yylineno, NULL, $3); }
+CaseClause: tCASE expression_list ':' StatementList    { $$ = make_case_clause(@$, $2, $4); }
+          | tDEFAULT ':' StatementList    { $$ = make_case_clause(@$, NULL, $3); }
+    ;
+
+TypeCaseClauses: /*empty*/             { $$ = make_case_clause_vec(); }
+           | TypeCaseClauses TypeCaseClause
+                {
+                $$ = $1;
+                case_clause_vec_push($$, $2);
+                }
+           ;
+
+// `nil` is parsed as a type name
+TypeCaseClause: tCASE TypeList ':' StatementList    { $$ = make_type_case_clause(@$, $2, $4); }
+          | tDEFAULT ':' StatementList    { $$ = make_type_case_clause(@$, NULL, $3); }
+    ;
+
+
+SelectStmt: tSELECT '{' CommClauses '}'   { $$ = make_select_statement(@$, $3); }
+          ;
+
+CommClauses: /*empty*/                 { $$ = make_case_clause_vec(); }
+           | CommClauses CommClause
+                {
+                $$ = $1;
+                case_clause_vec_push($$, $2);
+                }
+           ;
+
+// Any simple statement is parsed here; make_comm_clause only accepts sends and receives
+CommClause: tCASE SimpleStmt ':' StatementList    { $$ = make_comm_clause(@$, $2, $4); }
+          | tDEFAULT ':' StatementList    { $$ = make_comm_clause(@$, NULL, $3); }
     ;
 
 
 ForStmt: tFOR Block                         {
-       $$ = make_for_statement(yylineno,make_empty_statement(yylineno),NULL,make_empty_statement(yylineno), $2);
+       $$ = make_for_statement(@$,make_empty_statement(@1),NULL,make_empty_statement(@1), $2);
        }
     | tFOR Expression Block                 {
-       $$ = make_for_statement(yylineno,make_empty_statement(yylineno),$2,make_empty_statement(yylineno), $3);
+       $$ = make_for_statement(@$,make_empty_statement(@1),$2,make_empty_statement(@1), $3);
         }
     | tFOR SimpleStmt ';' Expression ';' SimpleStmt Block
-        { $$ = make_for_statement(yylineno, $2, $4, $6, $7); }
+        { $$ = make_for_statement(@$, $2, $4, $6, $7); }
     | tFOR SimpleStmt ';' ';' SimpleStmt Block
-        { $$ = make_for_statement(yylineno, $2, NULL, $5, $6); }
+        { $$ = make_for_statement(@$, $2, NULL, $5, $6); }
+    | tFOR tRANGE Expression Block
+        { $$ = make_for_range_statement(@$, NULL, $3, $4); }
+    | tFOR identifier_list tDEFINE tRANGE Expression Block
+        { $$ = make_for_range_statement(@$, $2, $5, $6); }
     ;
 
 // SimpleStmt can be empty, so not explicitly making them optional should be fine
 
-BreakStmt: tBREAK           { $$ = make_break_statement(yylineno); }
+BreakStmt: tBREAK           { $$ = make_break_statement(@$, NULL); }
+    | tBREAK tIDENTIFIER       { $$ = make_break_statement(@$, $2); }
+    ;
+
+ContinueStmt: tCONTINUE     { $$ = make_continue_statement(@$, NULL); }
+    | tCONTINUE tIDENTIFIER    { $$ = make_continue_statement(@$, $2); }
+    ;
+
+GotoStmt: tGOTO tIDENTIFIER { $$ = make_goto_statement(@$, $2); }
+    ;
+
+// Labels can be on any statement, but only labels of loops and switches can be broken out of
+LabeledStmt: tIDENTIFIER ':' Statement { $$ = make_labeled_statement(@$, $1, $3); }
     ;
 
-ContinueStmt: tCONTINUE     { $$ = make_continue_statement(yylineno); }
+FallthroughStmt: tFALLTHROUGH { $$ = make_fallthrough_statement(@$); }
     ;
 
 
@@ -647,20 +980,26 @@ ContinueStmt: tCONTINUE     { $$ = make_continue_statement(yylineno); }
 
 Expression: UnaryExpr                                    
           | Expression tOR Expression                    
-            {$$ = make_binary_operation_expression(yylineno, opOr, $1, $3); }
+            {$$ = make_binary_operation_expression(@$, opOr, $1, $3); }
           | Expression tAND Expression                   
-            {$$ = make_binary_operation_expression(yylineno, opAnd, $1, $3); }
+            {$$ = make_binary_operation_expression(@$, opAnd, $1, $3); }
           | Expression rel_op Expression %prec REL_PREC  
-            {$$ = make_binary_operation_expression(yylineno, $2, $1, $3); }
+            {$$ = make_binary_operation_expression(@$, $2, $1, $3); }
           | Expression add_op Expression %prec ADD_PREC  
-            {$$ = make_binary_operation_expression(yylineno, $2, $1, $3); }
+            {$$ = make_binary_operation_expression(@$, $2, $1, $3); }
           | Expression mul_op Expression %prec MUL_PREC  
-            {$$ = make_binary_operation_expression(yylineno, $2, $1, $3); }
+            {$$ = make_binary_operation_expression(@$, $2, $1, $3); }
           ;
 
 UnaryExpr: PrimaryExpr                                  
          | unary_op UnaryExpr %prec UNARY_PREC 
-            { $$ = make_unary_operation_expression(yylineno, $1, $2) ; }
+            { $$ = make_unary_operation_expression(@$, $1, $2) ; }
+         | '*' UnaryExpr %prec UNARY_PREC
+            { $$ = make_dereference_expression(@$, $2) ; }
+         | '&' UnaryExpr %prec UNARY_PREC
+            { $$ = make_address_of_expression(@$, $2) ; }
+         | tARROW UnaryExpr %prec UNARY_PREC
+            { $$ = make_receive_expression(@$, $2) ; }
          ;
 
 rel_op: tEQUAL              { $$ = opEq ; }
@@ -693,21 +1032,47 @@ unary_op: '+'               { $$ = opPlus ; }
         ;
 
 Operand: Literal
-       | tIDENTIFIER        { $$ = make_identifier_expression(yylineno, $1); }
+       | CompositeLit
+       | FunctionLit
+       | tIDENTIFIER        { $$ = make_identifier_expression(@$, $1); }
        | '(' Expression ')' { $$ = $2 ; }
        ;
 
-Literal: tINTVAL            {$$ = make_literal_expression(yylineno, $1, kInt);}
-       | tFLOATVAL          {$$ = make_literal_expression(yylineno, $1, kFloat);}
-       | tRUNEVAL           {$$ = make_literal_expression(yylineno, $1, kRune);}
-       | tSTRINGVAL         {$$ = make_literal_expression(yylineno, $1, kString);}
+Literal: tINTVAL            {$$ = make_literal_expression(@$, $1, kInt);}
+       | tFLOATVAL          {$$ = make_literal_expression(@$, $1, kFloat);}
+       | tRUNEVAL           {$$ = make_literal_expression(@$, $1, kRune);}
+       | tSTRINGVAL         {$$ = make_literal_expression(@$, $1, kString);}
        ;
 
 PrimaryExpr: Operand
-           | PrimaryExpr Selector   { $$ = make_selector_expression(yylineno, $1, $2) ; }
-           | PrimaryExpr Index      { $$ = make_index_expression(yylineno, $1, $2) ; }
+           | PrimaryExpr Selector   { $$ = make_selector_expression(@$, $1, $2) ; }
+           | PrimaryExpr Index      { $$ = make_index_expression(@$, $1, $2) ; }
+           | PrimaryExpr '[' ':' ']'
+                { $$ = make_slice_expression(@$, $1, NULL, NULL, NULL) ; }
+           | PrimaryExpr '[' Expression ':' ']'
+                { $$ = make_slice_expression(@$, $1, $3, NULL, NULL) ; }
+           | PrimaryExpr '[' ':' Expression ']'
+                { $$ = make_slice_expression(@$, $1, NULL, $4, NULL) ; }
+           | PrimaryExpr '[' Expression ':' Expression ']'
+                { $$ = make_slice_expression(@$, $1, $3, $5, NULL) ; }
+           | PrimaryExpr '[' ':' Expression ':' Expression ']'
+                { $$ = make_slice_expression(@$, $1, NULL, $4, $6) ; }
+           | PrimaryExpr '[' Expression ':' Expression ':' Expression ']'
+                { $$ = make_slice_expression(@$, $1, $3, $5, $7) ; }
            | AppendExpr             { $$ = $1 ; }
-           | PrimaryExpr Arguments  { $$ = make_function_call_expression(yylineno, $1, $2) ; }
+           | LenExpr                { $$ = $1 ; }
+           | CapExpr                { $$ = $1 ; }
+           | MakeExpr               { $$ = $1 ; }
+           | DeleteExpr             { $$ = $1 ; }
+           | NewExpr                { $$ = $1 ; }
+           | PanicExpr              { $$ = $1 ; }
+           | CloseExpr              { $$ = $1 ; }
+           | CopyExpr               { $$ = $1 ; }
+           | PrimaryExpr Arguments  { $$ = make_function_call_expression(@$, $1, $2) ; }
+           | PrimaryExpr '(' expression_list tELLIPSIS ')'
+                { $$ = make_spread_call_expression(@$, $1, $3) ; }
+           | PrimaryExpr '.' '(' Type ')'
+                { $$ = make_type_assertion_expression(@$, $1, $4) ; }
            ;
 
 Selector: '.' tIDENTIFIER   { $$ = $2 ; }
@@ -720,9 +1085,89 @@ Arguments: '(' OptionalExpressionList ')' { $$ = $2 ; }
          ;
 
 AppendExpr: tAPPEND '(' Expression ',' Expression ')' 
-          { $$ = make_append_expression(yylineno, $3, $5); }
+          { $$ = make_append_expression(@$, $3, $5); }
+          ;
+
+LenExpr: tLEN '(' Expression ')'    { $$ = make_len_expression(@$, $3); }
+       ;
+
+CapExpr: tCAP '(' Expression ')'    { $$ = make_cap_expression(@$, $3); }
+       ;
+
+MakeExpr: tMAKE '(' Type ')'
+            { $$ = make_make_expression(@$, $3, make_expression_vec()); }
+        | tMAKE '(' Type ',' expression_list ')'
+            { $$ = make_make_expression(@$, $3, $5); }
+        ;
+
+DeleteExpr: tDELETE '(' Expression ',' Expression ')'
+          { $$ = make_delete_expression(@$, $3, $5); }
           ;
 
+NewExpr: tNEW '(' Type ')'          { $$ = make_new_expression(@$, $3); }
+       ;
+
+PanicExpr: tPANIC '(' Expression ')'    { $$ = make_panic_expression(@$, $3); }
+         ;
+
+CloseExpr: tCLOSE '(' Expression ')'    { $$ = make_close_expression(@$, $3); }
+         ;
+
+CopyExpr: tCOPY '(' Expression ',' Expression ')'
+        { $$ = make_copy_expression(@$, $3, $5); }
+        ;
+
+
+// FUNCTION LITERALS
+// ============================
+
+FunctionLit: tFUNC FuncParameters FuncResult Block
+           { $$ = make_function_literal_expression(@$, $2, $3, $4); }
+           ;
+
+
+// COMPOSITE LITERALS
+// ============================
+
+CompositeLit: LiteralType LiteralValue  { $$ = make_composite_literal_expression(@$, $1, $2); }
+            ;
+
+// The length of `[...]T` is the number of elements of the literal, given by the typechecker
+LiteralType: StructType
+           | ArrayType
+           | '[' tELLIPSIS ']' Type     { $$ = make_array_kind(@$, $4, "..."); }
+           | SliceType
+           | MapType
+           | TypeName
+           ;
+
+// A trailing comma is allowed, so that the closing brace can go on its own line
+LiteralValue: '{' '}'                       { $$ = make_keyed_element_vec(); }
+            | '{' KeyedElementList '}'      { $$ = $2; }
+            | '{' KeyedElementList ',' '}'  { $$ = $2; }
+            ;
+
+KeyedElementList: KeyedElement
+                {
+                $$ = make_keyed_element_vec();
+                keyed_element_vec_push($$, $1);
+                }
+                | KeyedElementList ',' KeyedElement
+                {
+                $$ = $1;
+                keyed_element_vec_push($$, $3);
+                }
+                ;
+
+KeyedElement: Element ':' Element           { $$ = make_keyed_element(@$, $1, $3); }
+            | Element                       { $$ = make_keyed_element(@$, NULL, $1); }
+            ;
+
+// The type of a literal can be left out inside another literal, like `[]point{{1, 2}}`
+Element: Expression
+       | LiteralValue       { $$ = make_composite_literal_expression(@$, NULL, $1); }
+       ;
+
 
 // Type casts are syntactically function calls
 
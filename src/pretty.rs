@@ -1,4 +1,4 @@
-use ast::*;
+use crate::ast::*;
 
 
 fn indent_print(text: &str, indent: i32) {
@@ -33,6 +33,14 @@ fn pretty_print_top_level_declaration(node: &TopLevelDeclarationNode){
             }
             print!(")");
         },
+        TopLevelDeclarationVariant::ConstDeclarations { ref declarations } => {
+            println!("const (");
+            for const_spec in declarations.iter(){
+                pretty_print_var_declaration(const_spec, 1);
+                println!()
+            }
+            print!(")");
+        },
         TopLevelDeclarationVariant::TypeDeclarations { ref declarations } => {
             println!("type (");
             for type_spec in declarations.iter(){
@@ -41,8 +49,8 @@ fn pretty_print_top_level_declaration(node: &TopLevelDeclarationNode){
             }
             print!(")");
         },
-        TopLevelDeclarationVariant::FunctionDeclaration (Function{ ref name, ref parameters, ref return_kind, ref body} )=>  {
-            pretty_print_function_declaration(&name, &parameters, &return_kind, &body);
+        TopLevelDeclarationVariant::FunctionDeclaration (Function{ ref name, ref receiver, ref parameters, ref results, ref body} )=>  {
+            pretty_print_function_declaration(name, receiver, parameters, results, body);
         }
     }
 }
@@ -104,11 +112,31 @@ fn pretty_print_type_declaration(type_spec: &TypeSpec, indent: i32){
 
 /// Pretty print function declarations
 fn pretty_print_function_declaration(name: &String,
-                                     parameters: &Vec<Field>,
-                                     return_kind: &Option<Box<AstKindNode>>,
+                                     receiver: &Option<Receiver>,
+                                     parameters: &[Field],
+                                     results: &[Field],
                                      body: &Vec<StatementNode> ) {
 
-    print!("func {} (", name);
+    print!("func ");
+    if let Some(receiver) = receiver {
+        print!("({} {}", receiver.name, if receiver.pointer { "*" } else { "" });
+        pretty_print_ast_kind(&receiver.kind, 0);
+        print!(") ");
+    }
+    print!("{} ", name);
+    pretty_print_signature(parameters, results);
+
+    println!(" {{");
+
+    pretty_print_statement_vector(body, 1);
+
+    print!("}}");
+
+}
+
+/// Pretty prints the parameters and results of a function declaration, literal or type
+fn pretty_print_signature(parameters: &[Field], results: &[Field]) {
+    print!("(");
 
     let len = parameters.len();
     let mut count = 0;
@@ -125,19 +153,21 @@ fn pretty_print_function_declaration(name: &String,
 
     print!(") ");
 
-    match return_kind {
-        &Some(ref k) => {
-            pretty_print_ast_kind(k, 1)
-        },
-        &None =>()
+    // A single unnamed result is the only one printed without parentheses
+    match results.len() {
+        0 => (),
+        1 if results[0].identifiers.is_empty() => pretty_print_ast_kind(&results[0].kind, 1),
+        _ => {
+            print!("(");
+            for (count, r) in results.iter().enumerate() {
+                pretty_print_field(r, 1);
+                if count < results.len() - 1 {
+                    print!(", ")
+                }
+            }
+            print!(")");
+        }
     }
-
-    println!(" {{");
-
-    pretty_print_statement_vector(body, 1);
-
-    print!("}}");
-
 }
 
 /// Pretty prints ast kinds such as identifier, slices, arrays, and structs
@@ -162,6 +192,38 @@ fn pretty_print_ast_kind(kind: &AstKindNode, indent: i32){
             indent_print("", indent);
             print!("}}");
         }
+        AstKindVariant::Map { key, value } => {
+            print!("map[");
+            pretty_print_ast_kind(key, indent);
+            print!("]");
+            pretty_print_ast_kind(value, indent)
+        }
+        AstKindVariant::Function { parameters, results } => {
+            print!("func ");
+            pretty_print_signature(parameters, results);
+        }
+        AstKindVariant::Pointer { base } => {
+            print!("*");
+            pretty_print_ast_kind(base, indent)
+        }
+        AstKindVariant::Chan { base } => {
+            print!("chan ");
+            pretty_print_ast_kind(base, indent)
+        }
+        AstKindVariant::Interface { methods } => {
+            println!("interface {{");
+            for method in methods.iter() {
+                indent_print("", indent + 1);
+                // Methods are written like function types, with their name instead of `func`
+                print!("{}", method.identifiers[0]);
+                if let AstKindVariant::Function { ref parameters, ref results } = method.kind.variant {
+                    pretty_print_signature(parameters, results);
+                }
+                println!()
+            }
+            indent_print("", indent);
+            print!("}}");
+        }
     }
 }
 
@@ -182,8 +244,14 @@ fn pretty_print_field(field: &Field, indent: i32){
 
         count = count + 1;
     }
-    print!(" ");
+    // Unnamed function results are only a type
+    if len > 0 {
+        print!(" ");
+    }
 
+    if field.variadic {
+        print!("...");
+    }
     pretty_print_ast_kind(&field.kind, indent);
 }
 
@@ -232,6 +300,14 @@ fn pretty_print_statement(stmt: &StatementNode, indent: i32) {
             }
             indent_print(")", indent);
         },
+        StatementVariant::ConstDeclarations { ref declarations } => {
+            println!("const (");
+            for decl in declarations.iter() {
+                pretty_print_var_declaration(decl, indent+1);
+                println!();
+            }
+            indent_print(")", indent);
+        },
         StatementVariant::TypeDeclarations { ref declarations } => {
             println!("type (");
             for decl in declarations.iter() {
@@ -320,6 +396,22 @@ fn pretty_print_statement(stmt: &StatementNode, indent: i32) {
             indent_print("", indent);
             print!("}}");
         },
+        StatementVariant::ForRange {ref key, ref value, ref expr, ref body } => {
+            print!("for ");
+            match (key, value) {
+                (Some(key), Some(value)) => print!("{}, {} := ", key, value),
+                (Some(key), None) => print!("{} := ", key),
+                _ => {},
+            }
+            print!("range ");
+            pretty_print_expression(expr);
+            println!(" {{");
+
+            pretty_print_statement_vector(body, indent+1);
+
+            indent_print("", indent);
+            print!("}}");
+        },
         StatementVariant::Switch { ref init, ref expr, ref body } => {
             print!("switch ");
 
@@ -343,17 +435,67 @@ fn pretty_print_statement(stmt: &StatementNode, indent: i32) {
             indent_print("", indent);
             print!("}}");
         },
-        StatementVariant::Break => print!("break"),
-        StatementVariant::Continue => print!("continue"),
-        StatementVariant::Return(ref expr) => {
+        StatementVariant::TypeSwitch { ref init, ref binding, ref expr, ref body } => {
+            print!("switch ");
+
+            if let StatementVariant::Empty = init.variant {
+            } else {
+                pretty_print_statement(init, 0);
+                print!("; ");
+            }
+
+            if let Some(ref binding) = *binding {
+                print!("{} := ", binding);
+            }
+            pretty_print_expression(expr);
+            println!(".(type) {{");
+
+            for case_clause in body.iter(){
+                pretty_print_case_clause(case_clause, indent+1);
+            }
+
+            indent_print("", indent);
+            print!("}}");
+        },
+        StatementVariant::Break(None) => print!("break"),
+        StatementVariant::Break(Some(ref label)) => print!("break {}", label),
+        StatementVariant::Continue(None) => print!("continue"),
+        StatementVariant::Continue(Some(ref label)) => print!("continue {}", label),
+        StatementVariant::Goto(ref label) => print!("goto {}", label),
+        StatementVariant::Fallthrough => print!("fallthrough"),
+        StatementVariant::Return(ref exprs) => {
             print!("return");
-            match expr {
-                &Some(ref e) => {
-                    print!(" ");
-                    pretty_print_expression(&*e);
-                }
-                &None => ()
+            if !exprs.is_empty() {
+                print!(" ");
+                pretty_print_expression_list(exprs);
+            }
+        }
+        StatementVariant::Defer(ref call) => {
+            print!("defer ");
+            pretty_print_statement(call, indent);
+        }
+        StatementVariant::Labeled { ref label, ref statement } => {
+            print!("{}: ", label);
+            pretty_print_statement(statement, indent);
+        }
+        StatementVariant::Go(ref call) => {
+            print!("go ");
+            pretty_print_statement(call, indent);
+        }
+        StatementVariant::Send { ref channel, ref value } => {
+            pretty_print_expression(channel);
+            print!(" <- ");
+            pretty_print_expression(value);
+        }
+        StatementVariant::Select { ref body } => {
+            println!("select {{");
+
+            for case_clause in body.iter(){
+                pretty_print_case_clause(case_clause, indent+1);
             }
+
+            indent_print("", indent);
+            print!("}}");
         }
     }
 }
@@ -381,12 +523,27 @@ fn comma_separated_expressions(v: &Vec<Expression>) {
 fn pretty_print_switch_case(switch_case: &SwitchCase, indent: i32){
     indent_print("", indent);
     match switch_case {
-        &SwitchCase::Default => print!("default: "),
-        &SwitchCase::Cases(ref v) => {
+        SwitchCase::Default => print!("default: "),
+        SwitchCase::Cases(v) => {
             print!("case ");
             comma_separated_expressions(v);
             print!(": ");
         }
+        SwitchCase::Kinds(kinds) => {
+            print!("case ");
+            for (count, kind) in kinds.iter().enumerate() {
+                pretty_print_ast_kind(kind, indent);
+                if count < kinds.len() - 1 {
+                    print!(", ");
+                }
+            }
+            print!(": ");
+        }
+        SwitchCase::Comm(comm) => {
+            print!("case ");
+            pretty_print_statement(comm, indent);
+            print!(": ");
+        }
     }
 }
 
@@ -432,17 +589,47 @@ fn pretty_print_expression(expr: &Expression){
                 }
             }
         },
+        // Parenthesized so that they can be the primary of a selector, index or call
+        ExpressionVariant::Dereference { ref expr } => {
+            print!("(*");
+            pretty_print_expression(expr);
+            print!(")");
+        },
+        ExpressionVariant::AddressOf { ref expr } => {
+            print!("(&");
+            pretty_print_expression(expr);
+            print!(")");
+        },
+        ExpressionVariant::Nil => print!("nil"),
         ExpressionVariant::Index { ref primary, ref index } => {
-            pretty_print_expression(&*primary);
+            pretty_print_expression(primary);
             print!("[");
             pretty_print_expression(&*index);
             print!("]");
         },
-        ExpressionVariant::Selector { ref primary, ref name} => {
+        ExpressionVariant::Slice { ref primary, ref low, ref high, ref max } => {
+            pretty_print_expression(primary);
+            print!("[");
+            if let Some(ref low) = *low {
+                pretty_print_expression(low);
+            }
+            print!(":");
+            if let Some(ref high) = *high {
+                pretty_print_expression(high);
+            }
+            if let Some(ref max) = *max {
+                print!(":");
+                pretty_print_expression(max);
+            }
+            print!("]");
+        },
+        ExpressionVariant::Selector { ref primary, ref name} |
+        ExpressionVariant::Method { receiver: ref primary, ref name, .. } |
+        ExpressionVariant::InterfaceMethod { receiver: ref primary, ref name } => {
             pretty_print_expression(&*primary);
             print!(".{}", name);
         },
-        ExpressionVariant::FunctionCall { ref primary, ref arguments } => {
+        ExpressionVariant::FunctionCall { ref primary, ref arguments, spread } => {
             pretty_print_expression(&*primary);
             print!("(");
 
@@ -459,6 +646,10 @@ fn pretty_print_expression(expr: &Expression){
                 count = count + 1;
             }
 
+            if spread {
+                print!("...");
+            }
+
             print!(" )");
         },
         ExpressionVariant::Append { ref lhs, ref rhs } => {
@@ -468,11 +659,102 @@ fn pretty_print_expression(expr: &Expression){
             pretty_print_expression(&*rhs);
             print!(" )");
         },
+        ExpressionVariant::Len { ref expr } => {
+            print!("len( ");
+            pretty_print_expression(expr);
+            print!(" )");
+        },
+        ExpressionVariant::Cap { ref expr } => {
+            print!("cap( ");
+            pretty_print_expression(expr);
+            print!(" )");
+        },
         ExpressionVariant::TypeCast {ref name, ref expr} => {
             print!("{}( ",name);
             pretty_print_expression(expr);
             print!(" )");
         }
+        ExpressionVariant::Make { ref kind, ref arguments } => {
+            print!("make( ");
+            pretty_print_ast_kind(kind, 0);
+
+            for arg in arguments.iter() {
+                print!(" , ");
+                pretty_print_expression(arg);
+            }
+
+            print!(" )");
+        },
+        ExpressionVariant::New { ref kind } => {
+            print!("new( ");
+            pretty_print_ast_kind(kind, 0);
+            print!(" )");
+        },
+        ExpressionVariant::Panic { ref expr } => {
+            print!("panic( ");
+            pretty_print_expression(expr);
+            print!(" )");
+        },
+        ExpressionVariant::Delete { ref map, ref key } => {
+            print!("delete( ");
+            pretty_print_expression(map);
+            print!(" , ");
+            pretty_print_expression(key);
+            print!(" )");
+        },
+        ExpressionVariant::CompositeLiteral { ref kind, ref elements } => {
+            if let Some(ref kind) = *kind {
+                pretty_print_ast_kind(kind, 0);
+            }
+            print!("{{ ");
+
+            let len = elements.len();
+
+            for (count, element) in elements.iter().enumerate() {
+                if let Some(ref key) = element.key {
+                    pretty_print_expression(key);
+                    print!(" : ");
+                }
+                pretty_print_expression(&element.value);
+
+                if count < len - 1 {
+                    print!(" , ")
+                }
+            }
+
+            print!(" }}");
+        },
+        ExpressionVariant::CommaOk { ref expr } |
+        ExpressionVariant::ToInterface { ref expr } => pretty_print_expression(expr),
+        ExpressionVariant::TypeAssertion { ref expr, ref kind } => {
+            pretty_print_expression(expr);
+            print!(".(");
+            pretty_print_ast_kind(kind, 0);
+            print!(")");
+        },
+        ExpressionVariant::Receive { ref channel } => {
+            print!("<-");
+            pretty_print_expression(channel);
+        },
+        ExpressionVariant::Close { ref channel } => {
+            print!("close( ");
+            pretty_print_expression(channel);
+            print!(" )");
+        },
+        ExpressionVariant::Copy { ref dst, ref src } => {
+            print!("copy( ");
+            pretty_print_expression(dst);
+            print!(" , ");
+            pretty_print_expression(src);
+            print!(" )");
+        },
+        ExpressionVariant::FunctionLiteral { ref function } => {
+            print!("func ");
+            pretty_print_signature(&function.parameters, &function.results);
+            println!(" {{");
+            pretty_print_statement_vector(&function.body, 1);
+            print!("}}");
+        }
     }
 }
 
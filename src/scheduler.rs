@@ -0,0 +1,473 @@
+//! The goroutines of the interpreter and the channels they communicate through. Only the
+//! goroutine with the turn runs: the turn goes to another goroutine when it blocks on a channel
+//! or finishes. The goroutine that gets it is picked at random among the runnable ones, from a
+//! seed, so that a run can be repeated.
+//!
+//! The functions of the interpreter that can reach a channel operation are async, so a goroutine
+//! is a future. Blocking gives `Poll::Pending` back up through the calls the goroutine is in, and
+//! its future picks up from there when it is polled again. `run` polls the goroutine with the
+//! turn until main returns, all on the thread of the interpreter. A goroutine is only its task
+//! until it first gets the turn, and its future is dropped once it finishes.
+
+use crate::interpret::{Interpretation, RuntimeResult};
+use crate::value::Value;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::fmt;
+use std::future;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Status {
+    Runnable,
+    Blocked,
+    Done,
+}
+
+/// Why a goroutine waiting on a channel was stopped instead
+#[derive(Debug)]
+pub enum Interruption {
+    /// The channel it sends on was closed
+    Closed,
+    /// Every goroutine is blocked. Only the main goroutine is stopped with this.
+    Deadlock,
+}
+
+struct State<T> {
+    // The goroutine with the turn. Goroutines are numbered from 1, the main one.
+    running: usize,
+    statuses: Vec<Status>,
+    runnable: Runnable,
+    // The tasks of the goroutines that haven't got the turn yet
+    tasks: Vec<Option<T>>,
+    // xorshift32, the same generator as the JavaScript runtime
+    random: u32,
+    deadlock: bool,
+}
+
+impl<T> State<T> {
+    fn choose(&mut self, n: usize) -> usize {
+        if n <= 1 {
+            return 0;
+        }
+        self.random ^= self.random << 13;
+        self.random ^= self.random >> 17;
+        self.random ^= self.random << 5;
+        (self.random % n as u32) as usize
+    }
+
+    fn set_status(&mut self, id: usize, status: Status) {
+        let was_runnable = self.statuses[id - 1] == Status::Runnable;
+        self.statuses[id - 1] = status;
+        if was_runnable != (status == Status::Runnable) {
+            self.runnable.update(id, !was_runnable);
+        }
+    }
+
+    // Picks a runnable goroutine for the turn, or the main one to report a deadlock
+    fn switch(&mut self) {
+        if self.runnable.len == 0 {
+            self.deadlock = true;
+            self.running = 1;
+        } else {
+            let i = self.choose(self.runnable.len);
+            self.running = self.runnable.nth(i);
+        }
+    }
+}
+
+// The runnable goroutines, counted in a Fenwick tree over their ids. Picking the one with a given
+// rank among them, in the order of their ids like the JavaScript runtime, then doesn't go through
+// all the goroutines, of which there can be many blocked ones.
+struct Runnable {
+    // The element for id `i` counts the runnable goroutines with ids in `(i - lowbit(i), i]`
+    tree: Vec<usize>,
+    len: usize,
+}
+
+impl Runnable {
+    // The number of runnable goroutines with ids up to `id`
+    fn prefix(&self, mut id: usize) -> usize {
+        let mut count = 0;
+        while id > 0 {
+            count += self.tree[id - 1];
+            id &= id - 1;
+        }
+        count
+    }
+
+    // Adds a runnable goroutine with the next id
+    fn push(&mut self) {
+        let id = self.tree.len() + 1;
+        let low = id & id.wrapping_neg();
+        let covered = self.prefix(id - 1) - self.prefix(id - low);
+        self.tree.push(covered + 1);
+        self.len += 1;
+    }
+
+    fn update(&mut self, mut id: usize, runnable: bool) {
+        if runnable {
+            self.len += 1;
+        } else {
+            self.len -= 1;
+        }
+        while id <= self.tree.len() {
+            if runnable {
+                self.tree[id - 1] += 1;
+            } else {
+                self.tree[id - 1] -= 1;
+            }
+            id += id & id.wrapping_neg();
+        }
+    }
+
+    // The id of the runnable goroutine with `rank` runnable ones before it
+    fn nth(&self, mut rank: usize) -> usize {
+        let mut id = 0;
+        let mut step = self.tree.len().next_power_of_two();
+        while step > 0 {
+            if id + step <= self.tree.len() && self.tree[id + step - 1] <= rank {
+                id += step;
+                rank -= self.tree[id - 1];
+            }
+            step /= 2;
+        }
+        id + 1
+    }
+}
+
+/// Runs the goroutines, which start from a task of type `T`
+pub struct Scheduler<T> {
+    state: RefCell<State<T>>,
+}
+
+impl<T> Scheduler<T> {
+    pub fn new(seed: u32) -> Scheduler<T> {
+        let random = seed ^ 0x2545F491;
+        Scheduler {
+            state: RefCell::new(State {
+                running: 1,
+                statuses: vec![Status::Runnable],
+                runnable: Runnable { tree: vec![1], len: 1 },
+                tasks: vec![None],
+                random: if random == 0 { 1 } else { random },
+                deadlock: false,
+            }),
+        }
+    }
+
+    /// The goroutine with the turn
+    pub fn current(&self) -> usize {
+        self.state.borrow().running
+    }
+
+    /// Picks one of `n` choices at random
+    pub fn choose(&self, n: usize) -> usize {
+        self.state.borrow_mut().choose(n)
+    }
+
+    /// Adds a goroutine running `task`. It gets the turn when the current goroutine blocks or
+    /// finishes and it is picked.
+    pub fn spawn(&self, task: T) {
+        let mut state = self.state.borrow_mut();
+        state.statuses.push(Status::Runnable);
+        state.runnable.push();
+        state.tasks.push(Some(task));
+    }
+
+    /// Stops the current goroutine until another one wakes it up and it gets the turn back
+    pub async fn block(&self) -> Result<(), Interruption> {
+        let id = self.current();
+        let mut blocked = false;
+        future::poll_fn(|_| {
+            let mut state = self.state.borrow_mut();
+            if !blocked {
+                blocked = true;
+                state.set_status(id, Status::Blocked);
+                state.switch();
+                Poll::Pending
+            } else if state.deadlock {
+                Poll::Ready(Err(Interruption::Deadlock))
+            } else {
+                Poll::Ready(Ok(()))
+            }
+        }).await
+    }
+
+    /// Lets a blocked goroutine run again once it gets the turn
+    pub fn wake(&self, id: usize) {
+        let mut state = self.state.borrow_mut();
+        if state.statuses[id - 1] == Status::Blocked {
+            state.set_status(id, Status::Runnable);
+        }
+    }
+
+    /// Runs the goroutines until main returns or another goroutine panics, and gives the result
+    /// of main or that panic. The future of a goroutine is made from its task by `start` when it
+    /// first gets the turn. Those still running when the program ends are dropped.
+    pub fn run<'a, F>(&self, main: Interpretation<'a, ()>, start: F) -> RuntimeResult<()>
+    where F: Fn(T) -> Interpretation<'a, ()> {
+        let mut goroutines = vec![Some(main)];
+        let mut context = Context::from_waker(Waker::noop());
+        loop {
+            let id = self.current();
+            let task = self.state.borrow_mut().tasks[id - 1].take();
+            if let Some(task) = task {
+                if goroutines.len() < id {
+                    goroutines.resize_with(id, || None);
+                }
+                goroutines[id - 1] = Some(start(task));
+            }
+            let goroutine = goroutines[id - 1].as_mut().expect("A goroutine got the turn after it finished");
+            // Until it blocks or finishes, the goroutine keeps the turn
+            if let Poll::Ready(result) = goroutine.as_mut().poll(&mut context) {
+                if id == 1 {
+                    return result;
+                }
+                if let Err(mut panic) = result {
+                    panic.goroutine = id;
+                    return Err(panic);
+                }
+                goroutines[id - 1] = None;
+                let mut state = self.state.borrow_mut();
+                state.set_status(id, Status::Done);
+                state.switch();
+            }
+        }
+    }
+
+    /// Sends a value on a channel, or blocks until it can
+    pub async fn send(&self, channel: Option<&Channel>, v: Value) -> Result<(), Interruption> {
+        self.select(&[Case::Send(channel.cloned(), v)], false).await.map(|_| ())
+    }
+
+    /// Receives a value from a channel, or blocks until there is one. The zero value and false
+    /// are given once the channel is closed and empty.
+    pub async fn receive(&self, channel: Option<&Channel>) -> Result<(Value, bool), Interruption> {
+        self.select(&[Case::Receive(channel.cloned())], false).await
+            .map(|chosen| { let (_, v, ok) = chosen.unwrap(); (v, ok) })
+    }
+
+    /// Carries out one of the cases that can go on, chosen at random, or gives None if none can
+    /// and there is a default case. Otherwise the goroutine blocks until one can. The index of
+    /// the case is given with the value received and whether it was sent.
+    pub async fn select(&self, cases: &[Case], has_default: bool)
+        -> Result<Option<(usize, Value, bool)>, Interruption> {
+        let ready: Vec<usize> = (0..cases.len()).filter(|&i| cases[i].is_ready()).collect();
+        if !ready.is_empty() {
+            let i = ready[self.choose(ready.len())];
+            return cases[i].complete(self).map(|(v, ok)| Some((i, v, ok)));
+        }
+        if has_default {
+            return Ok(None);
+        }
+
+        let waiter = Rc::new(Waiter {
+            goroutine: self.current(),
+            waiting: Cell::new(true),
+            fired: RefCell::new(None),
+        });
+        for (i, case) in cases.iter().enumerate() {
+            match *case {
+                Case::Send(Some(ref channel), ref v) => {
+                    channel.0.borrow_mut().senders.push_back((waiter.clone(), i, v.clone()));
+                }
+                Case::Receive(Some(ref channel)) => {
+                    channel.0.borrow_mut().receivers.push_back((waiter.clone(), i));
+                }
+                // Nil channels block forever
+                _ => {}
+            }
+        }
+        self.block().await?;
+        let fired = waiter.fired.borrow_mut().take();
+        match fired {
+            Some((i, Fired::Received(v, ok))) => Ok(Some((i, v, ok))),
+            Some((i, Fired::Sent)) => Ok(Some((i, Value::Void, true))),
+            Some((_, Fired::Closed)) => Err(Interruption::Closed),
+            None => panic!("A goroutine was woken up without its channel operation"),
+        }
+    }
+
+    /// Closes a channel. The goroutines receiving from it get the zero value, and those sending
+    /// on it panic.
+    pub fn close(&self, channel: &Channel) -> Result<(), &'static str> {
+        let mut state = channel.0.borrow_mut();
+        if state.closed {
+            return Err("close of closed channel");
+        }
+        state.closed = true;
+        while let Some((waiter, i)) = state.pop_receiver() {
+            waiter.fire(i, Fired::Received(state.zero.clone(), false));
+            self.wake(waiter.goroutine);
+        }
+        while let Some((waiter, i, _)) = state.pop_sender() {
+            waiter.fire(i, Fired::Closed);
+            self.wake(waiter.goroutine);
+        }
+        Ok(())
+    }
+}
+
+/// One of the channel operations of a select statement. The value to send is evaluated before
+/// the select blocks.
+pub enum Case {
+    Send(Option<Channel>, Value),
+    Receive(Option<Channel>),
+}
+
+impl Case {
+    fn is_ready(&self) -> bool {
+        match *self {
+            Case::Send(Some(ref channel), _) => {
+                let mut state = channel.0.borrow_mut();
+                state.closed || state.has_receiver() || state.buffer.len() < state.capacity
+            }
+            Case::Receive(Some(ref channel)) => {
+                let mut state = channel.0.borrow_mut();
+                !state.buffer.is_empty() || state.has_sender() || state.closed
+            }
+            _ => false,
+        }
+    }
+
+    // Sends or receives without blocking, once the case is ready
+    fn complete<T>(&self, scheduler: &Scheduler<T>) -> Result<(Value, bool), Interruption> {
+        match *self {
+            Case::Send(Some(ref channel), ref v) => {
+                let mut state = channel.0.borrow_mut();
+                if state.closed {
+                    return Err(Interruption::Closed);
+                }
+                // A waiting receiver gets the value directly
+                if let Some((waiter, i)) = state.pop_receiver() {
+                    waiter.fire(i, Fired::Received(v.clone(), true));
+                    scheduler.wake(waiter.goroutine);
+                } else {
+                    state.buffer.push_back(v.clone());
+                }
+                Ok((Value::Void, true))
+            }
+            Case::Receive(Some(ref channel)) => {
+                let mut state = channel.0.borrow_mut();
+                if let Some(v) = state.buffer.pop_front() {
+                    // The first waiting sender gets its value in the buffer
+                    if let Some((waiter, i, sent)) = state.pop_sender() {
+                        state.buffer.push_back(sent);
+                        waiter.fire(i, Fired::Sent);
+                        scheduler.wake(waiter.goroutine);
+                    }
+                    Ok((v, true))
+                } else if let Some((waiter, i, sent)) = state.pop_sender() {
+                    waiter.fire(i, Fired::Sent);
+                    scheduler.wake(waiter.goroutine);
+                    Ok((sent, true))
+                } else {
+                    Ok((state.zero.clone(), false))
+                }
+            }
+            _ => panic!("Operations on nil channels are never ready"),
+        }
+    }
+}
+
+// What happened to a blocked goroutine's channel operation
+enum Fired {
+    Received(Value, bool),
+    Sent,
+    Closed,
+}
+
+// A blocked goroutine, waiting in the queues of the channels of its cases. Once one of them fires
+// the others are stale, and are dropped from the queues when they come up.
+struct Waiter {
+    goroutine: usize,
+    // Stays false once a case fired, even after the goroutine took what it got
+    waiting: Cell<bool>,
+    fired: RefCell<Option<(usize, Fired)>>,
+}
+
+impl Waiter {
+    fn is_waiting(&self) -> bool {
+        self.waiting.get()
+    }
+
+    fn fire(&self, case: usize, fired: Fired) {
+        self.waiting.set(false);
+        *self.fired.borrow_mut() = Some((case, fired));
+    }
+}
+
+pub struct ChannelState {
+    buffer: VecDeque<Value>,
+    capacity: usize,
+    closed: bool,
+    // What receiving from the channel gives once it is closed
+    zero: Value,
+    receivers: VecDeque<(Rc<Waiter>, usize)>,
+    senders: VecDeque<(Rc<Waiter>, usize, Value)>,
+}
+
+impl ChannelState {
+    fn has_receiver(&mut self) -> bool {
+        while self.receivers.front().is_some_and(|(waiter, _)| !waiter.is_waiting()) {
+            self.receivers.pop_front();
+        }
+        !self.receivers.is_empty()
+    }
+
+    fn has_sender(&mut self) -> bool {
+        while self.senders.front().is_some_and(|(waiter, _, _)| !waiter.is_waiting()) {
+            self.senders.pop_front();
+        }
+        !self.senders.is_empty()
+    }
+
+    fn pop_receiver(&mut self) -> Option<(Rc<Waiter>, usize)> {
+        if self.has_receiver() { self.receivers.pop_front() } else { None }
+    }
+
+    fn pop_sender(&mut self) -> Option<(Rc<Waiter>, usize, Value)> {
+        if self.has_sender() { self.senders.pop_front() } else { None }
+    }
+}
+
+/// A non-nil channel. Like the entries of a map, its state is shared by the copies of it.
+#[derive(Clone)]
+pub struct Channel(Rc<RefCell<ChannelState>>);
+
+impl Channel {
+    pub fn new(capacity: usize, zero: Value) -> Channel {
+        Channel(Rc::new(RefCell::new(ChannelState {
+            buffer: VecDeque::new(),
+            capacity,
+            closed: false,
+            zero,
+            receivers: VecDeque::new(),
+            senders: VecDeque::new(),
+        })))
+    }
+
+    /// The number of values in the buffer
+    pub fn len(&self) -> usize {
+        self.0.borrow().buffer.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.0.borrow().capacity
+    }
+}
+
+// Channels are equal when they were made by the same call to make
+impl PartialEq for Channel {
+    fn eq(&self, other: &Channel) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Channel({:p})", Rc::as_ptr(&self.0))
+    }
+}
@@ -0,0 +1,205 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::{Rc, Weak};
+
+/// A position in a source file. Lines and columns start at 1, columns count bytes and the offset
+/// is the number of bytes since the start of the file.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Position {
+    pub line: u32,
+    pub column: u32,
+    pub offset: u32,
+}
+
+/// The part of a source file covered by a token or an AST node. `end` is the position right after
+/// the last byte. This is also the location type used by the bison parser.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Span {
+    pub file: u32,
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    /// The span given to the declarations that are built into the language, like `int` or `true`
+    pub fn builtin() -> Span {
+        let position = Position { line: 0, column: 0, offset: 0 };
+        Span { file: u32::MAX, start: position, end: position }
+    }
+}
+
+struct SourceFile {
+    id: u32,
+    name: String,
+    text: String,
+}
+
+/// A registered source file. It stays registered, so that errors can quote the offending line, for
+/// as long as something holds on to it: the program parsed from it or an error pointing into it.
+#[derive(Clone)]
+pub struct Source(Rc<SourceFile>);
+
+impl Source {
+    /// The id that the spans pointing into the file have
+    pub fn id(&self) -> u32 {
+        self.0.id
+    }
+}
+
+impl fmt::Debug for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Source({:?})", self.0.name)
+    }
+}
+
+// There is only ever one handle per registration, so two sources are equal if they are the same file
+impl PartialEq for Source {
+    fn eq(&self, other: &Source) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Source {}
+
+thread_local! {
+    // The files are owned by their `Source`s, and are gone once every one of them is dropped
+    static SOURCE_FILES: RefCell<HashMap<u32, Weak<SourceFile>>> = RefCell::new(HashMap::new());
+    static NEXT_FILE: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Registers a source file. The spans pointing into it have the id of the returned `Source`.
+pub fn add_source_file(name: &str, text: &str) -> Source {
+    let id = NEXT_FILE.with(|next| {
+        let id = next.get();
+        next.set(id + 1);
+        id
+    });
+    let file = Rc::new(SourceFile { id, name: name.to_string(), text: text.to_string() });
+    SOURCE_FILES.with(|files| {
+        let mut files = files.borrow_mut();
+        files.retain(|_, file| file.strong_count() > 0);
+        files.insert(id, Rc::downgrade(&file));
+    });
+    Source(file)
+}
+
+/// The source file that spans with the given id point into, if it is still registered
+pub fn source_file(file: u32) -> Option<Source> {
+    SOURCE_FILES.with(|files| files.borrow().get(&file).and_then(Weak::upgrade).map(Source))
+}
+
+/// The name that a source file was registered with
+pub fn file_name(file: u32) -> Option<String> {
+    source_file(file).map(|source| source.0.name.clone())
+}
+
+/// Formats the file and the line of a span like `prog.go:4`, the way stack traces show them
+pub fn format_line(span: Span) -> String {
+    match file_name(span.file) {
+        Some(name) => format!("{}:{}", name, span.start.line),
+        None => format!("line {}", span.start.line),
+    }
+}
+
+/// Formats an error message like
+///
+/// ```text
+/// Error: prog.go:4:10: `x` is undefined.
+///     y := x + 1
+///          ^
+/// ```
+pub fn format_error(span: Span, message: &str) -> String {
+    let source = match source_file(span.file) {
+        Some(source) => source,
+        None => return format!("Error: line {}: {}", span.start.line, message),
+    };
+    let file = &source.0;
+
+    let mut ret = format!("Error: {}:{}:{}: {}",
+                          file.name, span.start.line, span.start.column, message);
+
+    let line = match file.text.lines().nth(span.start.line as usize - 1) {
+        Some(line) => line,
+        None => return ret,
+    };
+
+    // Keep the tabs from the source line so that the carets line up
+    let prefix_length = (span.start.column as usize - 1).min(line.len());
+    let prefix: String = line.bytes()
+        .take(prefix_length)
+        .map(|c| if c == b'\t' { '\t' } else { ' ' })
+        .collect();
+
+    // Spans that go over several lines are underlined until the end of the first one
+    let caret_count =
+        if span.end.line == span.start.line && span.end.column > span.start.column {
+            span.end.column - span.start.column
+        } else if line.len() > prefix_length {
+            (line.len() - prefix_length) as u32
+        } else {
+            1
+        };
+
+    ret.push_str(&format!("\n    {}\n    {}{}", line, prefix, "^".repeat(caret_count as usize)));
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(source: &Source, start: (u32, u32), end: (u32, u32)) -> Span {
+        Span {
+            file: source.id(),
+            start: Position { line: start.0, column: start.1, offset: 0 },
+            end: Position { line: end.0, column: end.1, offset: 0 },
+        }
+    }
+
+    #[test]
+    fn test_format_error() {
+        let source = add_source_file("prog.go", "package main\nfunc f() {\n    y := x + 1\n}\n");
+        assert_eq!(format_error(span(&source, (3, 10), (3, 11)), "`x` is undefined."),
+                   "Error: prog.go:3:10: `x` is undefined.\n        y := x + 1\n             ^");
+        assert_eq!(format_error(span(&source, (3, 5), (3, 15)), "bad"),
+                   "Error: prog.go:3:5: bad\n        y := x + 1\n        ^^^^^^^^^^");
+    }
+
+    #[test]
+    fn test_format_error_tabs() {
+        let source = add_source_file("tabs.go", "func f() {\n\t\tx := 1\n}\n");
+        assert_eq!(format_error(span(&source, (2, 3), (2, 4)), "bad"),
+                   "Error: tabs.go:2:3: bad\n    \t\tx := 1\n    \t\t^");
+        assert_eq!(format_error(span(&source, (2, 8), (2, 9)), "bad"),
+                   "Error: tabs.go:2:8: bad\n    \t\tx := 1\n    \t\t     ^");
+    }
+
+    #[test]
+    fn test_format_error_multiple_lines() {
+        let source = add_source_file("lines.go", "x := f(1,\n    2)\n");
+        assert_eq!(format_error(span(&source, (1, 6), (2, 7)), "bad"),
+                   "Error: lines.go:1:6: bad\n    x := f(1,\n         ^^^^");
+    }
+
+    #[test]
+    fn test_format_error_empty_span() {
+        let source = add_source_file("empty.go", "x := f(1\n");
+        // Spans that end where they start are underlined until the end of the line
+        assert_eq!(format_error(span(&source, (1, 6), (1, 6)), "bad"),
+                   "Error: empty.go:1:6: bad\n    x := f(1\n         ^^^");
+        // and get a single caret past the end of it
+        assert_eq!(format_error(span(&source, (1, 9), (1, 9)), "unexpected newline"),
+                   "Error: empty.go:1:9: unexpected newline\n    x := f(1\n            ^");
+    }
+
+    #[test]
+    fn test_format_error_unknown_file() {
+        let position = Position { line: 7, column: 2, offset: 0 };
+        let span = Span { file: u32::MAX - 1, start: position, end: position };
+        assert_eq!(format_error(span, "bad"),
+                   "Error: line 7: bad");
+    }
+}
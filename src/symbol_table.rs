@@ -1,24 +1,29 @@
 use std::collections::HashMap;
-use std::process::exit;
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::cell::Cell;
-use kind;
-use kind::Kind;
-use kind::BasicKind;
+use std::fmt::Write;
+use crate::kind;
+use crate::kind::Kind;
+use crate::kind::BasicKind;
+use crate::constant::Constant;
+use crate::span::Span;
+use crate::diagnostics::Diagnostics;
 
 
 #[derive(Clone)]
 pub enum Declaration {
     Variable(Kind),
-    Constant(Kind),
+    Constant(Kind, Constant),
     Type(Kind),
-    Function{params: Vec<Kind>, return_kind: Option<Kind>},
+    Function{params: Vec<Kind>, return_kind: Option<Kind>, variadic: bool},
+    /// The predeclared `nil`, which can be shadowed like any other identifier
+    Nil,
     Dummy,
 }
 
 pub struct Symbol {
-    pub line_number: u32,
+    pub span: Span,
     pub new_name: String,
     pub declaration: Declaration,
 }
@@ -27,16 +32,31 @@ pub struct SymbolTable<'a> {
     pub parent_scope: Option<&'a SymbolTable<'a>>,
     pub symbols: HashMap<String, Symbol>,
     pub return_kind: Option<Kind>,
+    /// The renamed result variables of the current function when they are named
+    pub result_names: Vec<String>,
     pub in_function: bool,
     pub level: u32,
     pub print_table: bool,
+    /// The printed symbol table, shared by all the scopes
+    pub table: Rc<RefCell<String>>,
     pub id_counter: Rc<Cell<u32>>,
     pub obfuscate: bool,
+    pub diagnostics: Diagnostics,
 }
 
 
 impl<'a> SymbolTable<'a>{
-    pub fn get_symbol<'b>(&'b self, identifier: &str, line_number: u32) -> &'b Symbol{
+    /// Looks up an identifier, recording an error if it is not declared
+    pub fn get_symbol<'b>(&'b self, identifier: &str, span: Span) -> Option<&'b Symbol> {
+        let ret = self.lookup(identifier);
+        if ret.is_none() {
+            self.error(span, &format!("`{}` is undefined.", identifier));
+        }
+        ret
+    }
+
+    /// Looks up an identifier without reporting anything if it is not declared
+    pub fn lookup<'b>(&'b self, identifier: &str) -> Option<&'b Symbol> {
 
         let mut current_scope = Some(self);
 
@@ -45,37 +65,44 @@ impl<'a> SymbolTable<'a>{
 
             match temp {
                 Some(ref sym ) => {
-                        return sym
+                        return Some(sym)
                 },
                 None => current_scope = x.parent_scope
             }
         }
 
-        eprintln!("Error: line {}: `{}` is undefined.", line_number, identifier);
-        exit(1);
+        None
     }
+
+    pub fn error(&self, span: Span, message: &str) {
+        self.diagnostics.error(span, message);
+    }
+
     pub fn is_in_current_scope<'b>(&'b self, identifier: &str) -> bool {
         self.symbols.get(identifier).is_some()
     }
     pub fn new_scope<'b>(&'b mut self) -> SymbolTable<'b> {
         if self.print_table {
-            indent(self.level + 1);
-            println!("{{");
+            self.indent(self.level + 1);
+            writeln!(self.table.borrow_mut(), "{{").unwrap();
         }
         return SymbolTable {
             parent_scope: Some(self),
             symbols: HashMap::new(),
             return_kind: self.return_kind.clone(),
+            result_names: self.result_names.clone(),
             in_function: self.in_function,
             level: self.level + 1,
             print_table: self.print_table,
+            table: self.table.clone(),
             id_counter: self.id_counter.clone(),
             obfuscate: self.obfuscate,
+            diagnostics: self.diagnostics.clone(),
         }
     }
 
     /// returns the name that the identifier should be renamed to
-    fn add_declaration(&mut self, name: String, line_number: u32, decl: Declaration, rename: bool)
+    fn add_declaration(&mut self, name: String, span: Span, decl: Declaration, rename: bool)
         -> String {
 
         if (&name == "init" || &name == "main") && self.level == 1 {
@@ -83,9 +110,8 @@ impl<'a> SymbolTable<'a>{
                 Declaration::Function{..} | Declaration::Dummy => {},
                 _ => {
                     // If declaration is anythig other than a dummy or a function
-                    eprintln!("Error: line {}: only functions can be called `{}` at global \
-                    scope.", line_number, name);
-                    exit(1);
+                    self.error(span, &format!("only functions can be called `{}` at global \
+                    scope.", name));
                 }
             }
         }
@@ -96,150 +122,200 @@ impl<'a> SymbolTable<'a>{
 
         match self.symbols.get(&name) {
             Some(&Symbol{declaration: Declaration::Dummy, ..})  => {},
-            Some(&Symbol{line_number: l, ..}) =>  {
-                eprintln!("Error: line {}: `{}` was already declared in the current scope at line {}.",
-                          line_number, name, l);
-                exit(1);
+            Some(&Symbol{span: previous, ref new_name, ..}) =>  {
+                self.error(span, &format!("`{}` was already declared in the current scope at line {}.",
+                                          name, previous.start.line));
+                // Keep the first declaration so that its uses don't get reported as well
+                return new_name.clone();
             }
             None => {},
         }
 
         let new_name = if rename && !(&name == "main" && self.level == 1) {
-            self.id_counter.set(self.id_counter.get() + 1);
-            if !self.obfuscate {
-                format!("{}·{}", name, &self.id_counter.get().to_string())
-            } else {
-                format!("_{}", &self.id_counter.get().to_string())
-            }
+            self.fresh_name(&name)
         } else {
             name.clone()
         };
         self.symbols.insert(name, Symbol{
-            line_number,
+            span,
             declaration: decl,
             new_name: new_name.clone()
         });
         return new_name;
     }
 
-    pub fn define_type(&mut self, name: String, line_number: u32, kind: Kind) -> String {
-        self.add_declaration( name.clone(), line_number, Declaration::Type(
+    pub fn define_type(&mut self, name: String, span: Span, kind: Kind) -> String {
+        self.add_declaration( name.clone(), span, Declaration::Type(
                 Kind::Defined(Rc::new(RefCell::new(
-                        kind::Definition { line_number, name, kind } ) ))),
+                        kind::Definition { span, name, kind, methods: HashMap::new() } ) ))),
                 /*rename*/ true)
     }
 
     pub fn print_type_definition(&mut self, name: &str, kind: &Kind) {
         if self.print_table {
-            indent(self.level + 1);
-            println!("{} [type] = {} -> {}", name, name, kind);
+            self.indent(self.level + 1);
+            writeln!(self.table.borrow_mut(), "{} [type] = {} -> {}", name, name, kind).unwrap();
         }
     }
 
 
     pub fn add_initial_type(&mut self, name: String, kind: Kind) {
         if self.print_table {
-            indent(self.level + 1);
-            println!("{} [type] = {}", name, kind);
+            self.indent(self.level + 1);
+            writeln!(self.table.borrow_mut(), "{} [type] = {}", name, kind).unwrap();
         }
 
-        self.add_declaration(name.clone(), 0, Declaration::Type(kind), /*rename*/ false);
+        self.add_declaration(name.clone(), Span::builtin(), Declaration::Type(kind), /*rename*/ false);
     }
 
-    pub fn replace_dummy_by_function(&mut self, name: String, line_number: u32,
-                        params: Vec<Kind>, return_kind: Option<Kind>) {
+    pub fn replace_dummy_by_function(&mut self, name: String, span: Span,
+                        params: Vec<Kind>, return_kind: Option<Kind>, variadic: bool) {
 
 
         if self.print_table {
-            indent(self.level + 1);
-            print!("{} [function] = ", name);
+            self.indent(self.level + 1);
+            write!(self.table.borrow_mut(), "{} [function] = ", name).unwrap();
 
             if &name == "_" || &name == "init" {
-                print!("<unmapped>");
+                write!(self.table.borrow_mut(), "<unmapped>").unwrap();
             }
             else {
-                print!("(");
-                for (i,param) in params.iter().enumerate() {
-                    if i<params.len()-1 {
-                        print!("{}, ", param);
-                    } else {
-                        print!("{}", param);
-                    }
-                }
-                print!(") -> ");
-                if let &Some(ref ret) = &return_kind {
-                    print!("{}", ret);
-                } else {
-                    print!("void");
-                }
+                self.print_signature(&params, &return_kind, variadic);
             }
-            println!();
+            writeln!(self.table.borrow_mut()).unwrap();
         }
 
 
         if (&name == "init" || &name == "main") &&
             (params.len() != 0 || return_kind.is_some()) {
-            eprintln!("Error: line {}: {} function must have type () -> void",
-                        line_number, name);
-            exit(1);
+            self.error(span, &format!("{} function must have type () -> void", name));
         }
 
         if let Some(sym) = self.symbols.get_mut(&name){
-            sym.declaration = Declaration::Function{
-                                 params: params,
-                                 return_kind: return_kind.clone()
-                             };
+            // The dummy is not there if the name was already taken
+            if let Declaration::Dummy = sym.declaration {
+                sym.declaration = Declaration::Function{
+                                     params,
+                                     return_kind: return_kind.clone(),
+                                     variadic,
+                                 };
+            }
         };
     }
 
-    pub fn add_variable(&mut self, name: String, line_number: u32, kind: Kind, is_inferred: bool)  -> String {
+    /// Returns the name of the function implementing a method of `receiver`. Methods are found
+    /// through the kind of their receiver, so the name is not added to any scope.
+    pub fn add_method(&mut self, receiver: &Kind, name: &str, params: &[Kind],
+                      return_kind: &Option<Kind>, variadic: bool) -> String {
+        if self.print_table {
+            self.indent(self.level + 1);
+            write!(self.table.borrow_mut(), "{}.{} [method] = ", receiver, name).unwrap();
+            self.print_signature(params, return_kind, variadic);
+            writeln!(self.table.borrow_mut()).unwrap();
+        }
+
+        self.fresh_name(name)
+    }
+
+    // A variadic parameter is written `...T` rather than as the slice it is given as
+    fn print_signature(&self, params: &[Kind], return_kind: &Option<Kind>, variadic: bool) {
+        write!(self.table.borrow_mut(), "(").unwrap();
+        for (i,param) in params.iter().enumerate() {
+            if i<params.len()-1 {
+                write!(self.table.borrow_mut(), "{}, ", param).unwrap();
+            } else if let (true, Kind::Slice(base)) = (variadic, param) {
+                write!(self.table.borrow_mut(), "...{}", base).unwrap();
+            } else {
+                write!(self.table.borrow_mut(), "{}", param).unwrap();
+            }
+        }
+        write!(self.table.borrow_mut(), ") -> ").unwrap();
+        if let Some(ret) = return_kind {
+            write!(self.table.borrow_mut(), "{}", ret).unwrap();
+        } else {
+            write!(self.table.borrow_mut(), "void").unwrap();
+        }
+    }
+
+    pub fn add_variable(&mut self, name: String, span: Span, kind: Kind, is_inferred: bool)  -> String {
         
 
         if self.print_table && &name != "_" {
-            indent(self.level + 1);
+            self.indent(self.level + 1);
             if !is_inferred {
 
-                println!("{} [variable] = {}", name, kind);
+                writeln!(self.table.borrow_mut(), "{} [variable] = {}", name, kind).unwrap();
             } else {
-                println!("{} [variable] = {} <inferred>", name, kind);
+                writeln!(self.table.borrow_mut(), "{} [variable] = {} <inferred>", name, kind).unwrap();
             }
         }
 
-        self.add_declaration(name, line_number, Declaration::Variable(kind), true)
+        self.add_declaration(name, span, Declaration::Variable(kind), true)
+
+    }
 
+    /// Adds a variable that cannot be referred to by name, like a result named `_`
+    pub fn add_hidden_variable(&mut self, span: Span, kind: Kind) -> String {
+        let new_name = self.fresh_name("_");
+        let name = format!("_·{}", self.id_counter.get());
+        self.symbols.insert(name, Symbol{
+            span,
+            declaration: Declaration::Variable(kind),
+            new_name: new_name.clone()
+        });
+        new_name
     }
 
-    pub fn add_constant(&mut self, name: String, line_number: u32, kind: Kind) {
+    pub fn add_constant(&mut self, name: String, span: Span, kind: Kind, value: Constant) {
 
         if self.print_table && &name != "_" {
-            indent(self.level + 1);
-            println!("{} [constant] = {}", name, kind);
+            self.indent(self.level + 1);
+            writeln!(self.table.borrow_mut(), "{} [constant] = {}", name, kind).unwrap();
         }
         
-        self.add_declaration(name, line_number, Declaration::Constant(kind), false);
+        self.add_declaration(name, span, Declaration::Constant(kind, value), false);
+
+    }
+
+    pub fn add_nil(&mut self) {
+        if self.print_table {
+            self.indent(self.level + 1);
+            writeln!(self.table.borrow_mut(), "nil [constant] = {}", Kind::Nil).unwrap();
+        }
 
+        self.add_declaration("nil".to_string(), Span::builtin(), Declaration::Nil, false);
     }
 
-    pub fn add_dummy(&mut self, name: String, line_number: u32) -> String {
+    pub fn add_dummy(&mut self, name: String, span: Span) -> String {
 
-        self.add_declaration(name, line_number,
+        self.add_declaration(name, span,
                              Declaration::Dummy, true)
 
     }
 
-}
+    /// A name that no other identifier is renamed to
+    fn fresh_name(&self, name: &str) -> String {
+        self.id_counter.set(self.id_counter.get() + 1);
+        if !self.obfuscate {
+            format!("{}·{}", name, self.id_counter.get())
+        } else {
+            format!("_{}", self.id_counter.get())
+        }
+    }
 
-fn indent(level: u32) {
-    for _ in 0..level {
-        print!("\t"); // we use tabs now
+    fn indent(&self, level: u32) {
+        for _ in 0..level {
+            self.table.borrow_mut().push('\t'); // we use tabs now
+        }
     }
+
 }
 
 impl<'a> Drop for SymbolTable<'a> {
     fn drop(&mut self) {
         if self.print_table {
-            indent(self.level);
-            println!("}}");
+            self.indent(self.level);
+            writeln!(self.table.borrow_mut(), "}}").unwrap();
         }
     }
 }
@@ -248,30 +324,49 @@ impl<'a> Drop for SymbolTable<'a> {
 
 
 /// Populates the symbol table with the Go default variables and types
-pub fn create_root_symbol_table<'a>(print_table: bool, obfuscate: bool) -> SymbolTable<'a>{
-    if print_table {
-        indent(0);
-        println!("{{");
-    }
+pub fn create_root_symbol_table<'a>(print_table: bool, obfuscate: bool, diagnostics: &Diagnostics)
+    -> SymbolTable<'a>{
     let mut root_scope = SymbolTable {
         parent_scope: None,
         symbols: HashMap::new(),
         return_kind: None,
+        result_names: Vec::new(),
         in_function: false,
         level: 0,
         print_table,
+        table: Rc::new(RefCell::new(String::new())),
         id_counter: Rc::new(Cell::new(0)),
-        obfuscate: obfuscate
+        obfuscate,
+        diagnostics: diagnostics.clone(),
     };
+    if print_table {
+        root_scope.indent(0);
+        writeln!(root_scope.table.borrow_mut(), "{{").unwrap();
+    }
 
     root_scope.add_initial_type("int".to_string(), Kind::Basic(BasicKind::Int));
     root_scope.add_initial_type("float64".to_string(), Kind::Basic(BasicKind::Float));
     root_scope.add_initial_type("bool".to_string(), Kind::Basic(BasicKind::Bool));
-    root_scope.add_initial_type("rune".to_string(), Kind::Basic(BasicKind::Rune));
     root_scope.add_initial_type("string".to_string(), Kind::Basic(BasicKind::String));
+    root_scope.add_initial_type("int8".to_string(), Kind::Basic(BasicKind::Int8));
+    root_scope.add_initial_type("int16".to_string(), Kind::Basic(BasicKind::Int16));
+    root_scope.add_initial_type("int32".to_string(), Kind::Basic(BasicKind::Int32));
+    root_scope.add_initial_type("int64".to_string(), Kind::Basic(BasicKind::Int64));
+    root_scope.add_initial_type("uint8".to_string(), Kind::Basic(BasicKind::Uint8));
+    root_scope.add_initial_type("uint16".to_string(), Kind::Basic(BasicKind::Uint16));
+    root_scope.add_initial_type("uint32".to_string(), Kind::Basic(BasicKind::Uint32));
+    root_scope.add_initial_type("uint64".to_string(), Kind::Basic(BasicKind::Uint64));
+    root_scope.add_initial_type("uintptr".to_string(), Kind::Basic(BasicKind::Uintptr));
+    root_scope.add_initial_type("float32".to_string(), Kind::Basic(BasicKind::Float32));
+    // Aliases, so byte and uint8 values can be mixed, and rune and int32 values
+    root_scope.add_initial_type("byte".to_string(), Kind::Basic(BasicKind::Uint8));
+    root_scope.add_initial_type("rune".to_string(), Kind::Basic(BasicKind::Int32));
         
-    root_scope.add_constant("true".to_string(), 0, Kind::Basic(BasicKind::Bool));
-    root_scope.add_constant("false".to_string(), 0, Kind::Basic(BasicKind::Bool));
+    root_scope.add_constant("true".to_string(), Span::builtin(), Kind::Basic(BasicKind::Bool),
+                            Constant::Bool(true));
+    root_scope.add_constant("false".to_string(), Span::builtin(), Kind::Basic(BasicKind::Bool),
+                            Constant::Bool(false));
+    root_scope.add_nil();
         
 
     return root_scope;
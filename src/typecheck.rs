@@ -1,23 +1,38 @@
-use ast::*;
+use crate::ast::*;
 use std::mem;
-use ast::Field;
-use kind;
-use kind::*;
-use kind::Kind;
-use kind::BasicKind;
-use symbol_table::*;
-use std::process::exit;
+use crate::ast::Field;
+use crate::kind;
+use crate::kind::*;
+use crate::kind::Kind;
+use crate::kind::BasicKind;
+use crate::symbol_table::*;
+use crate::span::Span;
+use crate::diagnostics::Diagnostics;
+use crate::constant::{BigInt, Constant};
 use std::collections::HashSet;
-use util;
-
-pub fn typecheck(root: &mut Program, print_table: bool, obfuscate: bool) {
+use std::rc::Rc;
+use std::char;
+use crate::util;
+use crate::weed;
+
+/// Typechecks and renames the program. Errors are recorded in `diagnostics` and the check goes on
+/// with `Kind::Undefined` in place of whatever was wrong. Returns the printed symbol table, which
+/// is empty unless `print_table` is set.
+pub fn typecheck(root: &mut Program, print_table: bool, obfuscate: bool, diagnostics: &Diagnostics)
+    -> String {
     // Because of how we defined the back pointers for the symbol table, the parent should be
-    let mut universe_block = create_root_symbol_table(print_table, obfuscate);
-    let symbol_table = &mut universe_block.new_scope();
+    let mut universe_block = create_root_symbol_table(print_table, obfuscate, diagnostics);
+    let table = universe_block.table.clone();
+    {
+        let symbol_table = &mut universe_block.new_scope();
 
-    for decl in &mut root.declarations {
-        typecheck_top_level_declaration(decl, symbol_table);
+        for decl in &mut root.declarations {
+            typecheck_top_level_declaration(decl, symbol_table);
+        }
     }
+    drop(universe_block); // closes the outermost scope of the printed table
+    let ret = table.borrow().clone();
+    ret
 }
 
 fn typecheck_top_level_declaration(decl: &mut TopLevelDeclarationNode, symbol_table: &mut SymbolTable) {
@@ -25,12 +40,20 @@ fn typecheck_top_level_declaration(decl: &mut TopLevelDeclarationNode, symbol_ta
         TopLevelDeclarationVariant::VarDeclarations { ref mut declarations } => {
             typecheck_variable_declarations(declarations, symbol_table);
         }
+        TopLevelDeclarationVariant::ConstDeclarations { ref mut declarations } => {
+            typecheck_constant_declarations(declarations, symbol_table);
+        }
         TopLevelDeclarationVariant::TypeDeclarations { ref mut declarations } => {
             typecheck_type_declarations(declarations, symbol_table);
         }
+        TopLevelDeclarationVariant::FunctionDeclaration (ref mut function) if function.receiver.is_some() => {
+            let renamed = typecheck_method_declaration(function, decl.span, symbol_table);
+
+            function.name = renamed;
+        }
         TopLevelDeclarationVariant::FunctionDeclaration (Function {
-            ref mut name, ref mut parameters, ref mut return_kind, ref mut body }) => {
-            let renamed = typecheck_function_declaration(name, parameters, return_kind, body, decl.line_number, symbol_table);
+            ref mut name, ref mut parameters, ref mut results, ref mut body, .. }) => {
+            let renamed = typecheck_function_declaration(name, parameters, results, body, decl.span, symbol_table);
 
             *name = renamed;
         }
@@ -51,7 +74,9 @@ fn typecheck_variable_declarations(declarations: &mut [VarSpec], symbol_table: &
         let maybe_rhs_kinds = 
             match spec.rhs {
                 Some(ref mut exprs) => {
-                    Some(typecheck_expression_vec(exprs, symbol_table))
+                    let kinds = vec![maybe_declared_kind.clone().unwrap_or(Kind::Undefined);
+                                     spec.names.len()];
+                    Some(typecheck_assigned_expressions(exprs, &kinds, spec.span, symbol_table))
                 },
                 None => None,
             };
@@ -64,13 +89,11 @@ fn typecheck_variable_declarations(declarations: &mut [VarSpec], symbol_table: &
                     let init_kind = &rhs_kinds[i];
                     spec.evaluated_kind = rhs_kinds[i].clone();
                     if !kind::are_identical(&init_kind, &declared_kind) {
-                        eprintln!("Error: line {}: trying to initialize variable `{}` \
-                        of type {} with type {}.",
-                                 spec.line_number, spec.names[i], declared_kind, init_kind);
-                        exit(1);
+                        symbol_table.error(spec.span, &format!("trying to initialize variable `{}` \
+                        of type {} with type {}.", spec.names[i], declared_kind, init_kind));
                     }
                     renamed = symbol_table.add_variable(spec.names[i].clone(),
-                            spec.line_number,
+                            spec.span,
                             declared_kind.clone(),
                             /*inferred*/ false);
                 },
@@ -78,14 +101,14 @@ fn typecheck_variable_declarations(declarations: &mut [VarSpec], symbol_table: &
                     let init_kind = &rhs_kinds[i];
                     spec.evaluated_kind = rhs_kinds[i].clone();
                     renamed = symbol_table.add_variable(spec.names[i].clone(),
-                            spec.line_number,
+                            spec.span,
                             init_kind.clone(),
                             /*inferred*/ true);
                 },
                 (&None, &Some(ref declared_kind)) => {
                     spec.evaluated_kind = declared_kind.clone();
                     renamed = symbol_table.add_variable(spec.names[i].clone(),
-                    spec.line_number,
+                    spec.span,
                     declared_kind.clone(),
                     /*inferred*/ false);
                 },
@@ -96,19 +119,61 @@ fn typecheck_variable_declarations(declarations: &mut [VarSpec], symbol_table: &
     }
 }
 
+fn typecheck_constant_declarations(declarations: &mut [VarSpec], symbol_table: &mut SymbolTable) {
+    // Specs without expressions repeat the type and the expressions of the last one that has
+    // them, with the next value of iota
+    let mut repeated = 0;
+    let mut declared_kind = None;
+
+    for iota in 0..declarations.len() {
+        if declarations[iota].rhs.is_some() {
+            repeated = iota;
+            declared_kind = declarations[iota].kind.as_mut().map(|k| typecheck_kind(k, symbol_table, None));
+        }
+
+        let values: Vec<_> = match declarations[repeated].rhs {
+            Some(ref exprs) => exprs.iter().map(|e| {
+                evaluate_constant(e, symbol_table, Some(iota as u32))
+            }).collect(),
+            None => Vec::new(),
+        };
+
+        let spec = &declarations[iota];
+        if spec.names.len() > values.len() {
+            symbol_table.error(spec.span, "missing init expression for constant declaration.");
+        } else if spec.names.len() < values.len() {
+            symbol_table.error(spec.span, "extra init expression for constant declaration.");
+        }
+
+        for (i, name) in spec.names.iter().enumerate() {
+            let (kind, value) = match (values.get(i), &declared_kind) {
+                (Some(&Some((ref value, ref kind))), Some(declared_kind)) => {
+                    match convert_constant(value, kind, declared_kind, spec.span, symbol_table) {
+                        Some(converted) => (declared_kind.clone(), converted),
+                        None => (Kind::Undefined, value.clone()),
+                    }
+                }
+                (Some(&Some((ref value, ref kind))), &None) => (kind.clone(), value.clone()),
+                // The value of a constant that failed is never looked at, because of its kind
+                _ => (Kind::Undefined, Constant::Bool(false)),
+            };
+            symbol_table.add_constant(name.clone(), spec.span, kind, value);
+        }
+    }
+}
+
 fn typecheck_type_declarations(declarations: &mut [TypeSpec], symbol_table: &mut SymbolTable) {
 
     for spec in declarations {
         let renamed = symbol_table.define_type(spec.name.clone(),
-                                 spec.line_number,
+                                 spec.span,
                                  Kind::Undefined);
         let kind = typecheck_kind(&mut spec.kind, symbol_table, Some(&spec.name));
         if &spec.name != "_" {
-            match symbol_table.get_symbol(&spec.name, spec.line_number) {
-                &Symbol{ declaration: Declaration::Type(Kind::Defined(ref r)), ..} => {
-                    r.borrow_mut().kind = kind.clone()
-                },
-                _ => panic!("This type should have been a type definition")
+            // Otherwise the name was already taken, which has been reported
+            if let Some(&Symbol{ declaration: Declaration::Type(Kind::Defined(ref r)), ..}) =
+                symbol_table.get_symbol(&spec.name, spec.span) {
+                r.borrow_mut().kind = kind.clone()
             }
             symbol_table.print_type_definition(&spec.name, &kind);
             spec.name = renamed;
@@ -125,99 +190,311 @@ fn typecheck_statements(statements: &mut [StatementNode], symbol_table: &mut Sym
 
 fn typecheck_function_declaration(name: &str,
                                    params: &mut [Field],
-                                   return_kind: &mut Option<Box<AstKindNode>>,
+                                   results: &mut [Field],
                                    body: &mut [StatementNode],
-                                   line: u32,
+                                   span: Span,
                                    symbol_table: &mut SymbolTable) -> String {
 
-    let renamed = symbol_table.add_dummy(name.to_string(), line);
+    let renamed = symbol_table.add_dummy(name.to_string(), span);
 
-    let mut param_tuples = Vec::new();
-    {
-    for (i,f) in params.iter_mut().enumerate() {
-        let k = typecheck_kind(&mut f.kind, symbol_table, None);
-        for j in 0..f.identifiers.len() {
-            param_tuples.push((f.identifiers[j].clone(), f.line_number, k.clone(), (i,j) ))
+    let param_kinds = typecheck_field_kinds(params, symbol_table);
+    let return_kind = result_kind(typecheck_field_kinds(results, symbol_table));
+
+    symbol_table.replace_dummy_by_function(name.to_string(),
+                                 span,
+                                 param_kinds,
+                                 return_kind.clone(),
+                                 is_variadic(params));
+
+    typecheck_function_body(None, params, results, body, return_kind, symbol_table);
+
+    renamed
+}
+
+/// Typechecks a method and adds it to the methods of the type of its receiver. Returns the name
+/// of the function implementing it.
+fn typecheck_method_declaration(function: &mut Function, span: Span,
+                                symbol_table: &mut SymbolTable) -> String {
+    let name = function.name.clone();
+    let receiver = function.receiver.as_mut().unwrap();
+    let receiver_kind = typecheck_kind(&mut receiver.kind, symbol_table, None);
+    let definition = match receiver_kind {
+        // Methods cannot be declared on interfaces
+        Kind::Defined(ref d) if !receiver_kind.is_interface() => Some(d.clone()),
+        // Already reported
+        Kind::Undefined => None,
+        _ => {
+            symbol_table.error(receiver.span, &format!("invalid receiver type {}.", receiver_kind));
+            None
+        }
+    };
+
+    let params = typecheck_field_kinds(&mut function.parameters, symbol_table);
+    let return_kind = result_kind(typecheck_field_kinds(&mut function.results, symbol_table));
+
+    let printed_kind = if receiver.pointer {
+        Kind::Pointer(Box::new(receiver_kind.clone()))
+    } else {
+        receiver_kind.clone()
+    };
+    let variadic = is_variadic(&function.parameters);
+    let renamed = symbol_table.add_method(&printed_kind, &name, &params, &return_kind, variadic);
+
+    // Added before the body is checked so that the method can call itself
+    if let Some(definition) = definition {
+        let has_field = match receiver_kind.resolve() {
+            Kind::Struct(ref fields) => fields.iter().any(|f| f.name == name),
+            _ => false,
+        };
+        let mut definition = definition.borrow_mut();
+        if name == "_" {
+            // Blank methods cannot be called
+        } else if definition.methods.contains_key(&name) {
+            symbol_table.error(span, &format!("method {}.{} is already declared.",
+                                              definition.name, name));
+        } else if has_field {
+            symbol_table.error(span, &format!("field and method with the same name {}.",
+                                              name));
+        } else {
+            definition.methods.insert(name.clone(), kind::Method {
+                function: renamed.clone(),
+                params,
+                return_kind: return_kind.clone(),
+                variadic,
+                pointer_receiver: receiver.pointer,
+            });
         }
     }
+
+    typecheck_function_body(Some(receiver), &mut function.parameters, &mut function.results,
+                            &mut function.body, return_kind, symbol_table);
+
+    renamed
+}
+
+/// Typechecks the kinds of a list of parameters or results, giving one kind per value. Unnamed
+/// results have no identifiers but still give a value.
+fn typecheck_field_kinds(fields: &mut [Field], symbol_table: &mut SymbolTable) -> Vec<Kind> {
+    let mut kinds = Vec::new();
+    for f in fields.iter_mut() {
+        let mut k = typecheck_kind(&mut f.kind, symbol_table, None);
+        if f.variadic {
+            k = Kind::Slice(Box::new(k));
+        }
+        for _ in 0..f.identifiers.len().max(1) {
+            kinds.push(k.clone());
+        }
     }
+    kinds
+}
 
-    let real_return_kind = 
-        match return_kind {
-            &mut Some(ref mut ret) => Some(typecheck_kind(ret, symbol_table, None)),
-            &mut None => None
-        };
+/// Whether the last of the parameters is variadic
+fn is_variadic(params: &[Field]) -> bool {
+    params.last().is_some_and(|f| f.variadic)
+}
 
-    symbol_table.replace_dummy_by_function(name.to_string(),
-                                 line,
-                                 param_tuples.iter().map(|x| x.2.clone()).collect(),
-                                 real_return_kind.clone());
+/// The return kind of a function with results of the given kinds
+fn result_kind(mut kinds: Vec<Kind>) -> Option<Kind> {
+    match kinds.len() {
+        0 => None,
+        1 => kinds.pop(),
+        _ => Some(Kind::Tuple(kinds)),
+    }
+}
 
+/// Typechecks the body of a function declaration or literal whose parameter and result kinds
+/// have been typechecked, in a new scope with the receiver, the parameters and the named results
+fn typecheck_function_body(receiver: Option<&mut Receiver>,
+                           params: &mut [Field],
+                           results: &mut [Field],
+                           body: &mut [StatementNode],
+                           return_kind: Option<Kind>,
+                           symbol_table: &mut SymbolTable) {
     let new_scope = &mut symbol_table.new_scope();
-    new_scope.return_kind = real_return_kind;
+    new_scope.return_kind = return_kind;
+    new_scope.result_names = Vec::new();
     new_scope.in_function = true;
 
-    for f in param_tuples {
-        let renamed = new_scope.add_variable(f.0, f.1, f.2, false);
-        params[(f.3).0].identifiers[(f.3).1] = renamed;
+    if let Some(receiver) = receiver {
+        let mut kind = receiver.kind.evaluated_kind.clone();
+        if receiver.pointer {
+            kind = Kind::Pointer(Box::new(kind));
+        }
+        receiver.name = new_scope.add_variable(receiver.name.clone(), receiver.span, kind, false);
+    }
+
+    for f in params.iter_mut() {
+        let mut kind = f.kind.evaluated_kind.clone();
+        if f.variadic {
+            kind = Kind::Slice(Box::new(kind));
+        }
+        for id in f.identifiers.iter_mut() {
+            *id = new_scope.add_variable(id.clone(), f.span, kind.clone(), false);
+        }
+    }
+
+    // Named results are variables of the function, which a bare return gives back
+    for f in results.iter_mut() {
+        for id in f.identifiers.iter_mut() {
+            let kind = f.kind.evaluated_kind.clone();
+            *id = if id == "_" {
+                new_scope.add_hidden_variable(f.span, kind)
+            } else {
+                new_scope.add_variable(id.clone(), f.span, kind, false)
+            };
+            new_scope.result_names.push(id.clone());
+        }
     }
 
     typecheck_statements(body, new_scope);
+}
 
-    renamed
+/// Typechecks the call of a defer or go statement, which can also be a print or println statement
+fn typecheck_call_statement(call: &mut StatementNode, statement: &str, symbol_table: &mut SymbolTable) {
+    if let StatementVariant::Expression(ref mut exp) = call.variant {
+        typecheck_expression(exp, symbol_table, true);
+        match exp.variant {
+            ExpressionVariant::FunctionCall {..} | ExpressionVariant::Delete {..} |
+            ExpressionVariant::Panic {..} | ExpressionVariant::Close {..} => {},
+            _ => symbol_table.error(exp.span, &format!("expression in {} must be function call.",
+                                                       statement)),
+        }
+    } else {
+        typecheck_statement(call, symbol_table);
+    }
 }
 
 fn typecheck_statement(stmt: &mut StatementNode,
                            symbol_table: &mut SymbolTable) {
+    let span = stmt.span;
     match stmt.variant {
         StatementVariant::Empty => {},
-        StatementVariant::Break => {},
-        StatementVariant::Continue => {},
+        // Labels aren't in the scopes of identifiers, so they are left as they are
+        StatementVariant::Break(..) => {},
+        StatementVariant::Continue(..) => {},
+        StatementVariant::Fallthrough => {},
+        StatementVariant::Goto(..) => {},
+        StatementVariant::Labeled { ref mut statement, .. } => {
+            typecheck_statement(statement, symbol_table);
+        },
         StatementVariant::Expression(ref mut exp) => {
             typecheck_expression(exp, symbol_table, true);
             match exp.variant {
-                ExpressionVariant::FunctionCall {..} => {},
+                ExpressionVariant::FunctionCall {..} | ExpressionVariant::Delete {..} |
+                ExpressionVariant::Panic {..} | ExpressionVariant::Receive {..} |
+                ExpressionVariant::Close {..} | ExpressionVariant::Copy {..} => {},
+                _ => {
+                    symbol_table.error(exp.span, "Invalid expression statement. \
+                              Expected a function call");
+                }
+            }
+        },
+        StatementVariant::Defer(ref mut call) => {
+            typecheck_call_statement(call, "defer", symbol_table);
+        },
+        StatementVariant::Go(ref mut call) => {
+            typecheck_call_statement(call, "go", symbol_table);
+        },
+        StatementVariant::Send { ref mut channel, ref mut value } => {
+            let kind = typecheck_expression(channel, symbol_table, false);
+            match kind.resolve() {
+                Kind::Chan(ref base) => {
+                    let value_kind = typecheck_expression_as(value, base, symbol_table);
+                    if !are_identical(&value_kind, base) {
+                        symbol_table.error(value.span, &format!("cannot send value of type {} \
+                        on channel of type {}.", value_kind, kind));
+                    }
+                }
+                Kind::Undefined => {
+                    typecheck_expression(value, symbol_table, false);
+                }
                 _ => {
-                    eprintln!("Error: line {}: Invalid expression statement. \
-                              Expected a function call", exp.line_number);
-                    exit(1);
+                    typecheck_expression(value, symbol_table, false);
+                    symbol_table.error(channel.span, &format!("cannot send to non-channel value \
+                    of type {}.", kind));
+                }
+            }
+        },
+        StatementVariant::Select { ref mut body } => {
+            // The variables declared by a receive are in the scope of the clause
+            for cc in body {
+                let new_scope = &mut symbol_table.new_scope();
+                if let SwitchCase::Comm(ref mut comm) = cc.switch_case {
+                    typecheck_statement(comm, new_scope);
+                }
+                for stmt in &mut cc.statements {
+                    typecheck_statement(stmt, new_scope);
                 }
             }
         },
-        StatementVariant::Return(ref mut exp) => {
+        StatementVariant::Return(ref mut exprs) => {
             // Since statements happen only inside functions, return only happens inside functions
-            let maybe_actual_kind =
-                if let &mut Some(ref mut exp) = exp {
-                    Some(typecheck_expression(&mut **exp, symbol_table, false))
-                } else {
-                    None
-                };
+            let result_kinds = match symbol_table.return_kind {
+                Some(Kind::Tuple(ref kinds)) => kinds.clone(),
+                Some(ref kind) => vec![kind.clone()],
+                None => vec![],
+            };
+
+            if exprs.is_empty() && !symbol_table.result_names.is_empty() {
+                // A bare return gives the named results
+                *exprs = symbol_table.result_names.iter().zip(result_kinds.iter())
+                    .map(|(name, kind)| Expression {
+                        span,
+                        kind: kind.clone(),
+                        variant: ExpressionVariant::Identifier {
+                            name: name.clone(),
+                            original_name: name.clone()
+                        },
+                    }).collect();
+                return;
+            }
 
-            match (maybe_actual_kind, &symbol_table.return_kind)  {
-                (Some( ref actual_kind ), &Some(ref required_kind)) => {
+            let actual_kinds = if exprs.len() == 1 && result_kinds.len() > 1 {
+                // The results of a call can be returned directly
+                match typecheck_expression(&mut exprs[0], symbol_table, true) {
+                    Kind::Tuple(kinds) => kinds,
+                    kind => vec![kind],
+                }
+            } else if exprs.len() == result_kinds.len() {
+                exprs.iter_mut().zip(result_kinds.iter())
+                    .map(|(e, k)| typecheck_expression_as(e, k, symbol_table))
+                    .collect()
+            } else {
+                typecheck_expression_vec(exprs, symbol_table)
+            };
+
+            if actual_kinds.iter().any(|k| k.is_undefined()) {
+                // Already reported
+            } else if actual_kinds.len() != result_kinds.len() {
+                if result_kinds.is_empty() {
+                    symbol_table.error(span, "trying to return something from void function.");
+                } else if actual_kinds.is_empty() {
+                    let return_kind = symbol_table.return_kind.clone().unwrap();
+                    symbol_table.error(span, &format!("must return a value of type {}", return_kind));
+                } else {
+                    symbol_table.error(span, &format!("wrong number of return values: \
+                        expected {}, found {}.", result_kinds.len(), actual_kinds.len()));
+                }
+            } else {
+                for (actual_kind, required_kind) in actual_kinds.iter().zip(result_kinds.iter()) {
                     if !are_identical(actual_kind, required_kind) {
-                        eprintln!("Error: line {}: invalid return type {}. \
-                            Type used in function header is {}.",
-                                 stmt.line_number, actual_kind, required_kind);
-                        exit(1);
+                        symbol_table.error(span, &format!("invalid return type {}. \
+                            Type used in function header is {}.", actual_kind, required_kind));
                     }
-                },
-                (None, &None) => {},
-                (Some(_), &None) => {
-                        eprintln!("Error: line {}: trying to return something from void function.",
-                                 stmt.line_number);
-                        exit(1);
-
-                },
-                (None, &Some(ref k)) => {
-                        eprintln!("Error: line {}: must return a value of type {}", stmt.line_number, k);
-                        exit(1);
-                },
+                }
             }
 
         },
         StatementVariant::ShortVariableDeclaration { ref mut identifier_list, ref mut expression_list, ref mut is_assigning } => {
-            let rhs_kinds = typecheck_expression_vec(expression_list.as_mut_slice(), symbol_table);
+            // Constants assigned to variables that already exist take the kind of the variable
+            let assigned_kinds: Vec<Kind> = identifier_list.iter().map(|id| {
+                match symbol_table.symbols.get(id.as_str()) {
+                    Some(&Symbol { declaration: Declaration::Variable(ref k), .. }) => k.clone(),
+                    _ => Kind::Undefined,
+                }
+            }).collect();
+            let rhs_kinds = typecheck_assigned_expressions(expression_list, &assigned_kinds,
+                                                           stmt.span, symbol_table);
 
             let mut new_count = 0;
             let mut vars_to_add = Vec::<String>::new();
@@ -227,9 +504,7 @@ fn typecheck_statement(stmt: &mut StatementNode,
                 // Check identifier doesn't appear twice in lhs
                 for name in vars_to_add.clone() {
                     if &name == id {
-                        eprintln!("Error: line {}: variable name {} used twice in lhs of assignment",
-                                  stmt.line_number, id);
-                        exit(1);
+                        symbol_table.error(stmt.span, &format!("variable name {} used twice in lhs of assignment", id));
                     }
                 }
                 if id != "_" {
@@ -240,22 +515,18 @@ fn typecheck_statement(stmt: &mut StatementNode,
 
                 if symbol_table.is_in_current_scope(&id) {
 
-                    let sym = symbol_table.get_symbol(id, stmt.line_number);
+                    let sym = &symbol_table.symbols[id.as_str()];
                     renamed = sym.new_name.clone();
                     is_assigning.push(true);
 
                     match sym.declaration {
                         Declaration::Variable(ref k) =>{
                             if !are_identical(k, exp_kind) { // 3
-                                eprintln!("Error: line {}: invalid type of expression assigned to {}.",
-                                         stmt.line_number,
-                                         id);
-                                exit(1);
+                                symbol_table.error(stmt.span, &format!("invalid type of expression assigned to {}.", id));
                             }
                         },
                         _ => {
-                            eprintln!("Error: line {}: Trying to redeclare non-variable in short variable assignment.", stmt.line_number);
-                            exit(1);
+                            symbol_table.error(stmt.span, "Trying to redeclare non-variable in short variable assignment.");
                         }
                     }
                 } else {
@@ -264,7 +535,7 @@ fn typecheck_statement(stmt: &mut StatementNode,
 
                     if &*id != "_" {
                         renamed = symbol_table.add_variable(id.clone(),
-                                                     stmt.line_number,
+                                                     stmt.span,
                                                      exp_kind.clone(),
                                                      true);
                         new_count += 1;
@@ -278,8 +549,7 @@ fn typecheck_statement(stmt: &mut StatementNode,
 
             }
             if new_count == 0 {
-                eprintln!("Error: line {}: no new variable on lhs.", stmt.line_number);
-                exit(1);
+                symbol_table.error(stmt.span, "no new variable on lhs.");
             }
         }
 
@@ -287,52 +557,50 @@ fn typecheck_statement(stmt: &mut StatementNode,
             typecheck_variable_declarations(declarations, symbol_table);
         }
 
+        StatementVariant::ConstDeclarations { ref mut declarations } => {
+            typecheck_constant_declarations(declarations, symbol_table);
+        }
+
         StatementVariant::TypeDeclarations { ref mut declarations } => {
             typecheck_type_declarations(declarations, symbol_table);
         }
 
         StatementVariant::Assignment { ref mut lhs, ref mut rhs } => {
-            for i in 0..lhs.len() {
-                let lhs_exp = &mut lhs[i];
-                let rhs_exp = &mut rhs[i];
-
-                let lhs_kind = typecheck_expression(lhs_exp, symbol_table, false);
-                let rhs_kind = typecheck_expression(rhs_exp, symbol_table, false);
-
-                if !is_exp_addressable(lhs_exp, symbol_table) {
-                     eprintln!("Error: line {}: lvalue {} in list is not addressable.", 
-                              stmt.line_number,
-                              i + 1);
-                     exit(1);
+            let lhs_kinds: Vec<Kind> = lhs.iter_mut()
+                .map(|e| typecheck_expression(e, symbol_table, false))
+                .collect();
+            let rhs_kinds = typecheck_assigned_expressions(rhs, &lhs_kinds, stmt.span,
+                                                           symbol_table);
+
+            for (i, lhs_exp) in lhs.iter_mut().enumerate() {
+                let lhs_kind = &lhs_kinds[i];
+                let rhs_kind = &rhs_kinds[i];
+
+                if !is_exp_assignable(lhs_exp, symbol_table) {
+                     symbol_table.error(stmt.span, &format!("lvalue {} in list is not addressable.", i + 1));
                 }
 
-                if !are_identical(&lhs_kind, &rhs_kind) {
-                    eprintln!("Error: line {}: In position {} of assignment list, \
+                if !are_identical(lhs_kind, rhs_kind) {
+                    symbol_table.error(stmt.span, &format!("In position {} of assignment list, \
                     trying to assign a value of type {} \
-                    to an expression expression {}", 
-                    stmt.line_number,
-                    i + 1,
-                    rhs_kind,
-                    lhs_kind);
-                    exit(1);
+                    to an expression expression {}", i + 1, rhs_kind, lhs_kind));
                 }
             }
         }
 
         StatementVariant::OpAssignment { ref mut lhs, ref mut rhs, ref mut operator } => {
             let lhs_kind = typecheck_expression(lhs, symbol_table, false);
-            let rhs_kind = typecheck_expression(rhs, symbol_table, false);
+            let rhs_kind = typecheck_expression_as(rhs, &lhs_kind, symbol_table);
 
-            if !is_exp_addressable(lhs, symbol_table) {
-                 eprintln!("Error: line {}: unadressable lvalue.", stmt.line_number);
-                 exit(1);
+            if !is_exp_assignable(lhs, symbol_table) {
+                 symbol_table.error(stmt.span, "unadressable lvalue.");
             }
 
-            let assigned_kind = get_kind_binary_op(&lhs_kind, &rhs_kind, *operator, stmt.line_number);
+            let assigned_kind = get_kind_binary_op(&lhs_kind, &rhs_kind, *operator, stmt.span,
+                                                   symbol_table);
 
             if !are_identical(&lhs_kind, &assigned_kind) {
-                eprintln!("Error: line {}: invalid assignment type.", stmt.line_number);
-                exit(1);
+                symbol_table.error(stmt.span, "invalid assignment type.");
             }
         }
 
@@ -347,11 +615,10 @@ fn typecheck_statement(stmt: &mut StatementNode,
             for expr in exprs {
                 let kind = typecheck_expression(expr, symbol_table, false);
                 let resolved_kind = kind.resolve();
-                if let Kind::Basic(..) = resolved_kind {
-                } else {
-                    eprintln!("Error: line {}: trying to print something that resolves \
-                    to a {}", expr.line_number, resolved_kind);
-                    exit(1);
+                match resolved_kind {
+                    Kind::Basic(..) | Kind::Undefined => {},
+                    _ => symbol_table.error(expr.span, &format!("trying to print something that resolves \
+                    to a {}", resolved_kind)),
                 }
             }
         }
@@ -369,9 +636,7 @@ fn typecheck_statement(stmt: &mut StatementNode,
                 };
 
             if !are_identical(&exp_type.resolve(), &Kind::Basic(BasicKind::Bool)) {
-                eprintln!("Error: line {}: condition must be of type bool.",
-                         stmt.line_number);
-                exit(1);
+                init_scope.error(condition.as_ref().map_or(stmt.span, |c| c.span), "condition must be of type bool.");
             }
 
             typecheck_statement(post, init_scope);
@@ -380,15 +645,53 @@ fn typecheck_statement(stmt: &mut StatementNode,
             typecheck_statements(body, new_scope);
         }
 
+        StatementVariant::ForRange { ref mut key, ref mut value, ref mut expr, ref mut body } => {
+            let kind = typecheck_expression(expr, symbol_table, false);
+            // The values received from a channel are the only iteration variable
+            if kind.is_chan() && value.is_some() {
+                symbol_table.error(stmt.span, &format!("range over channel of type {} permits \
+                only one iteration variable.", kind));
+            }
+            let (key_kind, value_kind) = match kind.resolve() {
+                Kind::Array(ref base, _) | Kind::Slice(ref base) => {
+                    (Kind::Basic(BasicKind::Int), (**base).clone())
+                }
+                // Strings are iterated over one rune at a time
+                Kind::Basic(BasicKind::String) => {
+                    (Kind::Basic(BasicKind::Int), Kind::Basic(BasicKind::Int32))
+                }
+                Kind::Map(ref key, ref value) => ((**key).clone(), (**value).clone()),
+                Kind::Chan(ref base) => ((**base).clone(), Kind::Undefined),
+                Kind::Undefined => (Kind::Undefined, Kind::Undefined),
+                _ => {
+                    symbol_table.error(expr.span, &format!("cannot range over expression of type {}.", kind));
+                    (Kind::Undefined, Kind::Undefined)
+                }
+            };
+
+            // Like the init statement of a for loop, the iteration variables get their own scope
+            let range_scope = &mut symbol_table.new_scope();
+            if key.is_some() && key.iter().chain(value.iter()).all(|name| name == "_") {
+                range_scope.error(stmt.span, "no new variable on lhs.");
+            }
+            if let Some(ref mut key) = *key {
+                *key = range_scope.add_variable(key.clone(), stmt.span, key_kind, true);
+            }
+            if let Some(ref mut value) = *value {
+                *value = range_scope.add_variable(value.clone(), stmt.span, value_kind, true);
+            }
+
+            let new_scope = &mut range_scope.new_scope();
+            typecheck_statements(body, new_scope);
+        }
+
         StatementVariant::If { ref mut init, ref mut condition, ref mut if_branch, ref mut else_branch } => {
             let init_scope = &mut symbol_table.new_scope();
             typecheck_statement(init, init_scope);
             let exp_type = typecheck_expression(condition, init_scope, false);
 
             if !are_identical(&exp_type.resolve(), &Kind::Basic(BasicKind::Bool)) {
-                eprintln!("Error: line {}: condition must be of type bool.", 
-                         stmt.line_number);
-                exit(1);
+                init_scope.error(condition.span, "condition must be of type bool.");
             }
 
             {
@@ -414,9 +717,7 @@ fn typecheck_statement(stmt: &mut StatementNode,
             if let Some(ref mut expr) = *expr {
                 exp_type = typecheck_expression(expr, init_scope, false);
                 if !exp_type.resolve().is_comparable() {
-                    eprintln!("Error: line {}: type {} is not comparable",
-                              expr.line_number, exp_type);
-                    exit(1);
+                    init_scope.error(expr.span, &format!("type {} is not comparable", exp_type));
                 }
             } else {
                 exp_type = Kind::Basic(BasicKind::Bool);
@@ -426,16 +727,16 @@ fn typecheck_statement(stmt: &mut StatementNode,
                 match cc.switch_case {
                     SwitchCase::Cases(ref mut cases) => {
                         for case in cases {
-                            let cc_type = typecheck_expression(case, init_scope, false);
+                            let cc_type = typecheck_expression_as(case, &exp_type, init_scope);
                             if !are_identical(&cc_type, &exp_type) {
-                                eprintln!("Error: line {}: mismatched case type {}; \
-                                         expected {}.", 
-                                         cc.line_number, cc_type, exp_type);
-                                exit(1);
+                                init_scope.error(cc.span, &format!("mismatched case type {}; \
+                                         expected {}.", cc_type, exp_type));
                             }
                         }
                     }
                     SwitchCase::Default => {},
+                    SwitchCase::Kinds(..) => panic!("Only type switches have kinds in their cases."),
+                    SwitchCase::Comm(..) => panic!("Only select statements have sends and receives in their cases."),
                 }
 
                 let new_scope = &mut init_scope.new_scope();
@@ -444,17 +745,59 @@ fn typecheck_statement(stmt: &mut StatementNode,
                 }
             }
         }
+        StatementVariant::TypeSwitch { ref mut init, ref binding, ref mut expr, ref mut body } => {
+            let init_scope = &mut symbol_table.new_scope();
+            typecheck_statement(init, init_scope);
+
+            let kind = typecheck_expression(expr, init_scope, false);
+            let methods = match kind.resolve() {
+                Kind::Interface(methods) => Some(methods),
+                Kind::Undefined => None,
+                _ => {
+                    init_scope.error(expr.span, &format!("cannot type switch on value of type {} \
+                    which is not an interface.", kind));
+                    None
+                }
+            };
+            if binding.as_ref().is_some_and(|name| name == "_") {
+                init_scope.error(stmt.span, "no new variable on lhs.");
+            }
+
+            for cc in body {
+                let mut case_kinds = Vec::new();
+                if let SwitchCase::Kinds(ref mut kinds) = cc.switch_case {
+                    for case in kinds {
+                        case_kinds.push(typecheck_type_case(case, &kind, methods.as_ref(), init_scope));
+                    }
+                }
+
+                let new_scope = &mut init_scope.new_scope();
+                if let Some(ref name) = *binding {
+                    // The variable has the kind of the case if there is a single one
+                    let binding_kind = match case_kinds.first() {
+                        Some(case_kind) if case_kinds.len() == 1 && !matches!(*case_kind, Kind::Nil) => {
+                            case_kind.clone()
+                        }
+                        _ => kind.clone(),
+                    };
+                    if name != "_" {
+                        cc.binding = Some(new_scope.add_variable(name.clone(), cc.span, binding_kind, true));
+                    }
+                }
+                for stmt in &mut cc.statements {
+                    typecheck_statement(stmt, new_scope);
+                }
+            }
+        }
         StatementVariant::IncDec { ref mut expr, .. } => {
             let exp_type = typecheck_expression(expr, symbol_table, false);
-            if !is_exp_addressable(expr, symbol_table) {
-                eprintln!("Error: line {}: expression is not addressable", expr.line_number);
-                exit(1);
+            if !is_exp_assignable(expr, symbol_table) {
+                symbol_table.error(expr.span, "expression is not addressable");
             }
             let base = exp_type.resolve();
-            if !base.is_numeric() {
-                eprintln!("Error: line {}: attempt to increment/decrement a non-numeric type \
-                {},", expr.line_number, exp_type);
-                exit(1);
+            if !base.is_numeric() && !base.is_undefined() {
+                symbol_table.error(expr.span, &format!("attempt to increment/decrement a non-numeric type \
+                {},", exp_type));
             }
         }
     }
@@ -465,64 +808,220 @@ fn typecheck_kind(ast: &mut AstKindNode,
                       symbol_table: &mut SymbolTable, 
                       top_name: Option<&str>) -> Kind { 
                     // top_name is to prevent recursive definitions in structs
-    match ast.variant {
+    let kind = match ast.variant {
         AstKindVariant::Identifier { ref mut name } => {
             match top_name {
                 Some(ref top_name) => {
                     if name == top_name {
-                        eprintln!("Error: line {}: trying to recursively use {} \
-                        in type definition:", ast.line_number, name);
-                        exit(1);
+                        symbol_table.error(ast.span, &format!("trying to recursively use {} \
+                        in type definition:", name));
+                        // Using the definition itself would make kinds that contain themselves
+                        ast.evaluated_kind = Kind::Undefined;
+                        return Kind::Undefined;
                     }
                 }
                 None => {},
             }
 
-            let symbol = symbol_table.get_symbol(&*name, ast.line_number);
-
-            if let Declaration::Type(ref kind) = symbol.declaration {
-                *name = symbol.new_name.clone();
-                return kind.clone();
-            } else {
-                eprintln!("Error: line {}: `{}` is not a type.", ast.line_number, name);
-                exit(1);
+            match symbol_table.get_symbol(&*name, ast.span) {
+                Some(&Symbol { declaration: Declaration::Type(ref kind), ref new_name, .. }) => {
+                    *name = new_name.clone();
+                    kind.clone()
+                }
+                Some(_) => {
+                    symbol_table.error(ast.span, &format!("`{}` is not a type.", name));
+                    Kind::Undefined
+                }
+                None => Kind::Undefined,
             }
         },
         AstKindVariant::Slice { ref mut base } => {
-            return Kind::Slice(Box::new(typecheck_kind(base, symbol_table, None)))
+            Kind::Slice(Box::new(typecheck_kind(base, symbol_table, None)))
+        },
+        AstKindVariant::Pointer { ref mut base } => {
+            // Like slices, pointers can point to the type being defined
+            Kind::Pointer(Box::new(typecheck_kind(base, symbol_table, None)))
         },
         AstKindVariant::Array { ref mut base, ref size } => {
-            return Kind::Array(Box::new(typecheck_kind(base, symbol_table, top_name)),
-                                util::string_to_int(size))
+            // The length of `[...]T` is set by the literal it is the kind of
+            let length = if size == "..." { 0 } else { util::string_to_int(size) };
+            Kind::Array(Box::new(typecheck_kind(base, symbol_table, top_name)), length)
         },
         AstKindVariant::Struct { ref mut fields } => {
             let mut kind_fields = Vec::new();
             let mut previous_names = HashSet::new();
             for field in fields {
+                // Taken before the type name is renamed
+                let embedded_name = if field.identifiers.is_empty() {
+                    Some(embedded_field_name(&field.kind))
+                } else {
+                    None
+                };
                 let field_kind = typecheck_kind(&mut field.kind, symbol_table, top_name);
-                for id in &field.identifiers {
+                let names = match embedded_name {
+                    Some(ref name) => {
+                        check_embedded_kind(&field_kind, field.span, symbol_table);
+                        vec![name.clone()]
+                    }
+                    None => field.identifiers.clone(),
+                };
+                for id in names {
                     if &*id != "_" {
                         if previous_names.contains(&*id) {
-                            eprintln!("Error: line {}: duplicate struct field `{}`.", ast.line_number, id);
-                            exit(1);
+                            symbol_table.error(ast.span, &format!("duplicate struct field `{}`.", id));
                         }
                         previous_names.insert(id.clone());
                     }
-                    kind_fields.push(kind::Field{name: id.clone(), kind: field_kind.clone()});
+                    kind_fields.push(kind::Field{name: id, kind: field_kind.clone(),
+                                                 embedded: embedded_name.is_some()});
+                }
+            }
+            Kind::Struct(kind_fields)
+        }
+        AstKindVariant::Function { ref mut parameters, ref mut results } => {
+            // Like slices, functions can take and return the type being defined
+            let params = typecheck_field_kinds(parameters, symbol_table);
+            let return_kind = result_kind(typecheck_field_kinds(results, symbol_table));
+            Kind::Function(params, return_kind.map(Box::new), is_variadic(parameters))
+        }
+        AstKindVariant::Interface { ref mut methods } => {
+            // Like functions, methods can take and return the type being defined
+            let mut interface_methods: Vec<InterfaceMethod> = Vec::new();
+            for method in methods {
+                let kind = typecheck_kind(&mut method.kind, symbol_table, None);
+                let name = method.identifiers[0].clone();
+                if name == "_" {
+                    symbol_table.error(method.span, "methods must have a unique non-blank name.");
+                } else if interface_methods.iter().any(|m| m.name == name) {
+                    symbol_table.error(method.span, &format!("duplicate method {}.", name));
+                } else {
+                    interface_methods.push(InterfaceMethod { name, kind });
                 }
             }
-            return Kind::Struct(kind_fields)
+            interface_methods.sort_by(|a, b| a.name.cmp(&b.name));
+            Kind::Interface(interface_methods)
+        }
+        AstKindVariant::Chan { ref mut base } => {
+            // Like slices, channels can carry the type being defined
+            Kind::Chan(Box::new(typecheck_kind(base, symbol_table, None)))
+        }
+        AstKindVariant::Map { ref mut key, ref mut value } => {
+            // Like slices, maps can contain the type being defined
+            let key_kind = typecheck_kind(key, symbol_table, None);
+            if !key_kind.is_comparable() {
+                symbol_table.error(key.span, &format!("invalid map key type {}.", key_kind));
+            }
+            Kind::Map(Box::new(key_kind), Box::new(typecheck_kind(value, symbol_table, None)))
+        }
+    };
+    ast.evaluated_kind = kind.clone();
+    kind
+}
+
+/// Makes `x` select the embedded fields leading to a promoted field or method of it, like `x.T`
+fn select_embedded_fields(primary: &mut Box<Expression>, path: &[kind::Field]) {
+    for field in path {
+        let span = primary.span;
+        let placeholder = Expression { span, kind: Kind::Undefined, variant: ExpressionVariant::Nil };
+        let embedding = mem::replace(&mut **primary, placeholder);
+        **primary = Expression {
+            span,
+            kind: field.kind.clone(),
+            variant: ExpressionVariant::Selector { primary: Box::new(embedding), name: field.name.clone() },
+        };
+    }
+}
+
+/// The name of an embedded field, which is the name of its type
+fn embedded_field_name(kind: &AstKindNode) -> String {
+    match kind.variant {
+        AstKindVariant::Identifier { ref name } => name.clone(),
+        AstKindVariant::Pointer { ref base } => embedded_field_name(base),
+        _ => unreachable!("The parser only embeds type names and pointers to them"),
+    }
+}
+
+/// Reports the kinds that cannot be embedded in a struct
+fn check_embedded_kind(kind: &Kind, span: Span, symbol_table: &SymbolTable) {
+    match *kind {
+        Kind::Pointer(ref base) if base.is_pointer() || base.is_interface() => {
+            symbol_table.error(span, &format!("embedded field type cannot be a pointer to {}.", base));
         }
+        Kind::Pointer(..) => {}
+        _ if kind.is_pointer() => {
+            symbol_table.error(span, &format!("embedded field type cannot be the pointer type {}.", kind));
+        }
+        _ => {}
     }
 }
 
+/// Typechecks a case of a type switch on a value of the interface kind `kind`, which has the
+/// given methods if it typechecked. `nil` has the nil kind, for the case of a nil interface.
+fn typecheck_type_case(case: &mut AstKindNode, kind: &Kind, methods: Option<&Vec<InterfaceMethod>>,
+                       symbol_table: &mut SymbolTable) -> Kind {
+    let is_nil = match case.variant {
+        AstKindVariant::Identifier { ref name } => {
+            matches!(symbol_table.lookup(name), Some(&Symbol { declaration: Declaration::Nil, .. }))
+        }
+        _ => false,
+    };
+    if is_nil {
+        case.evaluated_kind = Kind::Nil;
+        return Kind::Nil;
+    }
+    let case_kind = typecheck_kind(case, symbol_table, None);
+    if let Some(methods) = methods {
+        if !case_kind.is_interface() {
+            if let Some(method) = case_kind.missing_method(methods) {
+                symbol_table.error(case.span, &format!("impossible type switch case: {} cannot \
+                have dynamic type {} (missing method {}).", kind, case_kind, method));
+            }
+        }
+    }
+    case_kind
+}
 
 fn typecheck_expression(exp: &mut Expression, 
                         symbol_table: &mut SymbolTable, 
                         from_expression_statement: bool) -> Kind {
+    typecheck_untyped_expression(exp, symbol_table, from_expression_statement);
+    // Nothing decides the kind of an untyped constant here, so it gets its default one
+    convert_untyped(exp, &Kind::Undefined, symbol_table)
+}
+
+/// Like typecheck_expression, but an untyped constant gets the given kind if it can have it
+fn typecheck_expression_as(exp: &mut Expression, kind: &Kind, symbol_table: &mut SymbolTable)
+    -> Kind {
+    typecheck_untyped_expression(exp, symbol_table, false);
+    convert_untyped(exp, kind, symbol_table)
+}
+
+/// Typechecks an expression that can be an untyped constant, which is left as it is until
+/// something gives it a kind
+fn typecheck_untyped_expression(exp: &mut Expression,
+                                symbol_table: &mut SymbolTable,
+                                from_expression_statement: bool) -> Kind {
+
+    // Set to the length of the array when a `len` can be replaced by a constant
+    let mut folded_len = None;
+    // Set when the expression is a typed constant that can be replaced by a literal
+    let mut folded_constant = None;
+    // Set when both operands are untyped, which makes the operation an untyped constant as well
+    let mut is_untyped_operation = false;
+    // Set when a selector names a method, with the method's name, function and receiver
+    let mut selected_method = None;
+    // Set when a selector names a method of an interface
+    let mut selected_interface_method = false;
+    // Set when the identifier is the predeclared nil
+    let mut is_nil = false;
 
     match exp.variant {
-        ExpressionVariant::RawLiteral{..} => {
+        // Literals are untyped constants, which get their kind from where they are used
+        ExpressionVariant::RawLiteral { ref value } => {
+            if let Kind::Basic(basic) = exp.kind {
+                let basic = if util::is_rune_literal(value) { BasicKind::Rune } else { basic };
+                exp.kind = Kind::Untyped(basic);
+            }
         }
 
         ExpressionVariant::Identifier { ref mut name, .. } => {
@@ -530,240 +1029,678 @@ fn typecheck_expression(exp: &mut Expression,
                 exp.kind = Kind::Underscore;
                 return exp.kind.clone();
             }
-            let symbol = symbol_table.get_symbol(name, exp.line_number);
-            match symbol.declaration {
-                Declaration::Variable(ref kind) | Declaration::Constant(ref kind) => {
+            if name == "iota" && symbol_table.lookup(name).is_none() {
+                symbol_table.error(exp.span, "cannot use iota outside constant declaration.");
+                exp.kind = Kind::Undefined;
+                return exp.kind.clone();
+            }
+            match symbol_table.get_symbol(name, exp.span) {
+                Some(&Symbol { declaration: Declaration::Variable(ref kind), ref new_name, .. }) => {
                     exp.kind = kind.clone();
-                    *name = symbol.new_name.clone();
+                    *name = new_name.clone();
                 }
-                _ => {
-                    eprintln!("Error: line {}: `{}` is not a variable or a constant.", 
-                              exp.line_number, name);
-                    exit(1);
+                Some(&Symbol { declaration: Declaration::Function{ ref params, ref return_kind, variadic }, ref new_name, .. }) => {
+                    exp.kind = Kind::Function(params.clone(), return_kind.clone().map(Box::new), variadic);
+                    *name = new_name.clone();
+                }
+                Some(&Symbol { declaration: Declaration::Constant(ref kind, ref value), .. }) => {
+                    match *kind {
+                        // Folded once it gets a kind
+                        Kind::Untyped(..) | Kind::Undefined => exp.kind = kind.clone(),
+                        _ => folded_constant = Some((value.clone(), kind.clone())),
+                    }
+                }
+                Some(&Symbol { declaration: Declaration::Nil, .. }) => {
+                    exp.kind = Kind::Nil;
+                    is_nil = true;
                 }
+                Some(_) => {
+                    symbol_table.error(exp.span, &format!("`{}` is not a variable, a constant or a function.", name));
+                    exp.kind = Kind::Undefined;
+                }
+                None => exp.kind = Kind::Undefined,
             }
         }
 
         ExpressionVariant::UnaryOperation { ref op, ref mut rhs } => {
-            let kind = typecheck_expression(rhs, symbol_table, false);
-            let op_kind = get_kind_unary_op(&kind, op.clone(), exp.line_number);
-            exp.kind = op_kind;
+            let kind = typecheck_untyped_expression(rhs, symbol_table, false);
+            if let Kind::Untyped(..) = kind {
+                is_untyped_operation = true;
+            } else {
+                let op_kind = get_kind_unary_op(&kind, *op, exp.span, symbol_table);
+                exp.kind = op_kind;
+            }
         }
 
         ExpressionVariant::BinaryOperation { ref op, ref mut lhs, ref mut rhs } => {
-            let lhs_kind = typecheck_expression(lhs, symbol_table, false);
-            let rhs_kind = typecheck_expression(rhs, symbol_table, false);
-            let op_kind = get_kind_binary_op(&lhs_kind, &rhs_kind, op.clone(), exp.line_number);
-            exp.kind = op_kind;
+            let lhs_kind = typecheck_untyped_expression(lhs, symbol_table, false);
+            let rhs_kind = typecheck_untyped_expression(rhs, symbol_table, false);
+            match (lhs_kind, rhs_kind) {
+                (Kind::Untyped(..), Kind::Untyped(..)) => is_untyped_operation = true,
+                (_, rhs_kind) => {
+                    // An untyped operand takes the kind of the other one
+                    let lhs_kind = convert_untyped(lhs, &rhs_kind, symbol_table);
+                    let rhs_kind = convert_untyped(rhs, &lhs_kind, symbol_table);
+                    // Function values are not comparable, except to nil
+                    let is_nil = |e: &Expression| matches!(e.variant, ExpressionVariant::Nil);
+                    let compares_function_to_nil =
+                        (*op == BinaryOperator::Eq || *op == BinaryOperator::Neq) &&
+                        (is_nil(lhs) || is_nil(rhs)) &&
+                        matches!(lhs_kind.resolve(), Kind::Function(..)) &&
+                        are_identical(&lhs_kind, &rhs_kind);
+                    let op_kind = if compares_function_to_nil {
+                        Kind::Basic(BasicKind::Bool)
+                    } else {
+                        get_kind_binary_op(&lhs_kind, &rhs_kind, *op, exp.span,
+                                           symbol_table)
+                    };
+                    exp.kind = op_kind;
+                }
+            }
         }
 
         ref mut a@ExpressionVariant::FunctionCall { .. } => {
-            // This block is a horror
-
             // Here I do this weird thing where I reassign the node to either a function call or a
             // type cast depending on what the primary expression is.
             
-            let primary;
+            let mut primary;
             let mut arguments;
+            let spread;
             
             // To move things out of a borrowed value, I need to put something in its place
-            if let ExpressionVariant::FunctionCall{ primary: p, arguments: a } = mem::replace(
+            if let ExpressionVariant::FunctionCall{ primary: p, arguments: a, spread: s } = mem::replace(
                 a, ExpressionVariant::Identifier{ name: "^ this is dumb ^".to_string(),
                 original_name: "".to_string()}) {
                 primary = p;
                 arguments = a;
+                spread = s;
             } else {
                 unreachable!();
             }
 
+            // Only a type name makes the call a type cast, anything else is called as a function
+            let cast = match primary.variant {
+                ExpressionVariant::Identifier{ ref name, .. } => match symbol_table.lookup(name) {
+                    Some(&Symbol { declaration: Declaration::Type(ref kind), ref new_name, .. }) => {
+                        Some((kind.clone(), new_name.clone()))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            };
+
             *a = 
-            if let Some(ref name) =
-                // fml
-                if let ExpressionVariant::Identifier{ref name, ..} = primary.variant {
-                    Some(name.clone())
-                }
-                else {
-                    None
+            if let Some((cast_kind, renamed)) = cast {
+                if spread {
+                    symbol_table.error(exp.span, "cannot use ... in type cast.");
                 }
-            {
-                // Note: we don't need to check if we're trying to call init because init is not
-                // going to be in scope anyway.
-                // And we can also have a type called `init`.
-                //
-                let renamed;
-                {
-                    let symbol = symbol_table.get_symbol(&name, exp.line_number);
-                    renamed = symbol.new_name.clone();
-                }
-                let decl = symbol_table.get_symbol(&name, exp.line_number).declaration.clone();
-                match decl { 
-                    Declaration::Type(cast_kind) => {
-                        if arguments.len() != 1 {
-                            eprintln!("Error: line {}: Type casts take exactly one parameter.",
-                                      exp.line_number);
-                            exit(1);
-                        }
-                        let mut inner_expr = arguments.drain(0..1).next().unwrap();
-                        let expr_kind = typecheck_expression(&mut inner_expr, symbol_table, false);
+                if arguments.len() == 1 {
+                    let mut inner_expr = arguments.drain(0..1).next().unwrap();
+                    let expr_kind = typecheck_expression_as(&mut inner_expr, &cast_kind,
+                                                            symbol_table);
 
-                        let resolved_cast_kind = cast_kind.resolve();
-                        let resolved_expr_kind = expr_kind.resolve();
+                    let resolved_cast_kind = cast_kind.resolve();
+                    let resolved_expr_kind = expr_kind.resolve();
 
-                        if let Kind::Basic(ref cast_basic) = resolved_cast_kind {
-                            if are_identical(&resolved_cast_kind, &resolved_expr_kind) ||
-                                (resolved_cast_kind.is_numeric() && resolved_expr_kind.is_numeric()) ||
-                                    (cast_basic == &BasicKind::String && resolved_expr_kind.is_integer()) {
+                    if let Kind::Basic(ref cast_basic) = resolved_cast_kind {
+                        if are_identical(&resolved_cast_kind, &resolved_expr_kind) ||
+                            (resolved_cast_kind.is_numeric() && resolved_expr_kind.is_numeric()) ||
+                                (cast_basic == &BasicKind::String && resolved_expr_kind.is_integer()) {
 
-                            } else {
-                                eprintln!("Error: line {}: Trying to cast expression of type {} \
-                                to incompatible type {}.",
-                                    exp.line_number, expr_kind, cast_kind);
-                                exit(1);
-
-                            }
                         } else {
-                            eprintln!("Error: line {}: Cast type must resolve to a basic type; \
-                                {} resolves to {} which is not a basic type.",
-                                exp.line_number, cast_kind, resolved_cast_kind);
-                            exit(1);
+                            symbol_table.error(exp.span, &format!("Trying to cast expression of type {} \
+                            to incompatible type {}.", expr_kind, cast_kind));
+
                         }
+                    } else if let Kind::Interface(..) = resolved_cast_kind {
+                        // The value has been converted to the interface, or reported
+                    } else if !resolved_cast_kind.is_undefined() {
+                        symbol_table.error(exp.span, &format!("Cast type must resolve to a basic type; \
+                            {} resolves to {} which is not a basic type.", cast_kind, resolved_cast_kind));
+                    }
 
-                        exp.kind = cast_kind.clone();
-                        ExpressionVariant::TypeCast{name:renamed, expr: Box::new(inner_expr) }
-                    },
-                    Declaration::Function{ref params, ref return_kind} => {
+                    exp.kind = cast_kind.clone();
+                    ExpressionVariant::TypeCast{name:renamed, expr: Box::new(inner_expr) }
+                } else {
+                    symbol_table.error(exp.span, "Type casts take exactly one parameter.");
+                    // The arguments can still have errors of their own
+                    typecheck_expression_vec(&mut arguments, symbol_table);
+                    exp.kind = Kind::Undefined;
+                    ExpressionVariant::FunctionCall{ primary, arguments, spread }
+                }
+            } else {
+                // Used in the error messages
+                let callee = match primary.variant {
+                    ExpressionVariant::Identifier{ ref name, .. } => format!("`{}`", name),
+                    _ => "function value".to_string(),
+                };
 
-                        if arguments.len() != params.len() {
-                            eprintln!("Error: line {}: `{}` takes {} arguments but only {} were provided.",
-                                      exp.line_number, &name, params.len(), arguments.len());
-                            exit(1);
+                let primary_kind = typecheck_expression(&mut primary, symbol_table, false);
+                match primary_kind.resolve() {
+                    Kind::Function(ref params, ref return_kind, variadic) => {
+                        // The trailing arguments of a variadic function are packed into a slice,
+                        // unless a slice is spread as the variadic parameter
+                        let packed = variadic && !spread;
+                        let fixed = if packed { params.len() - 1 } else { params.len() };
+                        if spread && !variadic {
+                            symbol_table.error(exp.span, &format!("cannot use ... in call to non-variadic {}.", callee));
+                        } else if packed && arguments.len() < fixed {
+                            symbol_table.error(exp.span, &format!("{} takes at least {} arguments but only {} were provided.", callee, fixed, arguments.len()));
+                        } else if !packed && arguments.len() != params.len() {
+                            symbol_table.error(exp.span, &format!("{} takes {} arguments but only {} were provided.", callee, params.len(), arguments.len()));
                         }
 
-                        let argument_kinds = typecheck_expression_vec(&mut arguments,symbol_table);
-                        for (i, (ref ak, ref pk)) in argument_kinds.iter().zip(params.iter()).enumerate() {
+                        let param_kinds: Vec<Kind> = (0..arguments.len()).map(|i| match params.get(i.min(fixed)) {
+                            Some(Kind::Slice(base)) if i >= fixed => (**base).clone(),
+                            Some(param) if i < fixed => param.clone(),
+                            _ => Kind::Undefined,
+                        }).collect();
+                        let mut argument_kinds = Vec::new();
+                        for (argument, param) in arguments.iter_mut().zip(param_kinds.iter()) {
+                            argument_kinds.push(typecheck_expression_as(argument, param, symbol_table));
+                        }
+                        for (i, (ref ak, ref pk)) in argument_kinds.iter().zip(param_kinds.iter()).enumerate() {
                             if !are_identical(&ak, &pk) {
-                                eprintln!("Error: line {}: argment {} that was provided for function `{}` is of type {} \
-                                but should be of type {}.",
-                                          exp.line_number, i+1, &name, ak, pk);
-                                exit(1);
+                                symbol_table.error(exp.span, &format!("argment {} that was provided for {} is of type {} \
+                                but should be of type {}.", i+1, callee, ak, pk));
                             }
                         }
-                        
-                        match return_kind {
-                            &Some(ref r) => {
-                                exp.kind = r.clone();
-                            }
-                            &None => {
-                                exp.kind = Kind::Void;
-                            }
+
+                        exp.kind = match return_kind {
+                            Some(r) => (**r).clone(),
+                            None => Kind::Void,
+                        };
+                    }
+                    kind => {
+                        // An undefined primary has already been reported
+                        if !kind.is_undefined() {
+                            symbol_table.error(exp.span, &format!("{} is not a type or a function.", callee));
                         }
-                        ExpressionVariant::FunctionCall{ primary: Box::new(Expression{
-                            kind: primary.kind.clone(),
-                            line_number: exp.line_number,
-                            variant: ExpressionVariant::Identifier{
-                                name: renamed,
-                                original_name: name.to_string()
-                            }})
-                            , arguments }
-                    },
-                    _ => {
-                        eprintln!("Error: line {}: `{}` is not a type or a function.",
-                                  exp.line_number, name);
-                        exit(1);
+                        // The arguments can still have errors of their own
+                        typecheck_expression_vec(&mut arguments, symbol_table);
+                        exp.kind = Kind::Undefined;
                     }
                 }
-            } else {
-                eprintln!("Error: line {}: primary expression for function call or \
-                type cast must be an identifier.", exp.line_number);
-                exit(1);
+                ExpressionVariant::FunctionCall{ primary, arguments, spread }
             };
 
         }
 
         ExpressionVariant::Index { ref mut primary, ref mut index } => {
             let primary_kind = typecheck_expression(primary, symbol_table, false);
-            let index_kind = typecheck_expression(index, symbol_table, false);
+            let index_target = match primary_kind.resolve() {
+                Kind::Map(ref key, _) => (**key).clone(),
+                _ => Kind::Basic(BasicKind::Int),
+            };
+            let index_kind = typecheck_expression_as(index, &index_target, symbol_table);
             match primary_kind.resolve() {
+                Kind::Map(ref key, ref value) => {
+                    if !are_identical(&index_kind, key) {
+                        symbol_table.error(exp.span, &format!("cannot use index of type {} as \
+                        map key of type {}.", index_kind, key));
+                    }
+                    exp.kind = (**value).clone();
+                }
                 Kind::Array(ref a_kind, ..) | Kind::Slice(ref a_kind) => {
-                    if let Kind::Basic(ref kind)=index_kind.resolve()  {
-                        if let &BasicKind::Int = kind {
-                            exp.kind = *a_kind.clone();
-                        } else {
-                            eprintln!("Error: line {}: index expression does not resolve \
-                            to int", exp.line_number);
-                            exit(1);
+                    match index_kind.resolve() {
+                        Kind::Basic(BasicKind::Int) | Kind::Undefined => {},
+                        Kind::Basic(..) => {
+                            symbol_table.error(exp.span, "index expression does not resolve \
+                            to int");
+                        }
+                        _ => {
+                            symbol_table.error(exp.span, "index expression does not resolve to \
+                            Basic type");
                         }
-                    } else {
-                        eprintln!("Error: line {}: index expression does not resolve to \
-                        Basic type", exp.line_number);
-                        exit(1);
                     }
+                    exp.kind = *a_kind.clone();
                 }
+                Kind::Undefined => exp.kind = Kind::Undefined,
                 _ => {
-                    eprintln!("Error: line {}: primary expression does not resolve to \
-                               Slice or Array type", exp.line_number);
-                    exit(1);
+                    symbol_table.error(exp.span, "primary expression does not resolve to \
+                               Slice, Array or Map type");
+                    exp.kind = Kind::Undefined;
+                }
+            }
+        }
+
+        ExpressionVariant::Slice { ref mut primary, ref mut low, ref mut high, ref mut max } => {
+            let primary_kind = typecheck_expression(primary, symbol_table, false);
+            let is_full = max.is_some();
+            // The indices that are constants are checked now, like in Go
+            let mut constants: Vec<i64> = Vec::new();
+            for index in low.iter_mut().chain(high.iter_mut()).chain(max.iter_mut()) {
+                let index_kind = typecheck_expression_as(index, &Kind::Basic(BasicKind::Int),
+                                                         symbol_table);
+                match index_kind.resolve() {
+                    Kind::Basic(BasicKind::Int) | Kind::Undefined => {},
+                    _ => symbol_table.error(index.span, "slice index does not resolve to int."),
+                }
+                let i = match constant_index(index) {
+                    Some(i) => i,
+                    None => continue,
+                };
+                if i < 0 {
+                    symbol_table.error(index.span, &format!("invalid slice index {} (index must \
+                    be non-negative).", i));
+                } else if let Kind::Array(_, size) = primary_kind.resolve() {
+                    if i > size as i64 {
+                        symbol_table.error(index.span, &format!("invalid slice index {} (out of \
+                        bounds for {}-element array).", i, size));
+                    }
                 }
+                if let Some(&previous) = constants.iter().find(|&&p| p > i) {
+                    symbol_table.error(index.span, &format!("invalid slice indices: {} < {}.",
+                                                            i, previous));
+                }
+                constants.push(i);
             }
+            exp.kind = match primary_kind.resolve() {
+                Kind::Slice(..) => primary_kind.clone(),
+                // The slice shares the elements of the array, so the array must be a variable
+                Kind::Array(ref element, _) => {
+                    if !is_addressable(primary) {
+                        symbol_table.error(exp.span, "cannot slice unaddressable array.");
+                    }
+                    Kind::Slice(element.clone())
+                }
+                Kind::Basic(BasicKind::String) => {
+                    if is_full {
+                        symbol_table.error(exp.span, "3-index slice of string.");
+                    }
+                    primary_kind.clone()
+                }
+                Kind::Undefined => Kind::Undefined,
+                _ => {
+                    symbol_table.error(exp.span, &format!("cannot slice {}.", primary_kind));
+                    Kind::Undefined
+                }
+            };
         }
 
         ExpressionVariant::Selector { ref mut primary, ref name } => {
             let kind = typecheck_expression(primary, symbol_table, false);
-            if let Kind::Struct(ref fields) = kind.resolve() {
-                let mut found = false;  // used to skip over error printing messages
-                for field in fields {
-                    if field.name == *name {
-                        exp.kind = field.kind.clone();
-                        found = true;
-                        break;
+            exp.kind = Kind::Undefined;
+            // The fields of a struct can be selected through a pointer to it
+            let base = match kind.resolve() {
+                Kind::Pointer(base) => *base,
+                _ => kind.clone(),
+            };
+            if let Kind::Interface(ref methods) = kind.resolve() {
+                match methods.iter().find(|m| m.name == *name) {
+                    Some(method) => {
+                        exp.kind = method.kind.clone();
+                        selected_interface_method = true;
                     }
+                    None => symbol_table.error(exp.span, &format!("{} has no method {}.", kind, name)),
                 }
-                if !found {
-                    eprintln!("Error: line {}: unknown field \"{}\"", exp.line_number, name);
-                    exit(1);
+            } else { match kind.lookup(name) {
+                Lookup::Found(path, selection) => {
+                    // Promoted fields and methods are selected through the embedded fields
+                    select_embedded_fields(primary, &path);
+                    match selection {
+                        Selection::Method(method) => {
+                            // Pointer methods implicitly take the address of their receiver
+                            if method.pointer_receiver && !primary.kind.is_pointer() &&
+                                !is_addressable(primary) {
+                                symbol_table.error(exp.span, &format!("cannot call pointer method \
+                                {} on {}.", name, kind));
+                            }
+                            exp.kind = method.kind();
+                            selected_method = Some((name.clone(), method.function,
+                                                    method.pointer_receiver));
+                        }
+                        Selection::InterfaceMethod(method_kind) => {
+                            exp.kind = method_kind;
+                            selected_interface_method = true;
+                        }
+                        Selection::Field(field_kind) => exp.kind = field_kind,
+                    }
                 }
-            } else {
-                eprintln!("Error: line {}: primary expression does not resolve to \
-                          Struct type", exp.line_number);
-                exit(1);
-            }
+                Lookup::Ambiguous => {
+                    symbol_table.error(exp.span, &format!("ambiguous selector {}.", name));
+                }
+                Lookup::Missing => match base.resolve() {
+                    Kind::Struct(..) => {
+                        symbol_table.error(exp.span, &format!("unknown field \"{}\"", name));
+                    }
+                    Kind::Undefined => {},
+                    _ => {
+                        symbol_table.error(exp.span, "primary expression does not resolve to \
+                                  Struct type");
+                    }
+                },
+            } }
         }
 
         ExpressionVariant::Append { ref mut lhs, ref mut rhs } => {
             let s_kind = typecheck_expression(lhs, symbol_table, false);
-            let kind = typecheck_expression(rhs, symbol_table, false);
+            let element_kind = match s_kind.resolve() {
+                Kind::Slice(ref t_kind) => (**t_kind).clone(),
+                _ => Kind::Undefined,
+            };
+            let kind = typecheck_expression_as(rhs, &element_kind, symbol_table);
 
-            if let Kind::Slice(ref t_kind) = s_kind.resolve() {
-                if are_identical(t_kind, &kind) {
+            match s_kind.resolve() {
+                Kind::Slice(ref t_kind) => {
+                    if !are_identical(t_kind, &kind) {
+                        symbol_table.error(exp.span, "mismatched types in \
+                        append expression");
+                    }
                     exp.kind = s_kind.clone();
+                }
+                Kind::Undefined => exp.kind = Kind::Undefined,
+                _ => {
+                    symbol_table.error(exp.span, "lhs does not resolve to Slice \
+                    in append expression");
+                    exp.kind = Kind::Undefined;
+                }
+            }
+        }
+
+        ExpressionVariant::Len { ref mut expr } => {
+            let kind = typecheck_expression(expr, symbol_table, false);
+            exp.kind = Kind::Basic(BasicKind::Int);
+            match kind.resolve() {
+                // Like in Go, the length of an array is only a constant if getting the array
+                // doesn't call anything
+                Kind::Array(_, size) if !contains_call(expr) => folded_len = Some(size),
+                Kind::Array(..) | Kind::Slice(..) | Kind::Basic(BasicKind::String) |
+                Kind::Map(..) | Kind::Chan(..) | Kind::Undefined => {}
+                _ => {
+                    symbol_table.error(exp.span, &format!("invalid argument of type {} for \
+                    len", kind));
+                }
+            }
+        }
+
+        ExpressionVariant::Cap { ref mut expr } => {
+            let kind = typecheck_expression(expr, symbol_table, false);
+            exp.kind = Kind::Basic(BasicKind::Int);
+            match kind.resolve() {
+                Kind::Array(..) | Kind::Slice(..) | Kind::Chan(..) | Kind::Undefined => {}
+                _ => {
+                    symbol_table.error(exp.span, &format!("invalid argument of type {} for \
+                    cap", kind));
+                }
+            }
+        }
+
+        ExpressionVariant::Make { ref mut kind, ref mut arguments } => {
+            exp.kind = typecheck_kind(kind, symbol_table, None);
+            match exp.kind.resolve() {
+                // The argument is the size hint of a map, or the buffer size of a channel
+                Kind::Map(..) | Kind::Chan(..) => {
+                    if arguments.len() > 1 {
+                        symbol_table.error(exp.span, "too many arguments to make.");
+                    }
+                }
+                // The arguments are the length of the slice, and its capacity if it is bigger
+                Kind::Slice(..) => {
+                    if arguments.is_empty() {
+                        symbol_table.error(exp.span, &format!("missing len argument to make({}).",
+                                                              exp.kind));
+                    } else if arguments.len() > 2 {
+                        symbol_table.error(exp.span, "too many arguments to make.");
+                    }
+                }
+                Kind::Undefined => {}
+                _ => {
+                    symbol_table.error(exp.span, &format!("cannot make type {}.", exp.kind));
+                    exp.kind = Kind::Undefined;
+                }
+            }
+            for argument in arguments.iter_mut() {
+                let argument_kind = typecheck_expression_as(argument, &Kind::Basic(BasicKind::Int),
+                                                            symbol_table);
+                if !argument_kind.resolve().is_integer() && !argument_kind.is_undefined() {
+                    symbol_table.error(argument.span, &format!("size argument of type {} \
+                    in make is not an integer.", argument_kind));
+                }
+                if constant_index(argument).is_some_and(|size| size < 0) {
+                    symbol_table.error(argument.span, "negative size argument in make.");
+                }
+            }
+            if arguments.len() == 2 {
+                if let (Some(length), Some(capacity)) = (constant_index(&arguments[0]),
+                                                         constant_index(&arguments[1])) {
+                    if length > capacity {
+                        symbol_table.error(exp.span, &format!("len larger than cap in make({}).",
+                                                              exp.kind));
+                    }
+                }
+            }
+        }
+
+        ExpressionVariant::New { ref mut kind } => {
+            exp.kind = match typecheck_kind(kind, symbol_table, None) {
+                Kind::Undefined => Kind::Undefined,
+                kind => Kind::Pointer(Box::new(kind)),
+            };
+        }
+
+        ExpressionVariant::Dereference { ref mut expr } => {
+            let kind = typecheck_expression(expr, symbol_table, false);
+            exp.kind = match kind.resolve() {
+                Kind::Pointer(base) => *base,
+                Kind::Undefined => Kind::Undefined,
+                _ => {
+                    symbol_table.error(exp.span, &format!("cannot dereference value of type \
+                    {}.", kind));
+                    Kind::Undefined
+                }
+            };
+        }
+
+        ExpressionVariant::AddressOf { ref mut expr } => {
+            let kind = typecheck_expression(expr, symbol_table, false);
+            // Functions and folded constants are identifiers as well, but only variables have
+            // an address
+            let is_variable = match expr.variant {
+                ExpressionVariant::Identifier { ref original_name, .. } => {
+                    matches!(symbol_table.lookup(original_name),
+                             Some(&Symbol { declaration: Declaration::Variable(..), .. }))
+                }
+                _ => true,
+            };
+            // `&T{}` gives a new variable holding the literal
+            let is_literal = matches!(expr.variant, ExpressionVariant::CompositeLiteral { .. });
+            exp.kind = Kind::Undefined;
+            if !kind.is_undefined() {
+                if is_variable && is_addressable(expr) || is_literal {
+                    exp.kind = Kind::Pointer(Box::new(kind));
                 } else {
-                    eprintln!("Error: line {}: mismatched types in \
-                    append expression", exp.line_number);
-                    exit(1);
+                    symbol_table.error(exp.span, "cannot take the address of an expression \
+                    that is not addressable.");
+                }
+            }
+        }
+
+        ExpressionVariant::Delete { ref mut map, ref mut key } => {
+            let map_kind = typecheck_expression(map, symbol_table, false);
+            exp.kind = Kind::Void;
+            match map_kind.resolve() {
+                Kind::Map(ref key_kind, _) => {
+                    let kind = typecheck_expression_as(key, key_kind, symbol_table);
+                    if !are_identical(&kind, key_kind) {
+                        symbol_table.error(key.span, &format!("cannot use key of type {} \
+                        to delete from map of type {}.", kind, map_kind));
+                    }
+                }
+                Kind::Undefined => {
+                    typecheck_expression(key, symbol_table, false);
+                }
+                _ => {
+                    typecheck_expression(key, symbol_table, false);
+                    symbol_table.error(map.span, &format!("first argument of delete has \
+                    type {} instead of a map.", map_kind));
+                }
+            }
+        }
+
+        ExpressionVariant::Panic { ref mut expr } => {
+            // Like print, panic only takes values that it can show
+            let kind = typecheck_expression(expr, symbol_table, false);
+            match kind.resolve() {
+                Kind::Basic(..) | Kind::Undefined => {},
+                _ => symbol_table.error(expr.span, &format!("cannot panic with a value of type {}.",
+                                                            kind)),
+            }
+            exp.kind = Kind::Void;
+        }
+
+        ExpressionVariant::Receive { ref mut channel } => {
+            let kind = typecheck_expression(channel, symbol_table, false);
+            exp.kind = match kind.resolve() {
+                Kind::Chan(base) => *base,
+                Kind::Undefined => Kind::Undefined,
+                _ => {
+                    symbol_table.error(channel.span, &format!("cannot receive from non-channel \
+                    value of type {}.", kind));
+                    Kind::Undefined
+                }
+            };
+        }
+
+        ExpressionVariant::Close { ref mut channel } => {
+            let kind = typecheck_expression(channel, symbol_table, false);
+            match kind.resolve() {
+                Kind::Chan(..) | Kind::Undefined => {},
+                _ => symbol_table.error(channel.span, &format!("invalid argument of type {} for \
+                close.", kind)),
+            }
+            exp.kind = Kind::Void;
+        }
+
+        ExpressionVariant::Copy { ref mut dst, ref mut src } => {
+            let dst_kind = typecheck_expression(dst, symbol_table, false);
+            let src_kind = typecheck_expression(src, symbol_table, false);
+            match (dst_kind.resolve(), src_kind.resolve()) {
+                (Kind::Undefined, _) | (_, Kind::Undefined) => {},
+                (Kind::Slice(ref a), Kind::Slice(ref b)) => {
+                    if !are_identical(a, b) {
+                        symbol_table.error(exp.span, &format!("arguments to copy have different \
+                        element types {} and {}.", a, b));
+                    }
+                }
+                (Kind::Slice(..), _) => {
+                    symbol_table.error(src.span, &format!("second argument to copy should be \
+                    slice; have {}.", src_kind));
+                }
+                _ => {
+                    symbol_table.error(dst.span, &format!("first argument to copy should be \
+                    slice; have {}.", dst_kind));
+                }
+            }
+            exp.kind = Kind::Basic(BasicKind::Int);
+        }
+
+        ExpressionVariant::CompositeLiteral { ref mut kind, ref mut elements } => {
+            // A literal without a kind was given one by the literal it is in
+            let mut inferred_length = false;
+            if let Some(ref mut kind) = *kind {
+                exp.kind = typecheck_kind(kind, symbol_table, None);
+                inferred_length = matches!(kind.variant,
+                                           AstKindVariant::Array { ref size, .. } if size == "...");
+            }
+            if inferred_length {
+                // The elements of `[...]T{}` are checked like those of a slice literal, and the
+                // array gets the length they need
+                if let Kind::Array(base, _) = exp.kind.clone() {
+                    let length = typecheck_literal_elements(&Kind::Slice(base.clone()), elements,
+                                                            exp.span, symbol_table);
+                    exp.kind = Kind::Array(base, length);
+                }
+                if let Some(ref mut kind) = *kind {
+                    kind.evaluated_kind = exp.kind.clone();
                 }
             } else {
-                eprintln!("Error: line {}: lhs does not resolve to Slice \
-                in append expression", exp.line_number);
-                exit(1);
+                typecheck_literal_elements(&exp.kind, elements, exp.span, symbol_table);
+            }
+        }
+
+        ExpressionVariant::TypeAssertion { ref mut expr, ref mut kind } => {
+            let expr_kind = typecheck_expression(expr, symbol_table, false);
+            exp.kind = typecheck_kind(kind, symbol_table, None);
+            match expr_kind.resolve() {
+                Kind::Interface(ref methods) => {
+                    // Only the kinds that have the methods can be in the interface
+                    if !exp.kind.is_interface() {
+                        if let Some(method) = exp.kind.missing_method(methods) {
+                            symbol_table.error(exp.span, &format!("impossible type assertion: {} \
+                            does not implement {} (missing method {}).", exp.kind, expr_kind,
+                                                                  method));
+                        }
+                    }
+                }
+                Kind::Undefined => {}
+                _ => {
+                    symbol_table.error(expr.span, &format!("cannot make a type assertion on value \
+                    of type {} which is not an interface.", expr_kind));
+                    exp.kind = Kind::Undefined;
+                }
             }
         }
 
-        ExpressionVariant::TypeCast { .. } => {
+        ExpressionVariant::FunctionLiteral { ref mut function } => {
+            // The literal is only shared once the interpreter makes closures from it
+            let function = Rc::get_mut(function).unwrap();
+            let params = typecheck_field_kinds(&mut function.parameters, symbol_table);
+            let return_kind = result_kind(typecheck_field_kinds(&mut function.results, symbol_table));
+            typecheck_function_body(None, &mut function.parameters, &mut function.results,
+                                    &mut function.body, return_kind.clone(), symbol_table);
+            weed::weed_function_terminating_statements(function, exp.span, &symbol_table.diagnostics);
+            exp.kind = Kind::Function(params, return_kind.map(Box::new), is_variadic(&function.parameters));
+        }
+
+        ExpressionVariant::TypeCast { .. } | ExpressionVariant::CommaOk { .. } |
+        ExpressionVariant::Method { .. } | ExpressionVariant::ToInterface { .. } |
+        ExpressionVariant::InterfaceMethod { .. } | ExpressionVariant::Nil => {
             panic!("This should not happen at this phase.");
         }
     } 
+    if is_nil {
+        exp.variant = ExpressionVariant::Nil;
+    }
+    if let Some((name, function, pointer_receiver)) = selected_method {
+        let placeholder = ExpressionVariant::RawLiteral { value: String::new() };
+        if let ExpressionVariant::Selector { primary, .. } = mem::replace(&mut exp.variant, placeholder) {
+            exp.variant = ExpressionVariant::Method { receiver: primary, name, function, pointer_receiver };
+        }
+    }
+    if selected_interface_method {
+        let placeholder = ExpressionVariant::RawLiteral { value: String::new() };
+        if let ExpressionVariant::Selector { primary, name } = mem::replace(&mut exp.variant, placeholder) {
+            exp.variant = ExpressionVariant::InterfaceMethod { receiver: primary, name };
+        }
+    }
+    if let Some(size) = folded_len {
+        exp.variant = ExpressionVariant::RawLiteral { value: size.to_string() };
+    }
+    if let Some((value, kind)) = folded_constant {
+        *exp = constant_expression(&value, &kind, exp.span);
+    }
+    if is_untyped_operation {
+        // Evaluated now so that mistakes are found even if the result is not used
+        exp.kind = match evaluate_constant(exp, symbol_table, None) {
+            Some((_, kind)) => kind,
+            None => Kind::Undefined,
+        };
+    }
     if let Kind::Void = exp.kind {
         if !from_expression_statement {
-            eprintln!("Error: line {}: Void value used in expression.", exp.line_number);
-            exit(1);
+            symbol_table.error(exp.span, "Void value used in expression.");
+            exp.kind = Kind::Undefined;
+        }
+    }
+    if let Kind::Tuple(ref kinds) = exp.kind.clone() {
+        if !from_expression_statement {
+            symbol_table.error(exp.span, &format!("multiple-value function call ({} values) used \
+            in single-value context.", kinds.len()));
+            exp.kind = Kind::Undefined;
         }
-        return exp.kind.clone()
-    }else if let Kind::Undefined = exp.kind {
-        eprintln!("Error: line {}: Undefined value used in expression.", exp.line_number);
-        exit(1);
-    } else {
-        return exp.kind.clone()
     }
+    exp.kind.clone()
 }
 
 fn typecheck_expression_vec(exprs: &mut [Expression], symbol_table: &mut SymbolTable) ->
@@ -775,6 +1712,677 @@ Vec<Kind> {
     ret
 }
 
+/// Typechecks the expressions assigned to values of the given kinds, and gives the kind of each
+/// value. A single expression can give all the values, like a comma-ok lookup in a map.
+fn typecheck_assigned_expressions(exprs: &mut Vec<Expression>, kinds: &[Kind], span: Span,
+                                  symbol_table: &mut SymbolTable) -> Vec<Kind> {
+    if exprs.len() == kinds.len() {
+        return exprs.iter_mut().zip(kinds.iter())
+            .map(|(e, k)| typecheck_expression_as(e, k, symbol_table))
+            .collect();
+    }
+
+    // The parser only lets a different number of values through when there is one expression,
+    // which can be a call giving several values
+    let kind = typecheck_expression(&mut exprs[0], symbol_table, true);
+    match kind {
+        Kind::Tuple(ref results) if results.len() == kinds.len() => return results.clone(),
+        Kind::Tuple(ref results) => {
+            symbol_table.error(span, &format!("assignment mismatch: {} variables but {} values.",
+                                              kinds.len(), results.len()));
+            return vec![Kind::Undefined; kinds.len()];
+        }
+        Kind::Void => {
+            symbol_table.error(exprs[0].span, "Void value used in expression.");
+            return vec![Kind::Undefined; kinds.len()];
+        }
+        _ => {}
+    }
+    let is_comma_ok = match exprs[0].variant {
+        ExpressionVariant::Index { ref primary, .. } => matches!(primary.kind.resolve(), Kind::Map(..)),
+        ExpressionVariant::TypeAssertion { .. } | ExpressionVariant::Receive { .. } => true,
+        _ => false,
+    };
+    if is_comma_ok && kinds.len() == 2 {
+        let index = exprs.pop().unwrap();
+        let kinds = vec![kind, Kind::Basic(BasicKind::Bool)];
+        exprs.push(Expression {
+            span: index.span,
+            variant: ExpressionVariant::CommaOk { expr: Box::new(index) },
+            kind: Kind::Tuple(kinds.clone()),
+        });
+        return kinds;
+    }
+
+    if !kind.is_undefined() {
+        symbol_table.error(span, &format!("assignment mismatch: {} variables but 1 value.",
+                                          kinds.len()));
+    }
+    vec![Kind::Undefined; kinds.len()]
+}
+
+// Whether evaluating the expression calls a function or a builtin
+fn contains_call(exp: &Expression) -> bool {
+    match exp.variant {
+        ExpressionVariant::Identifier { .. } | ExpressionVariant::RawLiteral { .. } |
+        ExpressionVariant::Nil => false,
+        // The body only runs when the function is called
+        ExpressionVariant::FunctionLiteral { .. } => false,
+        ExpressionVariant::BinaryOperation { ref lhs, ref rhs, .. } => {
+            contains_call(lhs) || contains_call(rhs)
+        }
+        ExpressionVariant::UnaryOperation { ref rhs, .. } => contains_call(rhs),
+        ExpressionVariant::Dereference { ref expr } |
+        ExpressionVariant::AddressOf { ref expr } => contains_call(expr),
+        ExpressionVariant::Index { ref primary, ref index } => {
+            contains_call(primary) || contains_call(index)
+        }
+        ExpressionVariant::Selector { ref primary, .. } => contains_call(primary),
+        ExpressionVariant::Slice { ref primary, ref low, ref high, ref max } => {
+            contains_call(primary) ||
+                low.iter().chain(high.iter()).chain(max.iter()).any(|i| contains_call(i))
+        }
+        ExpressionVariant::Method { ref receiver, .. } => contains_call(receiver),
+        ExpressionVariant::TypeCast { ref expr, .. } => contains_call(expr),
+        ExpressionVariant::CommaOk { ref expr } => contains_call(expr),
+        ExpressionVariant::TypeAssertion { ref expr, .. } |
+        ExpressionVariant::ToInterface { ref expr } => contains_call(expr),
+        ExpressionVariant::InterfaceMethod { ref receiver, .. } => contains_call(receiver),
+        ExpressionVariant::CompositeLiteral { ref elements, .. } => {
+            elements.iter().any(|e| {
+                e.key.as_ref().is_some_and(contains_call) || contains_call(&e.value)
+            })
+        }
+        ExpressionVariant::FunctionCall { .. } | ExpressionVariant::Append { .. } |
+        ExpressionVariant::Len { .. } | ExpressionVariant::Cap { .. } |
+        ExpressionVariant::Make { .. } | ExpressionVariant::Delete { .. } |
+        ExpressionVariant::New { .. } | ExpressionVariant::Panic { .. } |
+        ExpressionVariant::Receive { .. } | ExpressionVariant::Close { .. } |
+        ExpressionVariant::Copy { .. } => true,
+    }
+}
+
+/// Gives an untyped constant expression the kind `target`, or its default kind if it cannot have
+/// that one, and folds it into literals. Gives nil the kind `target` if it is a pointer, function
+/// or interface kind, and reports it if there is no target. Other expressions are only converted
+/// if `target` is an interface kind. Returns the kind of the expression.
+fn convert_untyped(exp: &mut Expression, target: &Kind, symbol_table: &mut SymbolTable) -> Kind {
+    let basic = match exp.kind {
+        Kind::Untyped(basic) => basic,
+        // Like constants, nil takes the kind it is used as
+        Kind::Nil if target.is_nillable() => {
+            exp.kind = target.clone();
+            return exp.kind.clone();
+        }
+        // Unlike constants, nil has no default kind
+        Kind::Nil if matches!(*target, Kind::Undefined) => {
+            symbol_table.error(exp.span, "use of untyped nil.");
+            exp.kind = Kind::Undefined;
+            return exp.kind.clone();
+        }
+        _ => return convert_to_interface(exp, target, symbol_table),
+    };
+    let interface = target;
+    let target = match target.resolve() {
+        Kind::Basic(t) if t == basic || (Kind::Basic(basic).is_numeric() &&
+                                         Kind::Basic(t).is_numeric()) => {
+            target.clone()
+        }
+        // A mismatch gets reported by whatever needed the kind
+        _ => Kind::Basic(basic.typed()),
+    };
+    // It has been evaluated when it was typechecked, so this does not report anything
+    let converted = match evaluate_constant(exp, symbol_table, None) {
+        Some((value, kind)) => convert_constant(&value, &kind, &target, exp.span, symbol_table),
+        None => None,
+    };
+    match converted {
+        Some(value) => *exp = constant_expression(&value, &target, exp.span),
+        None => exp.kind = Kind::Undefined,
+    }
+    // Constants used as interfaces have their default kind in them
+    convert_to_interface(exp, interface, symbol_table)
+}
+
+/// Puts a value used as the interface kind `target` in an interface value, if it doesn't already
+/// have that kind. Its kind must have the methods of the interface. Returns the kind of the
+/// expression.
+fn convert_to_interface(exp: &mut Expression, target: &Kind, symbol_table: &mut SymbolTable) -> Kind {
+    let methods = match target.resolve() {
+        Kind::Interface(methods) => methods,
+        _ => return exp.kind.clone(),
+    };
+    match exp.kind {
+        // Those are reported by whatever needed the value
+        Kind::Undefined | Kind::Void | Kind::Tuple(..) | Kind::Underscore | Kind::Nil => {
+            return exp.kind.clone();
+        }
+        _ if are_identical(&exp.kind, target) => return exp.kind.clone(),
+        _ => {}
+    }
+    if let Some(name) = exp.kind.missing_method(&methods) {
+        match exp.kind.lookup(&name) {
+            Lookup::Found(_, Selection::Method(ref method)) if method.pointer_receiver => {
+                symbol_table.error(exp.span, &format!("cannot use value of type {} as {}: method \
+                {} has pointer receiver.", exp.kind, target, name));
+            }
+            _ => {
+                symbol_table.error(exp.span, &format!("cannot use value of type {} as {}: missing \
+                method {}.", exp.kind, target, name));
+            }
+        }
+        exp.kind = Kind::Undefined;
+        return Kind::Undefined;
+    }
+    let span = exp.span;
+    let value = mem::replace(exp, Expression { span, kind: target.clone(), variant: ExpressionVariant::Nil });
+    exp.variant = ExpressionVariant::ToInterface { expr: Box::new(value) };
+    exp.kind.clone()
+}
+
+/// Gives a constant of kind `kind` the kind `target`, reporting it if the value cannot be
+/// represented by the target kind
+fn convert_constant(value: &Constant, kind: &Kind, target: &Kind, span: Span,
+                    symbol_table: &SymbolTable) -> Option<Constant> {
+    if target.is_undefined() {
+        return None;
+    }
+    let basic = match target.resolve() {
+        Kind::Basic(basic) => basic,
+        _ => {
+            symbol_table.error(span, &format!("invalid constant type {}.", target));
+            return None;
+        }
+    };
+    match *kind {
+        Kind::Untyped(..) => {
+            match value.convert(basic) {
+                Ok(converted) => Some(converted),
+                Err(message) => {
+                    symbol_table.error(span, &message);
+                    None
+                }
+            }
+        }
+        _ if are_identical(kind, target) => Some(value.clone()),
+        _ => {
+            symbol_table.error(span, &format!("cannot use constant of type {} as type {}.",
+                                              kind, target));
+            None
+        }
+    }
+}
+
+/// Makes the expression that a constant gets folded into. The value must already be converted to
+/// the kind.
+fn constant_expression(value: &Constant, kind: &Kind, span: Span) -> Expression {
+    let literal = |variant, basic| Expression { span, kind: Kind::Basic(basic), variant };
+    // Numbers are written as literals of the sized kind they have
+    let numeric = match kind.resolve() {
+        Kind::Basic(basic) if basic.is_integer() || basic.is_float() => Some(basic),
+        _ => None,
+    };
+    let (mut folded, is_negative) = match *value {
+        Constant::Bool(b) => (literal(ExpressionVariant::Identifier {
+            name: b.to_string(),
+            original_name: b.to_string()
+        }, BasicKind::Bool), false),
+        Constant::String(ref s) => (literal(ExpressionVariant::RawLiteral {
+            value: util::quote_string_literal(s)
+        }, BasicKind::String), false),
+        Constant::Int(ref i) => {
+            (literal(ExpressionVariant::RawLiteral {
+                value: if i.is_negative() { i.neg().to_string() } else { i.to_string() }
+            }, numeric.unwrap_or(BasicKind::Int)), i.is_negative())
+        }
+        Constant::Float(ref r) => {
+            let f = r.to_f64().unwrap();
+            (literal(ExpressionVariant::RawLiteral {
+                value: format!("{:?}", f.abs())
+            }, numeric.unwrap_or(BasicKind::Float)), f < 0.0)
+        }
+    };
+    // Literals are never negative
+    if is_negative {
+        folded = Expression {
+            span,
+            kind: folded.kind.clone(),
+            variant: ExpressionVariant::UnaryOperation {
+                op: UnaryOperator::Neg,
+                rhs: Box::new(folded)
+            }
+        };
+    }
+    // Defined kinds have no literals of their own
+    if !are_identical(&folded.kind, kind) {
+        folded = Expression {
+            span,
+            kind: kind.clone(),
+            variant: ExpressionVariant::TypeCast {
+                name: kind.to_string(),
+                expr: Box::new(folded)
+            }
+        };
+    }
+    folded
+}
+
+/// Evaluates a constant expression, reporting why if it is not one. `iota` is only given inside
+/// constant declarations.
+fn evaluate_constant(exp: &Expression, symbol_table: &SymbolTable, iota: Option<u32>)
+    -> Option<(Constant, Kind)> {
+    match exp.variant {
+        ExpressionVariant::RawLiteral { ref value } => {
+            let basic = match exp.kind {
+                // Rune literals are untyped runes
+                Kind::Basic(_) | Kind::Untyped(_) if util::is_rune_literal(value) => BasicKind::Rune,
+                Kind::Basic(basic) => basic,
+                Kind::Untyped(basic) => basic,
+                _ => unreachable!(),
+            };
+            let kind = if iota.is_some() { Kind::Untyped(basic) } else { exp.kind.clone() };
+            Some((Constant::parse_literal(basic, value), kind))
+        }
+
+        ExpressionVariant::Identifier { ref name, .. } => {
+            if let (Some(iota), "iota", None) = (iota, name.as_str(), symbol_table.lookup(name)) {
+                return Some((Constant::Int(BigInt::from_i64(iota as i64)),
+                             Kind::Untyped(BasicKind::Int)));
+            }
+            match symbol_table.get_symbol(name, exp.span) {
+                Some(&Symbol { declaration: Declaration::Constant(ref kind, ref value), .. }) => {
+                    if kind.is_undefined() {
+                        None
+                    } else {
+                        Some((value.clone(), kind.clone()))
+                    }
+                }
+                Some(_) => {
+                    symbol_table.error(exp.span, &format!("`{}` is not a constant.", name));
+                    None
+                }
+                None => None,
+            }
+        }
+
+        ExpressionVariant::UnaryOperation { op, ref rhs } => {
+            let (value, kind) = evaluate_constant(rhs, symbol_table, iota)?;
+            if !kind.is_untyped() &&
+                get_kind_unary_op(&kind, op, exp.span, symbol_table).is_undefined() {
+                return None;
+            }
+            let result = value.unary_operation(op);
+            check_constant_operation(result, kind, exp.span, symbol_table)
+        }
+
+        ExpressionVariant::BinaryOperation { op, ref lhs, ref rhs } => {
+            let (mut a, a_kind) = evaluate_constant(lhs, symbol_table, iota)?;
+            let (mut b, b_kind) = evaluate_constant(rhs, symbol_table, iota)?;
+            let is_shift = op == BinaryOperator::LShift || op == BinaryOperator::RShift;
+
+            let kind = match (a_kind.is_untyped(), b_kind.is_untyped()) {
+                (true, true) if is_shift => {
+                    b = match b.convert(BasicKind::Int) {
+                        Ok(count) => count,
+                        Err(message) => {
+                            symbol_table.error(exp.span, &message);
+                            return None;
+                        }
+                    };
+                    a_kind
+                }
+                (true, true) => {
+                    match untyped_operation_kind(&a_kind, &b_kind) {
+                        Some(Kind::Untyped(BasicKind::Float)) => {
+                            a = a.to_float();
+                            b = b.to_float();
+                            Kind::Untyped(BasicKind::Float)
+                        }
+                        Some(kind) => kind,
+                        None => {
+                            symbol_table.error(exp.span, &format!("trying to do operation {:?} \
+                            on constants of different types {} and {}", op, a_kind, b_kind));
+                            return None;
+                        }
+                    }
+                }
+                // The untyped operand takes the kind of the other one
+                (true, false) => {
+                    a = convert_constant(&a, &a_kind, &b_kind, exp.span, symbol_table)?;
+                    b_kind
+                }
+                (false, true) => {
+                    b = convert_constant(&b, &b_kind, &a_kind, exp.span, symbol_table)?;
+                    a_kind
+                }
+                (false, false) => {
+                    if !are_identical(&a_kind, &b_kind) {
+                        // Reports the mismatch
+                        get_kind_binary_op(&a_kind, &b_kind, op, exp.span, symbol_table);
+                        return None;
+                    }
+                    a_kind
+                }
+            };
+
+            let result = a.binary_operation(op, &b);
+            let result_kind = match op {
+                BinaryOperator::Eq | BinaryOperator::Neq | BinaryOperator::Lt |
+                BinaryOperator::Leq | BinaryOperator::Gt | BinaryOperator::Geq
+                    if kind.is_untyped() => Kind::Untyped(BasicKind::Bool),
+                _ if kind.is_untyped() => kind,
+                _ => {
+                    let result_kind = get_kind_binary_op(&kind, &kind, op, exp.span,
+                                                         symbol_table);
+                    if result_kind.is_undefined() {
+                        return None;
+                    }
+                    result_kind
+                }
+            };
+            check_constant_operation(result, result_kind, exp.span, symbol_table)
+        }
+
+        ExpressionVariant::FunctionCall { ref primary, ref arguments, .. } => {
+            // Conversions of constants to basic kinds are constants as well
+            let cast_kind = match (&primary.variant, arguments.len()) {
+                (ExpressionVariant::Identifier { name, .. }, 1) => {
+                    match symbol_table.lookup(name) {
+                        Some(&Symbol { declaration: Declaration::Type(ref kind), .. }) => {
+                            Some(kind.clone())
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+            let cast_kind = match cast_kind {
+                Some(kind) => kind,
+                None => {
+                    symbol_table.error(exp.span, "function calls are not constant.");
+                    return None;
+                }
+            };
+            let value = evaluate_constant(&arguments[0], symbol_table, iota)?.0;
+            let converted = match (cast_kind.resolve(), &value) {
+                (Kind::Basic(BasicKind::String), Constant::Int(i)) => {
+                    let c = i.to_i64()
+                        .and_then(|i| if (0..=0x10ffff).contains(&i) { char::from_u32(i as u32) } else { None })
+                        .unwrap_or('\u{fffd}');
+                    Ok(Constant::String(c.to_string()))
+                }
+                (Kind::Basic(basic), _) => value.convert(basic),
+                _ => Err(format!("cannot convert constant {} to type {}.", value, cast_kind)),
+            };
+            match converted {
+                Ok(converted) => Some((converted, cast_kind)),
+                Err(message) => {
+                    symbol_table.error(exp.span, &message);
+                    None
+                }
+            }
+        }
+
+        ExpressionVariant::Len { ref expr } => {
+            // Like in Go, the length of an array variable or of a constant string is a constant
+            if let ExpressionVariant::Identifier { ref name, .. } = expr.variant {
+                match symbol_table.lookup(name) {
+                    Some(&Symbol { declaration: Declaration::Variable(ref kind), .. }) => {
+                        if let Kind::Array(_, size) = kind.resolve() {
+                            return Some((Constant::Int(BigInt::from_i64(size as i64)),
+                                         Kind::Basic(BasicKind::Int)));
+                        }
+                    }
+                    Some(&Symbol { declaration: Declaration::Constant(_, Constant::String(ref s)), .. }) => {
+                        return Some((Constant::Int(BigInt::from_i64(s.len() as i64)),
+                                     Kind::Basic(BasicKind::Int)));
+                    }
+                    _ => {}
+                }
+            }
+            symbol_table.error(exp.span, "len of this expression is not constant.");
+            None
+        }
+
+        _ => {
+            symbol_table.error(exp.span, "expression is not constant.");
+            None
+        }
+    }
+}
+
+/// The kind of an operation on two untyped constants of different kinds, if they can be mixed
+fn untyped_operation_kind(a: &Kind, b: &Kind) -> Option<Kind> {
+    // Mixing numeric constants gives whichever of int, rune and float comes last
+    let rank = |k: &Kind| match *k {
+        Kind::Untyped(BasicKind::Int) => Some(0),
+        Kind::Untyped(BasicKind::Rune) => Some(1),
+        Kind::Untyped(BasicKind::Float) => Some(2),
+        _ => None,
+    };
+    match (rank(a), rank(b)) {
+        (Some(a_rank), Some(b_rank)) => Some(if a_rank >= b_rank { a.clone() } else { b.clone() }),
+        _ if are_identical(a, b) => Some(a.clone()),
+        _ => None,
+    }
+}
+
+/// Reports a failed constant operation, or a typed result that does not fit in its kind
+fn check_constant_operation(result: Result<Constant, String>, kind: Kind, span: Span,
+                            symbol_table: &SymbolTable) -> Option<(Constant, Kind)> {
+    let result = match result {
+        Ok(result) => result,
+        Err(message) => {
+            symbol_table.error(span, &message);
+            return None;
+        }
+    };
+    match kind.resolve() {
+        Kind::Basic(basic) if basic != BasicKind::Bool => {
+            match result.convert(basic) {
+                Ok(result) => Some((result, kind)),
+                Err(message) => {
+                    symbol_table.error(span, &message);
+                    None
+                }
+            }
+        }
+        _ => Some((result, kind)),
+    }
+}
+
+/// The value of an index that was folded from an int constant, if it was
+fn constant_index(exp: &Expression) -> Option<i64> {
+    match exp.variant {
+        ExpressionVariant::RawLiteral { ref value } if matches!(exp.kind, Kind::Basic(BasicKind::Int)) => {
+            Some(util::string_to_int(value) as i64)
+        }
+        ExpressionVariant::UnaryOperation { op: UnaryOperator::Neg, ref rhs } => {
+            constant_index(rhs).map(|i| -i)
+        }
+        _ => None,
+    }
+}
+
+/// Typechecks the elements of a composite literal of the given kind, and gives each element of a
+/// struct, array or slice literal its key. Returns the length of the array the elements need.
+fn typecheck_literal_elements(kind: &Kind, elements: &mut [KeyedElement], span: Span,
+                              symbol_table: &mut SymbolTable) -> u32 {
+    match kind.resolve() {
+        Kind::Struct(ref fields) => {
+            typecheck_struct_literal(kind, fields, elements, span, symbol_table);
+            0
+        }
+        Kind::Array(ref base, length) => {
+            typecheck_indexed_literal(base, Some(length), elements, symbol_table)
+        }
+        Kind::Slice(ref base) => typecheck_indexed_literal(base, None, elements, symbol_table),
+        Kind::Map(ref key_kind, ref value_kind) => {
+            for element in elements {
+                match element.key {
+                    Some(ref mut key) => {
+                        let kind = typecheck_element(key, key_kind, symbol_table);
+                        if !are_identical(&kind, key_kind) {
+                            symbol_table.error(key.span, &format!("cannot use key of type {} \
+                            in map literal with keys of type {}.", kind, key_kind));
+                        }
+                    }
+                    None => symbol_table.error(element.span, "missing key in map literal."),
+                }
+                let kind = typecheck_element(&mut element.value, value_kind, symbol_table);
+                if !are_identical(&kind, value_kind) {
+                    symbol_table.error(element.value.span, &format!("cannot use value of type \
+                    {} in map literal with values of type {}.", kind, value_kind));
+                }
+            }
+            0
+        }
+        _ => {
+            if !kind.is_undefined() {
+                symbol_table.error(span, &format!("invalid composite literal type {}.", kind));
+            }
+            // The keys may be field names, so only the values can be checked
+            for element in elements {
+                typecheck_element(&mut element.value, &Kind::Undefined, symbol_table);
+            }
+            0
+        }
+    }
+}
+
+/// Typechecks an element of a composite literal as the given kind, which is also the kind of an
+/// element literal written without one. The `&` of `&T{}` can be left out as well.
+fn typecheck_element(element: &mut Expression, kind: &Kind, symbol_table: &mut SymbolTable) -> Kind {
+    if let ExpressionVariant::CompositeLiteral { kind: None, .. } = element.variant {
+        element.kind = kind.clone();
+        if let Kind::Pointer(ref base) = kind.resolve() {
+            element.kind = (**base).clone();
+            let placeholder = Expression {
+                span: element.span,
+                kind: Kind::Undefined,
+                variant: ExpressionVariant::RawLiteral { value: String::new() },
+            };
+            let literal = mem::replace(element, placeholder);
+            element.variant = ExpressionVariant::AddressOf { expr: Box::new(literal) };
+        }
+    }
+    typecheck_expression_as(element, kind, symbol_table)
+}
+
+/// Typechecks a struct literal, which either names the fields it sets or gives every field in
+/// order. Positional elements are given the name of their field as their key.
+fn typecheck_struct_literal(kind: &Kind, fields: &[kind::Field], elements: &mut [KeyedElement],
+                            span: Span, symbol_table: &mut SymbolTable) {
+    let keyed = elements.iter().filter(|e| e.key.is_some()).count();
+    if keyed == 0 {
+        if elements.len() > fields.len() {
+            symbol_table.error(elements[fields.len()].span, &format!("too many values in struct \
+            literal of type {}.", kind));
+        } else if !elements.is_empty() && elements.len() < fields.len() {
+            symbol_table.error(span, &format!("too few values in struct literal of type {}.", kind));
+        }
+        for (i, element) in elements.iter_mut().enumerate() {
+            let field_kind = fields.get(i).map_or(Kind::Undefined, |f| f.kind.clone());
+            check_field_value(&mut element.value, &field_kind, symbol_table);
+            if let Some(field) = fields.get(i) {
+                element.key = Some(Expression {
+                    span: element.span,
+                    kind: Kind::Undefined,
+                    variant: ExpressionVariant::Identifier {
+                        name: field.name.clone(),
+                        original_name: field.name.clone(),
+                    },
+                });
+            }
+        }
+        return;
+    }
+    if keyed < elements.len() {
+        symbol_table.error(span, "mixture of field:value and value elements in struct literal.");
+    }
+    let mut names = HashSet::new();
+    for element in elements {
+        let mut field_kind = Kind::Undefined;
+        match element.key {
+            Some(Expression { variant: ExpressionVariant::Identifier { ref name, .. }, span, .. }) => {
+                match fields.iter().find(|f| f.name == *name && name != "_") {
+                    Some(field) => field_kind = field.kind.clone(),
+                    None => symbol_table.error(span, &format!("unknown field {} in struct literal \
+                    of type {}.", name, kind)),
+                }
+                if !names.insert(name.clone()) {
+                    symbol_table.error(span, &format!("duplicate field name {} in struct \
+                    literal.", name));
+                }
+            }
+            Some(ref key) => symbol_table.error(key.span, "invalid field name in struct literal."),
+            None => {}
+        }
+        check_field_value(&mut element.value, &field_kind, symbol_table);
+    }
+}
+
+fn check_field_value(value: &mut Expression, field_kind: &Kind, symbol_table: &mut SymbolTable) {
+    let kind = typecheck_element(value, field_kind, symbol_table);
+    if !are_identical(&kind, field_kind) {
+        symbol_table.error(value.span, &format!("cannot use value of type {} as field of type {} \
+        in struct literal.", kind, field_kind));
+    }
+}
+
+/// Typechecks the elements of an array or slice literal, and gives each its index as its key.
+/// Elements without an index follow the previous one. Returns the number of elements the indices
+/// need.
+fn typecheck_indexed_literal(element_kind: &Kind, length: Option<u32>,
+                             elements: &mut [KeyedElement], symbol_table: &mut SymbolTable) -> u32 {
+    let int = Kind::Basic(BasicKind::Int);
+    let mut indices = HashSet::new();
+    let mut index = 0;
+    let mut needed = 0;
+    for element in elements {
+        if let Some(ref mut key) = element.key {
+            let key_kind = typecheck_expression_as(key, &int, symbol_table);
+            match constant_index(key) {
+                Some(i) if i >= 0 => index = i,
+                _ => if !key_kind.is_undefined() {
+                    symbol_table.error(key.span, "index must be non-negative integer constant.");
+                }
+            }
+        }
+        match length {
+            Some(length) if index >= length as i64 => {
+                symbol_table.error(element.span, &format!("array index {} out of bounds [0:{}].",
+                                                          index, length));
+            }
+            _ => if !indices.insert(index) {
+                symbol_table.error(element.span, &format!("duplicate index {} in array or slice \
+                literal.", index));
+            }
+        }
+        element.key = Some(Expression {
+            span: element.span,
+            kind: int.clone(),
+            variant: ExpressionVariant::RawLiteral { value: index.to_string() },
+        });
+        let kind = typecheck_element(&mut element.value, element_kind, symbol_table);
+        if !are_identical(&kind, element_kind) {
+            symbol_table.error(element.value.span, &format!("cannot use value of type {} as \
+            element of type {} in array or slice literal.", kind, element_kind));
+        }
+        index += 1;
+        needed = needed.max(index);
+    }
+    needed as u32
+}
+
+// Whether the expression can be on the left of an assignment
+fn is_exp_assignable(exp: &mut Expression, symbol_table: &mut SymbolTable) -> bool {
+    match exp.variant {
+        ExpressionVariant::Index { ref primary, .. } if matches!(primary.kind.resolve(), Kind::Map(..)) => true,
+        _ => is_exp_addressable(exp, symbol_table),
+    }
+}
+
 // this will not typecheck and will not rename variables
 fn is_exp_addressable(exp: &mut Expression, symbol_table: &mut SymbolTable) -> bool {
     match exp.variant {
@@ -782,20 +2390,22 @@ fn is_exp_addressable(exp: &mut Expression, symbol_table: &mut SymbolTable) -> b
             if original_name == "_" {
                 return true
             }
-            let symbol = symbol_table.get_symbol(original_name, exp.line_number);
-            match symbol.declaration {
-                Declaration::Variable(..)  => {
-                    return true;
-                }
-                _ => {
-                    eprintln!("Error: line {}: Cannot assign to `{}`: is not a variable.",
-                              exp.line_number, original_name);
-                    exit(1);
+            match symbol_table.get_symbol(original_name, exp.span) {
+                Some(&Symbol { declaration: Declaration::Variable(..), .. }) => {}
+                Some(_) => {
+                    symbol_table.error(exp.span, &format!("Cannot assign to `{}`: is not a variable.", original_name));
                 }
+                None => {}
             }
+            // Anything other than a variable has been reported already
+            true
         },
+        // Map elements can be assigned to, but they are not addressable
+        ExpressionVariant::Index { ref primary, .. } if matches!(primary.kind.resolve(), Kind::Map(..)) => false,
+        // Whatever a pointer points to can be assigned to
+        ExpressionVariant::Dereference { .. } => true,
         ExpressionVariant::Index { ref mut primary, .. } | ExpressionVariant::Selector{ ref mut primary, .. } => {
-            if let Kind::Slice(..) = primary.kind {
+            if let Kind::Slice(..) | Kind::Pointer(..) = primary.kind.resolve() {
                 return true;
             } else {
                 return is_exp_addressable(primary, symbol_table);
@@ -805,13 +2415,35 @@ fn is_exp_addressable(exp: &mut Expression, symbol_table: &mut SymbolTable) -> b
     }
 }
 
+// Whether the address of a typechecked expression can be taken, like a pointer method does with
+// its receiver
+fn is_addressable(exp: &Expression) -> bool {
+    match exp.variant {
+        // Constants have been replaced by literals, and functions have no methods
+        ExpressionVariant::Identifier { ref name, .. } => name != "_",
+        ExpressionVariant::Index { ref primary, .. } => match primary.kind.resolve() {
+            Kind::Slice(..) => true,
+            Kind::Array(..) => is_addressable(primary),
+            _ => false,
+        },
+        ExpressionVariant::Selector { ref primary, .. } => {
+            primary.kind.is_pointer() || is_addressable(primary)
+        }
+        ExpressionVariant::Dereference { .. } => true,
+        _ => false,
+    }
+}
 
 // Need also to check if kinds are valid for op
-fn get_kind_binary_op(a: &Kind, b: &Kind, op: BinaryOperator, line_number: u32) -> Kind {
+fn get_kind_binary_op(a: &Kind, b: &Kind, op: BinaryOperator, span: Span,
+                      symbol_table: &SymbolTable) -> Kind {
+    if a.is_undefined() || b.is_undefined() {
+        return Kind::Undefined;
+    }
     if !are_identical(a, b) {
-        eprintln!("Error: line {}: trying to do operation {:?} on expressions \
-        of different types {} and {}", line_number, op, a, b);
-        exit(1);
+        symbol_table.error(span, &format!("trying to do operation {:?} on expressions \
+        of different types {} and {}", op, a, b));
+        return Kind::Undefined;
     }
 
     match op {
@@ -820,24 +2452,24 @@ fn get_kind_binary_op(a: &Kind, b: &Kind, op: BinaryOperator, line_number: u32)
                 return a.clone();
                //return Kind::Basic(BasicKind::Bool)
             } else {
-               eprintln!("Error: line {}: trying to perform an invalid operation on a {}", line_number, a);
-               exit(1)
+               symbol_table.error(span, &format!("trying to perform an invalid operation on a {}", a));
+               Kind::Undefined
             }
         },
         BinaryOperator::Eq | BinaryOperator::Neq => {
            if a.is_comparable() {
                return Kind::Basic(BasicKind::Bool)
            } else {
-               eprintln!("Error: line {}: trying to perform an invalid operation on a non-comparable type {}", line_number, a);
-               exit(1)
+               symbol_table.error(span, &format!("trying to perform an invalid operation on a non-comparable type {}", a));
+               Kind::Undefined
            }
         },
         BinaryOperator::Lt | BinaryOperator::Leq | BinaryOperator::Gt | BinaryOperator::Geq => {
            if a.is_ordered() {
                return Kind::Basic(BasicKind::Bool)
            } else {
-               eprintln!("Error: line {}: trying to perform an invalid operation on a non-ordered type {}", line_number, a);
-               exit(1)
+               symbol_table.error(span, &format!("trying to perform an invalid operation on a non-ordered type {}", a));
+               Kind::Undefined
            }
         },
         BinaryOperator::Sub | BinaryOperator::Mul | BinaryOperator::Div |  BinaryOperator::Add=> {
@@ -851,8 +2483,8 @@ fn get_kind_binary_op(a: &Kind, b: &Kind, op: BinaryOperator, line_number: u32)
             if a.is_numeric() || (is_add && a.is_string()) {
                 return a.clone()
             } else {
-                eprintln!("Error: line {}: trying to perform an arithmetic operation on non-numerical (or string) type {}", line_number, a);
-                exit(1)
+                symbol_table.error(span, &format!("trying to perform an arithmetic operation on non-numerical (or string) type {}", a));
+                Kind::Undefined
             }
         },
         BinaryOperator::BwXor | BinaryOperator::BwOr | BinaryOperator::Mod
@@ -861,33 +2493,34 @@ fn get_kind_binary_op(a: &Kind, b: &Kind, op: BinaryOperator, line_number: u32)
             if a.is_integer() {
                 return a.clone();
             } else {
-                eprintln!("Error: line {}: trying to perform a bitwise operation on non-integer type {}", line_number, a);
-                exit(1)
+                symbol_table.error(span, &format!("trying to perform a bitwise operation on non-integer type {}", a));
+                Kind::Undefined
             }
         },
    }
 }
 
-fn get_kind_unary_op(kind: &Kind, op: UnaryOperator, line_number: u32) -> Kind {
+fn get_kind_unary_op(kind: &Kind, op: UnaryOperator, span: Span,
+                     symbol_table: &SymbolTable) -> Kind {
+    if kind.is_undefined() {
+        return Kind::Undefined;
+    }
     match op {
         UnaryOperator::Plus | UnaryOperator::Neg =>  {
             match kind.resolve() {
-                Kind::Basic(BasicKind::Int) => Kind::Basic(BasicKind::Int),
-                Kind::Basic(BasicKind::Float) => Kind::Basic(BasicKind::Float),
-                Kind::Basic(BasicKind::Rune) => Kind::Basic(BasicKind::Rune),
+                basic if basic.is_numeric() => basic,
                 _ => {
-                    eprintln!("Error: line {}: trying to perform an invalid operation on a non-numerical type {}", line_number, kind);
-                    exit(1);
+                    symbol_table.error(span, &format!("trying to perform an invalid operation on a non-numerical type {}", kind));
+                    Kind::Undefined
                 }
             }
         },
         UnaryOperator::BwCompl => {
             match kind.resolve() {
-                Kind::Basic(BasicKind::Int) => Kind::Basic(BasicKind::Int),
-                Kind::Basic(BasicKind::Rune) => Kind::Basic(BasicKind::Rune),
+                basic if basic.is_integer() => basic,
                 _ => {
-                    eprintln!("Error: line {}: trying to perform an invalid bitwise negation on a {}", line_number, kind);
-                    exit(1);
+                    symbol_table.error(span, &format!("trying to perform an invalid bitwise negation on a {}", kind));
+                    Kind::Undefined
                 }
             }
         }
@@ -895,8 +2528,8 @@ fn get_kind_unary_op(kind: &Kind, op: UnaryOperator, line_number: u32) -> Kind {
             match kind.resolve() {
                 Kind::Basic(BasicKind::Bool) => Kind::Basic(BasicKind::Bool),
                 _ => {
-                    eprintln!("Error: line {}: trying to perform an invalid logical negation operation on a {}", line_number, kind);
-                    exit(1);
+                    symbol_table.error(span, &format!("trying to perform an invalid logical negation operation on a {}", kind));
+                    Kind::Undefined
                 }
             }
         }
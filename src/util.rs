@@ -1,19 +1,25 @@
 use std::u32;
+use std::convert::TryFrom;
 use std::fmt::Write;
 
 pub fn string_to_int(s: &str) -> u32 {
+    u32::try_from(string_to_u64(s)).unwrap()
+}
+
+/// Like string_to_int, for literals that can be as large as a uint64
+pub fn string_to_u64(s: &str) -> u64 {
     assert_ne!(s, "");
     if s == "0" {
         return 0;
     }
     if &s[0..1] == "0" &&
         (&s[1..2] == "x" || &s[1..2] == "X")  {
-            return u32::from_str_radix(&s[2..], 16).unwrap();
+            return u64::from_str_radix(&s[2..], 16).unwrap();
         }
     if &s[0..1] == "0" {
-        return u32::from_str_radix(&s[1..], 8).unwrap();
+        return u64::from_str_radix(&s[1..], 8).unwrap();
     }
-    return u32::from_str_radix(s, 10).unwrap();
+    s.parse().unwrap()
 }
 
 pub fn indent(size: u32) -> String {
@@ -24,14 +30,15 @@ pub fn indent(size: u32) -> String {
     ret
 }
 
+/// Rune literals are the only ones in single quotes. They have the kind int32, which `rune` is
+/// another name for.
+pub fn is_rune_literal(value: &str) -> bool {
+    value.starts_with('\'')
+}
+
 // !!! This assumes it is a valid rune literal.
 pub fn parse_rune_literal(value: &str) -> i32 {
-    let letter : &str;
-    if value.len() == 4 {
-        letter = &value[1..3];
-    } else {
-        letter = &value[1..2];
-    }
+    let letter = &value[1..value.len()-1];
 
     let code_no = match letter {
         "\\a" => 7,
@@ -43,7 +50,7 @@ pub fn parse_rune_literal(value: &str) -> i32 {
         "\\v" => 11,
         "\\\\" => 92,
         "\\'" => 39,
-        _ => letter.chars().next().unwrap() as i32 // Will this work?
+        _ => letter.chars().next().unwrap() as i32
     };
     return code_no;
 }
@@ -98,6 +105,43 @@ pub fn parse_string_literal(value: &str) -> String {
     }
 }
 
+/// The byte offset and the code point of each rune in a string, like a range loop gives them.
+/// Each byte that isn't part of a valid UTF-8 sequence is a rune of its own, U+FFFD.
+pub fn decode_runes(bytes: &[u8]) -> Vec<(usize, char)> {
+    let mut runes = Vec::new();
+    let mut offset = 0;
+    for chunk in bytes.utf8_chunks() {
+        runes.extend(chunk.valid().char_indices().map(|(i, c)| (offset + i, c)));
+        offset += chunk.valid().len();
+        for _ in chunk.invalid() {
+            runes.push((offset, char::REPLACEMENT_CHARACTER));
+            offset += 1;
+        }
+    }
+    runes
+}
+
+/// Writes a string as an interpreted string literal that parse_string_literal reads back
+pub fn quote_string_literal(s: &str) -> String {
+    let mut ret = "\"".to_string();
+    for c in s.chars() {
+        match c {
+            '\x07' => ret.push_str("\\a"),
+            '\x08' => ret.push_str("\\b"),
+            '\x0c' => ret.push_str("\\f"),
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            '\t' => ret.push_str("\\t"),
+            '\x0b' => ret.push_str("\\v"),
+            '\\' => ret.push_str("\\\\"),
+            '"' => ret.push_str("\\\""),
+            _ => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -122,4 +166,20 @@ mod tests {
         assert_eq!(string_to_int("0xfedced1"), 0xfedced1);
     }
 
+    #[test]
+    fn test_string_to_u64() {
+        assert_eq!(string_to_u64("4294967296"), 4294967296);
+        assert_eq!(string_to_u64("18446744073709551615"), 18446744073709551615);
+        assert_eq!(string_to_u64("0xffffffffffffffff"), 0xffffffffffffffff);
+        assert_eq!(string_to_u64("01777777777777777777777"), 0xffffffffffffffff);
+    }
+
+    #[test]
+    fn test_decode_runes() {
+        assert_eq!(decode_runes(b"a\xc3\xa9b"), vec![(0, 'a'), (1, '\u{e9}'), (3, 'b')]);
+        assert_eq!(decode_runes(b"\xc3"), vec![(0, '\u{fffd}')]);
+        assert_eq!(decode_runes(b"\xe2\x82a"), vec![(0, '\u{fffd}'), (1, '\u{fffd}'), (2, 'a')]);
+        assert_eq!(decode_runes(b""), vec![]);
+    }
+
 }
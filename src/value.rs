@@ -1,48 +1,348 @@
-use kind;
-use kind::Kind;
-use kind::BasicKind;
+use crate::ast;
+use crate::interpret::ReferenceModifier;
+use crate::scheduler;
+use crate::kind;
+use crate::kind::Kind;
+use crate::kind::BasicKind;
 use std::rc::Rc;
 use std::cell::RefCell;
 use std::collections::HashMap;
-use util::string_to_int;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use crate::util::string_to_u64;
 use std::convert::TryFrom;
-use util;
+use crate::util;
 
 use std::fmt;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Slice {
+    pub offset: usize,
     pub length: usize,
+    pub capacity: usize,
+    // The slice is the elements of the contents from offset on, of which there are capacity.
+    // Slices of the same array or slice share the contents, so they see each other's changes.
     pub contents: Rc<[RefCell<Value>]>,
-    // Note, length might be less than the len(contents)
-    // Used to represent a growable slice with a length and capacity
 }
 
-const ONE_OVER_MAX: u32 = 2147483648;
-const SMALLEST_INT: i32 = -2147483648;
+impl Slice {
+    /// A slice of new contents holding the values
+    pub fn new(values: Vec<Value>) -> Slice {
+        let length = values.len();
+        Slice {
+            offset: 0,
+            length,
+            capacity: length,
+            contents: values.into_iter().map(RefCell::new).collect::<Vec<_>>().into(),
+        }
+    }
+
+    /// The element at an index of the slice, which must be below its capacity
+    pub fn element(&self, i: usize) -> &RefCell<Value> {
+        &self.contents[self.offset + i]
+    }
+}
+
+/// The elements of an array. Arrays are values, so a copy of one gets elements of its own, but the
+/// slices of an array share its elements.
+#[derive(Debug, PartialEq)]
+pub struct Array {
+    pub contents: Rc<[RefCell<Value>]>,
+}
+
+impl Array {
+    pub fn new(values: Vec<Value>) -> Array {
+        Array { contents: values.into_iter().map(RefCell::new).collect::<Vec<_>>().into() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.contents.len()
+    }
+}
+
+impl Clone for Array {
+    fn clone(&self) -> Array {
+        Array::new(self.contents.iter().map(|v| v.borrow().clone()).collect())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Map {
+    // None for a nil map, which can be read from but not written to. Like the contents of a
+    // slice, the entries are shared by the copies of the map.
+    pub entries: Option<Rc<RefCell<MapEntries>>>,
+}
+
+/// The entries of a non-nil map, in the order they were added, which is the order that range loops
+/// go over them in, like in the generated JavaScript. The keys are hashed to find their entry.
+#[derive(Debug, Default, PartialEq)]
+pub struct MapEntries {
+    // The positions in `entries` of the keys with each hash
+    positions: HashMap<u64, Vec<usize>>,
+    // Deleted entries leave a hole until there are as many holes as entries
+    entries: Vec<Option<(Value, Value)>>,
+    len: usize,
+}
+
+impl MapEntries {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    // Keys are compared with ==, like in Go
+    fn position(&self, key: &Value) -> Option<usize> {
+        self.positions.get(&hash_key(key))?.iter().cloned().find(|&i| match self.entries[i] {
+            Some((ref k, _)) => k == key,
+            None => false,
+        })
+    }
+
+    pub fn get(&self, key: &Value) -> Option<&Value> {
+        self.position(key).and_then(|i| self.entries[i].as_ref()).map(|(_, v)| v)
+    }
+
+    pub fn insert(&mut self, key: Value, v: Value) {
+        match self.position(&key) {
+            Some(i) => self.entries[i] = Some((key, v)),
+            None => {
+                self.positions.entry(hash_key(&key)).or_default().push(self.entries.len());
+                self.entries.push(Some((key, v)));
+                self.len += 1;
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &Value) {
+        if let Some(i) = self.position(key) {
+            self.entries[i] = None;
+            self.len -= 1;
+            if self.entries.len() >= 2 * self.len + 8 {
+                let entries = mem::take(&mut self.entries);
+                *self = MapEntries::default();
+                for (k, v) in entries.into_iter().flatten() {
+                    self.insert(k, v);
+                }
+            } else {
+                let hash = hash_key(key);
+                let positions = self.positions.get_mut(&hash).unwrap();
+                positions.retain(|&j| j != i);
+                if positions.is_empty() {
+                    self.positions.remove(&hash);
+                }
+            }
+        }
+    }
+
+    pub fn keys(&self) -> Vec<Value> {
+        self.entries.iter().flatten().map(|(k, _)| k.clone()).collect()
+    }
+}
+
+/// A hash of a map key that agrees with ==: equal values have the same hash
+fn hash_key(key: &Value) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hash_value(key, &mut hasher);
+    hasher.finish()
+}
+
+fn hash_value(v: &Value, hasher: &mut DefaultHasher) {
+    mem::discriminant(v).hash(hasher);
+    match v {
+        Value::Int(i) => i.hash(hasher),
+        // 0.0 and -0.0 are equal
+        Value::Float(f) => (if *f == 0.0 { 0 } else { f.to_bits() }).hash(hasher),
+        Value::String(s) => s.hash(hasher),
+        Value::Bool(b) => b.hash(hasher),
+        Value::Array(a) => {
+            for element in a.contents.iter() {
+                hash_value(&element.borrow(), hasher);
+            }
+        }
+        // The fields are in no particular order, so their hashes are combined in a way that
+        // doesn't depend on it
+        Value::Struct(fields) => {
+            let combined = fields.iter().fold(0u64, |combined, (name, value)| {
+                let mut field_hasher = DefaultHasher::new();
+                name.hash(&mut field_hasher);
+                hash_value(value, &mut field_hasher);
+                combined.wrapping_add(field_hasher.finish())
+            });
+            combined.hash(hasher);
+        }
+        Value::Pointer(Some(pointer)) => match pointer.place {
+            Place::Variable(ref v) => Rc::as_ptr(v).hash(hasher),
+            Place::Element(ref contents, i) => (contents.as_ptr(), i).hash(hasher),
+        },
+        Value::Interface(Some(interface)) => hash_value(&interface.value, hasher),
+        // Channels and nil values are only told apart by their variant
+        _ => {}
+    }
+}
+
+/// The kind of a value in an interface in a map key that can't be hashed, like a slice. The kinds
+/// of map keys are comparable, so only interfaces can hold those, and Go panics on such keys.
+pub fn unhashable_kind(key: &Value) -> Option<Kind> {
+    match key {
+        Value::Interface(Some(interface)) if !interface.kind.is_comparable() => {
+            Some(interface.kind.clone())
+        }
+        Value::Interface(Some(interface)) => unhashable_kind(&interface.value),
+        Value::Array(a) => a.contents.iter().find_map(|element| unhashable_kind(&element.borrow())),
+        Value::Struct(fields) => fields.values().find_map(unhashable_kind),
+        _ => None,
+    }
+}
+
+/// A variable of the interpreter. Closures share the variables of the scopes they were made in.
+pub type Variable = Rc<RefCell<Value>>;
+
+/// A function value. Top-level functions are looked up by name when they are called, function
+/// literals keep the variables they can refer to, and methods keep their receiver.
+#[derive(Clone)]
+pub enum Function {
+    Nil,
+    TopLevel(String),
+    Closure(Rc<ast::Function>, HashMap<String, Variable>),
+    Method(String, Box<Value>),
+}
+
+// Function values cannot be compared in Go, but values derive the comparison
+impl PartialEq for Function {
+    fn eq(&self, other: &Function) -> bool {
+        match (self, other) {
+            (Function::Nil, Function::Nil) => true,
+            (Function::TopLevel(a), Function::TopLevel(b)) => a == b,
+            (Function::Closure(a, _), Function::Closure(b, _)) => Rc::ptr_eq(a, b),
+            (Function::Method(a, r), Function::Method(b, s)) => a == b && r == s,
+            _ => false,
+        }
+    }
+}
+
+// A closure can capture the variable it is stored in, so the captured values are not printed
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Function::Nil => write!(f, "Nil"),
+            Function::TopLevel(name) => write!(f, "TopLevel({:?})", name),
+            Function::Closure(_, captured) => write!(f, "Closure({} captured)", captured.len()),
+            Function::Method(name, receiver) => write!(f, "Method({:?}, {:?})", name, receiver),
+        }
+    }
+}
+
+/// A non-nil pointer: a place holding a value, and the fields and array elements leading from it
+/// to the value pointed to
+#[derive(Clone, Debug)]
+pub struct Pointer {
+    pub place: Place,
+    pub path: Vec<ReferenceModifier>,
+}
+
+// Pointers are equal when they point to the same value, wherever their indices were written
+impl PartialEq for Pointer {
+    fn eq(&self, other: &Pointer) -> bool {
+        self.place == other.place && self.path.len() == other.path.len() &&
+            self.path.iter().zip(other.path.iter()).all(|(a, b)| match (a, b) {
+                (ReferenceModifier::Selector(a), ReferenceModifier::Selector(b)) => a == b,
+                (ReferenceModifier::Index(i, _), ReferenceModifier::Index(j, _)) => i == j,
+                _ => false,
+            })
+    }
+}
+
+/// Somewhere a value is stored. The elements of a slice are stored in its contents rather than in
+/// the variable holding the slice, since slices share them.
+#[derive(Clone)]
+pub enum Place {
+    Variable(Variable),
+    Element(Rc<[RefCell<Value>]>, usize),
+}
+
+impl PartialEq for Place {
+    fn eq(&self, other: &Place) -> bool {
+        match (self, other) {
+            (Place::Variable(a), Place::Variable(b)) => Rc::ptr_eq(a, b),
+            (Place::Element(a, i), Place::Element(b, j)) => Rc::ptr_eq(a, b) && i == j,
+            _ => false,
+        }
+    }
+}
+
+impl Place {
+    pub fn cell(&self) -> &RefCell<Value> {
+        match self {
+            Place::Variable(v) => v,
+            Place::Element(contents, i) => &contents[*i],
+        }
+    }
+}
+
+// A value can contain a pointer to itself, so what is pointed to is not printed
+impl fmt::Debug for Place {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Place::Variable(v) => write!(f, "Variable({:p})", Rc::as_ptr(v)),
+            Place::Element(contents, i) => write!(f, "Element({:p}, {})", contents.as_ptr(), i),
+        }
+    }
+}
+
+/// A non-nil interface value: the kind of the value put in it, and the value
+#[derive(Clone, Debug)]
+pub struct Interface {
+    pub kind: Kind,
+    pub value: Box<Value>,
+}
+
+// Interfaces are equal when they hold equal values of identical kinds
+impl PartialEq for Interface {
+    fn eq(&self, other: &Interface) -> bool {
+        kind::are_identical(&self.kind, &other.kind) && self.value == other.value
+    }
+}
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Value {
-    Int(i32),
+    // Wide enough for the values of every integer kind, which are wrapped around to fit it
+    Int(i128),
     Float(f64),
-    String(String),
+    // The bytes of the string, which are only decoded by range loops and for printing
+    String(Vec<u8>),
     Bool(bool),
 
-    OneOverMax, // A special value to deal with the expression -2147483648 (Since the biggest positive int is 2147483647)
-    
-    Array(Box<[Value]>),
+    Array(Array),
     Slice(Slice),
     Struct(HashMap<String,Value>),
+    Map(Map),
+    Function(Function),
+    // None for a nil pointer
+    Pointer(Option<Pointer>),
+    // None for a nil interface
+    Interface(Option<Interface>),
+    // None for a nil channel
+    Channel(Option<scheduler::Channel>),
+    // Several values given by one expression, like a comma-ok lookup
+    Tuple(Vec<Value>),
     Void,
 
 
-    Undefined, // Used for initializing Slices (although maybe it shouldn't and I should just use the zero type?
+    Undefined, // The zero value of kinds that were not typechecked
 }
 
 impl Value {
+    // Used on indices, clamped so the ones past the range of an i32 still fail the bounds checks
     pub fn get_integer(&self) -> Option<i32> {
         match self {
-            Value::Int(i) => Some(*i),
+            Value::Int(i) => Some((*i).clamp(i32::MIN as i128, i32::MAX as i128) as i32),
+            _ => None
+        }
+    }
+    // The size given to make, None when it is negative or past the largest index
+    pub fn get_size(&self) -> Option<usize> {
+        match self {
+            Value::Int(i) if (0..=i32::MAX as i128).contains(i) => Some(*i as usize),
             _ => None
         }
     }
@@ -101,10 +401,8 @@ impl fmt::Display for Value {
         match self {
             Int(a) => write!(f, "{}", a),
             Float(a) => format_float(*a, f),
-            String(ref a) => write!(f, "{}", a), // prints the string without quotation marks
+            String(ref a) => write!(f, "{}", std::string::String::from_utf8_lossy(a)), // prints the string without quotation marks
             Bool(a) => write!(f, "{}", a), // prints true or false
-            
-            Value::OneOverMax => write!(f, "2147483648"),
 
             Value::Void => write!(f, "()"),
 
@@ -113,30 +411,31 @@ impl fmt::Display for Value {
             Array(..) => write!(f, "<array>"),
             Slice(s) => {
                 write!(f, "<slice> [")?;
-                for v in s.contents.iter() {
+                for v in s.contents[s.offset..s.offset + s.capacity].iter() {
                     write!(f, "{} ", v.borrow())?;
                 }
                 write!(f, "]")
             },
 
             Struct(..) => write!(f, "<struct>"),
+            Map(..) => write!(f, "<map>"),
+            Function(..) => write!(f, "<func>"),
+            Pointer(..) => write!(f, "<pointer>"),
+            Interface(..) => write!(f, "<interface>"),
+            Channel(..) => write!(f, "<chan>"),
+            Tuple(..) => write!(f, "<tuple>"),
         }
     }
 }
 
 pub fn parse_with_kind(s: &str,k: &Kind) -> Value {
     match k {
-        Kind::Basic(BasicKind::Int) => {
-            let base: u32 = string_to_int(s);
-            if base == ONE_OVER_MAX {
-                Value::OneOverMax
-            } else {
-                Value::Int(i32::try_from(base).unwrap())
-            }
-        }
-        Kind::Basic(BasicKind::Float) => Value::Float(s.parse::<f64>().unwrap()),
-        Kind::Basic(BasicKind::Rune) => Value::Int(util::parse_rune_literal(s)),
-        Kind::Basic(BasicKind::String) => Value::String(util::parse_string_literal(s)),
+        Kind::Basic(_) if util::is_rune_literal(s) => Value::Int(util::parse_rune_literal(s) as i128),
+        // Literals are never negative, so the literal of the smallest value of a signed kind only
+        // fits once it is negated
+        Kind::Basic(basic) if basic.is_integer() => Value::Int(string_to_u64(s) as i128),
+        Kind::Basic(basic) if basic.is_float() => fit_kind(Value::Float(s.parse::<f64>().unwrap()), k),
+        Kind::Basic(BasicKind::String) => Value::String(util::parse_string_literal(s).into_bytes()),
         Kind::Basic(BasicKind::Bool) => Value::Bool(match s {
             "true" => true,
             "false" => false,
@@ -147,22 +446,44 @@ pub fn parse_with_kind(s: &str,k: &Kind) -> Value {
     }
 }
 
+/// Wraps an integer around to fit in the bits of its kind, like Go does when an operation
+/// overflows
+pub fn wrap_integer(i: i128, kind: BasicKind) -> i128 {
+    let modulus = 1i128 << kind.integer_bits().unwrap();
+    let wrapped = i.rem_euclid(modulus);
+    if !kind.is_unsigned() && wrapped >= modulus / 2 {
+        wrapped - modulus
+    } else {
+        wrapped
+    }
+}
+
+/// Makes a numeric value fit its kind, by wrapping integers around and rounding float32s
+pub fn fit_kind(v: Value, k: &Kind) -> Value {
+    match (k.resolve(), v) {
+        (Kind::Basic(basic), Value::Int(i)) if basic.is_integer() => {
+            Value::Int(wrap_integer(i, basic))
+        }
+        (Kind::Basic(BasicKind::Float32), Value::Float(f)) => Value::Float(f as f32 as f64),
+        (_, v) => v,
+    }
+}
+
 pub fn zero_array(k: &Kind, len: u32) -> Value {
     let mut v: Vec<Value> = Vec::new();
     for _ in 0..len {
         v.push(zero_value(k));
     }
-    Value::Array(v.into())
+    Value::Array(Array::new(v))
 }
 
 pub fn zero_slice(k: &Kind) -> Value {
     _ = k; // interestingly, I don't even need to know the kind.
-    let v: Vec<RefCell<Value>> = Vec::new();
-    Value::Slice(Slice{length:0, contents:v.into()})
+    Value::Slice(Slice::new(Vec::new()))
 }
 pub fn zero_struct(fields: &[kind::Field]) -> Value {
     let mut ret = HashMap::<String, Value>::new();
-    for kind::Field{name, kind} in fields {
+    for kind::Field{name, kind, ..} in fields {
         ret.insert(name.clone(), zero_value(kind));
     }
     Value::Struct(ret)
@@ -172,21 +493,18 @@ pub fn zero_value(k: &Kind) -> Value {
     use self::Kind::*;
     match k {
         Undefined => Value::Undefined,
-        Basic(BasicKind::Int) => {
-            Value::Int(0)
-        }
-        Basic(BasicKind::Float) => {
-            Value::Float(0.0)
-        }
-        Basic(BasicKind::Rune) => {
+        Basic(basic) if basic.is_integer() => {
             Value::Int(0)
         }
         Basic(BasicKind::String) => {
-            Value::String("".to_string())
+            Value::String(Vec::new())
         }
         Basic(BasicKind::Bool) => {
             Value::Bool(false)
         }
+        Basic(_) => {
+            Value::Float(0.0)
+        }
         Defined(ref def) => {
             zero_value(&def.borrow().kind)
         },
@@ -199,15 +517,23 @@ pub fn zero_value(k: &Kind) -> Value {
         Struct(ref fields) => {
             zero_struct(fields)
         },
+        Map(..) => Value::Map(self::Map { entries: None }),
+        Function(..) => Value::Function(self::Function::Nil),
+        Pointer(..) => Value::Pointer(None),
+        Interface(..) => Value::Interface(None),
+        Chan(..) => Value::Channel(None),
+        Tuple(..) => panic!("Tuples are only on the right of assignments"),
         Underscore => panic!("It does not make sense to instantiate the underscore type"),
+        Untyped(..) => panic!("Untyped constants are folded by the typechecker"),
+        Nil => panic!("nil is given a kind by the typechecker"),
         Void => Value::Void,
     }
 
 }
 
 pub mod builtins {
-    use value;
-    use value::*;
+    use crate::value;
+    use crate::value::*;
 
     pub fn cast(kk: &Kind, v: &Value) -> Value {
         let k = kk.resolve();
@@ -216,21 +542,20 @@ pub mod builtins {
             match (bk, v) {
                 (BasicKind::String, Value::Int(i)) => {
                     // In modern Go, this will work only for runes, it is deprecated for other ints
-                    let i = *i;
-                    let c = char::from_u32(i as u32).unwrap();
-                    return Value::String(format!("{}",c))
+                    let c = u32::try_from(*i).ok().and_then(char::from_u32).unwrap_or('\u{fffd}');
+                    return Value::String(c.to_string().into_bytes())
                 },
-                (BasicKind::Int, Value::Float(f)) => {
-                    let f = *f;
-                    return Value::Int(f as i32);
+                (_, Value::Float(f)) if bk.is_integer() => {
+                    // Truncated towards zero. Go leaves values that do not fit to the
+                    // implementation, they get wrapped around like the JavaScript backend does
+                    let f = if f.is_finite() { f.trunc() % 18446744073709551616.0 } else { 0.0 };
+                    return Value::Int(wrap_integer(f as i128, bk));
                 },
-                (BasicKind::Rune, Value::Float(f)) => {
-                    let f = *f;
-                    return Value::Int(f as i32);
+                (_, Value::Int(i)) if bk.is_float() => {
+                    return fit_kind(Value::Float(*i as f64), &k);
                 },
-                (BasicKind::Float, Value::Int(i)) => {
-                    let i = *i;
-                    return Value::Float(i as f64);
+                (_, Value::Int(_)) | (_, Value::Float(_)) => {
+                    return fit_kind(v.clone(), &k);
                 },
                 (_,_) => {},
             }
@@ -239,29 +564,33 @@ pub mod builtins {
         return v.clone();
     }
 
-    pub fn append(l: Value, r: Value) -> Value {
+    // The elements past the length of the new contents get the zero value, since the slice can
+    // be extended over them
+    pub fn append(l: Value, r: Value, kind: &Kind) -> Value {
         if let Value::Slice(slice) = l {
             let length = slice.length;
             let new_l = length+1;
-            if new_l <= slice.contents.len() {
-                *slice.contents[new_l-1].borrow_mut() = r;
+            if new_l <= slice.capacity {
+                *slice.element(length).borrow_mut() = r;
                 Value::Slice(value::Slice {
                     length: new_l,
-                    contents: slice.contents
+                    ..slice
                 })
             } else {
                 let new_capacity = if length==0 {1} else {length*2};
                 let mut new_contents: Vec<RefCell<Value>> = Vec::with_capacity(new_capacity);
-                for a in slice.contents.iter() {
-                    new_contents.push(RefCell::new(a.borrow_mut().clone()));
+                for i in 0..length {
+                    new_contents.push(RefCell::new(slice.element(i).borrow().clone()));
                 }
                 new_contents.push(RefCell::new(r));
                 while new_contents.len()<new_capacity {
-                    new_contents.push(RefCell::new(Value::Undefined));
+                    new_contents.push(RefCell::new(zero_value(kind)));
                 }
 
                 Value::Slice(value::Slice {
+                    offset: 0,
                     length: new_l,
+                    capacity: new_capacity,
                     contents: new_contents.into(),
                 })
             }
@@ -271,27 +600,103 @@ pub mod builtins {
         }
     }
 
+    // Strings are measured in bytes, like in Go
+    pub fn len(v: &Value) -> Value {
+        match v {
+            Value::String(s) => Value::Int(s.len() as i128),
+            Value::Array(a) => Value::Int(a.len() as i128),
+            Value::Slice(s) => Value::Int(s.length as i128),
+            Value::Map(m) => Value::Int(m.entries.as_ref().map_or(0, |e| e.borrow().len()) as i128),
+            Value::Channel(c) => Value::Int(c.as_ref().map_or(0, |c| c.len()) as i128),
+            _ => panic!("Cannot take the length of {:?}", v),
+        }
+    }
+
+    pub fn cap(v: &Value) -> Value {
+        match v {
+            Value::Array(a) => Value::Int(a.len() as i128),
+            Value::Slice(s) => Value::Int(s.capacity as i128),
+            Value::Channel(c) => Value::Int(c.as_ref().map_or(0, |c| c.capacity()) as i128),
+            _ => panic!("Cannot take the capacity of {:?}", v),
+        }
+    }
+
+    // The elements past the length are zero values as well, since the slice can be extended over
+    // them
+    pub fn make_slice(kind: &Kind, length: usize, capacity: usize) -> Value {
+        let element = match kind.resolve() {
+            Kind::Slice(element) => *element,
+            _ => panic!("Cannot make a slice of {:?}", kind),
+        };
+        let contents = (0..capacity).map(|_| zero_value(&element)).collect();
+        Value::Slice(value::Slice { length, ..value::Slice::new(contents) })
+    }
+
+    // The elements are read before any is written, so that slices sharing their elements copy
+    // them like Go does when they overlap
+    pub fn copy(dst: &Value, src: &Value) -> Value {
+        match (dst, src) {
+            (Value::Slice(dst), Value::Slice(src)) => {
+                let count = dst.length.min(src.length);
+                let values: Vec<Value> = (0..count).map(|i| src.element(i).borrow().clone()).collect();
+                for (i, v) in values.into_iter().enumerate() {
+                    *dst.element(i).borrow_mut() = v;
+                }
+                Value::Int(count as i128)
+            }
+            _ => panic!("Cannot copy {:?} to {:?}", src, dst),
+        }
+    }
+
+    pub fn make_map() -> Value {
+        Value::Map(value::Map { entries: Some(Rc::new(RefCell::new(value::MapEntries::default()))) })
+    }
+
+    pub fn map_lookup(m: &value::Map, key: &Value) -> Option<Value> {
+        m.entries.as_ref().and_then(|entries| entries.borrow().get(key).cloned())
+    }
+
+    // The map must not be nil, which is a runtime error left to the caller
+    pub fn map_insert(m: &value::Map, key: Value, v: Value) {
+        m.entries.as_ref().expect("Cannot insert into a nil map").borrow_mut().insert(key, v);
+    }
+
+    // Deleting from a nil map or a missing key does nothing
+    pub fn map_delete(m: &Value, key: &Value) {
+        match m {
+            Value::Map(value::Map { entries: Some(entries) }) => entries.borrow_mut().remove(key),
+            Value::Map(_) => {}
+            _ => panic!("Cannot delete from {:?}", m),
+        }
+    }
+
+    pub fn map_keys(m: &value::Map) -> Vec<Value> {
+        match m.entries {
+            Some(ref entries) => entries.borrow().keys(),
+            None => Vec::new(),
+        }
+    }
+
     pub fn plus(v: &Value) -> Value {
         v.clone()
     }
-    pub fn neg(v: &Value) -> Value {
-        use value::Value::*;
+    pub fn neg(v: &Value, kind: &Kind) -> Value {
+        use crate::value::Value::*;
         match v {
-            Int(a) => Int(-a),
-            OneOverMax => Int(SMALLEST_INT),
+            Int(a) => fit_kind(Int(-a), kind),
             Float(f) => Float(-f),
             _ => panic!("Cannot negate"),
         }
     }
-    pub fn bw_compl(v: &Value) -> Value {
-        use value::Value::*;
+    pub fn bw_compl(v: &Value, kind: &Kind) -> Value {
+        use crate::value::Value::*;
         match v {
-            Int(a) => Int(!a),
+            Int(a) => fit_kind(Int(!a), kind),
             _ => panic!("Cannot take complement"),
         }
     }
     pub fn not(v: &Value) -> Value {
-        use value::Value::*;
+        use crate::value::Value::*;
         match v {
             Bool(a) => Bool(!a),
             _ => panic!("Cannot take not"),
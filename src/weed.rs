@@ -1,25 +1,31 @@
-use ast::*;
-use std::process::exit;
+use std::collections::HashSet;
+use crate::ast::*;
+use crate::span::Span;
+use crate::diagnostics::Diagnostics;
 
-/// Weeds the tree for incorrect break, continue, and blank identifier usage
-pub fn weed_ast(root: &Program){
+/// Weeds the tree for incorrect break, continue, fallthrough, label, and blank identifier usage
+pub fn weed_ast(root: &Program, diagnostics: &Diagnostics){
     if &root.package_name == "_" {
-        eprintln!("Error: package name cannot be `_`");
-        exit(1);
+        diagnostics.error(root.package_span, "package name cannot be `_`");
     }
     for node in root.declarations.iter() {
         match node.variant {
-            TopLevelDeclarationVariant::FunctionDeclaration (Function { ref name, ref parameters, ref return_kind, ref body }) => {
-                check_blank_func_decl(name, parameters, return_kind, body, node.line_number);
+            TopLevelDeclarationVariant::FunctionDeclaration (Function { ref name, ref receiver, ref parameters, ref results, ref body }) => {
+                if let Some(ref receiver) = *receiver {
+                    check_blank_type(&receiver.kind, diagnostics);
+                }
+                check_blank_func_decl(name, parameters, results, body, node.span, diagnostics);
                 for stmt in body.iter() {
-                    check_for_correct_break_and_continue_usage(stmt, false);
-                    traverse_stmt_for_invalid_blank(stmt);
+                    check_for_correct_break_and_continue_usage(stmt, false, diagnostics);
+                    check_for_correct_fallthrough_usage(stmt, diagnostics);
                 }
+                check_labels(body, diagnostics);
 
             },
-            TopLevelDeclarationVariant::VarDeclarations { ref declarations } => {
+            TopLevelDeclarationVariant::VarDeclarations { ref declarations } |
+            TopLevelDeclarationVariant::ConstDeclarations { ref declarations } => {
                 for decl in declarations.iter() {
-                    check_blank_var_decl(&decl);
+                    check_blank_var_decl(decl, diagnostics);
                 }
             },
             _ => {},
@@ -27,140 +33,146 @@ pub fn weed_ast(root: &Program){
     }
 }
 
-pub fn weed_terminating_statements(root: &Program) {
+pub fn weed_terminating_statements(root: &Program, diagnostics: &Diagnostics) {
     for node in root.declarations.iter() {
         match node.variant {
-            TopLevelDeclarationVariant::FunctionDeclaration (Function { ref return_kind, ref body, .. }) => {
-                match return_kind {
-                    &Some(..) => check_correct_terminating_statements(body, node.line_number),
-                    &None => {},
-                }
+            TopLevelDeclarationVariant::FunctionDeclaration (ref function) => {
+                weed_function_terminating_statements(function, node.span, diagnostics);
             }
             _ => {},
         }
     }
 }
 
-fn check_correct_terminating_statements(body: &Vec<StatementNode>, line_number: u32){
-    let length = body.len();
-    match length {
-        0 => error_missing_terminating_statement(line_number),
-        _ => {
-            match body[length-1].variant {
-                StatementVariant::Return(..) => return,
-                StatementVariant::Block(ref body) => check_correct_terminating_statements(body,line_number),
-                StatementVariant::If { ref if_branch, ref else_branch, .. } => {
-                    match else_branch {
-                        &Some(ref else_branch) => {
-                            check_correct_terminating_statements(if_branch, line_number);
-                            check_correct_terminating_statements_elseif(else_branch, line_number);
-                        }
-                        &None => error_missing_terminating_statement(line_number),
-                    }
-                }
-                StatementVariant::For { ref body, ref condition, .. } => {
-                    match condition {
-                        &None => {},
-                        &Some(..) => error_missing_terminating_statement(line_number),
-                    }
-                    if find_break(body) {
-                        error_missing_terminating_statement(line_number)
-                    }
-                }
-                StatementVariant::Switch { ref body, .. } => {
-                    let mut flag = false;
-                    for case_clause in body {
-                        match case_clause.switch_case {
-                            SwitchCase::Default => flag = true,
-                            _ => {},
-                        }
-                        if find_break(&case_clause.statements) {
-                            error_missing_terminating_statement(line_number)
-                        } 
-                        check_correct_terminating_statements(&case_clause.statements, line_number);
-                    }
+/// Checks that a function with results ends in a terminating statement. Function literals are
+/// checked by the typechecker when it finds them.
+pub fn weed_function_terminating_statements(function: &Function, span: Span, diagnostics: &Diagnostics) {
+    if !function.results.is_empty() {
+        check_correct_terminating_statements(&function.body, span, diagnostics);
+    }
+}
 
-                    if !flag {
-                        error_missing_terminating_statement(line_number)
-                    }
-                }
-                _ => error_missing_terminating_statement(line_number),
-            }
-        }
+fn check_correct_terminating_statements(body: &[StatementNode], span: Span, diagnostics: &Diagnostics){
+    match body.last() {
+        None => error_missing_terminating_statement(span, diagnostics),
+        Some(stmt) => check_correct_terminating_statement(stmt, None, span, diagnostics),
     }
 }
 
-fn find_break(body: &Vec<StatementNode>) -> bool {
-    for stmt in body {
-        match stmt.variant {
-            StatementVariant::Break => return true,
-            StatementVariant::Block(ref body) => {
-                if find_break(body) {
-                    return true;
+/// Checks the last statement of a body, which has the given label if it is labeled
+fn check_correct_terminating_statement(stmt: &StatementNode, label: Option<&String>, span: Span, diagnostics: &Diagnostics){
+    match stmt.variant {
+        StatementVariant::Return(..) | StatementVariant::Goto(..) => {}
+        // A call to panic does not return
+        StatementVariant::Expression(ref exp) => match exp.variant {
+            ExpressionVariant::Panic { .. } => {}
+            _ => error_missing_terminating_statement(span, diagnostics),
+        },
+        StatementVariant::Labeled { ref label, ref statement } => {
+            check_correct_terminating_statement(statement, Some(label), span, diagnostics)
+        }
+        StatementVariant::Block(ref body) => check_correct_terminating_statements(body,span, diagnostics),
+        StatementVariant::If { ref if_branch, ref else_branch, .. } => {
+            match else_branch {
+                Some(else_branch) => {
+                    check_correct_terminating_statements(if_branch, span, diagnostics);
+                    check_correct_terminating_statements_elseif(else_branch, span, diagnostics);
                 }
+                &None => error_missing_terminating_statement(span, diagnostics),
             }
-            StatementVariant::If { ref if_branch, ref else_branch, .. } => {
-                if find_break(if_branch) {
-                    return true;
+        }
+        StatementVariant::For { ref body, ref condition, .. } => {
+            match *condition {
+                None => {},
+                Some(..) => error_missing_terminating_statement(span, diagnostics),
+            }
+            if find_break(body, label) {
+                error_missing_terminating_statement(span, diagnostics)
+            }
+        }
+        StatementVariant::Switch { ref body, .. } | StatementVariant::TypeSwitch { ref body, .. } => {
+            let mut flag = false;
+            for case_clause in body {
+                if let SwitchCase::Default = case_clause.switch_case {
+                    flag = true;
                 }
-                match else_branch {
-                    &Some(ref else_branch) => {
-                        if find_break_elseif(else_branch) {
-                            return true;
-                        }
-                    }
-                    &None => {},
+                if find_break(&case_clause.statements, label) {
+                    error_missing_terminating_statement(span, diagnostics)
+                } 
+                // A clause that falls through ends like the next one, which is checked too
+                if !case_clause.falls_through() {
+                    check_correct_terminating_statements(&case_clause.statements, span, diagnostics);
                 }
             }
-            _ => {},
+
+            if !flag {
+                error_missing_terminating_statement(span, diagnostics)
+            }
         }
+        // A select without clauses blocks forever, and one that can't be broken out of ends
+        // like its clauses
+        StatementVariant::Select { ref body } => {
+            for case_clause in body {
+                if find_break(&case_clause.statements, label) {
+                    error_missing_terminating_statement(span, diagnostics)
+                }
+                check_correct_terminating_statements(&case_clause.statements, span, diagnostics);
+            }
+        }
+        _ => error_missing_terminating_statement(span, diagnostics),
     }
-    return false;
 }
 
-fn find_break_elseif(stmt: &StatementNode) -> bool {
+/// Checks for breaks out of the loop or switch with the body and label. Unlabeled breaks in
+/// inner loops and switches only leave those.
+fn find_break(body: &[StatementNode], label: Option<&String>) -> bool {
+    body.iter().any(|stmt| find_break_in_statement(stmt, label, false))
+}
+
+fn find_break_in_statement(stmt: &StatementNode, label: Option<&String>, nested: bool) -> bool {
     match stmt.variant {
+        StatementVariant::Break(None) => !nested,
+        StatementVariant::Break(Some(ref l)) => Some(l) == label,
         StatementVariant::Block(ref body) => {
-            return find_break(body)
+            body.iter().any(|stmt| find_break_in_statement(stmt, label, nested))
         }
         StatementVariant::If { ref if_branch, ref else_branch, .. } => {
-            if find_break(if_branch) {
-                return true;
-            }
-            match else_branch {    
-                &Some(ref else_branch) => {
-                    if find_break_elseif(else_branch) {
-                        return true;
-                    }
-                }
-                &None => {},
-            }
+            if_branch.iter().any(|stmt| find_break_in_statement(stmt, label, nested)) ||
+                else_branch.as_ref().is_some_and(|stmt| find_break_in_statement(stmt, label, nested))
+        }
+        StatementVariant::For { ref body, .. } | StatementVariant::ForRange { ref body, .. } => {
+            body.iter().any(|stmt| find_break_in_statement(stmt, label, true))
         }
-        _ => return false,
+        StatementVariant::Switch { ref body, .. } | StatementVariant::TypeSwitch { ref body, .. } |
+        StatementVariant::Select { ref body } => {
+            body.iter().any(|case_clause| {
+                case_clause.statements.iter().any(|stmt| find_break_in_statement(stmt, label, true))
+            })
+        }
+        StatementVariant::Labeled { ref statement, .. } => find_break_in_statement(statement, label, nested),
+        _ => false,
     }
-    return false;
 }
 
-fn error_missing_terminating_statement(line_number: u32) {
-    print!("Error: line {}: missing terminating statement in function declaration", line_number);
-    exit(1);
+fn error_missing_terminating_statement(span: Span, diagnostics: &Diagnostics) {
+    diagnostics.error(span, "missing terminating statement in function declaration");
 }
 
-fn check_correct_terminating_statements_elseif(stmt: &StatementNode, line_number: u32) {
+fn check_correct_terminating_statements_elseif(stmt: &StatementNode, span: Span, diagnostics: &Diagnostics) {
     match stmt.variant {
         StatementVariant::If { ref if_branch, ref else_branch, .. } => {
             match else_branch {
                 &Some(ref else_branch) => {
-                    check_correct_terminating_statements(if_branch, line_number);
-                    check_correct_terminating_statements_elseif(else_branch, line_number);
+                    check_correct_terminating_statements(if_branch, span, diagnostics);
+                    check_correct_terminating_statements_elseif(else_branch, span, diagnostics);
                 }
-                &None => error_missing_terminating_statement(line_number),
+                &None => error_missing_terminating_statement(span, diagnostics),
             }
         }
         StatementVariant::Block(ref body) => {
-            check_correct_terminating_statements(body, line_number);
+            check_correct_terminating_statements(body, span, diagnostics);
         }
-        _ => error_missing_terminating_statement(line_number),
+        _ => error_missing_terminating_statement(span, diagnostics),
     }
 }
 
@@ -169,103 +181,332 @@ BREAK/CONTINUE USAGE WEED FUNCTIONS
 ========================================= */
 
 /// Checks for correct usage of break and continue
-/// Note that `continue` is only valid for loops whereas `break` is balid for loops and switch
-fn check_for_correct_break_and_continue_usage(stmt: &StatementNode, can_break: bool){
+/// Note that `continue` is only valid for loops whereas `break` is balid for loops, switch and
+/// select
+fn check_for_correct_break_and_continue_usage(stmt: &StatementNode, can_break: bool, diagnostics: &Diagnostics){
     match stmt.variant {
         StatementVariant::Block(ref v) => {
             for x in v {
-                check_for_correct_break_and_continue_usage(&x, can_break);
+                check_for_correct_break_and_continue_usage(x, can_break, diagnostics);
             }
         },
         StatementVariant::If { ref if_branch, ref else_branch, .. } => {
 
             for x in if_branch {
-                check_for_correct_break_and_continue_usage(&x, can_break);
+                check_for_correct_break_and_continue_usage(x, can_break, diagnostics);
             }
 
-            match else_branch {
-                &Some(ref else_branch) => check_for_correct_break_and_continue_usage(&*else_branch, can_break),
-                &None => return,
+            if let Some(else_branch) = else_branch {
+                check_for_correct_break_and_continue_usage(else_branch, can_break, diagnostics);
             }
         },
-        StatementVariant::Switch { ref body, .. } => {
+        StatementVariant::Switch { ref body, .. } | StatementVariant::TypeSwitch { ref body, .. } |
+        StatementVariant::Select { ref body } => {
 
             for case_clause in body {
                 for stmt in &case_clause.statements {
-                    check_for_correct_break_and_continue_usage(&stmt, true);
+                    check_for_correct_break_and_continue_usage(stmt, true, diagnostics);
                 }
             }
         },
-        StatementVariant::Break => {
+        StatementVariant::Labeled { ref statement, .. } => {
+            check_for_correct_break_and_continue_usage(statement, can_break, diagnostics);
+        },
+        // Labeled ones are checked with the other uses of labels
+        StatementVariant::Break(None) => {
             if !can_break {
-                eprintln!("Error: line {}: break outside loop or switch.", stmt.line_number);
-                exit(1);
+                diagnostics.error(stmt.span, "break outside loop or switch.");
             }
         },
-        StatementVariant::Continue => {
-            eprintln!("Error: line {}: continue outside loop.", stmt.line_number);
-            exit(1);
+        StatementVariant::Continue(None) => {
+            diagnostics.error(stmt.span, "continue outside loop.");
         },
         _ => return
     }
 }
 
+/*
+FALLTHROUGH USAGE WEED FUNCTIONS
+========================================= */
+
+/// Checks that `fallthrough` is only the last statement of a case clause, and that the clause
+/// isn't the final one of its switch
+fn check_for_correct_fallthrough_usage(stmt: &StatementNode, diagnostics: &Diagnostics){
+    match stmt.variant {
+        StatementVariant::Block(ref body) |
+        StatementVariant::For { ref body, .. } |
+        StatementVariant::ForRange { ref body, .. } => {
+            for x in body {
+                check_for_correct_fallthrough_usage(x, diagnostics);
+            }
+        },
+        StatementVariant::If { ref if_branch, ref else_branch, .. } => {
+            for x in if_branch {
+                check_for_correct_fallthrough_usage(x, diagnostics);
+            }
+            if let Some(else_branch) = else_branch {
+                check_for_correct_fallthrough_usage(else_branch, diagnostics);
+            }
+        },
+        StatementVariant::Switch { ref body, .. } => {
+            for (i, case_clause) in body.iter().enumerate() {
+                let (last, rest) = match case_clause.statements.split_last() {
+                    Some(split) => split,
+                    None => continue,
+                };
+                for x in rest {
+                    check_for_correct_fallthrough_usage(x, diagnostics);
+                }
+                match last.variant {
+                    StatementVariant::Fallthrough if i + 1 == body.len() => {
+                        diagnostics.error(last.span, "cannot fallthrough final case in switch.");
+                    },
+                    StatementVariant::Fallthrough => {},
+                    _ => check_for_correct_fallthrough_usage(last, diagnostics),
+                }
+            }
+        },
+        // The clauses of a type switch declare variables of different kinds
+        StatementVariant::TypeSwitch { ref body, .. } => {
+            for case_clause in body {
+                for x in &case_clause.statements {
+                    match x.variant {
+                        StatementVariant::Fallthrough => {
+                            diagnostics.error(x.span, "cannot fallthrough in type switch.");
+                        },
+                        _ => check_for_correct_fallthrough_usage(x, diagnostics),
+                    }
+                }
+            }
+        },
+        // There is no next clause to fall through to in a select
+        StatementVariant::Select { ref body } => {
+            for case_clause in body {
+                for x in &case_clause.statements {
+                    check_for_correct_fallthrough_usage(x, diagnostics);
+                }
+            }
+        },
+        StatementVariant::Labeled { ref statement, .. } => {
+            check_for_correct_fallthrough_usage(statement, diagnostics);
+        },
+        StatementVariant::Fallthrough => {
+            diagnostics.error(stmt.span, "fallthrough statement out of place.");
+        },
+        _ => {}
+    }
+}
+
+/*
+LABEL USAGE WEED FUNCTIONS
+========================================= */
+
+// Where a statement is, as the blocks around it from the outermost one and the index of the
+// statement in each of them
+type Position = Vec<(usize, usize)>;
+
+struct LabelChecker<'a> {
+    diagnostics: &'a Diagnostics,
+    // The statements of each block, to find the declarations that a goto jumps over
+    blocks: Vec<&'a [StatementNode]>,
+    labels: Vec<(&'a str, Span, Position)>,
+    gotos: Vec<(&'a str, Span, Position)>,
+    used_labels: HashSet<&'a str>,
+    // The labels of the loops and switches around the statement being checked, and whether each
+    // is a loop
+    targets: Vec<(&'a str, bool)>,
+}
+
+/// Checks that the labels of a function body are defined once and used, that labeled break and
+/// continue statements are in the loop or switch with that label, and that gotos don't jump into
+/// blocks or over variable declarations
+fn check_labels(body: &[StatementNode], diagnostics: &Diagnostics) {
+    let mut checker = LabelChecker {
+        diagnostics,
+        blocks: Vec::new(),
+        labels: Vec::new(),
+        gotos: Vec::new(),
+        used_labels: HashSet::new(),
+        targets: Vec::new(),
+    };
+    checker.check_block(body, &mut Vec::new());
+
+    for &(label, span, _) in &checker.labels {
+        if !checker.used_labels.contains(label) {
+            diagnostics.error(span, &format!("label {} defined and not used.", label));
+        }
+    }
+
+    for &(label, span, ref position) in &checker.gotos {
+        match checker.labels.iter().find(|&&(l, _, _)| l == label) {
+            Some((_, _, label_position)) => checker.check_goto(label, span, position, label_position),
+            None => diagnostics.error(span, &format!("label {} not defined.", label)),
+        }
+    }
+}
+
+impl<'a> LabelChecker<'a> {
+    /// A goto can only jump to a label in its own block or one around it, and can't jump forward
+    /// over a variable declaration in that block
+    fn check_goto(&self, label: &str, span: Span, position: &Position, label_position: &Position) {
+        let (block, label_index) = *label_position.last().unwrap();
+        match position.iter().find(|&&(b, _)| b == block) {
+            Some(&(_, goto_index)) if goto_index < label_index => {
+                let skipped = &self.blocks[block][goto_index + 1..label_index];
+                if let Some(declaration) = skipped.iter().find(|stmt| declares_variables(stmt)) {
+                    self.diagnostics.error(span, &format!("goto {} jumps over variable declaration at line {}.",
+                                                          label, declaration.span.start.line));
+                }
+            },
+            Some(..) => {},
+            None => self.diagnostics.error(span, &format!("goto {} jumps into block.", label)),
+        }
+    }
+
+    fn check_block(&mut self, statements: &'a [StatementNode], position: &mut Position) {
+        let block = self.blocks.len();
+        self.blocks.push(statements);
+        for (i, stmt) in statements.iter().enumerate() {
+            position.push((block, i));
+            self.check_statement(stmt, position);
+            position.pop();
+        }
+    }
+
+    fn check_statement(&mut self, stmt: &'a StatementNode, position: &mut Position) {
+        match stmt.variant {
+            StatementVariant::Labeled { ref label, ref statement } => {
+                if self.labels.iter().any(|&(l, _, _)| l == label) {
+                    self.diagnostics.error(stmt.span, &format!("label {} already defined.", label));
+                } else {
+                    self.labels.push((label, stmt.span, position.clone()));
+                }
+
+                let is_loop = match statement.variant {
+                    StatementVariant::For { .. } | StatementVariant::ForRange { .. } => Some(true),
+                    StatementVariant::Switch { .. } | StatementVariant::TypeSwitch { .. } |
+                    StatementVariant::Select { .. } => Some(false),
+                    _ => None,
+                };
+                if let Some(is_loop) = is_loop {
+                    self.targets.push((label, is_loop));
+                }
+                self.check_statement(statement, position);
+                if is_loop.is_some() {
+                    self.targets.pop();
+                }
+            },
+            StatementVariant::Break(Some(ref label)) => {
+                self.used_labels.insert(label);
+                if !self.targets.iter().any(|&(l, _)| l == label) {
+                    self.diagnostics.error(stmt.span, &format!("invalid break label {}.", label));
+                }
+            },
+            StatementVariant::Continue(Some(ref label)) => {
+                self.used_labels.insert(label);
+                if !self.targets.iter().any(|&(l, is_loop)| l == label && is_loop) {
+                    self.diagnostics.error(stmt.span, &format!("invalid continue label {}.", label));
+                }
+            },
+            StatementVariant::Goto(ref label) => {
+                self.used_labels.insert(label);
+                self.gotos.push((label, stmt.span, position.clone()));
+            },
+            StatementVariant::Block(ref body) |
+            StatementVariant::For { ref body, .. } |
+            StatementVariant::ForRange { ref body, .. } => self.check_block(body, position),
+            StatementVariant::If { ref if_branch, ref else_branch, .. } => {
+                self.check_block(if_branch, position);
+                if let Some(else_branch) = else_branch {
+                    self.check_statement(else_branch, position);
+                }
+            },
+            StatementVariant::Switch { ref body, .. } | StatementVariant::TypeSwitch { ref body, .. } |
+            StatementVariant::Select { ref body } => {
+                for case_clause in body {
+                    self.check_block(&case_clause.statements, position);
+                }
+            },
+            _ => {},
+        }
+    }
+}
+
+fn declares_variables(stmt: &StatementNode) -> bool {
+    match stmt.variant {
+        StatementVariant::VarDeclarations { .. } |
+        StatementVariant::ShortVariableDeclaration { .. } => true,
+        StatementVariant::Labeled { ref statement, .. } => declares_variables(statement),
+        _ => false,
+    }
+}
+
 /*
 BLANK IDENTIFIER USAGE WEED FUNCTIONS
 ========================================= */
 
 /// Checks if any blanks exist on rhs of variable declaration
-fn check_blank_var_decl(var_spec: &VarSpec){
+fn check_blank_var_decl(var_spec: &VarSpec, diagnostics: &Diagnostics){
     if let Some(ref vec) = var_spec.rhs {
             for exp in vec {
-                traverse_exp_for_invalid_blank(exp);
+                traverse_exp_for_invalid_blank(exp, diagnostics);
             }
         }
     if let Some(ref kind) = var_spec.kind {
-        check_blank_type(kind);
+        check_blank_type(kind, diagnostics);
     }
 }
 
 /// Checks if any blanks exist on rhs of type declarations
-fn check_blank_type_decl(spec: &TypeSpec){
-    check_blank_type(&*spec.kind);
+fn check_blank_type_decl(spec: &TypeSpec, diagnostics: &Diagnostics){
+    check_blank_type(&spec.kind, diagnostics);
 }
 
 /// Checks a functions name, params and body for any invalid blank identifier usage
 fn check_blank_func_decl(_name: &String,
                          params: &Vec<Field>,
-                         return_kind: &Option<Box<AstKindNode>>,
+                         results: &[Field],
                          body: &Vec<StatementNode>,
-                         _line: u32){
+                         _span: Span, diagnostics: &Diagnostics){
 
-    for field in params.iter(){
-        check_blank_field(field);
-    }
-
-    match return_kind {
-        &Some(ref return_kind) => check_blank_type(return_kind),
-        &None => return
+    for field in params.iter().chain(results.iter()){
+        check_blank_field(field, diagnostics);
     }
 
     for stmt in body.iter(){
-        traverse_stmt_for_invalid_blank(stmt)
+        traverse_stmt_for_invalid_blank(stmt, diagnostics)
     }
 }
 
 /// Checks a type for blank identifier usage
-fn check_blank_type(kind: &AstKindNode) {
+fn check_blank_type(kind: &AstKindNode, diagnostics: &Diagnostics) {
     match kind.variant {
         AstKindVariant::Identifier { ref name } => {
             if name == "_" {
-                eprintln!("Error: line {}: Invalid type name. Cannot be blank identifier.", kind.line_number);
-                exit(1);
+                diagnostics.error(kind.span, "Invalid type name. Cannot be blank identifier.");
             }
         }
-        AstKindVariant::Slice { ref base } => check_blank_type(&** base),
-        AstKindVariant::Array { ref base, .. } => check_blank_type(&** base),
+        AstKindVariant::Slice { ref base } => check_blank_type(base, diagnostics),
+        AstKindVariant::Pointer { ref base } => check_blank_type(base, diagnostics),
+        AstKindVariant::Chan { ref base } => check_blank_type(base, diagnostics),
+        AstKindVariant::Array { ref base, .. } => check_blank_type(base, diagnostics),
         AstKindVariant::Struct { ref fields } => {
             for field in fields {
-                check_blank_field(field);
+                check_blank_field(field, diagnostics);
+            }
+        }
+        AstKindVariant::Map { ref key, ref value } => {
+            check_blank_type(key, diagnostics);
+            check_blank_type(value, diagnostics);
+        }
+        AstKindVariant::Function { ref parameters, ref results } => {
+            for field in parameters.iter().chain(results.iter()) {
+                check_blank_field(field, diagnostics);
+            }
+        }
+        AstKindVariant::Interface { ref methods } => {
+            for method in methods {
+                check_blank_field(method, diagnostics);
             }
         }
     }
@@ -273,212 +514,359 @@ fn check_blank_type(kind: &AstKindNode) {
 
 /// Checks if any of the field have blank type
 /// (In no situation should blank identifiers be rejected
-fn check_blank_field(field: &Field){
-    check_blank_type(&*field.kind)
+fn check_blank_field(field: &Field, diagnostics: &Diagnostics){
+    check_blank_type(&field.kind, diagnostics)
 }
 
 /// Recursively traverses statements to detect any invalid blank id usage
-fn traverse_stmt_for_invalid_blank(stmt: &StatementNode){
+fn traverse_stmt_for_invalid_blank(stmt: &StatementNode, diagnostics: &Diagnostics){
     match stmt.variant {
         StatementVariant::Block(ref v) => {
             for x in v {
-                traverse_stmt_for_invalid_blank(&x)
+                traverse_stmt_for_invalid_blank(x, diagnostics)
             }
         },
         StatementVariant::Expression(ref exp) => {
-            traverse_exp_for_invalid_blank(&*exp)
+            traverse_exp_for_invalid_blank(exp, diagnostics)
         },
         StatementVariant::Assignment {ref lhs, ref rhs, ..} => {
             for exp in lhs.iter(){
-                traverse_assignable_exp_for_invalid_blank(exp)
+                traverse_assignable_exp_for_invalid_blank(exp, diagnostics)
             }
             for exp in rhs.iter(){
-                traverse_exp_for_invalid_blank(exp)
+                traverse_exp_for_invalid_blank(exp, diagnostics)
             }
         },
         StatementVariant::OpAssignment { ref lhs, ref rhs, .. } => {
-            traverse_exp_for_invalid_blank(&*lhs);
-            traverse_exp_for_invalid_blank(&*rhs)
+            traverse_exp_for_invalid_blank(lhs, diagnostics);
+            traverse_exp_for_invalid_blank(rhs, diagnostics)
         },
-        StatementVariant::VarDeclarations { ref declarations } => {
+        StatementVariant::VarDeclarations { ref declarations } |
+        StatementVariant::ConstDeclarations { ref declarations } => {
             for decl in declarations.iter(){
-                check_blank_var_decl(decl)
+                check_blank_var_decl(decl, diagnostics)
             }
         },
         StatementVariant::ShortVariableDeclaration { ref expression_list, .. } => {
             for exp in expression_list.iter(){
-                traverse_exp_for_invalid_blank(exp)
+                traverse_exp_for_invalid_blank(exp, diagnostics)
             }
         },
         StatementVariant::IncDec { ref expr, .. } => {
-            traverse_exp_for_invalid_blank(&*expr)
+            traverse_exp_for_invalid_blank(expr, diagnostics)
         },
         StatementVariant::Print { ref exprs } => {
             for exp in exprs.iter(){
-                traverse_exp_for_invalid_blank(exp)
+                traverse_exp_for_invalid_blank(exp, diagnostics)
             }
         },
         StatementVariant::Println { ref exprs } => {
             for exp in exprs.iter(){
-                traverse_exp_for_invalid_blank(exp)
+                traverse_exp_for_invalid_blank(exp, diagnostics)
             }
         },
         StatementVariant::If { ref init, ref condition, ref if_branch, ref else_branch } => {
-            traverse_stmt_for_invalid_blank(&*init);
-            traverse_exp_for_invalid_blank(&*condition);
+            traverse_stmt_for_invalid_blank(init, diagnostics);
+            traverse_exp_for_invalid_blank(condition, diagnostics);
 
             for stmt in if_branch.iter() {
-                traverse_stmt_for_invalid_blank(stmt)
+                traverse_stmt_for_invalid_blank(stmt, diagnostics)
             }
 
-            match else_branch {
-                &Some(ref else_branch) => traverse_stmt_for_invalid_blank(&*else_branch),
-                &None => return,
+            if let Some(else_branch) = else_branch {
+                traverse_stmt_for_invalid_blank(else_branch, diagnostics);
             }
         },
         StatementVariant::For { ref init, ref condition, ref post, ref body } => {
-            traverse_stmt_for_invalid_blank(&*init);
+            traverse_stmt_for_invalid_blank(init, diagnostics);
             match condition {
-                &Some(ref condition) => traverse_exp_for_invalid_blank(&*condition),
+                Some(condition) => traverse_exp_for_invalid_blank(condition, diagnostics),
                 &None => return,
             }
-            traverse_stmt_for_invalid_blank(&*post);
+            traverse_stmt_for_invalid_blank(post, diagnostics);
 
             for stmt in body.iter() {
-                traverse_stmt_for_invalid_blank(stmt)
+                traverse_stmt_for_invalid_blank(stmt, diagnostics)
+            }
+        },
+        StatementVariant::ForRange { ref expr, ref body, .. } => {
+            traverse_exp_for_invalid_blank(expr, diagnostics);
+
+            for stmt in body.iter() {
+                traverse_stmt_for_invalid_blank(stmt, diagnostics)
             }
         },
         StatementVariant::Switch { ref init, ref expr, ref body } => {
-            traverse_stmt_for_invalid_blank(&*init);
+            traverse_stmt_for_invalid_blank(init, diagnostics);
 
             match expr {
-                &Some(ref expr) => traverse_exp_for_invalid_blank(&*expr),
+                Some(expr) => traverse_exp_for_invalid_blank(expr, diagnostics),
                 &None => ()
             }
 
             for case_clause in body.iter() {
-                traverse_case_clause_for_invalid_blank(case_clause)
+                traverse_case_clause_for_invalid_blank(case_clause, diagnostics)
             }
         },
-        StatementVariant::Return( ref expr) => {
-            match expr {
-                &Some( ref expr ) => traverse_exp_for_invalid_blank(&*expr),
-                &None => ()
+        StatementVariant::TypeSwitch { ref init, ref expr, ref body, .. } => {
+            traverse_stmt_for_invalid_blank(init, diagnostics);
+            traverse_exp_for_invalid_blank(expr, diagnostics);
+
+            for case_clause in body.iter() {
+                traverse_case_clause_for_invalid_blank(case_clause, diagnostics)
+            }
+        },
+        StatementVariant::Return( ref exprs) => {
+            for expr in exprs.iter() {
+                traverse_exp_for_invalid_blank(expr, diagnostics)
+            }
+        }
+        StatementVariant::Defer(ref call) | StatementVariant::Go(ref call) => {
+            traverse_stmt_for_invalid_blank(call, diagnostics)
+        }
+        StatementVariant::Send { ref channel, ref value } => {
+            traverse_exp_for_invalid_blank(channel, diagnostics);
+            traverse_exp_for_invalid_blank(value, diagnostics)
+        }
+        StatementVariant::Select { ref body } => {
+            for case_clause in body.iter() {
+                traverse_case_clause_for_invalid_blank(case_clause, diagnostics)
             }
         }
         StatementVariant::TypeDeclarations{ ref declarations } => {
             for spec in declarations {
-                check_blank_type_decl(spec)
+                check_blank_type_decl(spec, diagnostics)
             }
         }
-        StatementVariant::Empty | StatementVariant::Break | StatementVariant::Continue => {}
+        StatementVariant::Labeled { ref statement, .. } => {
+            traverse_stmt_for_invalid_blank(statement, diagnostics)
+        }
+        StatementVariant::Empty | StatementVariant::Break(..) | StatementVariant::Continue(..) |
+        StatementVariant::Fallthrough | StatementVariant::Goto(..) => {}
     }
 }
 
 
-fn traverse_assignable_exp_for_invalid_blank(exp: &Expression) {
+fn traverse_assignable_exp_for_invalid_blank(exp: &Expression, diagnostics: &Diagnostics) {
     match exp.variant {
         ExpressionVariant::Identifier { .. } => return,
         ExpressionVariant::RawLiteral { .. } => {
-            eprintln!("Error: line {}: cannot assign to RawLiteral.", exp.line_number);
-            exit(1);
+            diagnostics.error(exp.span, "cannot assign to RawLiteral.");
         }
         ExpressionVariant::BinaryOperation { .. } => {
-            eprintln!("Error: line {}: cannot assign to Binary expression.", exp.line_number);
-            exit(1);
+            diagnostics.error(exp.span, "cannot assign to Binary expression.");
         }
         ExpressionVariant::UnaryOperation { .. } => {
-            eprintln!("Error: line {}: cannot assign to Unary expression.", exp.line_number);
-            exit(1);
+            diagnostics.error(exp.span, "cannot assign to Unary expression.");
         }
         ExpressionVariant::Index { .. } => {
-            traverse_exp_for_invalid_blank(exp);
+            traverse_exp_for_invalid_blank(exp, diagnostics);
         }
         ExpressionVariant::Selector { .. } => {
-            traverse_exp_for_invalid_blank(exp);
+            traverse_exp_for_invalid_blank(exp, diagnostics);
+        }
+        ExpressionVariant::Dereference { .. } => {
+            traverse_exp_for_invalid_blank(exp, diagnostics);
         }
         ExpressionVariant::FunctionCall { .. } => {
-            eprintln!("Error: line {}: cannot assign to function call.", exp.line_number);
-            exit(1);
+            diagnostics.error(exp.span, "cannot assign to function call.");
         }
         ExpressionVariant::Append { .. } => {
-            eprintln!("Error: line {}: cannot assign to append expression.", exp.line_number);
-            exit(1);
+            diagnostics.error(exp.span, "cannot assign to append expression.");
+        }
+        ExpressionVariant::Len { .. } => {
+            diagnostics.error(exp.span, "cannot assign to len expression.");
+        }
+        ExpressionVariant::Cap { .. } => {
+            diagnostics.error(exp.span, "cannot assign to cap expression.");
+        }
+        ExpressionVariant::Slice { .. } => {
+            diagnostics.error(exp.span, "cannot assign to slice expression.");
         }
         ExpressionVariant::TypeCast { .. } => {
-            eprintln!("Error: line {}: cannot assign to type cast.", exp.line_number);
-            exit(1);
+            diagnostics.error(exp.span, "cannot assign to type cast.");
+        }
+        ExpressionVariant::Make { .. } => {
+            diagnostics.error(exp.span, "cannot assign to make expression.");
+        }
+        ExpressionVariant::Delete { .. } => {
+            diagnostics.error(exp.span, "cannot assign to delete expression.");
+        }
+        ExpressionVariant::CompositeLiteral { .. } => {
+            diagnostics.error(exp.span, "cannot assign to composite literal.");
+        }
+        ExpressionVariant::TypeAssertion { .. } => {
+            diagnostics.error(exp.span, "cannot assign to type assertion.");
+        }
+        ExpressionVariant::CommaOk { .. } => {
+            diagnostics.error(exp.span, "cannot assign to comma-ok expression.");
+        }
+        ExpressionVariant::ToInterface { .. } => {
+            diagnostics.error(exp.span, "cannot assign to interface conversion.");
+        }
+        ExpressionVariant::InterfaceMethod { .. } => {
+            diagnostics.error(exp.span, "cannot assign to method.");
+        }
+        ExpressionVariant::FunctionLiteral { .. } => {
+            diagnostics.error(exp.span, "cannot assign to function literal.");
+        }
+        ExpressionVariant::Method { .. } => {
+            diagnostics.error(exp.span, "cannot assign to method.");
+        }
+        ExpressionVariant::New { .. } => {
+            diagnostics.error(exp.span, "cannot assign to new expression.");
+        }
+        ExpressionVariant::AddressOf { .. } => {
+            diagnostics.error(exp.span, "cannot assign to address-of expression.");
+        }
+        ExpressionVariant::Nil => {
+            diagnostics.error(exp.span, "cannot assign to nil.");
+        }
+        ExpressionVariant::Panic { .. } => {
+            diagnostics.error(exp.span, "cannot assign to panic expression.");
+        }
+        ExpressionVariant::Receive { .. } => {
+            diagnostics.error(exp.span, "cannot assign to receive expression.");
+        }
+        ExpressionVariant::Close { .. } => {
+            diagnostics.error(exp.span, "cannot assign to close expression.");
+        }
+        ExpressionVariant::Copy { .. } => {
+            diagnostics.error(exp.span, "cannot assign to copy expression.");
         }
     }
 }
 
 
 /// Recursively traverses expression in order to detect any invalid blank id usage
-fn traverse_exp_for_invalid_blank(exp: &Expression){
+fn traverse_exp_for_invalid_blank(exp: &Expression, diagnostics: &Diagnostics){
     match exp.variant {
         ExpressionVariant::Identifier { ref name, .. } => {
             if name == "_" {
-                eprintln!("Error: line {}: invalid use of blank identifier within expression.", exp.line_number);
-                exit(1);
+                diagnostics.error(exp.span, "invalid use of blank identifier within expression.");
             }
         },
         ExpressionVariant::RawLiteral { ref value } => {
             if value == "_" {
-                eprintln!("Error: line {}: invalid use of blank identifier within expression.", exp.line_number);
-                exit(1);
+                diagnostics.error(exp.span, "invalid use of blank identifier within expression.");
             }
         },
         ExpressionVariant::BinaryOperation { ref lhs, ref rhs, .. } => {
-            traverse_exp_for_invalid_blank(&*lhs);
-            traverse_exp_for_invalid_blank(&*rhs);
+            traverse_exp_for_invalid_blank(lhs, diagnostics);
+            traverse_exp_for_invalid_blank(rhs, diagnostics);
 
         },
         ExpressionVariant::UnaryOperation { ref rhs, .. } => {
-            traverse_exp_for_invalid_blank( &*rhs);
+            traverse_exp_for_invalid_blank( rhs, diagnostics);
         }
         ExpressionVariant::Index { ref primary, ref index } => {
-            traverse_exp_for_invalid_blank( &*primary);
-            traverse_exp_for_invalid_blank( &*index);
+            traverse_exp_for_invalid_blank( primary, diagnostics);
+            traverse_exp_for_invalid_blank( index, diagnostics);
+        }
+        ExpressionVariant::Slice { ref primary, ref low, ref high, ref max } => {
+            traverse_exp_for_invalid_blank( primary, diagnostics);
+            for index in low.iter().chain(high.iter()).chain(max.iter()) {
+                traverse_exp_for_invalid_blank( index, diagnostics);
+            }
         }
         ExpressionVariant::Selector { ref primary, ref name } => {
             if name == "_" {
-                eprintln!("Error: line {}: invalid use of blank identifier within selector.", exp.line_number);
-                exit(1);
+                diagnostics.error(exp.span, "invalid use of blank identifier within selector.");
             }
 
-            traverse_exp_for_invalid_blank(&*primary)
+            traverse_exp_for_invalid_blank(primary, diagnostics)
         }
-        ExpressionVariant::FunctionCall {ref primary, ref arguments } => {
-            traverse_exp_for_invalid_blank(&*primary);
+        ExpressionVariant::FunctionCall {ref primary, ref arguments, .. } => {
+            traverse_exp_for_invalid_blank(primary, diagnostics);
 
             for arg in arguments.iter() {
-                traverse_exp_for_invalid_blank( &arg);
+                traverse_exp_for_invalid_blank( arg, diagnostics);
             }
         }
         ExpressionVariant::Append { ref lhs, ref rhs } => {
-            traverse_exp_for_invalid_blank( &*lhs);
-            traverse_exp_for_invalid_blank( &*rhs);
+            traverse_exp_for_invalid_blank( lhs, diagnostics);
+            traverse_exp_for_invalid_blank( rhs, diagnostics);
+        }
+        ExpressionVariant::Len { ref expr } | ExpressionVariant::Cap { ref expr } |
+        ExpressionVariant::Dereference { ref expr } | ExpressionVariant::AddressOf { ref expr } => {
+            traverse_exp_for_invalid_blank( expr, diagnostics);
+        }
+        ExpressionVariant::TypeCast { ref expr, .. } |
+        ExpressionVariant::CommaOk { ref expr } |
+        ExpressionVariant::ToInterface { ref expr } |
+        ExpressionVariant::Method { receiver: ref expr, .. } |
+        ExpressionVariant::InterfaceMethod { receiver: ref expr, .. } => {
+            traverse_exp_for_invalid_blank( expr, diagnostics);
         }
-        ExpressionVariant::TypeCast { ref expr, .. } => {
-            traverse_exp_for_invalid_blank( &*expr);
+        ExpressionVariant::TypeAssertion { ref expr, ref kind } => {
+            traverse_exp_for_invalid_blank(expr, diagnostics);
+            check_blank_type(kind, diagnostics);
+        }
+        ExpressionVariant::Make { ref kind, ref arguments } => {
+            check_blank_type(kind, diagnostics);
+
+            for arg in arguments.iter() {
+                traverse_exp_for_invalid_blank( arg, diagnostics);
+            }
+        }
+        ExpressionVariant::Delete { ref map, ref key } => {
+            traverse_exp_for_invalid_blank( map, diagnostics);
+            traverse_exp_for_invalid_blank( key, diagnostics);
+        }
+        ExpressionVariant::New { ref kind } => check_blank_type(kind, diagnostics),
+        ExpressionVariant::Nil => {}
+        ExpressionVariant::Panic { ref expr } => traverse_exp_for_invalid_blank(expr, diagnostics),
+        ExpressionVariant::Receive { ref channel } |
+        ExpressionVariant::Close { ref channel } => traverse_exp_for_invalid_blank(channel, diagnostics),
+        ExpressionVariant::Copy { ref dst, ref src } => {
+            traverse_exp_for_invalid_blank(dst, diagnostics);
+            traverse_exp_for_invalid_blank(src, diagnostics);
+        }
+        ExpressionVariant::CompositeLiteral { ref kind, ref elements } => {
+            if let Some(ref kind) = *kind {
+                check_blank_type(kind, diagnostics);
+            }
+
+            for element in elements.iter() {
+                if let Some(ref key) = element.key {
+                    traverse_exp_for_invalid_blank( key, diagnostics);
+                }
+                traverse_exp_for_invalid_blank( &element.value, diagnostics);
+            }
+        }
+        ExpressionVariant::FunctionLiteral { ref function } => {
+            // The body of a literal is a function body of its own, even inside a loop
+            check_blank_func_decl(&function.name, &function.parameters, &function.results,
+                                  &function.body, exp.span, diagnostics);
+            for stmt in function.body.iter() {
+                check_for_correct_break_and_continue_usage(stmt, false, diagnostics);
+                check_for_correct_fallthrough_usage(stmt, diagnostics);
+            }
+            check_labels(&function.body, diagnostics);
         }
     }
 }
 
 
 /// Traverses through the statements and expressions that make up a case clause to detect any invalid blank id usage
-fn traverse_case_clause_for_invalid_blank(case_clause: &CaseClause){
+fn traverse_case_clause_for_invalid_blank(case_clause: &CaseClause, diagnostics: &Diagnostics){
     match case_clause.switch_case {
         SwitchCase::Cases(ref vec) => {
             for expr in vec.iter() {
-                traverse_exp_for_invalid_blank(expr)
+                traverse_exp_for_invalid_blank(expr, diagnostics)
+            }
+        },
+        SwitchCase::Kinds(ref kinds) => {
+            for kind in kinds.iter() {
+                check_blank_type(kind, diagnostics)
             }
         },
+        SwitchCase::Comm(ref comm) => traverse_stmt_for_invalid_blank(comm, diagnostics),
         _ => ()
     }
 
     for stmt in case_clause.statements.iter() {
-        traverse_stmt_for_invalid_blank(stmt)
+        traverse_stmt_for_invalid_blank(stmt, diagnostics)
     }
 }
 
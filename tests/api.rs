@@ -0,0 +1,107 @@
+use golite::span;
+
+const VALID: &str = "package main
+
+func main() {
+	println(\"hello\")
+}
+";
+
+const SYNTAX_ERROR: &str = "package main
+
+func main() {
+	x := 1 + )
+}
+";
+
+#[test]
+fn test_parse_source_twice() {
+    let program = golite::parse_source(VALID, "valid.go").unwrap();
+    let errors = golite::parse_source(SYNTAX_ERROR, "syntax_error.go").unwrap_err();
+
+    let file = program.source.as_ref().unwrap().id();
+    assert_eq!(span::file_name(file).as_deref(), Some("valid.go"));
+    assert_eq!(program.package_span.file, file);
+    assert_eq!(program.declarations.len(), 1);
+
+    assert!(!errors.is_empty());
+    for error in &errors {
+        assert_ne!(error.span.file, file);
+        assert_eq!(span::file_name(error.span.file).as_deref(), Some("syntax_error.go"));
+        assert_eq!(error.span.start.line, 4);
+        assert!(error.to_string().starts_with("Error: syntax_error.go:4:"), "{}", error);
+    }
+
+    // The syntax error leaves nothing behind for the next source
+    let again = golite::parse_source(VALID, "again.go").unwrap();
+    let again_file = again.source.as_ref().unwrap().id();
+    assert_eq!(span::file_name(again_file).as_deref(), Some("again.go"));
+    assert_eq!(again.package_span.file, again_file);
+}
+
+const PROGRAM: &str = "package main
+
+func fib(n int) int {
+	if n < 2 {
+		return n
+	}
+	return fib(n-1) + fib(n-2)
+}
+
+func main() {
+	for i := 0; i < 5; i++ {
+		print(fib(i), \" \")
+	}
+	println()
+}
+";
+
+#[test]
+fn test_pipeline() {
+    let mut program = golite::parse_source(PROGRAM, "fib.go").unwrap();
+    golite::weed(&program).unwrap();
+    golite::typecheck(&mut program).unwrap();
+
+    let mut output = Vec::new();
+    golite::interpret(&program, &mut output).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "0 1 1 2 3 \n");
+
+    let js = golite::codegen(&program).unwrap();
+    assert!(js.contains("fib"));
+}
+
+#[test]
+fn test_type_error() {
+    let source = "package main
+
+func main() {
+	var x int = \"hello\"
+	println(x)
+}
+";
+    let mut program = golite::parse_source(source, "type_error.go").unwrap();
+    golite::weed(&program).unwrap();
+    let errors = golite::typecheck(&mut program).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].span.start.line, 4);
+    assert!(errors[0].to_string().starts_with("Error: type_error.go:4:"), "{}", errors[0]);
+}
+
+#[test]
+fn test_runtime_panic() {
+    let source = "package main
+
+func main() {
+	println(\"before\")
+	panic(\"stop\")
+}
+";
+    let mut program = golite::parse_source(source, "panic.go").unwrap();
+    golite::weed(&program).unwrap();
+    golite::typecheck(&mut program).unwrap();
+
+    let mut output = Vec::new();
+    let panic = golite::interpret(&program, &mut output).unwrap_err();
+    assert_eq!(String::from_utf8(output).unwrap(), "before\n");
+    assert!(panic.to_string().contains("stop"), "{}", panic);
+}
@@ -0,0 +1,45 @@
+use std::process::Command;
+
+const SEVERAL_ERRORS: &str = "programs/2-typecheck/invalid/severalErrors.go";
+
+// Runs the compiler on a file and returns the first line of each error it printed
+fn errors(args: &[&str]) -> Vec<String> {
+    let output = Command::new(env!("CARGO_BIN_EXE_golite"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    String::from_utf8(output.stderr).unwrap()
+        .lines()
+        .filter(|line| line.starts_with("Error: "))
+        .map(String::from)
+        .collect()
+}
+
+// The line and the column of an error like `Error: prog.go:4:10: message`
+fn position(error: &str) -> (u32, u32) {
+    let mut parts = error["Error: ".len()..].split(':').skip(1);
+    let line = parts.next().unwrap().parse().unwrap();
+    let column = parts.next().unwrap().parse().unwrap();
+    (line, column)
+}
+
+#[test]
+fn test_several_errors() {
+    let errors = errors(&["typecheck", SEVERAL_ERRORS]);
+    let positions: Vec<_> = errors.iter().map(|e| position(e)).collect();
+    assert_eq!(positions, vec![(5, 2), (8, 5), (12, 2), (13, 7), (17, 2), (18, 10)]);
+}
+
+#[test]
+fn test_max_errors() {
+    let limited = errors(&["typecheck", "--max-errors=2", SEVERAL_ERRORS]);
+    assert_eq!(limited.len(), 3);
+    assert_eq!(position(&limited[0]), (5, 2));
+    assert_eq!(position(&limited[1]), (8, 5));
+    assert_eq!(limited[2], "Error: too many errors (4 more not shown)");
+
+    // 0 lifts the limit
+    assert_eq!(errors(&["typecheck", "--max-errors=0", SEVERAL_ERRORS]).len(), 6);
+}